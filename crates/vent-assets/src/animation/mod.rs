@@ -0,0 +1,234 @@
+//! Keyframe Animations as they are stored in glTF, Every Channel animates one Property of one Node
+
+use vent_math::{scalar::quat::Quat, vec::vec3::Vec3};
+
+mod morph;
+mod player;
+mod skeleton;
+
+pub use morph::MorphWeights;
+pub use player::AnimationPlayer;
+pub use skeleton::{Pose, Skeleton, SkeletonNode, Skin, Transform};
+
+pub struct AnimationClip {
+    pub name: Option<String>,
+    pub channels: Vec<Channel>,
+    /// Time of the last Keyframe in Seconds
+    pub duration: f32,
+}
+
+pub struct Channel {
+    /// Index into `Skeleton::nodes`
+    pub node: usize,
+    pub interpolation: Interpolation,
+    /// Keyframe Times in Seconds
+    pub times: Vec<f32>,
+    pub values: ChannelValues,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Interpolation {
+    Step,
+    Linear,
+    /// Every Keyframe stores an In-Tangent, the Value and an Out-Tangent
+    CubicSpline,
+}
+
+pub enum ChannelValues {
+    Translation(Vec<Vec3>),
+    Rotation(Vec<Quat>),
+    Scale(Vec<Vec3>),
+    /// All Morph Target Weights of an Keyframe are stored after each other
+    MorphWeights(Vec<f32>),
+}
+
+impl AnimationClip {
+    pub fn new(name: Option<String>, channels: Vec<Channel>) -> Self {
+        let duration = channels
+            .iter()
+            .filter_map(|channel| channel.times.last().copied())
+            .fold(0.0, f32::max);
+        Self {
+            name,
+            channels,
+            duration,
+        }
+    }
+
+    /// Overwrites every Property this Clip animates with its Value at `time`
+    pub fn sample(&self, time: f32, pose: &mut Pose) {
+        for channel in &self.channels {
+            let Some(transform) = pose.transforms.get_mut(channel.node) else {
+                continue;
+            };
+            let keyframe = KeyframePosition::find(&channel.times, time);
+            match &channel.values {
+                ChannelValues::Translation(values) => {
+                    transform.translation = keyframe.sample(channel.interpolation, |i| values[i]);
+                }
+                ChannelValues::Rotation(values) => {
+                    transform.rotation = keyframe.sample(channel.interpolation, |i| values[i]);
+                }
+                ChannelValues::Scale(values) => {
+                    transform.scale = keyframe.sample(channel.interpolation, |i| values[i]);
+                }
+                ChannelValues::MorphWeights(values) => {
+                    let mut count = values.len() / channel.times.len().max(1);
+                    if channel.interpolation == Interpolation::CubicSpline {
+                        count /= 3;
+                    }
+                    pose.morph_weights[channel.node] = Some(
+                        (0..count)
+                            .map(|weight| {
+                                keyframe
+                                    .sample(channel.interpolation, |i| values[i * count + weight])
+                            })
+                            .collect(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// The two Keyframes around an Time
+struct KeyframePosition {
+    previous: usize,
+    next: usize,
+    /// 0.0 at `previous`, 1.0 at `next`
+    factor: f32,
+    /// Time between both Keyframes
+    delta: f32,
+}
+
+impl KeyframePosition {
+    /// Times outside of the Keyframes are clamped
+    fn find(times: &[f32], time: f32) -> Self {
+        let next = times.partition_point(|&t| t <= time);
+        if next == 0 || next == times.len() {
+            let keyframe = next.saturating_sub(1);
+            return Self {
+                previous: keyframe,
+                next: keyframe,
+                factor: 0.0,
+                delta: 0.0,
+            };
+        }
+        let previous = next - 1;
+        let delta = times[next] - times[previous];
+        Self {
+            previous,
+            next,
+            factor: if delta > 0.0 {
+                (time - times[previous]) / delta
+            } else {
+                0.0
+            },
+            delta,
+        }
+    }
+
+    /// `get` returns the Value at an Index, For Cubic Splines the Index includes the Tangents
+    fn sample<T: Keyframe>(&self, interpolation: Interpolation, get: impl Fn(usize) -> T) -> T {
+        match interpolation {
+            Interpolation::Step => get(self.previous),
+            Interpolation::Linear if self.previous == self.next => get(self.previous),
+            Interpolation::Linear => T::linear(get(self.previous), get(self.next), self.factor),
+            Interpolation::CubicSpline if self.previous == self.next => get(self.previous * 3 + 1),
+            Interpolation::CubicSpline => T::cubic_spline(
+                get(self.previous * 3 + 1),
+                get(self.previous * 3 + 2),
+                get(self.next * 3 + 1),
+                get(self.next * 3),
+                self.factor,
+                self.delta,
+            ),
+        }
+    }
+}
+
+/// Values which can be interpolated between Keyframes
+trait Keyframe: Copy {
+    fn linear(from: Self, to: Self, factor: f32) -> Self;
+
+    /// Hermite Spline from `from` to `to`, as defined by the glTF Specification
+    fn cubic_spline(
+        from: Self,
+        out_tangent: Self,
+        to: Self,
+        in_tangent: Self,
+        factor: f32,
+        delta: f32,
+    ) -> Self;
+}
+
+/// Hermite Basis Functions for `from`, `out_tangent`, `to`, `in_tangent`
+fn hermite_basis(t: f32, delta: f32) -> [f32; 4] {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    [
+        2.0 * t3 - 3.0 * t2 + 1.0,
+        delta * (t3 - 2.0 * t2 + t),
+        -2.0 * t3 + 3.0 * t2,
+        delta * (t3 - t2),
+    ]
+}
+
+impl Keyframe for f32 {
+    fn linear(from: Self, to: Self, factor: f32) -> Self {
+        from + (to - from) * factor
+    }
+
+    fn cubic_spline(
+        from: Self,
+        out_tangent: Self,
+        to: Self,
+        in_tangent: Self,
+        factor: f32,
+        delta: f32,
+    ) -> Self {
+        let [a, b, c, d] = hermite_basis(factor, delta);
+        a * from + b * out_tangent + c * to + d * in_tangent
+    }
+}
+
+impl Keyframe for Vec3 {
+    fn linear(from: Self, to: Self, factor: f32) -> Self {
+        from.lerp(to, factor)
+    }
+
+    fn cubic_spline(
+        from: Self,
+        out_tangent: Self,
+        to: Self,
+        in_tangent: Self,
+        factor: f32,
+        delta: f32,
+    ) -> Self {
+        let [a, b, c, d] = hermite_basis(factor, delta);
+        from * a + out_tangent * b + to * c + in_tangent * d
+    }
+}
+
+impl Keyframe for Quat {
+    fn linear(from: Self, to: Self, factor: f32) -> Self {
+        from.slerp(to, factor)
+    }
+
+    fn cubic_spline(
+        from: Self,
+        out_tangent: Self,
+        to: Self,
+        in_tangent: Self,
+        factor: f32,
+        delta: f32,
+    ) -> Self {
+        let [a, b, c, d] = hermite_basis(factor, delta);
+        let [from, out_tangent, to, in_tangent] =
+            [from, out_tangent, to, in_tangent].map(Quat::to_array);
+        Quat::from_array(std::array::from_fn(|i| {
+            a * from[i] + b * out_tangent[i] + c * to[i] + d * in_tangent[i]
+        }))
+        .normalize()
+    }
+}
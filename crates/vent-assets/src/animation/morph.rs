@@ -0,0 +1,56 @@
+use std::ops::Range;
+
+use super::{Pose, Skeleton};
+
+/// Current Weights of the Morph Targets of every Mesh, All Meshes share one Buffer.
+/// Starts with the Default Weights of the Model, Animations write into it but it can also be set by Hand
+#[derive(Clone, Default)]
+pub struct MorphWeights {
+    pub weights: Vec<f32>,
+    /// Where the Weights of every Mesh are inside `weights`
+    pub meshes: Vec<Range<usize>>,
+}
+
+impl MorphWeights {
+    pub fn new(meshes: impl IntoIterator<Item = Vec<f32>>) -> Self {
+        let mut weights = Vec::new();
+        let meshes = meshes
+            .into_iter()
+            .map(|mesh_weights| {
+                let start = weights.len();
+                weights.extend(mesh_weights);
+                start..weights.len()
+            })
+            .collect();
+        Self { weights, meshes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.weights.is_empty()
+    }
+
+    pub fn mesh(&self, mesh: usize) -> &[f32] {
+        self.meshes
+            .get(mesh)
+            .map_or(&[], |range| &self.weights[range.clone()])
+    }
+
+    pub fn mesh_mut(&mut self, mesh: usize) -> &mut [f32] {
+        match self.meshes.get(mesh) {
+            Some(range) => &mut self.weights[range.clone()],
+            None => &mut [],
+        }
+    }
+
+    /// Copies the animated Weights of every Node into the Weights of its Mesh
+    pub fn apply_pose(&mut self, skeleton: &Skeleton, pose: &Pose) {
+        for (node, weights) in skeleton.nodes.iter().zip(&pose.morph_weights) {
+            let (Some(mesh), Some(weights)) = (node.mesh, weights) else {
+                continue;
+            };
+            for (weight, animated) in self.mesh_mut(mesh).iter_mut().zip(weights) {
+                *weight = *animated;
+            }
+        }
+    }
+}
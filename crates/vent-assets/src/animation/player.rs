@@ -0,0 +1,109 @@
+use super::{AnimationClip, Pose, Skeleton};
+
+/// Plays and blends Animation Clips, Every playing Clip is an Layer with its own Time and Weight
+#[derive(Default)]
+pub struct AnimationPlayer {
+    pub layers: Vec<AnimationLayer>,
+    /// Scratch Pose every Layer is sampled into
+    layer_pose: Pose,
+}
+
+pub struct AnimationLayer {
+    /// Index into the Clips of the Model
+    pub clip: usize,
+    /// Seconds
+    pub time: f32,
+    pub speed: f32,
+    pub weight: f32,
+    pub looping: bool,
+    /// Weight change per Second, Used for Cross Fades
+    fade: f32,
+}
+
+impl AnimationPlayer {
+    /// Stops everything else and plays `clip`
+    pub fn play(&mut self, clip: usize, looping: bool) {
+        self.layers.clear();
+        self.layers.push(AnimationLayer::new(clip, looping, 1.0));
+    }
+
+    /// Plays `clip` on top of the playing Clips, Blended by `weight`
+    pub fn blend(&mut self, clip: usize, looping: bool, weight: f32) {
+        self.layers
+            .push(AnimationLayer::new(clip, looping, weight.max(0.0)));
+    }
+
+    /// Fades out every playing Clip while `clip` fades in over `duration` Seconds
+    pub fn cross_fade(&mut self, clip: usize, looping: bool, duration: f32) {
+        if duration <= 0.0 {
+            self.play(clip, looping);
+            return;
+        }
+        for layer in &mut self.layers {
+            layer.fade = -layer.weight / duration;
+        }
+        let mut layer = AnimationLayer::new(clip, looping, 0.0);
+        layer.fade = 1.0 / duration;
+        self.layers.push(layer);
+    }
+
+    pub fn is_playing(&self) -> bool {
+        !self.layers.is_empty()
+    }
+
+    /// Advances every Layer by `delta` Seconds, Faded out Layers are removed
+    pub fn update(&mut self, delta: f32, clips: &[AnimationClip]) {
+        self.layers.retain_mut(|layer| {
+            let Some(clip) = clips.get(layer.clip) else {
+                return false;
+            };
+            layer.time += delta * layer.speed;
+            if layer.looping && clip.duration > 0.0 {
+                layer.time = layer.time.rem_euclid(clip.duration);
+            } else {
+                layer.time = layer.time.clamp(0.0, clip.duration);
+            }
+            if layer.fade != 0.0 {
+                layer.weight = (layer.weight + layer.fade * delta).clamp(0.0, 1.0);
+                if layer.weight >= 1.0 {
+                    layer.fade = 0.0;
+                }
+                return layer.fade >= 0.0 || layer.weight > 0.0;
+            }
+            true
+        });
+    }
+
+    /// Samples every Layer and blends them by Weight. Nodes which are not animated keep their Rest Transform
+    pub fn sample(&mut self, clips: &[AnimationClip], skeleton: &Skeleton, pose: &mut Pose) {
+        let rest_pose = skeleton.rest_pose();
+        pose.clone_from(&rest_pose);
+        let mut total_weight = 0.0;
+        for layer in &self.layers {
+            let Some(clip) = clips.get(layer.clip) else {
+                continue;
+            };
+            if layer.weight <= 0.0 {
+                continue;
+            }
+            self.layer_pose.clone_from(&rest_pose);
+            clip.sample(layer.time, &mut self.layer_pose);
+            // Running weighted average, The first Layer replaces the Rest Pose completely
+            total_weight += layer.weight;
+            pose.blend(&self.layer_pose, layer.weight / total_weight);
+        }
+    }
+}
+
+impl AnimationLayer {
+    fn new(clip: usize, looping: bool, weight: f32) -> Self {
+        Self {
+            clip,
+            time: 0.0,
+            speed: 1.0,
+            weight,
+            looping,
+            fade: 0.0,
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use vent_math::{
+    scalar::{mat4::Mat4, quat::Quat},
+    vec::vec3::Vec3,
+};
+
+use crate::model::data::{NodeData, SkinData};
+
+/// The Node Hierarchy of an Model together with its Skins
+#[derive(Default)]
+pub struct Skeleton {
+    pub nodes: Vec<SkeletonNode>,
+    pub skins: Vec<Skin>,
+    /// Node Indices, Parents always come before their Children
+    order: Vec<usize>,
+}
+
+pub struct SkeletonNode {
+    pub name: Option<String>,
+    pub parent: Option<usize>,
+    /// Transform when no Animation is playing
+    pub rest: Transform,
+    /// Animated Morph Weights are applied to this Mesh
+    pub mesh: Option<usize>,
+}
+
+pub struct Skin {
+    /// Node Indices
+    pub joints: Vec<usize>,
+    pub inverse_bind_matrices: Vec<Mat4>,
+    /// All Skins share one Joint Buffer, This is where the Joints of this Skin start
+    pub joint_offset: usize,
+}
+
+#[derive(Clone, Copy)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+/// Local Transforms of every Node at one Point in Time
+#[derive(Clone, Default)]
+pub struct Pose {
+    pub transforms: Vec<Transform>,
+    /// `None` when the Node has no animated Morph Weights
+    pub morph_weights: Vec<Option<Vec<f32>>>,
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        translation: Vec3::ZERO,
+        rotation: Quat::IDENTITY,
+        scale: Vec3::ONE,
+    };
+
+    pub fn matrix(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+
+    pub fn lerp(&self, other: &Self, s: f32) -> Self {
+        Self {
+            translation: self.translation.lerp(other.translation, s),
+            rotation: self.rotation.slerp(other.rotation, s),
+            scale: self.scale.lerp(other.scale, s),
+        }
+    }
+}
+
+impl Pose {
+    /// Blends `other` into this Pose, `s` = 1.0 replaces it completely
+    pub fn blend(&mut self, other: &Self, s: f32) {
+        for (transform, other) in self.transforms.iter_mut().zip(&other.transforms) {
+            *transform = transform.lerp(other, s);
+        }
+        for (weights, other) in self.morph_weights.iter_mut().zip(&other.morph_weights) {
+            match (weights.as_mut(), other) {
+                (Some(weights), Some(other)) => {
+                    for (weight, other) in weights.iter_mut().zip(other) {
+                        *weight += (other - *weight) * s;
+                    }
+                }
+                (None, Some(other)) => *weights = Some(other.clone()),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Skeleton {
+    /// Joints of every Skin are offset, So all Skins fit into one Joint Buffer
+    pub fn new(nodes: Vec<NodeData>, skins: Vec<SkinData>) -> Self {
+        let nodes: Vec<SkeletonNode> = nodes
+            .into_iter()
+            .map(|node| SkeletonNode {
+                name: node.name,
+                parent: node.parent,
+                rest: Transform {
+                    translation: node.translation.into(),
+                    rotation: Quat::from_array(node.rotation),
+                    scale: node.scale.into(),
+                },
+                mesh: node.mesh,
+            })
+            .collect();
+
+        let mut joint_offset = 0;
+        let skins = skins
+            .into_iter()
+            .map(|skin| {
+                let skin = Skin {
+                    inverse_bind_matrices: (0..skin.joints.len())
+                        .map(|i| {
+                            skin.inverse_bind_matrices
+                                .get(i)
+                                .map_or(Mat4::IDENTITY, Mat4::from_cols_array_2d)
+                        })
+                        .collect(),
+                    joints: skin.joints,
+                    joint_offset,
+                };
+                joint_offset += skin.joints.len();
+                skin
+            })
+            .collect();
+
+        let order = Self::sort_nodes(&nodes);
+        Self {
+            nodes,
+            skins,
+            order,
+        }
+    }
+
+    /// Parents before Children, Nodes with invalid or cyclic Parents are treated as Roots
+    fn sort_nodes(nodes: &[SkeletonNode]) -> Vec<usize> {
+        let mut children = vec![Vec::new(); nodes.len()];
+        let mut roots = Vec::new();
+        for (i, node) in nodes.iter().enumerate() {
+            match node.parent.filter(|&parent| parent < nodes.len()) {
+                Some(parent) => children[parent].push(i),
+                None => roots.push(i),
+            }
+        }
+        let mut order = Vec::with_capacity(nodes.len());
+        let mut visited = vec![false; nodes.len()];
+        let mut stack = roots;
+        loop {
+            while let Some(node) = stack.pop() {
+                if !std::mem::replace(&mut visited[node], true) {
+                    order.push(node);
+                    stack.extend(&children[node]);
+                }
+            }
+            // Only Nodes which are part of an Cycle are left
+            match visited.iter().position(|visited| !visited) {
+                Some(node) => stack.push(node),
+                None => break,
+            }
+        }
+        order
+    }
+
+    pub fn joint_count(&self) -> usize {
+        self.skins.iter().map(|skin| skin.joints.len()).sum()
+    }
+
+    pub fn rest_pose(&self) -> Pose {
+        Pose {
+            transforms: self.nodes.iter().map(|node| node.rest).collect(),
+            morph_weights: vec![None; self.nodes.len()],
+        }
+    }
+
+    /// Calculates the Transform of every Node relative to the Model, `global` is resized to the Node Count
+    pub fn global_transforms(&self, pose: &Pose, global: &mut Vec<Mat4>) {
+        global.clear();
+        global.resize(self.nodes.len(), Mat4::IDENTITY);
+        for &node in &self.order {
+            let local = pose
+                .transforms
+                .get(node)
+                .unwrap_or(&self.nodes[node].rest)
+                .matrix();
+            global[node] = match self.nodes[node].parent {
+                Some(parent) if parent < global.len() => global[parent] * local,
+                _ => local,
+            };
+        }
+    }
+
+    /// Calculates the Matrix of every Joint of every Skin from the Global Transforms, `joint_matrices` is cleared first
+    pub fn joint_matrices(&self, global: &[Mat4], joint_matrices: &mut Vec<Mat4>) {
+        joint_matrices.clear();
+        for skin in &self.skins {
+            joint_matrices.extend(skin.joints.iter().zip(&skin.inverse_bind_matrices).map(
+                |(&joint, inverse_bind)| {
+                    global.get(joint).copied().unwrap_or(Mat4::IDENTITY) * *inverse_bind
+                },
+            ));
+        }
+    }
+}
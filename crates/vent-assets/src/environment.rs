@@ -0,0 +1,282 @@
+//! HDR Environment Maps, Used for the Skybox and Image Based Lighting.
+//! An equirectangular Panorama (.hdr, .exr) or 6 Faces are converted into an floating Point Cubemap with Mip Levels
+
+use std::{f32::consts::PI, path::Path};
+
+use ash::vk;
+use half::f16;
+use image::{DynamicImage, Rgba32FImage};
+use vent_rendering::{
+    image::{SkyBoxImages, VulkanImage},
+    instance::VulkanInstance,
+};
+
+use crate::AssetError;
+
+/// Floats are needed for HDR, 16 Bit Floats can be filtered on every GPU
+pub const ENVIRONMENT_FORMAT: vk::Format = vk::Format::R16G16B16A16_SFLOAT;
+
+/// Panoramas are converted to Faces of a quarter of their Width, Bigger Faces would only waste Memory
+const MAX_FACE_SIZE: u32 = 2048;
+/// Irradiance is very smooth, So we project it from an small Level
+const IRRADIANCE_FACE_SIZE: u32 = 32;
+
+/// RGBA Texels of an Face, Row by Row
+type Face = Vec<[f32; 4]>;
+
+pub struct EnvironmentMap {
+    /// Width and Height of every Face in the first Level
+    pub face_size: u32,
+    /// Every Mip Level holds the 6 Faces (+X, -X, +Y, -Y, +Z, -Z) after another as `ENVIRONMENT_FORMAT`.
+    /// Levels are box filtered, Shaders use blurrier Levels for rougher Surfaces
+    pub levels: Vec<Vec<u8>>,
+    /// Diffuse Irradiance as 9 RGB Spherical Harmonics Coefficients, Already convolved with the Cosine Lobe
+    pub irradiance: [[f32; 3]; 9],
+}
+
+impl EnvironmentMap {
+    /// Loads an equirectangular Panorama, Every Format the `image` Crate can read works but only .hdr and .exr store HDR Colors
+    pub fn load(path: &Path) -> Result<Self, AssetError> {
+        let panorama = image::open(path)?;
+        let panorama = if is_hdr(&panorama) {
+            panorama.into_rgba32f()
+        } else {
+            to_linear(panorama)
+        };
+        if panorama.width() == 0 || panorama.height() == 0 {
+            return Err(AssetError::Parse("Empty Panorama".to_owned()));
+        }
+        Ok(Self::from_equirectangular(&panorama))
+    }
+
+    /// Loads 6 separate Faces, They must be squares of the same Size
+    pub fn load_faces(images: &SkyBoxImages) -> Result<Self, AssetError> {
+        let paths = [
+            &images.right,
+            &images.left,
+            &images.top,
+            &images.bottom,
+            &images.front,
+            &images.back,
+        ];
+        let mut faces: [Face; 6] = Default::default();
+        let mut face_size = None;
+        for (face, path) in faces.iter_mut().zip(paths) {
+            let image = image::open(path)?;
+            let image = if is_hdr(&image) {
+                image.into_rgba32f()
+            } else {
+                to_linear(image)
+            };
+            let size = image.width();
+            if size == 0 || image.height() != size || face_size.is_some_and(|s| s != size) {
+                return Err(AssetError::Parse(format!(
+                    "Cubemap Face {} must be an Square of the same Size as the other Faces",
+                    path.display()
+                )));
+            }
+            face_size = Some(size);
+            *face = image.pixels().map(|pixel| pixel.0).collect();
+        }
+        Ok(Self::from_faces(face_size.unwrap_or(1), faces))
+    }
+
+    pub fn from_equirectangular(panorama: &Rgba32FImage) -> Self {
+        let size = (panorama.width() / 4).clamp(1, MAX_FACE_SIZE);
+        let faces = std::array::from_fn(|face| {
+            (0..size * size)
+                .map(|i| {
+                    let direction = face_direction(face, i % size, i / size, size);
+                    sample_equirectangular(panorama, direction)
+                })
+                .collect()
+        });
+        Self::from_faces(size, faces)
+    }
+
+    /// Builds the Mip Chain and the Irradiance out of linear Faces
+    pub fn from_faces(face_size: u32, mut faces: [Face; 6]) -> Self {
+        let level_count = face_size.ilog2() + 1;
+        let mut levels = Vec::with_capacity(level_count as usize);
+        let mut irradiance = None;
+        let mut size = face_size;
+        loop {
+            if irradiance.is_none() && size <= IRRADIANCE_FACE_SIZE {
+                irradiance = Some(project_irradiance(&faces, size));
+            }
+            levels.push(
+                faces
+                    .iter()
+                    .flatten()
+                    .flatten()
+                    .flat_map(|&value| f16::from_f32(value.min(f16::MAX.to_f32())).to_le_bytes())
+                    .collect(),
+            );
+            if size == 1 {
+                break;
+            }
+            faces = faces.map(|face| downsample(&face, size));
+            size /= 2;
+        }
+
+        Self {
+            face_size,
+            levels,
+            irradiance: irradiance.unwrap_or_default(),
+        }
+    }
+
+    /// The Level a Shader should use for fully rough Surfaces
+    pub fn max_lod(&self) -> f32 {
+        self.levels.len().saturating_sub(1) as f32
+    }
+
+    pub fn upload(
+        &self,
+        instance: &VulkanInstance,
+        name: Option<&str>,
+    ) -> Result<VulkanImage, AssetError> {
+        Ok(VulkanImage::from_cubemap(
+            instance,
+            ENVIRONMENT_FORMAT,
+            self.face_size,
+            &self.levels,
+            name,
+        )?)
+    }
+}
+
+fn is_hdr(image: &DynamicImage) -> bool {
+    matches!(
+        image,
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+    )
+}
+
+/// LDR Images are stored in sRGB
+fn to_linear(image: DynamicImage) -> Rgba32FImage {
+    let mut image = image.into_rgba32f();
+    for pixel in image.pixels_mut() {
+        for c in &mut pixel.0[..3] {
+            *c = if *c <= 0.04045 {
+                *c / 12.92
+            } else {
+                ((*c + 0.055) / 1.055).powf(2.4)
+            };
+        }
+    }
+    image
+}
+
+/// Face Coordinates to an Direction, As defined by the Vulkan Spec for Cube Map Face Selection
+fn face_direction(face: usize, x: u32, y: u32, size: u32) -> [f32; 3] {
+    let s = 2.0 * (x as f32 + 0.5) / size as f32 - 1.0;
+    let t = 2.0 * (y as f32 + 0.5) / size as f32 - 1.0;
+    let [x, y, z] = match face {
+        0 => [1.0, -t, -s],
+        1 => [-1.0, -t, s],
+        2 => [s, 1.0, t],
+        3 => [s, -1.0, -t],
+        4 => [s, -t, 1.0],
+        _ => [-s, -t, -1.0],
+    };
+    let length = (x * x + y * y + z * z).sqrt();
+    [x / length, y / length, z / length]
+}
+
+fn sample_equirectangular(panorama: &Rgba32FImage, direction: [f32; 3]) -> [f32; 4] {
+    let [x, y, z] = direction;
+    let (width, height) = (panorama.width(), panorama.height());
+    let u = (0.5 + z.atan2(x) / (2.0 * PI)) * width as f32 - 0.5;
+    let v = y.clamp(-1.0, 1.0).acos() / PI * height as f32 - 0.5;
+
+    // Bilinear, Wrapping around horizontally
+    let (x0, y0) = (u.floor(), v.floor());
+    let (fx, fy) = (u - x0, v - y0);
+    let texel = |x: f32, y: f32| {
+        let x = (x as i64).rem_euclid(width as i64) as u32;
+        let y = (y as i64).clamp(0, height as i64 - 1) as u32;
+        panorama.get_pixel(x, y).0
+    };
+    let (a, b) = (texel(x0, y0), texel(x0 + 1.0, y0));
+    let (c, d) = (texel(x0, y0 + 1.0), texel(x0 + 1.0, y0 + 1.0));
+    std::array::from_fn(|i| {
+        let top = a[i] + (b[i] - a[i]) * fx;
+        let bottom = c[i] + (d[i] - c[i]) * fx;
+        top + (bottom - top) * fy
+    })
+}
+
+/// Averages 2x2 Texels into one
+fn downsample(face: &[[f32; 4]], size: u32) -> Face {
+    let half = (size / 2).max(1);
+    (0..half * half)
+        .map(|i| {
+            let (x, y) = (i % half * 2, i / half * 2);
+            let mut sum = [0.0; 4];
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let texel = face[((y + dy).min(size - 1) * size + (x + dx).min(size - 1)) as usize];
+                for c in 0..4 {
+                    sum[c] += texel[c] / 4.0;
+                }
+            }
+            sum
+        })
+        .collect()
+}
+
+/// Real Spherical Harmonics up to the 2nd Band, shader.frag evaluates the same Basis
+fn sh_basis([x, y, z]: [f32; 3]) -> [f32; 9] {
+    [
+        0.282095,
+        0.488603 * y,
+        0.488603 * z,
+        0.488603 * x,
+        1.092548 * x * y,
+        1.092548 * y * z,
+        0.315392 * (3.0 * z * z - 1.0),
+        1.092548 * x * z,
+        0.546274 * (x * x - y * y),
+    ]
+}
+
+/// Projects the Radiance onto Spherical Harmonics and convolves it with the Cosine Lobe, See "An Efficient Representation for Irradiance Environment Maps" (Ramamoorthi, Hanrahan)
+fn project_irradiance(faces: &[Face; 6], size: u32) -> [[f32; 3]; 9] {
+    let mut coefficients = [[0.0; 3]; 9];
+    let mut total_weight = 0.0;
+    for (face, texels) in faces.iter().enumerate() {
+        for (i, texel) in texels.iter().enumerate() {
+            let (x, y) = (i as u32 % size, i as u32 / size);
+            let s = 2.0 * (x as f32 + 0.5) / size as f32 - 1.0;
+            let t = 2.0 * (y as f32 + 0.5) / size as f32 - 1.0;
+            // Solid Angle of the Texel
+            let weight = (1.0 + s * s + t * t).powf(-1.5);
+            total_weight += weight;
+            let basis = sh_basis(face_direction(face, x, y, size));
+            for (coefficient, basis) in coefficients.iter_mut().zip(basis) {
+                for c in 0..3 {
+                    coefficient[c] += texel[c] * basis * weight;
+                }
+            }
+        }
+    }
+
+    let bands = [
+        PI,
+        2.0 * PI / 3.0,
+        2.0 * PI / 3.0,
+        2.0 * PI / 3.0,
+        PI / 4.0,
+        PI / 4.0,
+        PI / 4.0,
+        PI / 4.0,
+        PI / 4.0,
+    ];
+    let normalize = 4.0 * PI / total_weight;
+    for (coefficient, band) in coefficients.iter_mut().zip(bands) {
+        for value in coefficient {
+            *value *= normalize * band;
+        }
+    }
+    coefficients
+}
@@ -0,0 +1,71 @@
+use std::{fmt, io};
+
+use ash::vk;
+
+/// Everything that can go wrong while Loading an Asset, Loaders return this instead of panicking
+/// so a broken File can be logged (or shown in the Editor) without taking down the whole Engine
+#[derive(Debug)]
+pub enum AssetError {
+    /// The File could not be opened or read
+    Io(io::Error),
+    /// The File was read but its Content is invalid
+    Parse(String),
+    /// The File is valid but uses something we do not support
+    Unsupported(String),
+    /// Creating an GPU Resource failed
+    GpuUpload(vk::Result),
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "I/O error: {}", err),
+            Self::Parse(err) => write!(f, "Failed to parse: {}", err),
+            Self::Unsupported(err) => write!(f, "Unsupported: {}", err),
+            Self::GpuUpload(err) => write!(f, "GPU upload failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for AssetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Parse(_) | Self::Unsupported(_) | Self::GpuUpload(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for AssetError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<vk::Result> for AssetError {
+    fn from(err: vk::Result) -> Self {
+        Self::GpuUpload(err)
+    }
+}
+
+impl From<gltf::Error> for AssetError {
+    fn from(err: gltf::Error) -> Self {
+        match err {
+            gltf::Error::Io(err) => Self::Io(err),
+            gltf::Error::UnsupportedImageEncoding
+            | gltf::Error::UnsupportedImageFormat(_)
+            | gltf::Error::UnsupportedScheme => Self::Unsupported(err.to_string()),
+            err => Self::Parse(err.to_string()),
+        }
+    }
+}
+
+impl From<image::ImageError> for AssetError {
+    fn from(err: image::ImageError) -> Self {
+        match err {
+            image::ImageError::IoError(err) => Self::Io(err),
+            image::ImageError::Unsupported(err) => Self::Unsupported(err.to_string()),
+            err => Self::Parse(err.to_string()),
+        }
+    }
+}
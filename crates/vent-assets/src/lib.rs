@@ -1,10 +1,20 @@
+use animation::{AnimationClip, MorphWeights, Skeleton};
 use ash::vk::{self};
-use modelz::AlphaMode;
-use vent_rendering::{image::VulkanImage, mesh::Mesh3D};
+use model::{
+    data::{AlphaMode, TextureTransform},
+    scene::Scene,
+};
+use vent_rendering::{buffer::VulkanBuffer, image::VulkanImage, mesh::Mesh3D};
 
+pub use error::AssetError;
+
+pub mod animation;
+pub mod environment;
+mod error;
 mod image;
 pub mod io;
 pub mod model;
+pub mod texture;
 
 pub trait Asset: Send + Sync + 'static {}
 
@@ -14,8 +24,17 @@ pub struct Model3D {
     pub pipelines: Vec<ModelPipeline>,
     pub materials: Vec<Material>,
 
-    pub descriptor_pool: vk::DescriptorPool,
+    /// Node Hierarchy with Cameras and Lights, Empty when the Model has no Nodes
+    pub scene: Scene,
+    /// Names and Transforms of the Nodes, Empty when the Model has no Nodes
+    pub skeleton: Skeleton,
+    pub animations: Vec<AnimationClip>,
+    /// Default Morph Target Weights of every Mesh
+    pub morph_weights: MorphWeights,
+    /// Morph Target Deltas of every Mesh, See `Mesh3D::morph_targets`
+    pub morph_targets: Option<VulkanBuffer>,
 
+    /// Transform of the whole Model, Node Transforms are relative to it
     pub position: [f32; 3], // Default: 0.0, 0.0, 0.0
     pub rotation: [f32; 4], // Default: 0.0, 0.0, 0.0, 1.0
     pub scale: [f32; 3],    // Default: 1.0, 1.0, 1.0
@@ -31,14 +50,68 @@ pub struct ModelMaterial {
     pub material_index: usize,
     // So every App is Specfic and you will need to create your own DescriptorSet's out of this
     // We only binding them
-    pub meshes: Vec<Mesh3D>,
+    pub meshes: Vec<ModelMesh>,
+}
+
+/// An uploaded Primitive
+pub struct ModelMesh {
+    pub mesh: Mesh3D,
+    /// Index of the Mesh in the Model File this Primitive belongs to, See `Scene::mesh_instances`
+    pub mesh_index: usize,
 }
 
+/// glTF Metallic-Roughness Material, Every Texture is always set so we can bind them all
 pub struct Material {
-    pub diffuse_texture: VulkanImage,
-    pub descriptor_set: Option<Vec<vk::DescriptorSet>>,
+    pub base_color_texture: MaterialTexture,
+    pub metallic_roughness_texture: MaterialTexture,
+    pub normal_texture: MaterialTexture,
+    pub occlusion_texture: MaterialTexture,
+    pub emissive_texture: MaterialTexture,
+    pub descriptor_set: Option<vk::DescriptorSet>,
+    /// Slot in the Material Buffer when the Renderer uses Bindless Textures, Set by the Renderer just like `descriptor_set`
+    pub bindless_index: Option<u32>,
     pub base_color: [f32; 4],
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub normal_scale: f32,
+    pub occlusion_strength: f32,
+    pub emissive_factor: [f32; 3],
     pub alpha_mode: AlphaMode,
     pub double_sided: bool,
     pub alpha_cut: f32,
+    /// False when `normal_texture` is just an placeholder
+    pub has_normal_texture: bool,
+}
+
+/// An Texture used by an Material. Missing Textures are replaced with an neutral 1x1 Image
+pub struct MaterialTexture {
+    pub image: VulkanImage,
+    /// Which UV Set is used, 0 or 1
+    pub tex_coord: u32,
+    pub transform: TextureTransform,
+}
+
+impl Material {
+    /// All Textures in Binding order: Base Color, Metallic Roughness, Normal, Occlusion, Emissive
+    pub fn textures(&self) -> [&MaterialTexture; 5] {
+        [
+            &self.base_color_texture,
+            &self.metallic_roughness_texture,
+            &self.normal_texture,
+            &self.occlusion_texture,
+            &self.emissive_texture,
+        ]
+    }
+
+    pub fn destroy(&mut self, device: &ash::Device) {
+        for texture in [
+            &mut self.base_color_texture,
+            &mut self.metallic_roughness_texture,
+            &mut self.normal_texture,
+            &mut self.occlusion_texture,
+            &mut self.emissive_texture,
+        ] {
+            texture.image.destroy(device);
+        }
+    }
 }
@@ -0,0 +1,427 @@
+//! CPU side Model Data, Every Importer converts into these Types before we upload anything to the GPU
+
+use ash::vk;
+use vent_rendering::image::CompressedImage;
+
+use crate::{animation::AnimationClip, AssetError};
+
+/// Everything an Importer extracted from a Model File
+#[derive(Default)]
+pub struct ModelData {
+    pub meshes: Vec<MeshData>,
+    pub materials: Vec<MaterialData>,
+    pub nodes: Vec<NodeData>,
+    pub skins: Vec<SkinData>,
+    pub animations: Vec<AnimationClip>,
+    pub cameras: Vec<CameraData>,
+    pub lights: Vec<LightData>,
+    /// Nodes the shown Scene starts at, Nodes which can not be reached from them are not drawn
+    pub scene_roots: Vec<usize>,
+}
+
+/// An Node of the Model Hierarchy, Used as Joints by Skins and as Targets by Animations
+pub struct NodeData {
+    pub name: Option<String>,
+    pub parent: Option<usize>,
+    pub translation: [f32; 3],
+    /// Quaternion (x, y, z, w)
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+    pub mesh: Option<usize>,
+    pub skin: Option<usize>,
+    pub camera: Option<usize>,
+    pub light: Option<usize>,
+}
+
+pub struct SkinData {
+    pub name: Option<String>,
+    /// Node Indices, The Joint Indices of an Vertex point into this
+    pub joints: Vec<usize>,
+    /// One column major Matrix per Joint
+    pub inverse_bind_matrices: Vec<[[f32; 4]; 4]>,
+}
+
+/// An Camera looks along the local -Z Axis of its Node
+#[derive(Clone, Debug)]
+pub struct CameraData {
+    pub name: Option<String>,
+    pub projection: Projection,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Projection {
+    Perspective {
+        /// Vertical Field of View in Radians
+        yfov: f32,
+        /// `None` means the Aspect Ratio of the Viewport should be used
+        aspect_ratio: Option<f32>,
+        znear: f32,
+        /// `None` means an infinite Projection
+        zfar: Option<f32>,
+    },
+    Orthographic {
+        /// Half the Width of the View
+        xmag: f32,
+        /// Half the Height of the View
+        ymag: f32,
+        znear: f32,
+        zfar: f32,
+    },
+}
+
+/// KHR_lights_punctual, Directional and Spot Lights shine along the local -Z Axis of their Node
+#[derive(Clone, Debug)]
+pub struct LightData {
+    pub name: Option<String>,
+    pub kind: LightKind,
+    /// Linear RGB
+    pub color: [f32; 3],
+    /// Lux for Directional Lights, Candela for Point and Spot Lights
+    pub intensity: f32,
+    /// `None` means the Light has no Range
+    pub range: Option<f32>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LightKind {
+    Directional,
+    Point,
+    Spot {
+        /// Radians, Falloff begins here
+        inner_cone_angle: f32,
+        /// Radians, Falloff ends here
+        outer_cone_angle: f32,
+    },
+}
+
+pub struct MeshData {
+    pub name: Option<String>,
+    pub primitives: Vec<PrimitiveData>,
+    /// Default Morph Target Weights, Shared by all Primitives
+    pub weights: Vec<f32>,
+}
+
+/// A single Draw, Attributes which are missing in the Source File are `None` and will be generated by the Loader
+pub struct PrimitiveData {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Option<Vec<[f32; 3]>>,
+    pub tex_coords: Option<Vec<[f32; 2]>>,
+    /// Second UV Set, Used by Textures with `tex_coord` 1
+    pub tex_coords1: Option<Vec<[f32; 2]>>,
+    pub tangents: Option<Vec<[f32; 4]>>,
+    /// Up to 4 Joints which influence an Vertex, Only set for skinned Meshes
+    pub joints: Option<Vec<[u16; 4]>>,
+    pub weights: Option<Vec<[f32; 4]>>,
+    pub morph_targets: Vec<MorphTargetData>,
+    /// Always set, Non indexed Primitives get generated Indices
+    pub indices: Vec<u32>,
+    pub mode: PrimitiveMode,
+    /// Primitives without an Material use an default Material
+    pub material_index: Option<usize>,
+    /// Generate flat instead of smooth Normals when `normals` is `None`, glTF requires this
+    pub flat_normals: bool,
+}
+
+/// Per Vertex Differences to the Base Primitive, Missing Attributes do not change
+pub struct MorphTargetData {
+    pub positions: Option<Vec<[f32; 3]>>,
+    pub normals: Option<Vec<[f32; 3]>>,
+    pub tangents: Option<Vec<[f32; 3]>>,
+}
+
+/// glTF Metallic-Roughness Material
+pub struct MaterialData {
+    pub name: Option<String>,
+    pub base_color_texture: Option<TextureData>,
+    /// Blue Channel is Metalness, Green Channel is Roughness
+    pub metallic_roughness_texture: Option<TextureData>,
+    pub normal_texture: Option<TextureData>,
+    pub occlusion_texture: Option<TextureData>,
+    pub emissive_texture: Option<TextureData>,
+    pub base_color: [f32; 4],
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub normal_scale: f32,
+    pub occlusion_strength: f32,
+    pub emissive_factor: [f32; 3],
+    pub alpha_mode: AlphaMode,
+    pub alpha_cutoff: f32,
+    pub double_sided: bool,
+}
+
+pub struct TextureData {
+    pub image: TextureImage,
+    pub sampler: vk::SamplerCreateInfo<'static>,
+    /// Which UV Set is used, 0 or 1
+    pub tex_coord: u32,
+    pub transform: TextureTransform,
+}
+
+#[derive(Clone)]
+pub enum TextureImage {
+    /// PNG, JPEG, ..., Uploaded as RGBA8 with generated Mip Levels
+    Decoded(image::DynamicImage),
+    /// KTX2 or DDS, Uploaded in its own Format with its prebuilt Mip Levels
+    Compressed(CompressedImage),
+}
+
+impl TextureImage {
+    pub fn width(&self) -> u32 {
+        match self {
+            Self::Decoded(image) => image.width(),
+            Self::Compressed(image) => image.width,
+        }
+    }
+
+    pub fn height(&self) -> u32 {
+        match self {
+            Self::Decoded(image) => image.height(),
+            Self::Compressed(image) => image.height,
+        }
+    }
+}
+
+/// Magenta and Black Checkerboard which replaces Textures that could not be Loaded, So they are easy to spot
+pub fn missing_texture_image() -> image::DynamicImage {
+    const SIZE: u32 = 8;
+    let image = image::RgbaImage::from_fn(SIZE, SIZE, |x, y| {
+        if (x + y) % 2 == 0 {
+            image::Rgba([255, 0, 255, 255])
+        } else {
+            image::Rgba([0, 0, 0, 255])
+        }
+    });
+    image::DynamicImage::ImageRgba8(image)
+}
+
+/// KHR_texture_transform
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TextureTransform {
+    pub offset: [f32; 2],
+    /// Radians
+    pub rotation: f32,
+    pub scale: [f32; 2],
+}
+
+impl Default for TextureTransform {
+    fn default() -> Self {
+        Self {
+            offset: [0.0, 0.0],
+            rotation: 0.0,
+            scale: [1.0, 1.0],
+        }
+    }
+}
+
+impl TextureTransform {
+    /// Returns the column major 2x2 Matrix (Rotation * Scale) and the Offset,
+    /// The Shader does `uv = mat2(matrix.xy, matrix.zw) * uv + offset`
+    pub fn matrix(&self) -> ([f32; 4], [f32; 2]) {
+        let (sin, cos) = self.rotation.sin_cos();
+        let [sx, sy] = self.scale;
+        ([cos * sx, -sin * sx, sin * sy, cos * sy], self.offset)
+    }
+}
+
+/// Values are the same as the Shader expects them
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum AlphaMode {
+    Opaque = 1,
+    Mask = 2,
+    Blend = 3,
+}
+
+/// Primitive Topologies as they can be found in Model Files.
+/// Vulkan has no Line Loops, So they are drawn as Line Strips with the first Index repeated at the end
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PrimitiveMode {
+    Points,
+    Lines,
+    LineLoop,
+    LineStrip,
+    Triangles,
+    TriangleStrip,
+    TriangleFan,
+}
+
+impl Default for MaterialData {
+    /// Used for Primitives which have no Material
+    fn default() -> Self {
+        Self {
+            name: Some("Default Material".to_owned()),
+            base_color_texture: None,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            occlusion_texture: None,
+            emissive_texture: None,
+            base_color: [1.0, 1.0, 1.0, 1.0],
+            metallic_factor: 1.0,
+            roughness_factor: 1.0,
+            normal_scale: 1.0,
+            occlusion_strength: 1.0,
+            emissive_factor: [0.0, 0.0, 0.0],
+            alpha_mode: AlphaMode::Opaque,
+            alpha_cutoff: 0.5,
+            double_sided: false,
+        }
+    }
+}
+
+impl PrimitiveData {
+    /// Generates Indices for Primitives which have none
+    pub fn generate_indices(vertex_count: usize) -> Vec<u32> {
+        (0..vertex_count as u32).collect()
+    }
+
+    /// Checks that every Attribute has one Value per Vertex, Every Index points at an Vertex and there is
+    /// enough to draw. The Loader indexes Attributes directly, So broken Files have to be caught here
+    pub fn validate(&self) -> Result<(), AssetError> {
+        let vertex_count = self.positions.len();
+        if vertex_count == 0 {
+            return Err(AssetError::Parse("Primitive has no Vertices".to_owned()));
+        }
+        let min_indices = match self.mode {
+            PrimitiveMode::Points => 1,
+            PrimitiveMode::Lines | PrimitiveMode::LineLoop | PrimitiveMode::LineStrip => 2,
+            PrimitiveMode::Triangles
+            | PrimitiveMode::TriangleStrip
+            | PrimitiveMode::TriangleFan => 3,
+        };
+        if self.indices.len() < min_indices {
+            return Err(AssetError::Parse(format!(
+                "{:?} Primitive has {} Indices, Needs at least {}",
+                self.mode,
+                self.indices.len(),
+                min_indices
+            )));
+        }
+
+        let mut attributes = vec![
+            ("Normals", self.normals.as_ref().map(Vec::len)),
+            (
+                "Texture Coordinates",
+                self.tex_coords.as_ref().map(Vec::len),
+            ),
+            (
+                "Texture Coordinates 1",
+                self.tex_coords1.as_ref().map(Vec::len),
+            ),
+            ("Tangents", self.tangents.as_ref().map(Vec::len)),
+            ("Joints", self.joints.as_ref().map(Vec::len)),
+            ("Weights", self.weights.as_ref().map(Vec::len)),
+        ];
+        for target in &self.morph_targets {
+            attributes.extend([
+                (
+                    "Morph Target Positions",
+                    target.positions.as_ref().map(Vec::len),
+                ),
+                (
+                    "Morph Target Normals",
+                    target.normals.as_ref().map(Vec::len),
+                ),
+                (
+                    "Morph Target Tangents",
+                    target.tangents.as_ref().map(Vec::len),
+                ),
+            ]);
+        }
+        if let Some((name, len)) = attributes.into_iter().find_map(|(name, len)| {
+            len.filter(|&len| len != vertex_count)
+                .map(|len| (name, len))
+        }) {
+            return Err(AssetError::Parse(format!(
+                "Primitive has {} {} but {} Vertices",
+                len, name, vertex_count
+            )));
+        }
+
+        if let Some(&index) = self.indices.iter().find(|&&i| i as usize >= vertex_count) {
+            return Err(AssetError::Parse(format!(
+                "Primitive uses Vertex {} but there are only {}",
+                index, vertex_count
+            )));
+        }
+        Ok(())
+    }
+
+    /// Gives every Index its own Vertex, So no Vertex is shared between Triangles anymore.
+    /// Flat Normals need this, Since a shared Position has a different Normal in every Triangle
+    pub fn unweld(&mut self) {
+        fn gather<T: Copy>(attribute: &mut Vec<T>, indices: &[u32]) {
+            *attribute = indices.iter().map(|&i| attribute[i as usize]).collect();
+        }
+
+        let indices = &self.indices;
+        gather(&mut self.positions, indices);
+        if let Some(normals) = &mut self.normals {
+            gather(normals, indices);
+        }
+        for tex_coords in [&mut self.tex_coords, &mut self.tex_coords1]
+            .into_iter()
+            .flatten()
+        {
+            gather(tex_coords, indices);
+        }
+        for attribute in [&mut self.tangents, &mut self.weights]
+            .into_iter()
+            .flatten()
+        {
+            gather(attribute, indices);
+        }
+        if let Some(joints) = &mut self.joints {
+            gather(joints, indices);
+        }
+        for target in &mut self.morph_targets {
+            for attribute in [
+                &mut target.positions,
+                &mut target.normals,
+                &mut target.tangents,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                gather(attribute, indices);
+            }
+        }
+        self.indices = Self::generate_indices(self.indices.len());
+    }
+
+    /// Converts Strips, Fans and Loops into Lists where possible, So Normals, Tangents and LODs
+    /// only have to deal with Triangle Lists. Returns the Topology which must be used to draw the Primitive
+    pub fn normalize_topology(&mut self) -> vk::PrimitiveTopology {
+        let indices = &self.indices;
+        match self.mode {
+            PrimitiveMode::Points => vk::PrimitiveTopology::POINT_LIST,
+            PrimitiveMode::Lines => vk::PrimitiveTopology::LINE_LIST,
+            PrimitiveMode::LineStrip => vk::PrimitiveTopology::LINE_STRIP,
+            PrimitiveMode::LineLoop => {
+                if let Some(&first) = indices.first() {
+                    self.indices.push(first);
+                }
+                self.mode = PrimitiveMode::LineStrip;
+                vk::PrimitiveTopology::LINE_STRIP
+            }
+            PrimitiveMode::Triangles => vk::PrimitiveTopology::TRIANGLE_LIST,
+            PrimitiveMode::TriangleStrip => {
+                // Every second Triangle has to be flipped to keep the Winding order
+                self.indices = (0..indices.len().saturating_sub(2))
+                    .flat_map(|i| {
+                        let odd = i % 2;
+                        [indices[i], indices[i + 1 + odd], indices[i + 2 - odd]]
+                    })
+                    .collect();
+                self.mode = PrimitiveMode::Triangles;
+                vk::PrimitiveTopology::TRIANGLE_LIST
+            }
+            PrimitiveMode::TriangleFan => {
+                self.indices = (0..indices.len().saturating_sub(2))
+                    .flat_map(|i| [indices[i + 1], indices[i + 2], indices[0]])
+                    .collect();
+                self.mode = PrimitiveMode::Triangles;
+                vk::PrimitiveTopology::TRIANGLE_LIST
+            }
+        }
+    }
+}
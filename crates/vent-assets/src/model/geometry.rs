@@ -0,0 +1,197 @@
+//! Generates missing Vertex Attributes, glTF allows Normals, Texture Coordinates and Tangents to be omitted
+
+use vent_rendering::vertex::Vertex3D;
+
+/// Calculates Normals by accumulating the area weighted Face Normals of every Triangle a Vertex is used in.
+///
+/// Vertices which are shared between Triangles get smooth Normals, Vertices which are only used by a single
+/// Triangle (e.g. non indexed Meshes) get flat Normals
+pub fn compute_smooth_normals(vertices: &mut [Vertex3D], indices: &[u32]) {
+    let mut normals = vec![[0.0f32; 3]; vertices.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] =
+            [triangle[0], triangle[1], triangle[2]].map(|i| vertices[i as usize].position);
+        // Not normalized, So bigger Triangles have more influence
+        let face_normal = cross(sub(b, a), sub(c, a));
+        for &i in triangle {
+            let normal = &mut normals[i as usize];
+            normal
+                .iter_mut()
+                .zip(face_normal)
+                .for_each(|(n, f)| *n += f);
+        }
+    }
+    for (vertex, normal) in vertices.iter_mut().zip(normals) {
+        vertex.normal = normalize_or(normal, [0.0, 1.0, 0.0]);
+    }
+}
+
+/// Calculates flat Normals, Every Triangle must have its own Vertices (see `PrimitiveData::unweld`)
+/// because a shared Vertex can only have one Normal
+pub fn compute_flat_normals(vertices: &mut [Vertex3D], indices: &[u32]) {
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] =
+            [triangle[0], triangle[1], triangle[2]].map(|i| vertices[i as usize].position);
+        let normal = normalize_or(cross(sub(b, a), sub(c, a)), [0.0, 1.0, 0.0]);
+        for &i in triangle {
+            vertices[i as usize].normal = normal;
+        }
+    }
+}
+
+/// Generates Texture Coordinates by projecting the Positions onto the two largest Axes of the Bounding Box.
+/// This is not pretty but gives Textures and Tangent generation something to work with
+pub fn generate_tex_coords(vertices: &mut [Vertex3D]) {
+    let Some(first) = vertices.first() else {
+        return;
+    };
+    let mut min = first.position;
+    let mut max = first.position;
+    for vertex in vertices.iter() {
+        for i in 0..3 {
+            min[i] = min[i].min(vertex.position[i]);
+            max[i] = max[i].max(vertex.position[i]);
+        }
+    }
+    let extent = sub(max, min);
+    // Drop the smallest Axis
+    let smallest = (0..3)
+        .min_by(|&a, &b| extent[a].total_cmp(&extent[b]))
+        .unwrap_or(2);
+    let [u, v] = match smallest {
+        0 => [2, 1],
+        1 => [0, 2],
+        _ => [0, 1],
+    };
+    for vertex in vertices.iter_mut() {
+        let project = |axis: usize| {
+            if extent[axis] > f32::EPSILON {
+                (vertex.position[axis] - min[axis]) / extent[axis]
+            } else {
+                0.0
+            }
+        };
+        vertex.tex_coord = [project(u), 1.0 - project(v)];
+    }
+}
+
+/// Generates MikkTSpace Tangents, Requires Normals and Texture Coordinates.
+/// The Bitangent sign is stored in `tangent[3]`. Returns false when the Geometry is unsuitable
+pub fn generate_tangents(vertices: &mut [Vertex3D], indices: &[u32]) -> bool {
+    struct Geometry<'a> {
+        vertices: &'a mut [Vertex3D],
+        indices: &'a [u32],
+    }
+
+    impl Geometry<'_> {
+        fn vertex(&self, face: usize, vert: usize) -> &Vertex3D {
+            &self.vertices[self.indices[face * 3 + vert] as usize]
+        }
+    }
+
+    impl bevy_mikktspace::Geometry for Geometry<'_> {
+        fn num_faces(&self) -> usize {
+            self.indices.len() / 3
+        }
+
+        fn num_vertices_of_face(&self, _face: usize) -> usize {
+            3
+        }
+
+        fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+            self.vertex(face, vert).position
+        }
+
+        fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+            self.vertex(face, vert).normal
+        }
+
+        fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+            self.vertex(face, vert).tex_coord
+        }
+
+        fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+            let index = self.indices[face * 3 + vert] as usize;
+            self.vertices[index].tangent = tangent;
+        }
+    }
+
+    bevy_mikktspace::generate_tangents(&mut Geometry { vertices, indices })
+}
+
+/// Gives every Vertex without an Tangent (all zero, e.g. Lines, Meshes without Normal Map or Vertices MikkTSpace skipped)
+/// an unit Tangent perpendicular to its Normal with an Bitangent sign of 1. Shaders can then always build an valid TBN Matrix
+pub fn fill_missing_tangents(vertices: &mut [Vertex3D]) {
+    for vertex in vertices
+        .iter_mut()
+        .filter(|vertex| vertex.tangent[..3].iter().all(|&x| x.abs() <= f32::EPSILON))
+    {
+        let normal = normalize_or(vertex.normal, [0.0, 1.0, 0.0]);
+        // Cross with the Axis the Normal is least aligned with, So the Result never degenerates
+        let axis = if normal[0].abs() < 0.9 {
+            [1.0, 0.0, 0.0]
+        } else {
+            [0.0, 1.0, 0.0]
+        };
+        let [x, y, z] = normalize_or(cross(axis, normal), [1.0, 0.0, 0.0]);
+        vertex.tangent = [x, y, z, 1.0];
+    }
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(u: [f32; 3], v: [f32; 3]) -> [f32; 3] {
+    [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ]
+}
+
+fn normalize_or(v: [f32; 3], fallback: [f32; 3]) -> [f32; 3] {
+    let length = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    if length > f32::EPSILON {
+        v.map(|x| x / length)
+    } else {
+        fallback
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(normal: [f32; 3], tangent: [f32; 4]) -> Vertex3D {
+        Vertex3D {
+            position: [0.0; 3],
+            tex_coord: [0.0; 2],
+            normal,
+            tangent,
+            tex_coord1: [0.0; 2],
+            joints: [0; 4],
+            weights: [0.0; 4],
+        }
+    }
+
+    #[test]
+    fn missing_tangents_are_perpendicular() {
+        let mut vertices = [
+            vertex([0.0, 0.0, 1.0], [0.0; 4]),
+            vertex([1.0, 0.0, 0.0], [0.0; 4]),
+            vertex([0.0; 3], [0.0; 4]),
+            vertex([0.0, 0.0, 1.0], [0.0, 1.0, 0.0, -1.0]),
+        ];
+        fill_missing_tangents(&mut vertices);
+        for vertex in &vertices[..3] {
+            let [x, y, z, w] = vertex.tangent;
+            let normal = normalize_or(vertex.normal, [0.0, 1.0, 0.0]);
+            assert!((x * x + y * y + z * z - 1.0).abs() < 1e-5);
+            assert!((x * normal[0] + y * normal[1] + z * normal[2]).abs() < 1e-5);
+            assert_eq!(w, 1.0);
+        }
+        // Existing Tangents are kept
+        assert_eq!(vertices[3].tangent, [0.0, 1.0, 0.0, -1.0]);
+    }
+}
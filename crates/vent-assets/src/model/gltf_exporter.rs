@@ -0,0 +1,727 @@
+use std::{collections::HashMap, ffi::OsStr, io::Cursor, path::Path};
+
+use ash::vk;
+use image::DynamicImage;
+use serde_json::{json, Value};
+
+use crate::{
+    animation::{ChannelValues, Interpolation},
+    texture::{bcn, dds},
+    AssetError,
+};
+
+use super::data::{
+    self, AlphaMode, LightKind, MaterialData, ModelData, PrimitiveData, PrimitiveMode, Projection,
+    TextureData, TextureImage, TextureTransform,
+};
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const FLOAT: u32 = 5126;
+
+/// Writes Model Data as glTF 2.0, So the Editor can save Models it changed or generated.
+/// `.glb` Files contain everything, `.gltf` Files get an `.bin` File with the same Name next to them.
+/// Images are stored inside the Buffer, Decoded Images as PNG and BCn ones as DDS with MSFT_texture_dds and an PNG Fallback
+pub struct GltfExporter {}
+
+/// Collects the Binary Data and the JSON Objects which point into it
+#[derive(Default)]
+struct Writer {
+    buffer: Vec<u8>,
+    buffer_views: Vec<Value>,
+    accessors: Vec<Value>,
+    images: Vec<Value>,
+    /// Encoded Image Bytes to Image Index
+    image_indices: HashMap<Vec<u8>, usize>,
+    samplers: Vec<Value>,
+    textures: Vec<Value>,
+    extensions_used: Vec<&'static str>,
+}
+
+impl GltfExporter {
+    pub fn export(model: &ModelData, path: &Path) -> Result<(), AssetError> {
+        match path.extension().and_then(OsStr::to_str) {
+            Some(extension) if extension.eq_ignore_ascii_case("glb") => {
+                let (root, buffer) = Self::write(model, None)?;
+                std::fs::write(path, Self::write_glb(&root, &buffer)?)?;
+            }
+            Some(extension) if extension.eq_ignore_ascii_case("gltf") => {
+                let bin_name = format!(
+                    "{}.bin",
+                    path.file_stem().and_then(OsStr::to_str).unwrap_or("model")
+                );
+                let (root, buffer) = Self::write(model, Some(&bin_name))?;
+                std::fs::write(path.with_file_name(&bin_name), buffer)?;
+                std::fs::write(path, to_json(&root, true)?)?;
+            }
+            _ => {
+                return Err(AssetError::Unsupported(format!(
+                    "{} is neither .gltf nor .glb",
+                    path.display()
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the JSON Root and the Buffer, `buffer_uri` is `None` for GLB where the Buffer is the BIN Chunk
+    fn write(model: &ModelData, buffer_uri: Option<&str>) -> Result<(Value, Vec<u8>), AssetError> {
+        let mut writer = Writer::default();
+
+        let materials = model
+            .materials
+            .iter()
+            .map(|material| writer.material(material))
+            .collect::<Result<Vec<_>, _>>()?;
+        let meshes: Vec<Value> = model
+            .meshes
+            .iter()
+            .map(|mesh| {
+                let mut value = json!({
+                    "primitives": mesh
+                        .primitives
+                        .iter()
+                        .map(|primitive| writer.primitive(primitive))
+                        .collect::<Vec<_>>(),
+                });
+                insert_name(&mut value, &mesh.name);
+                if !mesh.weights.is_empty() {
+                    value["weights"] = json!(mesh.weights);
+                }
+                value
+            })
+            .collect();
+
+        let (nodes, scene_roots) = Self::write_nodes(model);
+        let skins: Vec<Value> = model
+            .skins
+            .iter()
+            .map(|skin| {
+                let mut value = json!({ "joints": skin.joints });
+                insert_name(&mut value, &skin.name);
+                if !skin.inverse_bind_matrices.is_empty() {
+                    let matrices: Vec<[f32; 16]> = skin
+                        .inverse_bind_matrices
+                        .iter()
+                        .map(flatten_matrix)
+                        .collect();
+                    value["inverseBindMatrices"] =
+                        json!(writer.accessor_f32(&matrices, "MAT4", None, false));
+                }
+                value
+            })
+            .collect();
+        let animations: Vec<Value> = model
+            .animations
+            .iter()
+            .map(|animation| {
+                let mut samplers = Vec::with_capacity(animation.channels.len());
+                let mut channels = Vec::with_capacity(animation.channels.len());
+                for channel in &animation.channels {
+                    let times: Vec<[f32; 1]> = channel.times.iter().map(|&time| [time]).collect();
+                    let input = writer.accessor_f32(&times, "SCALAR", None, true);
+                    let (path, output) = match &channel.values {
+                        ChannelValues::Translation(values) | ChannelValues::Scale(values) => {
+                            let values: Vec<[f32; 3]> =
+                                values.iter().map(|&value| value.into()).collect();
+                            let path = match channel.values {
+                                ChannelValues::Translation(_) => "translation",
+                                _ => "scale",
+                            };
+                            (path, writer.accessor_f32(&values, "VEC3", None, false))
+                        }
+                        ChannelValues::Rotation(values) => {
+                            let values: Vec<[f32; 4]> =
+                                values.iter().map(|value| value.to_array()).collect();
+                            (
+                                "rotation",
+                                writer.accessor_f32(&values, "VEC4", None, false),
+                            )
+                        }
+                        ChannelValues::MorphWeights(values) => {
+                            let values: Vec<[f32; 1]> =
+                                values.iter().map(|&value| [value]).collect();
+                            (
+                                "weights",
+                                writer.accessor_f32(&values, "SCALAR", None, false),
+                            )
+                        }
+                    };
+                    channels.push(json!({
+                        "sampler": samplers.len(),
+                        "target": { "node": channel.node, "path": path },
+                    }));
+                    samplers.push(json!({
+                        "input": input,
+                        "output": output,
+                        "interpolation": match channel.interpolation {
+                            Interpolation::Step => "STEP",
+                            Interpolation::Linear => "LINEAR",
+                            Interpolation::CubicSpline => "CUBICSPLINE",
+                        },
+                    }));
+                }
+                let mut value = json!({ "channels": channels, "samplers": samplers });
+                insert_name(&mut value, &animation.name);
+                value
+            })
+            .collect();
+
+        let cameras: Vec<Value> = model
+            .cameras
+            .iter()
+            .map(|camera| {
+                let mut value = match camera.projection {
+                    Projection::Perspective {
+                        yfov,
+                        aspect_ratio,
+                        znear,
+                        zfar,
+                    } => {
+                        let mut perspective = json!({ "yfov": yfov, "znear": znear });
+                        if let Some(aspect_ratio) = aspect_ratio {
+                            perspective["aspectRatio"] = json!(aspect_ratio);
+                        }
+                        if let Some(zfar) = zfar {
+                            perspective["zfar"] = json!(zfar);
+                        }
+                        json!({ "type": "perspective", "perspective": perspective })
+                    }
+                    Projection::Orthographic {
+                        xmag,
+                        ymag,
+                        znear,
+                        zfar,
+                    } => json!({
+                        "type": "orthographic",
+                        "orthographic": { "xmag": xmag, "ymag": ymag, "znear": znear, "zfar": zfar },
+                    }),
+                };
+                insert_name(&mut value, &camera.name);
+                value
+            })
+            .collect();
+
+        let mut root = json!({
+            "asset": { "version": "2.0", "generator": "Vent-Engine" },
+            "scene": 0,
+            "scenes": [{ "nodes": scene_roots }],
+            "nodes": nodes,
+        });
+        let mut insert_array = |key: &str, values: Vec<Value>| {
+            if !values.is_empty() {
+                root[key] = Value::Array(values);
+            }
+        };
+        insert_array("meshes", meshes);
+        insert_array("materials", materials);
+        insert_array("skins", skins);
+        insert_array("animations", animations);
+        insert_array("cameras", cameras);
+        insert_array("textures", writer.textures);
+        insert_array("images", writer.images);
+        insert_array("samplers", writer.samplers);
+        insert_array("accessors", writer.accessors);
+        insert_array("bufferViews", writer.buffer_views);
+
+        if !model.lights.is_empty() {
+            let lights: Vec<Value> = model
+                .lights
+                .iter()
+                .map(|light| {
+                    let mut value = json!({
+                        "type": match light.kind {
+                            LightKind::Directional => "directional",
+                            LightKind::Point => "point",
+                            LightKind::Spot { .. } => "spot",
+                        },
+                        "color": light.color,
+                        "intensity": light.intensity,
+                    });
+                    insert_name(&mut value, &light.name);
+                    if let Some(range) = light.range {
+                        value["range"] = json!(range);
+                    }
+                    if let LightKind::Spot {
+                        inner_cone_angle,
+                        outer_cone_angle,
+                    } = light.kind
+                    {
+                        value["spot"] = json!({
+                            "innerConeAngle": inner_cone_angle,
+                            "outerConeAngle": outer_cone_angle,
+                        });
+                    }
+                    value
+                })
+                .collect();
+            root["extensions"] = json!({ "KHR_lights_punctual": { "lights": lights } });
+            writer.extensions_used.push("KHR_lights_punctual");
+        }
+
+        let buffer = writer.buffer;
+        if !buffer.is_empty() {
+            let mut value = json!({ "byteLength": buffer.len() });
+            if let Some(uri) = buffer_uri {
+                value["uri"] = json!(uri);
+            }
+            root["buffers"] = json!([value]);
+        }
+        if !writer.extensions_used.is_empty() {
+            let mut used = writer.extensions_used;
+            used.sort_unstable();
+            used.dedup();
+            root["extensionsUsed"] = json!(used);
+        }
+        Ok((root, buffer))
+    }
+
+    /// Builds the Children Lists out of the Parents, Models without Nodes (e.g. from OBJ) get one Node per Mesh
+    fn write_nodes(model: &ModelData) -> (Vec<Value>, Vec<usize>) {
+        if model.nodes.is_empty() {
+            let nodes = (0..model.meshes.len())
+                .map(|mesh| json!({ "mesh": mesh }))
+                .collect();
+            return (nodes, (0..model.meshes.len()).collect());
+        }
+
+        let mut children = vec![Vec::new(); model.nodes.len()];
+        for (index, node) in model.nodes.iter().enumerate() {
+            if let Some(parent) = node.parent {
+                children[parent].push(index);
+            }
+        }
+        let nodes = model
+            .nodes
+            .iter()
+            .zip(children)
+            .map(|(node, children)| {
+                let mut value = json!({
+                    "translation": node.translation,
+                    "rotation": node.rotation,
+                    "scale": node.scale,
+                });
+                insert_name(&mut value, &node.name);
+                if !children.is_empty() {
+                    value["children"] = json!(children);
+                }
+                for (key, index) in [
+                    ("mesh", node.mesh),
+                    ("skin", node.skin),
+                    ("camera", node.camera),
+                ] {
+                    if let Some(index) = index {
+                        value[key] = json!(index);
+                    }
+                }
+                if let Some(light) = node.light {
+                    value["extensions"] = json!({ "KHR_lights_punctual": { "light": light } });
+                }
+                value
+            })
+            .collect();
+        let scene_roots = if model.scene_roots.is_empty() {
+            (0..model.nodes.len())
+                .filter(|&node| model.nodes[node].parent.is_none())
+                .collect()
+        } else {
+            model.scene_roots.clone()
+        };
+        (nodes, scene_roots)
+    }
+
+    fn write_glb(root: &Value, buffer: &[u8]) -> Result<Vec<u8>, AssetError> {
+        const JSON_CHUNK: u32 = 0x4E4F534A;
+        const BIN_CHUNK: u32 = 0x004E4942;
+
+        // Chunks must be 4 Byte aligned, JSON is padded with Spaces and the Buffer with Zeros
+        let mut json = to_json(root, false)?;
+        json.resize(json.len().next_multiple_of(4), b' ');
+        let mut bin = buffer.to_vec();
+        bin.resize(bin.len().next_multiple_of(4), 0);
+
+        let bin_size = if bin.is_empty() { 0 } else { 8 + bin.len() };
+        let length = 12 + 8 + json.len() + bin_size;
+        let mut glb = Vec::with_capacity(length);
+        glb.extend_from_slice(b"glTF");
+        glb.extend_from_slice(&2u32.to_le_bytes());
+        glb.extend_from_slice(&(length as u32).to_le_bytes());
+        glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        glb.extend_from_slice(&JSON_CHUNK.to_le_bytes());
+        glb.extend_from_slice(&json);
+        if !bin.is_empty() {
+            glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+            glb.extend_from_slice(&BIN_CHUNK.to_le_bytes());
+            glb.extend_from_slice(&bin);
+        }
+        Ok(glb)
+    }
+}
+
+impl Writer {
+    /// Appends `bytes` as an Buffer View, Views start 4 Byte aligned so every Component Type can be read
+    fn buffer_view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+        self.buffer.resize(self.buffer.len().next_multiple_of(4), 0);
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": self.buffer.len(),
+            "byteLength": bytes.len(),
+        });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        self.buffer.extend_from_slice(bytes);
+        self.buffer_views.push(view);
+        self.buffer_views.len() - 1
+    }
+
+    fn accessor(
+        &mut self,
+        bytes: &[u8],
+        count: usize,
+        component_type: u32,
+        ty: &str,
+        target: Option<u32>,
+    ) -> usize {
+        let view = self.buffer_view(bytes, target);
+        self.accessors.push(json!({
+            "bufferView": view,
+            "componentType": component_type,
+            "count": count,
+            "type": ty,
+        }));
+        self.accessors.len() - 1
+    }
+
+    /// `bounds` writes min and max, glTF requires them for Positions and Animation Times
+    fn accessor_f32<const N: usize>(
+        &mut self,
+        data: &[[f32; N]],
+        ty: &str,
+        target: Option<u32>,
+        bounds: bool,
+    ) -> usize {
+        let bytes: Vec<u8> = data
+            .iter()
+            .flatten()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let index = self.accessor(&bytes, data.len(), FLOAT, ty, target);
+        if bounds && !data.is_empty() {
+            let mut min = [f32::MAX; N];
+            let mut max = [f32::MIN; N];
+            for value in data {
+                for i in 0..N {
+                    min[i] = min[i].min(value[i]);
+                    max[i] = max[i].max(value[i]);
+                }
+            }
+            self.accessors[index]["min"] = json!(min.as_slice());
+            self.accessors[index]["max"] = json!(max.as_slice());
+        }
+        index
+    }
+
+    fn primitive(&mut self, primitive: &PrimitiveData) -> Value {
+        let mut attributes = json!({
+            "POSITION": self.accessor_f32(&primitive.positions, "VEC3", Some(ARRAY_BUFFER), true),
+        });
+        if let Some(normals) = &primitive.normals {
+            attributes["NORMAL"] =
+                json!(self.accessor_f32(normals, "VEC3", Some(ARRAY_BUFFER), false));
+        }
+        if let Some(tangents) = &primitive.tangents {
+            attributes["TANGENT"] =
+                json!(self.accessor_f32(tangents, "VEC4", Some(ARRAY_BUFFER), false));
+        }
+        for (key, tex_coords) in [
+            ("TEXCOORD_0", &primitive.tex_coords),
+            ("TEXCOORD_1", &primitive.tex_coords1),
+        ] {
+            if let Some(tex_coords) = tex_coords {
+                attributes[key] =
+                    json!(self.accessor_f32(tex_coords, "VEC2", Some(ARRAY_BUFFER), false));
+            }
+        }
+        if let Some(joints) = &primitive.joints {
+            let bytes: Vec<u8> = joints
+                .iter()
+                .flatten()
+                .flat_map(|j| j.to_le_bytes())
+                .collect();
+            attributes["JOINTS_0"] = json!(self.accessor(
+                &bytes,
+                joints.len(),
+                UNSIGNED_SHORT,
+                "VEC4",
+                Some(ARRAY_BUFFER)
+            ));
+        }
+        if let Some(weights) = &primitive.weights {
+            attributes["WEIGHTS_0"] =
+                json!(self.accessor_f32(weights, "VEC4", Some(ARRAY_BUFFER), false));
+        }
+
+        // Small Meshes get 16 Bit Indices
+        let indices = if primitive
+            .indices
+            .iter()
+            .all(|&index| index < u16::MAX as u32)
+        {
+            let bytes: Vec<u8> = primitive
+                .indices
+                .iter()
+                .flat_map(|&index| (index as u16).to_le_bytes())
+                .collect();
+            self.accessor(
+                &bytes,
+                primitive.indices.len(),
+                UNSIGNED_SHORT,
+                "SCALAR",
+                Some(ELEMENT_ARRAY_BUFFER),
+            )
+        } else {
+            let bytes: Vec<u8> = primitive
+                .indices
+                .iter()
+                .flat_map(|index| index.to_le_bytes())
+                .collect();
+            self.accessor(
+                &bytes,
+                primitive.indices.len(),
+                UNSIGNED_INT,
+                "SCALAR",
+                Some(ELEMENT_ARRAY_BUFFER),
+            )
+        };
+
+        let mut value = json!({
+            "attributes": attributes,
+            "indices": indices,
+            "mode": match primitive.mode {
+                PrimitiveMode::Points => 0,
+                PrimitiveMode::Lines => 1,
+                PrimitiveMode::LineLoop => 2,
+                PrimitiveMode::LineStrip => 3,
+                PrimitiveMode::Triangles => 4,
+                PrimitiveMode::TriangleStrip => 5,
+                PrimitiveMode::TriangleFan => 6,
+            },
+        });
+        if let Some(material) = primitive.material_index {
+            value["material"] = json!(material);
+        }
+        if !primitive.morph_targets.is_empty() {
+            let targets: Vec<Value> = primitive
+                .morph_targets
+                .iter()
+                .map(|target| {
+                    let mut value = json!({});
+                    if let Some(positions) = &target.positions {
+                        value["POSITION"] =
+                            json!(self.accessor_f32(positions, "VEC3", Some(ARRAY_BUFFER), true));
+                    }
+                    if let Some(normals) = &target.normals {
+                        value["NORMAL"] =
+                            json!(self.accessor_f32(normals, "VEC3", Some(ARRAY_BUFFER), false));
+                    }
+                    if let Some(tangents) = &target.tangents {
+                        value["TANGENT"] =
+                            json!(self.accessor_f32(tangents, "VEC3", Some(ARRAY_BUFFER), false));
+                    }
+                    value
+                })
+                .collect();
+            value["targets"] = json!(targets);
+        }
+        value
+    }
+
+    fn material(&mut self, material: &MaterialData) -> Result<Value, AssetError> {
+        let mut pbr = json!({
+            "baseColorFactor": material.base_color,
+            "metallicFactor": material.metallic_factor,
+            "roughnessFactor": material.roughness_factor,
+        });
+        if let Some(texture) = &material.base_color_texture {
+            pbr["baseColorTexture"] = self.texture_info(texture)?;
+        }
+        if let Some(texture) = &material.metallic_roughness_texture {
+            pbr["metallicRoughnessTexture"] = self.texture_info(texture)?;
+        }
+
+        // glTF Emissive Factors are limited to 1, Brighter ones need KHR_materials_emissive_strength
+        let strength = material.emissive_factor.into_iter().fold(1.0, f32::max);
+        let mut value = json!({
+            "pbrMetallicRoughness": pbr,
+            "emissiveFactor": material.emissive_factor.map(|factor| factor / strength),
+            "alphaMode": match material.alpha_mode {
+                AlphaMode::Opaque => "OPAQUE",
+                AlphaMode::Mask => "MASK",
+                AlphaMode::Blend => "BLEND",
+            },
+            "doubleSided": material.double_sided,
+        });
+        insert_name(&mut value, &material.name);
+        if material.alpha_mode == AlphaMode::Mask {
+            value["alphaCutoff"] = json!(material.alpha_cutoff);
+        }
+        if strength > 1.0 {
+            value["extensions"] =
+                json!({ "KHR_materials_emissive_strength": { "emissiveStrength": strength } });
+            self.extensions_used.push("KHR_materials_emissive_strength");
+        }
+        if let Some(texture) = &material.normal_texture {
+            value["normalTexture"] = self.texture_info(texture)?;
+            value["normalTexture"]["scale"] = json!(material.normal_scale);
+        }
+        if let Some(texture) = &material.occlusion_texture {
+            value["occlusionTexture"] = self.texture_info(texture)?;
+            value["occlusionTexture"]["strength"] = json!(material.occlusion_strength);
+        }
+        if let Some(texture) = &material.emissive_texture {
+            value["emissiveTexture"] = self.texture_info(texture)?;
+        }
+        Ok(value)
+    }
+
+    fn texture_info(&mut self, texture: &TextureData) -> Result<Value, AssetError> {
+        let sampler = self.sampler(&texture.sampler);
+        let texture_value = match &texture.image {
+            TextureImage::Decoded(image) => {
+                let source = self.image(encode_png(image)?, "image/png");
+                json!({ "sampler": sampler, "source": source })
+            }
+            // glTF needs an PNG Fallback in `source`, BCn can be decoded, Other Formats get the Missing Texture.
+            // KHR_texture_basisu is only for Basis Universal Data, Which we transcode when Loading
+            TextureImage::Compressed(image) => {
+                let fallback = bcn::decode(image)
+                    .map(DynamicImage::ImageRgba8)
+                    .unwrap_or_else(data::missing_texture_image);
+                let source = self.image(encode_png(&fallback)?, "image/png");
+                match dds::write(image) {
+                    Ok(bytes) => {
+                        let compressed = self.image(bytes, "image/vnd-ms.dds");
+                        self.extensions_used.push("MSFT_texture_dds");
+                        json!({
+                            "sampler": sampler,
+                            "source": source,
+                            "extensions": { "MSFT_texture_dds": { "source": compressed } },
+                        })
+                    }
+                    Err(AssetError::Unsupported(err)) => {
+                        log::warn!("{}, Only the PNG Fallback is exported", err);
+                        json!({ "sampler": sampler, "source": source })
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+        };
+        let index = match self
+            .textures
+            .iter()
+            .position(|texture| *texture == texture_value)
+        {
+            Some(index) => index,
+            None => {
+                self.textures.push(texture_value);
+                self.textures.len() - 1
+            }
+        };
+
+        let mut info = json!({ "index": index, "texCoord": texture.tex_coord });
+        if texture.transform != TextureTransform::default() {
+            info["extensions"] = json!({
+                "KHR_texture_transform": {
+                    "offset": texture.transform.offset,
+                    "rotation": texture.transform.rotation,
+                    "scale": texture.transform.scale,
+                }
+            });
+            self.extensions_used.push("KHR_texture_transform");
+        }
+        Ok(info)
+    }
+
+    /// Stores the encoded Image once, Importers clone shared Images so we merge them again
+    fn image(&mut self, bytes: Vec<u8>, mime_type: &str) -> usize {
+        if let Some(&image) = self.image_indices.get(&bytes) {
+            return image;
+        }
+        let view = self.buffer_view(&bytes, None);
+        self.images
+            .push(json!({ "bufferView": view, "mimeType": mime_type }));
+        self.image_indices.insert(bytes, self.images.len() - 1);
+        self.images.len() - 1
+    }
+
+    /// Converts Vulkan Sampler Info back into an glTF Sampler, Equal Samplers are shared
+    fn sampler(&mut self, sampler: &vk::SamplerCreateInfo) -> usize {
+        let filter = |filter: vk::Filter| match filter {
+            vk::Filter::NEAREST => 9728,
+            _ => 9729,
+        };
+        let min_filter = match (sampler.min_filter, sampler.mipmap_mode) {
+            (vk::Filter::NEAREST, vk::SamplerMipmapMode::NEAREST) => 9984,
+            (vk::Filter::NEAREST, _) => 9986,
+            (_, vk::SamplerMipmapMode::NEAREST) => 9985,
+            _ => 9987,
+        };
+        let wrap = |mode: vk::SamplerAddressMode| match mode {
+            vk::SamplerAddressMode::CLAMP_TO_EDGE => 33071,
+            vk::SamplerAddressMode::MIRRORED_REPEAT => 33648,
+            _ => 10497,
+        };
+        let value = json!({
+            "magFilter": filter(sampler.mag_filter),
+            "minFilter": min_filter,
+            "wrapS": wrap(sampler.address_mode_u),
+            "wrapT": wrap(sampler.address_mode_v),
+        });
+        match self.samplers.iter().position(|sampler| *sampler == value) {
+            Some(index) => index,
+            None => {
+                self.samplers.push(value);
+                self.samplers.len() - 1
+            }
+        }
+    }
+}
+
+fn insert_name(value: &mut Value, name: &Option<String>) {
+    if let Some(name) = name {
+        value["name"] = json!(name);
+    }
+}
+
+/// Column major, As glTF stores Matrices
+fn flatten_matrix(matrix: &[[f32; 4]; 4]) -> [f32; 16] {
+    std::array::from_fn(|i| matrix[i / 4][i % 4])
+}
+
+/// PNG can not store Floats, So HDR Images are written with 16 Bits per Channel
+fn encode_png(image: &DynamicImage) -> Result<Vec<u8>, AssetError> {
+    let mut bytes = Vec::new();
+    let converted;
+    let image = match image {
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_) => {
+            converted = DynamicImage::ImageRgba16(image.to_rgba16());
+            &converted
+        }
+        image => image,
+    };
+    image.write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)?;
+    Ok(bytes)
+}
+
+fn to_json(root: &Value, pretty: bool) -> Result<Vec<u8>, AssetError> {
+    let json = if pretty {
+        serde_json::to_vec_pretty(root)
+    } else {
+        serde_json::to_vec(root)
+    };
+    json.map_err(|err| AssetError::Parse(err.to_string()))
+}
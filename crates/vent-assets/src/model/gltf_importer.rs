@@ -0,0 +1,596 @@
+use std::path::Path;
+
+use ash::vk;
+use gltf::{
+    mesh::Mode,
+    texture::{MagFilter, MinFilter, WrappingMode},
+};
+use vent_rendering::DEFAULT_TEXTURE_FILTER;
+
+use vent_math::{scalar::quat::Quat, vec::vec3::Vec3};
+
+use crate::{
+    animation::{AnimationClip, Channel, ChannelValues, Interpolation},
+    texture, AssetError,
+};
+
+use super::data::{
+    self, AlphaMode, CameraData, LightData, LightKind, MaterialData, MeshData, ModelData,
+    MorphTargetData, NodeData, PrimitiveData, PrimitiveMode, Projection, SkinData, TextureData,
+    TextureImage, TextureTransform,
+};
+
+/// Imports glTF (.gltf, .glb) Files directly, So we get everything the Format offers
+pub(crate) struct GltfImporter {}
+
+impl GltfImporter {
+    pub fn import(path: &Path) -> Result<ModelData, AssetError> {
+        let gltf::Gltf { document, blob } = gltf::Gltf::open(path)?;
+        let base = path.parent();
+        let buffers = gltf::import_buffers(&document, base, blob)?;
+        // Images are loaded one by one, A missing or broken Image should not fail the whole Model
+        let images: Vec<Option<TextureImage>> = document
+            .images()
+            .map(|image| Self::import_image(image, base, &buffers))
+            .collect();
+
+        let materials = document
+            .materials()
+            .map(|material| Self::import_material(material, &images))
+            .collect();
+
+        let meshes = document
+            .meshes()
+            .map(|mesh| {
+                Ok(MeshData {
+                    name: mesh.name().map(str::to_owned),
+                    primitives: mesh
+                        .primitives()
+                        .filter_map(|primitive| {
+                            Self::import_primitive(&mesh, primitive, &buffers).transpose()
+                        })
+                        .collect::<Result<_, AssetError>>()?,
+                    weights: mesh.weights().map_or_else(Vec::new, <[f32]>::to_vec),
+                })
+            })
+            .collect::<Result<_, AssetError>>()?;
+
+        let nodes = Self::import_nodes(&document);
+        let scene_roots = Self::import_scene_roots(&document, &nodes);
+
+        let cameras = document.cameras().map(Self::import_camera).collect();
+        let lights = document
+            .lights()
+            .map_or_else(Vec::new, |lights| lights.map(Self::import_light).collect());
+
+        let skins = document
+            .skins()
+            .map(|skin| {
+                let reader = skin.reader(|buffer| Some(&buffers[buffer.index()]));
+                SkinData {
+                    name: skin.name().map(str::to_owned),
+                    joints: skin.joints().map(|joint| joint.index()).collect(),
+                    inverse_bind_matrices: reader
+                        .read_inverse_bind_matrices()
+                        .map_or_else(Vec::new, Iterator::collect),
+                }
+            })
+            .collect();
+
+        let animations = document
+            .animations()
+            .map(|animation| {
+                AnimationClip::new(
+                    animation.name().map(str::to_owned),
+                    animation
+                        .channels()
+                        .filter_map(|channel| Self::import_channel(channel, &buffers))
+                        .collect(),
+                )
+            })
+            .collect();
+
+        Ok(ModelData {
+            meshes,
+            materials,
+            nodes,
+            skins,
+            animations,
+            cameras,
+            lights,
+            scene_roots,
+        })
+    }
+
+    /// Uses the Default Scene or the first one, Files without Scenes show every Node
+    fn import_scene_roots(document: &gltf::Document, nodes: &[NodeData]) -> Vec<usize> {
+        match document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+        {
+            Some(scene) => scene.nodes().map(|node| node.index()).collect(),
+            None => (0..nodes.len())
+                .filter(|&node| nodes[node].parent.is_none())
+                .collect(),
+        }
+    }
+
+    fn import_camera(camera: gltf::Camera) -> CameraData {
+        let projection = match camera.projection() {
+            gltf::camera::Projection::Perspective(perspective) => Projection::Perspective {
+                yfov: perspective.yfov(),
+                aspect_ratio: perspective.aspect_ratio(),
+                znear: perspective.znear(),
+                zfar: perspective.zfar(),
+            },
+            gltf::camera::Projection::Orthographic(orthographic) => Projection::Orthographic {
+                xmag: orthographic.xmag(),
+                ymag: orthographic.ymag(),
+                znear: orthographic.znear(),
+                zfar: orthographic.zfar(),
+            },
+        };
+        CameraData {
+            name: camera.name().map(str::to_owned),
+            projection,
+        }
+    }
+
+    fn import_light(light: gltf::khr_lights_punctual::Light) -> LightData {
+        let kind = match light.kind() {
+            gltf::khr_lights_punctual::Kind::Directional => LightKind::Directional,
+            gltf::khr_lights_punctual::Kind::Point => LightKind::Point,
+            gltf::khr_lights_punctual::Kind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            } => LightKind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            },
+        };
+        LightData {
+            name: light.name().map(str::to_owned),
+            kind,
+            color: light.color(),
+            intensity: light.intensity(),
+            range: light.range(),
+        }
+    }
+
+    fn import_nodes(document: &gltf::Document) -> Vec<NodeData> {
+        let mut parents = vec![None; document.nodes().len()];
+        for node in document.nodes() {
+            for child in node.children() {
+                parents[child.index()] = Some(node.index());
+            }
+        }
+        document
+            .nodes()
+            .map(|node| {
+                let (translation, rotation, scale) = node.transform().decomposed();
+                NodeData {
+                    name: node.name().map(str::to_owned),
+                    parent: parents[node.index()],
+                    translation,
+                    rotation,
+                    scale,
+                    mesh: node.mesh().map(|mesh| mesh.index()),
+                    skin: node.skin().map(|skin| skin.index()),
+                    camera: node.camera().map(|camera| camera.index()),
+                    light: node.light().map(|light| light.index()),
+                }
+            })
+            .collect()
+    }
+
+    fn import_channel(
+        channel: gltf::animation::Channel,
+        buffers: &[gltf::buffer::Data],
+    ) -> Option<Channel> {
+        use gltf::animation::util::ReadOutputs;
+
+        let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
+        let (Some(times), Some(outputs)) = (reader.read_inputs(), reader.read_outputs()) else {
+            log::warn!("Skipping Animation Channel without Keyframes");
+            return None;
+        };
+        let values = match outputs {
+            ReadOutputs::Translations(values) => {
+                ChannelValues::Translation(values.map(Vec3::from).collect())
+            }
+            ReadOutputs::Rotations(values) => {
+                ChannelValues::Rotation(values.into_f32().map(Quat::from_array).collect())
+            }
+            ReadOutputs::Scales(values) => ChannelValues::Scale(values.map(Vec3::from).collect()),
+            ReadOutputs::MorphTargetWeights(values) => {
+                ChannelValues::MorphWeights(values.into_f32().collect())
+            }
+        };
+        let interpolation = match channel.sampler().interpolation() {
+            gltf::animation::Interpolation::Step => Interpolation::Step,
+            gltf::animation::Interpolation::Linear => Interpolation::Linear,
+            gltf::animation::Interpolation::CubicSpline => Interpolation::CubicSpline,
+        };
+        let times: Vec<f32> = times.collect();
+
+        // Cubic Splines store an In- and Out-Tangent for every Keyframe
+        let keyframe_size = if interpolation == Interpolation::CubicSpline {
+            3
+        } else {
+            1
+        };
+        let keyframe_count = times.len() * keyframe_size;
+        let valid = keyframe_count > 0
+            && match &values {
+                ChannelValues::Translation(values) | ChannelValues::Scale(values) => {
+                    values.len() == keyframe_count
+                }
+                ChannelValues::Rotation(values) => values.len() == keyframe_count,
+                // Every Keyframe has one Weight per Morph Target
+                ChannelValues::MorphWeights(values) => values.len() % keyframe_count == 0,
+            };
+        if !valid {
+            log::warn!(
+                "Skipping Animation Channel of Node {}, Keyframes do not match the Values",
+                channel.target().node().index()
+            );
+            return None;
+        }
+
+        Some(Channel {
+            node: channel.target().node().index(),
+            interpolation,
+            times,
+            values,
+        })
+    }
+
+    /// Returns `None` for Primitives without Positions, They are skipped. Primitives with broken Accessors fail the Import
+    fn import_primitive(
+        mesh: &gltf::Mesh,
+        primitive: gltf::Primitive,
+        buffers: &[gltf::buffer::Data],
+    ) -> Result<Option<PrimitiveData>, AssetError> {
+        let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+
+        let Some(positions) = reader.read_positions() else {
+            log::warn!("Skipping Primitive without Positions");
+            return Ok(None);
+        };
+        let positions: Vec<[f32; 3]> = positions.collect();
+
+        let indices = reader.read_indices().map_or_else(
+            || PrimitiveData::generate_indices(positions.len()),
+            |indices| indices.into_u32().collect(),
+        );
+
+        let data = PrimitiveData {
+            normals: reader.read_normals().map(Iterator::collect),
+            tex_coords: reader
+                .read_tex_coords(0)
+                .map(|tex_coords| tex_coords.into_f32().collect()),
+            tex_coords1: reader
+                .read_tex_coords(1)
+                .map(|tex_coords| tex_coords.into_f32().collect()),
+            tangents: reader.read_tangents().map(Iterator::collect),
+            joints: reader
+                .read_joints(0)
+                .map(|joints| joints.into_u16().collect()),
+            weights: reader
+                .read_weights(0)
+                .map(|weights| weights.into_f32().collect()),
+            morph_targets: reader
+                .read_morph_targets()
+                .map(|(positions, normals, tangents)| MorphTargetData {
+                    positions: positions.map(Iterator::collect),
+                    normals: normals.map(Iterator::collect),
+                    tangents: tangents.map(Iterator::collect),
+                })
+                .collect(),
+            positions,
+            indices,
+            mode: Self::convert_mode(primitive.mode()),
+            material_index: primitive.material().index(),
+            flat_normals: true,
+        };
+        data.validate().map_err(|err| {
+            AssetError::Parse(format!(
+                "Mesh {} ({:?}) Primitive {}: {}",
+                mesh.index(),
+                mesh.name(),
+                primitive.index(),
+                err
+            ))
+        })?;
+        Ok(Some(data))
+    }
+
+    fn import_material(material: gltf::Material, images: &[Option<TextureImage>]) -> MaterialData {
+        let pbr = material.pbr_metallic_roughness();
+        let normal_texture = material.normal_texture();
+        let occlusion_texture = material.occlusion_texture();
+        let emissive_strength = material.emissive_strength().unwrap_or(1.0);
+        MaterialData {
+            name: material.name().map(str::to_owned),
+            base_color_texture: pbr
+                .base_color_texture()
+                .map(|info| Self::import_texture_info(&info, images)),
+            metallic_roughness_texture: pbr
+                .metallic_roughness_texture()
+                .map(|info| Self::import_texture_info(&info, images)),
+            normal_texture: normal_texture.as_ref().map(|normal| {
+                Self::import_texture(
+                    normal.texture(),
+                    normal.tex_coord(),
+                    normal.extension_value("KHR_texture_transform"),
+                    images,
+                )
+            }),
+            occlusion_texture: occlusion_texture.as_ref().map(|occlusion| {
+                Self::import_texture(
+                    occlusion.texture(),
+                    occlusion.tex_coord(),
+                    occlusion.extension_value("KHR_texture_transform"),
+                    images,
+                )
+            }),
+            emissive_texture: material
+                .emissive_texture()
+                .map(|info| Self::import_texture_info(&info, images)),
+            base_color: pbr.base_color_factor(),
+            metallic_factor: pbr.metallic_factor(),
+            roughness_factor: pbr.roughness_factor(),
+            normal_scale: normal_texture.as_ref().map_or(1.0, |normal| normal.scale()),
+            occlusion_strength: occlusion_texture
+                .as_ref()
+                .map_or(1.0, |occlusion| occlusion.strength()),
+            emissive_factor: material
+                .emissive_factor()
+                .map(|factor| factor * emissive_strength),
+            alpha_mode: match material.alpha_mode() {
+                gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+                gltf::material::AlphaMode::Mask => AlphaMode::Mask,
+                gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+            },
+            alpha_cutoff: material.alpha_cutoff().unwrap_or(0.5),
+            double_sided: material.double_sided(),
+        }
+    }
+
+    fn import_texture_info(
+        info: &gltf::texture::Info,
+        images: &[Option<TextureImage>],
+    ) -> TextureData {
+        let transform = info.texture_transform();
+        TextureData {
+            image: Self::get_image(&info.texture(), images),
+            sampler: Self::convert_sampler(info.texture().sampler()),
+            tex_coord: transform
+                .as_ref()
+                .and_then(|transform| transform.tex_coord())
+                .unwrap_or(info.tex_coord()),
+            transform: transform.map_or_else(TextureTransform::default, |transform| {
+                TextureTransform {
+                    offset: transform.offset(),
+                    rotation: transform.rotation(),
+                    scale: transform.scale(),
+                }
+            }),
+        }
+    }
+
+    /// Normal and Occlusion Textures do not expose KHR_texture_transform, So we have to parse the raw Extension
+    fn import_texture(
+        texture: gltf::Texture,
+        tex_coord: u32,
+        transform: Option<&gltf::json::Value>,
+        images: &[Option<TextureImage>],
+    ) -> TextureData {
+        let image = Self::get_image(&texture, images);
+        let default = TextureTransform::default();
+        let vec2 = |key: &str, default: [f32; 2]| {
+            transform
+                .and_then(|transform| transform.get(key))
+                .and_then(|value| value.as_array())
+                .and_then(|value| {
+                    Some([
+                        value.first()?.as_f64()? as f32,
+                        value.get(1)?.as_f64()? as f32,
+                    ])
+                })
+                .unwrap_or(default)
+        };
+        TextureData {
+            image,
+            sampler: Self::convert_sampler(texture.sampler()),
+            tex_coord: transform
+                .and_then(|transform| transform.get("texCoord"))
+                .and_then(|value| value.as_u64())
+                .map_or(tex_coord, |value| value as u32),
+            transform: TextureTransform {
+                offset: vec2("offset", default.offset),
+                rotation: transform
+                    .and_then(|transform| transform.get("rotation"))
+                    .and_then(|value| value.as_f64())
+                    .map_or(default.rotation, |value| value as f32),
+                scale: vec2("scale", default.scale),
+            },
+        }
+    }
+
+    /// Images which failed to Load are replaced, So the Material still knows it should have an Texture there
+    fn get_image(texture: &gltf::Texture, images: &[Option<TextureImage>]) -> TextureImage {
+        images
+            .get(Self::image_index(texture))
+            .and_then(Clone::clone)
+            .unwrap_or_else(|| TextureImage::Decoded(data::missing_texture_image()))
+    }
+
+    /// KHR_texture_basisu and MSFT_texture_dds point to an compressed Image, `source` is only the PNG/JPEG Fallback
+    fn image_index(texture: &gltf::Texture) -> usize {
+        ["KHR_texture_basisu", "MSFT_texture_dds"]
+            .into_iter()
+            .find_map(|extension| texture.extension_value(extension)?.get("source")?.as_u64())
+            .map_or(texture.source().index(), |index| index as usize)
+    }
+
+    fn import_image(
+        image: gltf::Image,
+        base: Option<&Path>,
+        buffers: &[gltf::buffer::Data],
+    ) -> Option<TextureImage> {
+        let compressed = match image.source() {
+            gltf::image::Source::View { view, mime_type } if is_compressed_mime(mime_type) => {
+                let buffer = buffers.get(view.buffer().index())?;
+                let bytes = buffer.get(view.offset()..view.offset() + view.length());
+                Some(bytes.map(texture::load_compressed).unwrap_or_else(|| {
+                    Err(AssetError::Parse("Buffer View is out of Bounds".to_owned()))
+                }))
+            }
+            gltf::image::Source::Uri { uri, mime_type }
+                if mime_type.is_some_and(is_compressed_mime)
+                    || [".ktx2", ".dds"].iter().any(|ext| uri.ends_with(ext)) =>
+            {
+                let path = base.unwrap_or_else(|| Path::new("./")).join(uri);
+                Some(texture::load_compressed_file(&path))
+            }
+            _ => None,
+        };
+        if let Some(compressed) = compressed {
+            return compressed
+                .map_err(|err| log::warn!("Failed to Load Image {}: {}", image.index(), err))
+                .ok()
+                .map(TextureImage::Compressed);
+        }
+
+        gltf::image::Data::from_source(image.source(), base, buffers)
+            .map_err(|err| {
+                log::warn!("Failed to Load Image {}: {}", image.index(), err);
+            })
+            .ok()
+            .and_then(|data| Self::convert_image(&data))
+            .map(TextureImage::Decoded)
+    }
+
+    fn convert_image(data: &gltf::image::Data) -> Option<image::DynamicImage> {
+        use gltf::image::Format;
+        use image::{DynamicImage, ImageBuffer};
+
+        let (width, height) = (data.width, data.height);
+        let pixels = data.pixels.clone();
+        let wide = || -> Vec<u16> {
+            data.pixels
+                .chunks_exact(2)
+                .map(|b| u16::from_le_bytes([b[0], b[1]]))
+                .collect()
+        };
+        let float = || -> Vec<f32> {
+            data.pixels
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect()
+        };
+        let image = match data.format {
+            Format::R8 => {
+                ImageBuffer::from_raw(width, height, pixels).map(DynamicImage::ImageLuma8)
+            }
+            Format::R8G8 => {
+                ImageBuffer::from_raw(width, height, pixels).map(DynamicImage::ImageLumaA8)
+            }
+            Format::R8G8B8 => {
+                ImageBuffer::from_raw(width, height, pixels).map(DynamicImage::ImageRgb8)
+            }
+            Format::R8G8B8A8 => {
+                ImageBuffer::from_raw(width, height, pixels).map(DynamicImage::ImageRgba8)
+            }
+            Format::R16 => {
+                ImageBuffer::from_raw(width, height, wide()).map(DynamicImage::ImageLuma16)
+            }
+            Format::R16G16 => {
+                ImageBuffer::from_raw(width, height, wide()).map(DynamicImage::ImageLumaA16)
+            }
+            Format::R16G16B16 => {
+                ImageBuffer::from_raw(width, height, wide()).map(DynamicImage::ImageRgb16)
+            }
+            Format::R16G16B16A16 => {
+                ImageBuffer::from_raw(width, height, wide()).map(DynamicImage::ImageRgba16)
+            }
+            Format::R32G32B32FLOAT => {
+                ImageBuffer::from_raw(width, height, float()).map(DynamicImage::ImageRgb32F)
+            }
+            Format::R32G32B32A32FLOAT => {
+                ImageBuffer::from_raw(width, height, float()).map(DynamicImage::ImageRgba32F)
+            }
+        };
+        if image.is_none() {
+            log::warn!("Invalid Image Data {}x{} {:?}", width, height, data.format);
+        }
+        image
+    }
+
+    /// Converts an gltf Texture Sampler into Vulkan Sampler Info
+    fn convert_sampler(sampler: gltf::texture::Sampler) -> vk::SamplerCreateInfo<'static> {
+        let mag_filter =
+            sampler
+                .mag_filter()
+                .map_or(DEFAULT_TEXTURE_FILTER, |filter| match filter {
+                    MagFilter::Nearest => vk::Filter::NEAREST,
+                    MagFilter::Linear => vk::Filter::LINEAR,
+                });
+
+        let (min_filter, mipmap_filter) = sampler.min_filter().map_or(
+            (DEFAULT_TEXTURE_FILTER, vk::SamplerMipmapMode::LINEAR),
+            |filter| match filter {
+                MinFilter::Nearest => (vk::Filter::NEAREST, vk::SamplerMipmapMode::NEAREST),
+                MinFilter::Linear => (vk::Filter::LINEAR, vk::SamplerMipmapMode::NEAREST),
+                MinFilter::NearestMipmapNearest => {
+                    (vk::Filter::NEAREST, vk::SamplerMipmapMode::NEAREST)
+                }
+                MinFilter::LinearMipmapNearest => {
+                    (vk::Filter::LINEAR, vk::SamplerMipmapMode::NEAREST)
+                }
+                MinFilter::NearestMipmapLinear => {
+                    (vk::Filter::NEAREST, vk::SamplerMipmapMode::LINEAR)
+                }
+                MinFilter::LinearMipmapLinear => {
+                    (vk::Filter::LINEAR, vk::SamplerMipmapMode::LINEAR)
+                }
+            },
+        );
+
+        vk::SamplerCreateInfo {
+            mag_filter,
+            min_filter,
+            mipmap_mode: mipmap_filter,
+            address_mode_u: Self::convert_wrapping_mode(sampler.wrap_s()),
+            address_mode_v: Self::convert_wrapping_mode(sampler.wrap_t()),
+            ..Default::default()
+        }
+    }
+
+    #[must_use]
+    const fn convert_wrapping_mode(mode: WrappingMode) -> vk::SamplerAddressMode {
+        match mode {
+            WrappingMode::ClampToEdge => vk::SamplerAddressMode::CLAMP_TO_EDGE,
+            WrappingMode::MirroredRepeat => vk::SamplerAddressMode::MIRRORED_REPEAT,
+            WrappingMode::Repeat => vk::SamplerAddressMode::REPEAT,
+        }
+    }
+
+    #[must_use]
+    const fn convert_mode(mode: Mode) -> PrimitiveMode {
+        match mode {
+            Mode::Points => PrimitiveMode::Points,
+            Mode::Lines => PrimitiveMode::Lines,
+            Mode::LineLoop => PrimitiveMode::LineLoop,
+            Mode::LineStrip => PrimitiveMode::LineStrip,
+            Mode::Triangles => PrimitiveMode::Triangles,
+            Mode::TriangleStrip => PrimitiveMode::TriangleStrip,
+            Mode::TriangleFan => PrimitiveMode::TriangleFan,
+        }
+    }
+}
+
+fn is_compressed_mime(mime_type: &str) -> bool {
+    matches!(mime_type, "image/ktx2" | "image/vnd-ms.dds")
+}
@@ -1,15 +1,31 @@
-use std::{collections::HashMap, ffi::CStr, fs::File, path::Path};
+use std::{collections::HashMap, fs::File, mem::size_of_val, path::Path};
 
-use ash::{
-    util::read_spv,
-    vk::{self, PipelineShaderStageCreateInfo},
-};
+use ash::{util::read_spv, vk};
 use vent_rendering::{
-    image::VulkanImage, instance::VulkanInstance, mesh::Mesh3D, vertex::Vertex3D,
-    MaterialPipelineInfo, DEFAULT_TEXTURE_FILTER,
+    buffer::VulkanBuffer,
+    image::VulkanImage,
+    instance::VulkanInstance,
+    mesh::{Mesh3D, MorphTargets},
+    pipeline::PipelineBuilder,
+    vertex::{MorphDelta, Vertex3D},
+    Indices, MaterialPipelineInfo,
+};
+
+use crate::{
+    animation::{MorphWeights, Skeleton},
+    texture, AssetError, Material, MaterialTexture, Model3D, ModelMaterial, ModelMesh,
+    ModelPipeline,
 };
 
-use crate::{Material, Model3D, ModelPipeline};
+use super::{
+    data::{
+        self, MaterialData, MeshData, ModelData, PrimitiveData, PrimitiveMode, TextureData,
+        TextureImage, TextureTransform,
+    },
+    geometry,
+    lod::{self, LodSettings},
+    scene::Scene,
+};
 
 pub(crate) struct ModelLoader {}
 
@@ -19,364 +35,573 @@ impl ModelLoader {
         vertex_shader: &Path,
         fragment_shader: &Path,
         pipeline_layout: vk::PipelineLayout,
-        model: modelz::Model3D,
-    ) -> crate::Model3D {
-        // let mut matrix = None;
-
+        mut model: ModelData,
+    ) -> Result<crate::Model3D, AssetError> {
         // Do not load for every node, So we load it here
-        let vertex_code =
-            read_spv(&mut File::open(vertex_shader).expect("Failed to open Vertex File")).unwrap();
-        let vertex_module_info = vk::ShaderModuleCreateInfo::default().code(&vertex_code);
-        let fragment_code =
-            read_spv(&mut File::open(fragment_shader).expect("Failed to open Fragment File"))
-                .unwrap();
-        let fragment_module_info = vk::ShaderModuleCreateInfo::default().code(&fragment_code);
-
-        let vertex_module = unsafe {
-            instance
-                .device
-                .create_shader_module(&vertex_module_info, None)
-        }
-        .unwrap();
-        let fragment_module = unsafe {
-            instance
-                .device
-                .create_shader_module(&fragment_module_info, None)
+        let vertex_module = Self::create_shader_module(&instance.device, vertex_shader)?;
+        let fragment_module = Self::create_shader_module(&instance.device, fragment_shader)
+            .inspect_err(|_| unsafe {
+                instance.device.destroy_shader_module(vertex_module, None)
+            })?;
+
+        let binding = [Vertex3D::binding_description()];
+        let attrib = Vertex3D::input_descriptions();
+        // Models are drawn directly into the Swapchain, Every Material only changes Topology and Culling
+        let pipeline_builder =
+            PipelineBuilder::new(pipeline_layout, instance.surface_attachment_formats())
+                .shader(vk::ShaderStageFlags::VERTEX, vertex_module)
+                .shader(vk::ShaderStageFlags::FRAGMENT, fragment_module)
+                .vertex_input(&binding, &attrib)
+                .depth(vk::CompareOp::LESS, true);
+
+        // Importers registered by Games may not check their Output, Everything below relies on valid Primitives
+        for primitive in model.meshes.iter().flat_map(|mesh| &mesh.primitives) {
+            primitive.validate()?;
         }
-        .unwrap();
-
-        let shader_entry_name = unsafe { CStr::from_bytes_with_nul_unchecked(b"main\0") };
-        let shader_stage_create_info = [
-            vk::PipelineShaderStageCreateInfo {
-                module: vertex_module,
-                p_name: shader_entry_name.as_ptr(),
-                stage: vk::ShaderStageFlags::VERTEX,
-                ..Default::default()
-            },
-            vk::PipelineShaderStageCreateInfo {
-                module: fragment_module,
-                p_name: shader_entry_name.as_ptr(),
-                stage: vk::ShaderStageFlags::FRAGMENT,
-                ..Default::default()
-            },
-        ];
 
-        let mut materials = Vec::new();
-        for mat in model.materials {
-            materials.push(Self::load_material(instance, mat));
+        // Primitives without an Material get an default one
+        let default_material = model.materials.len();
+        if model
+            .meshes
+            .iter()
+            .flat_map(|mesh| &mesh.primitives)
+            .any(|primitive| primitive.material_index.is_none())
+        {
+            model.materials.push(MaterialData::default());
         }
 
-        let mut pipelines = Vec::new();
-        Self::load_meshes(
-            instance,
-            model.meshes,
-            &shader_stage_create_info,
-            pipeline_layout,
-            &materials,
-            &mut pipelines,
+        Self::offset_joints(&mut model);
+        let scene = Scene::new(
+            &model.nodes,
+            model.scene_roots,
+            model.cameras,
+            model.lights,
+            model.meshes.len(),
         );
+        let skeleton = Skeleton::new(model.nodes, model.skins);
+
+        // Every Morph Target needs an Weight, Even when the File has no Default Weights
+        for mesh in &mut model.meshes {
+            let target_count = mesh
+                .primitives
+                .iter()
+                .map(|primitive| primitive.morph_targets.len())
+                .max()
+                .unwrap_or_default();
+            if mesh.weights.len() < target_count {
+                mesh.weights.resize(target_count, 0.0);
+            }
+        }
+        let morph_weights = MorphWeights::new(model.meshes.iter().map(|mesh| mesh.weights.clone()));
+
+        // Everything we created so far is destroyed again when something fails
+        let mut loaded = Model3D {
+            materials: Vec::with_capacity(model.materials.len()),
+            pipelines: Vec::new(),
+            scene,
+            skeleton,
+            animations: model.animations,
+            morph_weights,
+            morph_targets: None,
+            position: [0.0, 0.0, 0.0],
+            rotation: [0.0, 0.0, 0.0, 1.0],
+            scale: [1.0, 1.0, 1.0],
+        };
+
+        let result = model
+            .materials
+            .into_iter()
+            .try_for_each(|mat| {
+                loaded.materials.push(Self::load_material(instance, mat)?);
+                Ok(())
+            })
+            .and_then(|()| {
+                Self::load_meshes(
+                    instance,
+                    model.meshes,
+                    &pipeline_builder,
+                    &loaded.materials,
+                    default_material,
+                    &mut loaded.pipelines,
+                )
+            });
 
         unsafe {
             instance.device.destroy_shader_module(vertex_module, None);
             instance.device.destroy_shader_module(fragment_module, None);
         }
 
-        // let matrix = matrix.unwrap_or_default();
-
-        let descriptor_pool = Self::create_descriptor_pool(
-            materials.len() as u32,
-            instance.swapchain_images.len() as u32,
-            &instance.device,
-        );
-
-        Model3D {
-            descriptor_pool,
-            materials,
-            pipelines,
-            position: [0.0, 0.0, 0.0], // TODO: matrix.0
-            rotation: [0.0, 0.0, 0.0, 1.0],
-            scale: [1.0, 1.0, 1.0],
+        let morph_deltas = match result {
+            Ok(morph_deltas) => morph_deltas,
+            Err(err) => {
+                loaded.destroy(&instance.device);
+                return Err(err);
+            }
+        };
+        if !morph_deltas.is_empty() {
+            match VulkanBuffer::cpu_to_gpu(
+                instance,
+                &morph_deltas,
+                size_of_val(morph_deltas.as_slice()) as vk::DeviceSize,
+                vk::BufferUsageFlags::STORAGE_BUFFER,
+                Some("Morph Targets"),
+            ) {
+                Ok(buffer) => loaded.morph_targets = Some(buffer),
+                Err(err) => {
+                    loaded.destroy(&instance.device);
+                    return Err(err.into());
+                }
+            }
         }
+        Ok(loaded)
     }
 
-    fn create_descriptor_pool(
-        material_count: u32,
-        swapchain_count: u32,
-        device: &ash::Device,
-    ) -> vk::DescriptorPool {
-        let pool_sizes = [
-            vk::DescriptorPoolSize {
-                ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-                descriptor_count: material_count * swapchain_count,
-            },
-            // Material UBO
-            vk::DescriptorPoolSize {
-                ty: vk::DescriptorType::UNIFORM_BUFFER,
-                descriptor_count: material_count * swapchain_count,
-            },
-        ];
+    /// All Skins share one Joint Buffer, So the Joint Indices of skinned Meshes are moved to where their Skin starts
+    fn offset_joints(model: &mut ModelData) {
+        let mut skin_offsets = Vec::with_capacity(model.skins.len());
+        let mut joint_count = 0;
+        for skin in &model.skins {
+            skin_offsets.push(joint_count);
+            joint_count += skin.joints.len();
+        }
+        if joint_count > u16::MAX as usize {
+            log::warn!(
+                "Model has {} Joints, Only {} are supported",
+                joint_count,
+                u16::MAX
+            );
+        }
 
-        let create_info = vk::DescriptorPoolCreateInfo::default()
-            .pool_sizes(&pool_sizes)
-            .max_sets(material_count * swapchain_count);
+        let mut mesh_offsets: Vec<Option<usize>> = vec![None; model.meshes.len()];
+        for node in &model.nodes {
+            let (Some(mesh), Some(skin)) = (node.mesh, node.skin) else {
+                continue;
+            };
+            let (Some(mesh_offset), Some(&skin_offset)) =
+                (mesh_offsets.get_mut(mesh), skin_offsets.get(skin))
+            else {
+                continue;
+            };
+            match mesh_offset {
+                Some(offset) if *offset != skin_offset => {
+                    log::warn!(
+                        "Mesh {} is used by multiple Skins, Only the first one is used",
+                        mesh
+                    )
+                }
+                _ => *mesh_offset = Some(skin_offset),
+            }
+        }
 
-        log::debug!(
-            "Creating Description Pool, Size {}",
-            material_count * swapchain_count
-        );
+        for (mesh, offset) in model.meshes.iter_mut().zip(mesh_offsets) {
+            let offset = offset.unwrap_or_default() as u16;
+            for primitive in &mut mesh.primitives {
+                if let Some(joints) = &mut primitive.joints {
+                    joints
+                        .iter_mut()
+                        .flatten()
+                        .for_each(|joint| *joint = joint.saturating_add(offset));
+                }
+            }
+        }
+    }
 
-        unsafe { device.create_descriptor_pool(&create_info, None) }.unwrap()
+    fn create_shader_module(
+        device: &ash::Device,
+        path: &Path,
+    ) -> Result<vk::ShaderModule, AssetError> {
+        let code = read_spv(&mut File::open(path)?)?;
+        let module_info = vk::ShaderModuleCreateInfo::default().code(&code);
+        Ok(unsafe { device.create_shader_module(&module_info, None) }?)
     }
 
     fn load_meshes(
         instance: &mut VulkanInstance,
-        meshes: Vec<modelz::Mesh>,
-        shader_stage_create_info: &[PipelineShaderStageCreateInfo],
-        pipeline_layout: vk::PipelineLayout,
+        meshes: Vec<MeshData>,
+        pipeline_builder: &PipelineBuilder,
         loaded_materials: &[Material],
+        default_material: usize,
         pipelines: &mut Vec<ModelPipeline>,
-    ) {
-        let surface_resolution = instance.surface_resolution;
-
-        let binding = [Vertex3D::binding_description()];
-        let attrib = Vertex3D::input_descriptions();
-        let vertex_input_state_info = vk::PipelineVertexInputStateCreateInfo::default()
-            .vertex_attribute_descriptions(&attrib)
-            .vertex_binding_descriptions(&binding);
-
-        let viewports = [vk::Viewport {
-            x: 0.0,
-            y: 0.0,
-            width: surface_resolution.width as f32,
-            height: surface_resolution.height as f32,
-            min_depth: 0.0,
-            max_depth: 1.0,
-        }];
-        let scissors = [surface_resolution.into()];
-        let viewport_state_info = vk::PipelineViewportStateCreateInfo::default()
-            .scissors(&scissors)
-            .viewports(&viewports);
-
+    ) -> Result<Vec<MorphDelta>, AssetError> {
         let mut cached_pipeline: HashMap<MaterialPipelineInfo, usize> = HashMap::new(); // We just need to store the pipelines vec index
 
-        for mesh in meshes {
+        let mut morph_deltas = Vec::new();
+        let mut next_weight = 0;
+        for (mesh_index, mesh) in meshes.into_iter().enumerate() {
             log::debug!("      Loading Mesh {:?}", &mesh.name);
-
-            let mut all_meshes = vec![];
-
-            let material_index = mesh.material_index.unwrap(); // TODO
-            let material = &loaded_materials[material_index];
-            {
-                let loaded_mesh = Mesh3D::new(
+            let first_weight = next_weight;
+            next_weight += mesh.weights.len();
+
+            for primitive in mesh.primitives {
+                let material_index = primitive
+                    .material_index
+                    .filter(|index| *index < loaded_materials.len())
+                    .unwrap_or(default_material);
+                let material = &loaded_materials[material_index];
+                let (mut loaded_mesh, topology) = Self::load_primitive(
                     instance,
-                    &Self::convert_vertices(&mesh.vertices),
-                    Self::convert_indices(mesh.indices.unwrap()),
+                    primitive,
+                    material.has_normal_texture,
                     mesh.name.as_deref(),
-                );
-                all_meshes.push(loaded_mesh);
-            }
-            let pipeline_info = MaterialPipelineInfo {
-                mode: vk::PrimitiveTopology::TRIANGLE_LIST, // TODO
-                alpha_cut: Some(ordered_float::OrderedFloat(material.alpha_cut)),
-                double_sided: material.double_sided,
-            };
-
-            let model_material = crate::ModelMaterial {
-                material_index,
-                meshes: all_meshes,
-            };
-
-            if let Some(pipeline_index) = cached_pipeline.get(&pipeline_info) {
-                pipelines[*pipeline_index].materials.push(model_material);
-            } else {
-                let vertex_input_assembly_state_info = vk::PipelineInputAssemblyStateCreateInfo {
-                    topology: pipeline_info.mode,
-                    ..Default::default()
-                };
-                let rasterization_info = vk::PipelineRasterizationStateCreateInfo {
-                    front_face: vk::FrontFace::COUNTER_CLOCKWISE,
-                    line_width: 1.0,
-                    polygon_mode: vk::PolygonMode::FILL,
-                    cull_mode: if pipeline_info.double_sided {
-                        vk::CullModeFlags::NONE
-                    } else {
-                        vk::CullModeFlags::BACK
-                    },
-                    ..Default::default()
+                    &mut morph_deltas,
+                    first_weight,
+                )?;
+
+                let pipeline_info = MaterialPipelineInfo {
+                    mode: topology,
+                    alpha_cut: Some(ordered_float::OrderedFloat(material.alpha_cut)),
+                    double_sided: material.double_sided,
                 };
 
-                {
-                    let multisample_state_info = vk::PipelineMultisampleStateCreateInfo {
-                        rasterization_samples: vk::SampleCountFlags::TYPE_1,
-                        ..Default::default()
-                    };
-
-                    let depth_state_info = vk::PipelineDepthStencilStateCreateInfo::default()
-                        .depth_test_enable(true)
-                        .depth_write_enable(true)
-                        .depth_compare_op(vk::CompareOp::LESS)
-                        .max_depth_bounds(1.0);
-                    let color_blend_attachment_states = [vk::PipelineColorBlendAttachmentState {
-                        color_write_mask: vk::ColorComponentFlags::RGBA,
-                        ..Default::default()
-                    }];
-                    let color_blend_state = vk::PipelineColorBlendStateCreateInfo::default()
-                        .logic_op(vk::LogicOp::COPY)
-                        .attachments(&color_blend_attachment_states);
-
-                    let dynamic_state = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR]; // TODO
-                    let dynamic_state_info = vk::PipelineDynamicStateCreateInfo::default()
-                        .dynamic_states(&dynamic_state);
-
-                    let graphic_pipeline_info = vk::GraphicsPipelineCreateInfo::default()
-                        .stages(shader_stage_create_info)
-                        .vertex_input_state(&vertex_input_state_info)
-                        .input_assembly_state(&vertex_input_assembly_state_info)
-                        .viewport_state(&viewport_state_info)
-                        .rasterization_state(&rasterization_info)
-                        .multisample_state(&multisample_state_info)
-                        .depth_stencil_state(&depth_state_info)
-                        .color_blend_state(&color_blend_state)
-                        .dynamic_state(&dynamic_state_info)
-                        .layout(pipeline_layout)
-                        .render_pass(instance.render_pass);
-
-                    let graphics_pipelines = unsafe {
-                        instance.device.create_graphics_pipelines(
-                            vk::PipelineCache::null(),
-                            &[graphic_pipeline_info],
-                            None,
-                        )
+                let pipeline_index = match cached_pipeline.get(&pipeline_info) {
+                    Some(pipeline_index) => *pipeline_index,
+                    None => {
+                        let pipeline = pipeline_builder
+                            .clone()
+                            .topology(pipeline_info.mode)
+                            .cull_mode(if pipeline_info.double_sided {
+                                vk::CullModeFlags::NONE
+                            } else {
+                                vk::CullModeFlags::BACK
+                            })
+                            .build(instance)
+                            .inspect_err(|_| loaded_mesh.destroy(&instance.device))?;
+                        cached_pipeline.insert(pipeline_info, pipelines.len());
+                        pipelines.push(ModelPipeline {
+                            pipeline,
+                            materials: Vec::new(),
+                        });
+                        pipelines.len() - 1
                     }
-                    .expect("Unable to create graphics pipeline");
-
-                    cached_pipeline.insert(pipeline_info, pipelines.len());
+                };
 
-                    pipelines.push(ModelPipeline {
-                        pipeline: graphics_pipelines[0],
-                        materials: vec![model_material], // TODO
-                    });
+                let loaded_mesh = ModelMesh {
+                    mesh: loaded_mesh,
+                    mesh_index,
+                };
+                // Group all Meshes with the same Material, So we only have to bind it once
+                let materials = &mut pipelines[pipeline_index].materials;
+                match materials
+                    .iter_mut()
+                    .find(|model_material| model_material.material_index == material_index)
+                {
+                    Some(model_material) => model_material.meshes.push(loaded_mesh),
+                    None => materials.push(ModelMaterial {
+                        material_index,
+                        meshes: vec![loaded_mesh],
+                    }),
                 }
             }
         }
+        Ok(morph_deltas)
     }
 
     /**
-     *  Creates an VulkanImage from Material Data, We want to do this Single threaded
+     *  Creates the VulkanImages from Material Data, We want to do this Single threaded
      *  RAM -> VRAM
      */
-    fn load_material(instance: &mut VulkanInstance, data: modelz::Material) -> Material {
-        let diffuse_texture = if let Some(diffuse_texture) = data.diffuse_texture {
-            VulkanImage::from_image(
-                instance,
-                diffuse_texture.image,
-                true,
-                Some(Self::convert_sampler(diffuse_texture.sampler)),
-                data.name.as_deref(), // TODO: use texture name not material name
-            )
-        } else {
-            VulkanImage::from_color(
-                instance,
-                [255, 255, 255, 255],
-                vk::Extent2D {
-                    width: 128,
-                    height: 128,
-                },
-                data.name.as_deref(), // TODO: use texture name not material name
-            )
+    fn load_material(
+        instance: &mut VulkanInstance,
+        data: MaterialData,
+    ) -> Result<Material, AssetError> {
+        let name = data.name.as_deref();
+        let has_normal_texture = data.normal_texture.is_some();
+        // Only Colors are stored in sRGB, Everything else is linear Data.
+        // Metallic & Roughness are multiplied with their Factors, The Normal is flat in Tangent Space
+        let textures = [
+            (data.base_color_texture, true, [255, 255, 255, 255]),
+            (data.metallic_roughness_texture, false, [255, 255, 255, 255]),
+            (data.normal_texture, false, [128, 128, 255, 255]),
+            (data.occlusion_texture, false, [255, 255, 255, 255]),
+            (data.emissive_texture, true, [255, 255, 255, 255]),
+        ];
+        let mut loaded: Vec<MaterialTexture> = Vec::with_capacity(textures.len());
+        for (texture, srgb, fallback_color) in textures {
+            match Self::load_texture(instance, texture, srgb, fallback_color, name) {
+                Ok(texture) => loaded.push(texture),
+                Err(err) => {
+                    for texture in &mut loaded {
+                        texture.image.destroy(&instance.device);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        let Ok(
+            [base_color_texture, metallic_roughness_texture, normal_texture, occlusion_texture, emissive_texture],
+        ) = <[MaterialTexture; 5]>::try_from(loaded)
+        else {
+            unreachable!("Every Material has 5 Textures")
         };
-
-        Material {
-            diffuse_texture,
+        Ok(Material {
+            base_color_texture,
+            metallic_roughness_texture,
+            normal_texture,
+            occlusion_texture,
+            emissive_texture,
             descriptor_set: None,
+            bindless_index: None,
+            base_color: data.base_color,
+            metallic_factor: data.metallic_factor,
+            roughness_factor: data.roughness_factor,
+            normal_scale: data.normal_scale,
+            occlusion_strength: data.occlusion_strength,
+            emissive_factor: data.emissive_factor,
             alpha_mode: data.alpha_mode,
-            alpha_cut: data.alpha_cutoff.unwrap_or(0.5),
+            alpha_cut: data.alpha_cutoff,
             double_sided: data.double_sided,
-            base_color: data.base_color.unwrap_or([1.0, 1.0, 1.0, 1.0]),
-        }
+            has_normal_texture,
+        })
     }
 
-    /// Converts an gltf Texture Sampler into Vulkan Sampler Info
-    fn convert_sampler(sampler: modelz::Sampler) -> vk::SamplerCreateInfo<'static> {
-        let mag_filter = sampler
-            .mag_filter
-            .map_or(DEFAULT_TEXTURE_FILTER, |filter| match filter {
-                modelz::MagFilter::Nearest => vk::Filter::NEAREST,
-                modelz::MagFilter::Linear => vk::Filter::LINEAR,
-            });
-
-        let (min_filter, mipmap_filter) = sampler.min_filter.map_or(
-            (DEFAULT_TEXTURE_FILTER, vk::SamplerMipmapMode::LINEAR),
-            |filter| match filter {
-                modelz::MinFilter::Nearest => (vk::Filter::NEAREST, vk::SamplerMipmapMode::NEAREST),
-                modelz::MinFilter::Linear => (vk::Filter::LINEAR, vk::SamplerMipmapMode::NEAREST),
-                modelz::MinFilter::NearestMipmapNearest => {
-                    (vk::Filter::NEAREST, vk::SamplerMipmapMode::NEAREST)
-                }
-                modelz::MinFilter::LinearMipmapNearest => {
-                    (vk::Filter::LINEAR, vk::SamplerMipmapMode::NEAREST)
-                }
-                modelz::MinFilter::NearestMipmapLinear => {
-                    (vk::Filter::LINEAR, vk::SamplerMipmapMode::LINEAR)
-                }
-                modelz::MinFilter::LinearMipmapLinear => {
-                    (vk::Filter::LINEAR, vk::SamplerMipmapMode::LINEAR)
+    /// Uploads an Material Texture, When the Material has none we use an 1x1 Image with the given Color.
+    /// `srgb` Textures are decoded to linear when sampled
+    fn load_texture(
+        instance: &mut VulkanInstance,
+        texture: Option<TextureData>,
+        srgb: bool,
+        fallback_color: [u8; 4],
+        name: Option<&str>, // TODO: use texture name not material name
+    ) -> Result<MaterialTexture, AssetError> {
+        match texture {
+            Some(mut texture) => {
+                if let Err(err) = Self::check_image_size(instance, &texture.image) {
+                    log::warn!("Replacing Texture of Material {:?}: {}", name, err);
+                    texture.image = TextureImage::Decoded(data::missing_texture_image());
                 }
-            },
-        );
-
-        let address_mode_u = Self::convert_wrapping_mode(&sampler.wrap_s);
-        let address_mode_v = Self::convert_wrapping_mode(&sampler.wrap_t);
+                let format = if srgb {
+                    vk::Format::R8G8B8A8_SRGB
+                } else {
+                    vk::Format::R8G8B8A8_UNORM
+                };
+                let image = match texture.image {
+                    TextureImage::Decoded(image) => VulkanImage::from_image(
+                        instance,
+                        image,
+                        format,
+                        true,
+                        Some(texture.sampler),
+                        name,
+                    )?,
+                    TextureImage::Compressed(image) => {
+                        // Only broken Files are replaced, The Device running out of Memory is an real Error
+                        match texture::upload(instance, image, srgb, Some(texture.sampler), name) {
+                            Err(err) if !matches!(err, AssetError::GpuUpload(_)) => {
+                                log::warn!("Replacing Texture of Material {:?}: {}", name, err);
+                                VulkanImage::from_image(
+                                    instance,
+                                    data::missing_texture_image(),
+                                    format,
+                                    true,
+                                    Some(texture.sampler),
+                                    name,
+                                )?
+                            }
+                            result => result?,
+                        }
+                    }
+                };
+                Ok(MaterialTexture {
+                    image,
+                    tex_coord: texture.tex_coord,
+                    transform: texture.transform,
+                })
+            }
+            None => Ok(MaterialTexture {
+                image: VulkanImage::from_color(
+                    instance,
+                    fallback_color,
+                    vk::Extent2D {
+                        width: 1,
+                        height: 1,
+                    },
+                    name,
+                )?,
+                tex_coord: 0,
+                transform: TextureTransform::default(),
+            }),
+        }
+    }
 
-        vk::SamplerCreateInfo {
-            mag_filter,
-            min_filter,
-            mipmap_mode: mipmap_filter,
-            address_mode_u,
-            address_mode_v,
-            ..Default::default()
+    /// The Device can not create Images which are empty or bigger than its Limits
+    fn check_image_size(instance: &VulkanInstance, image: &TextureImage) -> Result<(), AssetError> {
+        let max_size = unsafe {
+            instance
+                .instance
+                .get_physical_device_properties(instance.physical_device)
+        }
+        .limits
+        .max_image_dimension2_d;
+        let (width, height) = (image.width(), image.height());
+        if width == 0 || height == 0 || width > max_size || height > max_size {
+            return Err(AssetError::Unsupported(format!(
+                "Image size {}x{} (Max {})",
+                width, height, max_size
+            )));
         }
+        Ok(())
     }
 
-    #[must_use]
-    const fn convert_wrapping_mode(mode: &modelz::WrappingMode) -> vk::SamplerAddressMode {
-        match mode {
-            modelz::WrappingMode::ClampToEdge => vk::SamplerAddressMode::CLAMP_TO_EDGE,
-            modelz::WrappingMode::MirroredRepeat => vk::SamplerAddressMode::MIRRORED_REPEAT,
-            modelz::WrappingMode::Repeat => vk::SamplerAddressMode::REPEAT,
+    /// Uploads an Primitive, Returns the Mesh and the Topology it must be drawn with.
+    /// Morph Target Deltas are appended to `morph_deltas`, They are uploaded for all Meshes at once
+    fn load_primitive(
+        instance: &mut VulkanInstance,
+        mut primitive: PrimitiveData,
+        normal_mapped: bool,
+        name: Option<&str>,
+        morph_deltas: &mut Vec<MorphDelta>,
+        first_weight: usize,
+    ) -> Result<(Mesh3D, vk::PrimitiveTopology), AssetError> {
+        let topology = primitive.normalize_topology();
+        let triangles = primitive.mode == PrimitiveMode::Triangles;
+        let flat = triangles && primitive.normals.is_none() && primitive.flat_normals;
+        if flat {
+            primitive.unweld();
         }
+
+        let vertices = Self::convert_vertices(&primitive, triangles, normal_mapped);
+        let morph_targets = Self::convert_morph_targets(&primitive, morph_deltas, first_weight);
+        // Unwelded Vertices are all on Seams, The Simplifier could not collapse anything
+        let lods = if triangles && !flat {
+            lod::generate_lods(
+                &primitive.positions,
+                primitive.indices,
+                &LodSettings::default(),
+            )
+        } else {
+            vec![primitive.indices]
+        };
+        if lods.len() > 1 {
+            log::debug!(
+                "        Generated {} LODs, Indices {:?}",
+                lods.len(),
+                lods.iter().map(Vec::len).collect::<Vec<_>>()
+            );
+        }
+
+        let mut mesh = Mesh3D::new_with_lods(
+            instance,
+            &vertices,
+            &Self::convert_indices(lods, vertices.len()),
+            lod::bounding_sphere(&primitive.positions),
+            name,
+        )?;
+        mesh.morph_targets = morph_targets;
+        Ok((mesh, topology))
     }
 
-    fn convert_indices(indices: modelz::Indices) -> vent_rendering::Indices {
-        match indices {
-            modelz::Indices::U8(d) => vent_rendering::Indices::U8(d),
-            modelz::Indices::U16(d) => vent_rendering::Indices::U16(d),
-            modelz::Indices::U32(d) => vent_rendering::Indices::U32(d),
+    /// Interleaves the Deltas of all Targets per Vertex, So an Vertex reads one continuous Block
+    fn convert_morph_targets(
+        primitive: &PrimitiveData,
+        morph_deltas: &mut Vec<MorphDelta>,
+        first_weight: usize,
+    ) -> Option<MorphTargets> {
+        if primitive.morph_targets.is_empty() {
+            return None;
+        }
+        let first_delta = morph_deltas.len();
+        let delta = |attribute: &Option<Vec<[f32; 3]>>, vertex: usize| {
+            attribute
+                .as_ref()
+                .and_then(|deltas| deltas.get(vertex))
+                .map_or([0.0; 4], |&[x, y, z]| [x, y, z, 0.0])
+        };
+        for vertex in 0..primitive.positions.len() {
+            morph_deltas.extend(primitive.morph_targets.iter().map(|target| MorphDelta {
+                position: delta(&target.positions, vertex),
+                normal: delta(&target.normals, vertex),
+                tangent: delta(&target.tangents, vertex),
+            }));
         }
+        Some(MorphTargets {
+            first_delta: first_delta as u32,
+            target_count: primitive.morph_targets.len() as u32,
+            first_weight: first_weight as u32,
+        })
     }
 
-    fn convert_vertices(verticies: &[modelz::Vertex]) -> Vec<Vertex3D> {
-        verticies
-            .iter()
-            .map(|vertex| Vertex3D {
-                position: vertex.position,
-                tex_coord: vertex.tex_coord.unwrap_or_default(), // TODO
-                normal: vertex.normal.unwrap_or_default(),       // TODO
+    /// All LODs use the same Index Type, We only use U32 when U16 is not enough.
+    /// U8 is not used since we do not enable the uint8 Index extension yet
+    fn convert_indices(lods: Vec<Vec<u32>>, vertex_count: usize) -> Vec<Indices> {
+        let wide = vertex_count > u16::MAX as usize;
+        lods.into_iter()
+            .map(|lod| {
+                if wide {
+                    Indices::U32(lod)
+                } else {
+                    Indices::U16(lod.into_iter().map(|i| i as u16).collect())
+                }
             })
             .collect()
     }
 
-    // #[must_use]
-    // #[allow(dead_code)]
-    // const fn conv_primitive_mode(mode: Mode) -> vk::PrimitiveTopology {
-    //     match mode {
-    //         Mode::Points => vk::PrimitiveTopology::POINT_LIST,
-    //         Mode::Lines => vk::PrimitiveTopology::LINE_LIST,
-    //         Mode::LineLoop => vk::PrimitiveTopology::LINE_LIST,
-    //         Mode::LineStrip => vk::PrimitiveTopology::LINE_STRIP,
-    //         Mode::Triangles => vk::PrimitiveTopology::TRIANGLE_LIST,
-    //         Mode::TriangleStrip => vk::PrimitiveTopology::TRIANGLE_STRIP,
-    //         Mode::TriangleFan => vk::PrimitiveTopology::TRIANGLE_FAN,
-    //     }
-    // }
+    /// Converts the Vertices and generates the Attributes which are missing.
+    /// Normals and MikkTSpace Tangents can only be generated for Triangles, MikkTSpace Tangents only when the Material uses an Normal Map.
+    /// Every other Vertex gets an Tangent perpendicular to its Normal, So no Vertex is left with an zero Tangent
+    fn convert_vertices(
+        primitive: &PrimitiveData,
+        triangles: bool,
+        normal_mapped: bool,
+    ) -> Vec<Vertex3D> {
+        let mut vertices: Vec<Vertex3D> = primitive
+            .positions
+            .iter()
+            .enumerate()
+            .map(|(i, position)| Vertex3D {
+                position: *position,
+                tex_coord: primitive
+                    .tex_coords
+                    .as_ref()
+                    .map_or([0.0; 2], |tex_coords| tex_coords[i]),
+                normal: primitive
+                    .normals
+                    .as_ref()
+                    .map_or([0.0; 3], |normals| normals[i]),
+                tangent: primitive
+                    .tangents
+                    .as_ref()
+                    .map_or([0.0; 4], |tangents| tangents[i]),
+                joints: primitive.joints.as_ref().map_or([0; 4], |joints| joints[i]),
+                // Vertices without Weights are not skinned
+                weights: primitive
+                    .weights
+                    .as_ref()
+                    .filter(|_| primitive.joints.is_some())
+                    .map_or([0.0; 4], |weights| weights[i]),
+                tex_coord1: primitive
+                    .tex_coords1
+                    .as_ref()
+                    .or(primitive.tex_coords.as_ref())
+                    .map_or([0.0; 2], |tex_coords| tex_coords[i]),
+            })
+            .collect();
+
+        if primitive.normals.is_none() && triangles {
+            if primitive.flat_normals {
+                log::debug!("        Mesh has no Normals, Generating flat ones");
+                geometry::compute_flat_normals(&mut vertices, &primitive.indices);
+            } else {
+                log::debug!("        Mesh has no Normals, Generating smooth ones");
+                geometry::compute_smooth_normals(&mut vertices, &primitive.indices);
+            }
+        }
+        if primitive.tex_coords.is_none() {
+            log::debug!("        Mesh has no Texture Coordinates, Generating them");
+            geometry::generate_tex_coords(&mut vertices);
+            if primitive.tex_coords1.is_none() {
+                vertices
+                    .iter_mut()
+                    .for_each(|vertex| vertex.tex_coord1 = vertex.tex_coord);
+            }
+        }
+        if normal_mapped
+            && triangles
+            && primitive.tangents.is_none()
+            && !geometry::generate_tangents(&mut vertices, &primitive.indices)
+        {
+            log::warn!("Failed to generate Tangents, Normal Mapping will look wrong");
+        }
+        geometry::fill_missing_tangents(&mut vertices);
+        vertices
+    }
 }
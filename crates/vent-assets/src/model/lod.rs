@@ -0,0 +1,543 @@
+//! Level of Detail generation using Quadric Error Metrics (Garland & Heckbert).
+//!
+//! Edges are collapsed onto one of their existing Vertices, so every LOD can share the Vertex Buffer of the
+//! original Mesh and only needs its own Index Buffer.
+
+use std::collections::{BinaryHeap, HashMap};
+
+use ordered_float::OrderedFloat;
+use vent_rendering::mesh::BoundingSphere;
+
+/// Controls how LODs are generated at import time
+#[derive(Clone, Copy)]
+pub struct LodSettings {
+    /// Number of LODs, Including the full detail LOD 0
+    pub levels: usize,
+    /// Triangle count of each LOD relative to the previous one
+    pub reduction: f32,
+    /// Maximum allowed Error relative to the Mesh size, Simplification stops when reached
+    pub max_error: f32,
+    /// Meshes with fewer Indices are not simplified
+    pub min_index_count: usize,
+}
+
+impl Default for LodSettings {
+    fn default() -> Self {
+        Self {
+            levels: 4,
+            reduction: 0.5,
+            max_error: 0.05,
+            min_index_count: 3 * 64,
+        }
+    }
+}
+
+/// Generates the Index Buffers for all LODs of an Triangle List, The first one is always the original Index Buffer.
+/// Generation stops early when an LOD can not be reduced further
+pub fn generate_lods(
+    positions: &[[f32; 3]],
+    indices: Vec<u32>,
+    settings: &LodSettings,
+) -> Vec<Vec<u32>> {
+    let mut lods = vec![indices];
+    if lods[0].len() < settings.min_index_count {
+        return lods;
+    }
+
+    for level in 1..settings.levels {
+        let previous = &lods[level - 1];
+        let target = ((previous.len() / 3) as f32 * settings.reduction) as usize * 3;
+        let lod = simplify(positions, previous, target, settings.max_error);
+        // Stop when we did not get at least ~10% fewer Triangles, Everything else would just waste memory
+        if lod.is_empty() || lod.len() as f32 > previous.len() as f32 * 0.9 {
+            break;
+        }
+        lods.push(lod);
+    }
+    lods
+}
+
+/// Selects an LOD based on the projected Screen size of an Mesh.
+/// `screen_fraction` is the projected Radius of the Bounding Sphere relative to half of the Screen height.
+/// Every time the Mesh halves in size we go down one Level
+#[must_use]
+pub fn select_lod(screen_fraction: f32, lod_count: usize) -> usize {
+    if lod_count <= 1 || screen_fraction <= 0.0 {
+        return lod_count.saturating_sub(1);
+    }
+    let lod = (0.5 / screen_fraction).log2().floor().max(0.0) as usize;
+    lod.min(lod_count - 1)
+}
+
+/// Calculates an Bounding Sphere around the Center of the Axis aligned Bounding Box
+pub fn bounding_sphere(positions: &[[f32; 3]]) -> Option<BoundingSphere> {
+    let first = positions.first()?;
+    let mut min = *first;
+    let mut max = *first;
+    for position in positions {
+        for i in 0..3 {
+            min[i] = min[i].min(position[i]);
+            max[i] = max[i].max(position[i]);
+        }
+    }
+    let center = [
+        (min[0] + max[0]) * 0.5,
+        (min[1] + max[1]) * 0.5,
+        (min[2] + max[2]) * 0.5,
+    ];
+    let radius = positions
+        .iter()
+        .map(|p| length(sub(*p, center)))
+        .fold(0.0, f32::max);
+    Some(BoundingSphere { center, radius })
+}
+
+/// Simplifies an Triangle List until it has `target_index_count` Indices or the Error would exceed `max_error`.
+/// Returns the new Indices, Which still reference the original Vertices.
+///
+/// Vertices on open Borders and on Attribute Seams (e.g. UV Seams) are locked, So the Silhouette and Texturing stay intact
+pub fn simplify(
+    positions: &[[f32; 3]],
+    indices: &[u32],
+    target_index_count: usize,
+    max_error: f32,
+) -> Vec<u32> {
+    let mut simplifier = Simplifier::new(positions, indices);
+    let extent = simplifier.extent();
+    let max_cost = (max_error as f64 * extent) * (max_error as f64 * extent);
+    simplifier.run(target_index_count / 3, max_cost);
+    simplifier.indices()
+}
+
+type Quadric = [f64; 10];
+
+/// Triangles shrinking below this fraction of their previous area count as degenerated
+const DEGENERATE_AREA_RATIO: f32 = 1e-3;
+
+struct Simplifier<'a> {
+    positions: &'a [[f32; 3]],
+    /// Maps every Vertex to the first Vertex with the same Position
+    remap: Vec<u32>,
+    /// Triangles as Vertex Indices, The Position of every Corner is `remap[corner]`
+    triangles: Vec<[u32; 3]>,
+    removed: Vec<bool>,
+    live_triangles: usize,
+    /// Triangles adjacent to every welded Vertex
+    adjacency: Vec<Vec<u32>>,
+    quadrics: Vec<Quadric>,
+    locked: Vec<bool>,
+    collapsed: Vec<bool>,
+    version: Vec<u32>,
+}
+
+struct Collapse {
+    cost: OrderedFloat<f64>,
+    from: u32,
+    to: u32,
+    from_version: u32,
+    to_version: u32,
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    // Reversed, BinaryHeap is a max-heap and we want the cheapest collapse first
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other.cost.cmp(&self.cost)
+    }
+}
+
+impl<'a> Simplifier<'a> {
+    fn new(positions: &'a [[f32; 3]], indices: &[u32]) -> Self {
+        let vertex_count = positions.len();
+
+        let mut unique: HashMap<[u32; 3], u32> = HashMap::with_capacity(vertex_count);
+        let mut seam = vec![false; vertex_count];
+        let remap: Vec<u32> = positions
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let key = [p[0].to_bits(), p[1].to_bits(), p[2].to_bits()];
+                let first = *unique.entry(key).or_insert(i as u32);
+                if first != i as u32 {
+                    seam[first as usize] = true;
+                }
+                first
+            })
+            .collect();
+
+        let triangles: Vec<[u32; 3]> = indices
+            .chunks_exact(3)
+            .map(|t| [t[0], t[1], t[2]])
+            .collect();
+
+        let mut adjacency = vec![Vec::new(); vertex_count];
+        let mut quadrics = vec![[0.0; 10]; vertex_count];
+        let mut edges: HashMap<(u32, u32), u32> = HashMap::new();
+        for (t, triangle) in triangles.iter().enumerate() {
+            let welded = triangle.map(|v| remap[v as usize]);
+            let quadric = plane_quadric(
+                positions[welded[0] as usize],
+                positions[welded[1] as usize],
+                positions[welded[2] as usize],
+            );
+            for i in 0..3 {
+                adjacency[welded[i] as usize].push(t as u32);
+                add_quadric(&mut quadrics[welded[i] as usize], &quadric);
+                let (a, b) = (welded[i], welded[(i + 1) % 3]);
+                *edges.entry((a.min(b), a.max(b))).or_default() += 1;
+            }
+        }
+
+        // Lock Seams and Vertices on open Borders (Edges used by only one Triangle)
+        let mut locked = seam;
+        for (&(a, b), &count) in &edges {
+            if count == 1 {
+                locked[a as usize] = true;
+                locked[b as usize] = true;
+            }
+        }
+
+        Self {
+            positions,
+            remap,
+            removed: vec![false; triangles.len()],
+            live_triangles: triangles.len(),
+            triangles,
+            adjacency,
+            quadrics,
+            locked,
+            collapsed: vec![false; vertex_count],
+            version: vec![0; vertex_count],
+        }
+    }
+
+    fn extent(&self) -> f64 {
+        bounding_sphere(self.positions).map_or(1.0, |sphere| sphere.radius.max(f32::EPSILON) as f64)
+    }
+
+    fn run(&mut self, target_triangles: usize, max_cost: f64) {
+        let mut heap = BinaryHeap::new();
+        for v in 0..self.positions.len() as u32 {
+            if self.remap[v as usize] == v {
+                self.push_collapses(&mut heap, v);
+            }
+        }
+
+        while self.live_triangles > target_triangles {
+            let Some(collapse) = heap.pop() else {
+                break;
+            };
+            if collapse.cost.0 > max_cost {
+                break;
+            }
+            let (from, to) = (collapse.from as usize, collapse.to as usize);
+            if self.collapsed[from]
+                || self.collapsed[to]
+                || self.version[from] != collapse.from_version
+                || self.version[to] != collapse.to_version
+            {
+                continue;
+            }
+            if self.collapse(collapse.from, collapse.to) {
+                self.push_collapses(&mut heap, collapse.to);
+            }
+        }
+    }
+
+    /// Pushes the cheapest Collapse for every Edge of `v`
+    fn push_collapses(&self, heap: &mut BinaryHeap<Collapse>, v: u32) {
+        for neighbour in self.neighbours(v) {
+            let (a, b) = (v as usize, neighbour as usize);
+            let mut quadric = self.quadrics[a];
+            add_quadric(&mut quadric, &self.quadrics[b]);
+
+            let mut best: Option<(f64, u32, u32)> = None;
+            for (from, to) in [(v, neighbour), (neighbour, v)] {
+                if self.locked[from as usize] {
+                    continue;
+                }
+                let cost = eval_quadric(&quadric, self.positions[to as usize]);
+                let cheaper = match best {
+                    Some((best_cost, ..)) => cost < best_cost,
+                    None => true,
+                };
+                if cheaper {
+                    best = Some((cost, from, to));
+                }
+            }
+            if let Some((cost, from, to)) = best {
+                heap.push(Collapse {
+                    cost: OrderedFloat(cost.max(0.0)),
+                    from,
+                    to,
+                    from_version: self.version[from as usize],
+                    to_version: self.version[to as usize],
+                });
+            }
+        }
+    }
+
+    fn neighbours(&self, v: u32) -> Vec<u32> {
+        let mut neighbours = Vec::new();
+        for &t in &self.adjacency[v as usize] {
+            if self.removed[t as usize] {
+                continue;
+            }
+            for corner in self.triangles[t as usize] {
+                let w = self.remap[corner as usize];
+                if w != v && !neighbours.contains(&w) {
+                    neighbours.push(w);
+                }
+            }
+        }
+        neighbours
+    }
+
+    /// Collapses the Edge `from` -> `to`, Returns false if the Collapse would flip or degenerate an Triangle
+    fn collapse(&mut self, from: u32, to: u32) -> bool {
+        let to_position = self.positions[to as usize];
+
+        // `from` is never a Seam, So every Triangle on the Edge uses the same Vertex (wedge) for `to`
+        let mut to_wedge = None;
+        for &t in &self.adjacency[from as usize] {
+            if self.removed[t as usize] {
+                continue;
+            }
+            let triangle = self.triangles[t as usize];
+            let welded = triangle.map(|v| self.remap[v as usize]);
+            if let Some(i) = welded.iter().position(|&w| w == to) {
+                to_wedge = Some(triangle[i]);
+                continue;
+            }
+            // Check that moving `from` does not flip the Triangle
+            let before = welded.map(|w| self.positions[w as usize]);
+            let after = welded.map(|w| {
+                if w == from {
+                    to_position
+                } else {
+                    self.positions[w as usize]
+                }
+            });
+            let before_normal = normal(before[0], before[1], before[2]);
+            let after_normal = normal(after[0], after[1], after[2]);
+            // The Normal length is twice the Triangle area, Compare against the area before the Collapse so the
+            // check behaves the same no matter how large the Mesh is
+            if dot(before_normal, after_normal) <= 0.0
+                || length(after_normal) <= length(before_normal) * DEGENERATE_AREA_RATIO
+            {
+                return false;
+            }
+        }
+        let Some(to_wedge) = to_wedge else {
+            return false;
+        };
+
+        let from_triangles = std::mem::take(&mut self.adjacency[from as usize]);
+        for &t in &from_triangles {
+            if self.removed[t as usize] {
+                continue;
+            }
+            let triangle = &mut self.triangles[t as usize];
+            if triangle.iter().any(|&v| self.remap[v as usize] == to) {
+                self.removed[t as usize] = true;
+                self.live_triangles -= 1;
+            } else {
+                for v in triangle.iter_mut() {
+                    if self.remap[*v as usize] == from {
+                        *v = to_wedge;
+                    }
+                }
+                self.adjacency[to as usize].push(t);
+            }
+        }
+
+        let from_quadric = self.quadrics[from as usize];
+        add_quadric(&mut self.quadrics[to as usize], &from_quadric);
+        self.collapsed[from as usize] = true;
+        self.version[to as usize] += 1;
+        self.adjacency[to as usize].retain(|&t| !self.removed[t as usize]);
+        true
+    }
+
+    fn indices(&self) -> Vec<u32> {
+        self.triangles
+            .iter()
+            .zip(&self.removed)
+            .filter(|(_, removed)| !**removed)
+            .flat_map(|(triangle, _)| *triangle)
+            .collect()
+    }
+}
+
+fn plane_quadric(a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> Quadric {
+    let n = normal(a, b, c);
+    let len = length(n);
+    // Only skip fully degenerated Triangles, An absolute threshold would drop every Plane of small Meshes
+    if len <= 0.0 {
+        return [0.0; 10];
+    }
+    let [x, y, z] = n.map(|v| (v / len) as f64);
+    let d = -(x * a[0] as f64 + y * a[1] as f64 + z * a[2] as f64);
+    [
+        x * x,
+        x * y,
+        x * z,
+        x * d,
+        y * y,
+        y * z,
+        y * d,
+        z * z,
+        z * d,
+        d * d,
+    ]
+}
+
+fn add_quadric(q: &mut Quadric, other: &Quadric) {
+    q.iter_mut().zip(other).for_each(|(a, b)| *a += b);
+}
+
+/// Sum of squared Distances from `p` to all Planes of the Quadric
+fn eval_quadric(q: &Quadric, p: [f32; 3]) -> f64 {
+    let [x, y, z] = p.map(|v| v as f64);
+    q[0] * x * x
+        + 2.0 * q[1] * x * y
+        + 2.0 * q[2] * x * z
+        + 2.0 * q[3] * x
+        + q[4] * y * y
+        + 2.0 * q[5] * y * z
+        + 2.0 * q[6] * y
+        + q[7] * z * z
+        + 2.0 * q[8] * z
+        + q[9]
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn length(a: [f32; 3]) -> f32 {
+    dot(a, a).sqrt()
+}
+
+fn normal(a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> [f32; 3] {
+    let u = sub(b, a);
+    let v = sub(c, a);
+    [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Height field over an `n` x `n` Grid with an smooth Bump in the middle and a flat Border, Every Triangle faces +Z
+    fn height_field(n: u32) -> (Vec<[f32; 3]>, Vec<u32>) {
+        let mut positions = Vec::new();
+        for y in 0..=n {
+            for x in 0..=n {
+                let (u, v) = (x as f32 / n as f32 - 0.5, y as f32 / n as f32 - 0.5);
+                positions.push([u, v, 3.2 * (0.25 - u * u) * (0.25 - v * v)]);
+            }
+        }
+        let mut indices = Vec::new();
+        for y in 0..n {
+            for x in 0..n {
+                let i = y * (n + 1) + x;
+                indices.extend([i, i + 1, i + n + 1, i + 1, i + n + 2, i + n + 1]);
+            }
+        }
+        (positions, indices)
+    }
+
+    fn assert_facing_up(positions: &[[f32; 3]], indices: &[u32]) {
+        for triangle in indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| positions[triangle[i] as usize]);
+            assert!(normal(a, b, c)[2] > 0.0, "Triangle {triangle:?} is flipped");
+        }
+    }
+
+    #[test]
+    fn simplify_reduces_triangles() {
+        let (positions, indices) = height_field(16);
+        assert_facing_up(&positions, &indices);
+
+        let simplified = simplify(&positions, &indices, indices.len() / 4, 1.0);
+        assert_eq!(simplified.len() % 3, 0);
+        assert!(simplified.len() < indices.len() / 2);
+        assert!(simplified.iter().all(|&i| (i as usize) < positions.len()));
+        assert_facing_up(&positions, &simplified);
+    }
+
+    #[test]
+    fn simplify_respects_max_error() {
+        let (positions, indices) = height_field(16);
+        // The flat Border is locked and the Bump can not be collapsed without Error
+        let simplified = simplify(&positions, &indices, 0, 0.0);
+        assert!(!simplified.is_empty());
+        assert_facing_up(&positions, &simplified);
+    }
+
+    #[test]
+    fn simplify_scaled_mesh() {
+        // Tiny Meshes must simplify the same way as large ones
+        let (positions, indices) = height_field(16);
+        let tiny: Vec<[f32; 3]> = positions.iter().map(|p| p.map(|v| v * 1e-4)).collect();
+        let simplified = simplify(&positions, &indices, indices.len() / 4, 1.0);
+        let simplified_tiny = simplify(&tiny, &indices, indices.len() / 4, 1.0);
+        assert_eq!(simplified.len(), simplified_tiny.len());
+        assert_facing_up(&tiny, &simplified_tiny);
+    }
+
+    #[test]
+    fn generate_lod_chain() {
+        let (positions, indices) = height_field(16);
+        let original = indices.len();
+        let lods = generate_lods(&positions, indices, &LodSettings::default());
+        assert!(lods.len() > 1);
+        assert_eq!(lods[0].len(), original);
+        for pair in lods.windows(2) {
+            assert!(pair[1].len() < pair[0].len());
+        }
+        for lod in &lods {
+            assert_facing_up(&positions, lod);
+        }
+    }
+
+    #[test]
+    fn small_meshes_are_not_simplified() {
+        let (positions, indices) = height_field(2);
+        let lods = generate_lods(&positions, indices, &LodSettings::default());
+        assert_eq!(lods.len(), 1);
+    }
+
+    #[test]
+    fn lod_selection() {
+        assert_eq!(select_lod(1.0, 4), 0);
+        assert_eq!(select_lod(0.5, 4), 0);
+        assert_eq!(select_lod(0.25, 4), 1);
+        assert_eq!(select_lod(0.1, 4), 2);
+        assert_eq!(select_lod(0.001, 4), 3);
+        assert_eq!(select_lod(0.0, 4), 3);
+        assert_eq!(select_lod(0.1, 1), 0);
+    }
+}
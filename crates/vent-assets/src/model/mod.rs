@@ -2,15 +2,28 @@ use std::path::Path;
 
 use ash::vk;
 use loader::ModelLoader;
-use vent_rendering::instance::VulkanInstance;
+use registry::ImporterRegistry;
+use vent_rendering::{instance::VulkanInstance, mesh::Mesh3D};
 use vent_sdk::utils::stopwatch::Stopwatch;
 
-use crate::Model3D;
+use crate::{AssetError, Material, Model3D};
 
+pub mod data;
+pub mod geometry;
+pub mod gltf_exporter;
+mod gltf_importer;
 mod loader;
+pub mod lod;
+mod modelz_importer;
+mod obj_importer;
 mod optimizer;
+mod ply_importer;
+pub mod registry;
+pub mod scene;
+mod stl_importer;
 
 impl Model3D {
+    /// Loads an Model from a File, Errors are returned so an broken Model can be reported instead of crashing
     #[inline]
     pub async fn load<P: AsRef<Path>>(
         instance: &mut VulkanInstance,
@@ -18,9 +31,29 @@ impl Model3D {
         fragment_shader: P,
         pipeline_layout: vk::PipelineLayout,
         path: P,
-    ) -> Self {
+    ) -> Result<Self, AssetError> {
+        Self::load_with_registry(
+            instance,
+            &ImporterRegistry::default(),
+            vertex_shader,
+            fragment_shader,
+            pipeline_layout,
+            path,
+        )
+        .await
+    }
+
+    /// Same as `load` but Imports the File with the Importers of `registry`
+    pub async fn load_with_registry<P: AsRef<Path>>(
+        instance: &mut VulkanInstance,
+        registry: &ImporterRegistry,
+        vertex_shader: P,
+        fragment_shader: P,
+        pipeline_layout: vk::PipelineLayout,
+        path: P,
+    ) -> Result<Self, AssetError> {
         let sw = Stopwatch::new_and_start();
-        let model = modelz::Model3D::load(path.as_ref()).expect("Failed to Load 3D Model");
+        let model = registry.import(path.as_ref())?;
         let model = ModelLoader::load(
             instance,
             vertex_shader.as_ref(),
@@ -28,7 +61,7 @@ impl Model3D {
             pipeline_layout,
             model,
         )
-        .await;
+        .await?;
         log::info!(
             "Model {} took {}ms to Load, {} Pipelines, {} Materials",
             path.as_ref().display(),
@@ -36,7 +69,7 @@ impl Model3D {
             model.pipelines.len(),
             model.materials.len(),
         );
-        model
+        Ok(model)
     }
 
     /// So your ideal render loop would be
@@ -47,13 +80,17 @@ impl Model3D {
     ///      Set material bind group
     ///       For each primitive that uses material with pipeline
     ///        Draw primitive
+    ///
+    /// Every Mesh is drawn once for every Node which uses it.
+    /// `prepare_mesh` is called before every Draw with the Node (`None` means the Origin of the Model),
+    /// It can push per Mesh Constants and returns the Level of Detail which should be drawn.
+    /// `bind_material` is called before the Meshes of every Material, e.g. to bind its Descriptor Set
     pub fn draw(
         &self,
         device: &ash::Device,
-        pipeline_layout: vk::PipelineLayout,
         command_buffer: vk::CommandBuffer,
-        buffer_index: usize,
-        with_descriptor_set: bool,
+        mut bind_material: impl FnMut(&Material),
+        mut prepare_mesh: impl FnMut(&Mesh3D, Option<usize>) -> usize,
     ) {
         self.pipelines.iter().for_each(|pipeline| {
             unsafe {
@@ -64,27 +101,16 @@ impl Model3D {
                 )
             }
             pipeline.materials.iter().for_each(|material| {
-                if with_descriptor_set {
-                    let material = &self.materials[material.material_index];
-                    if let Some(ds) = &material.descriptor_set {
-                        unsafe {
-                            device.cmd_bind_descriptor_sets(
-                                command_buffer,
-                                vk::PipelineBindPoint::GRAPHICS,
-                                pipeline_layout,
-                                0,
-                                &ds[buffer_index..=buffer_index],
-                                &[],
-                            )
-                        }
-                    }
-                }
-                material.meshes.iter().for_each(|mesh| {
+                bind_material(&self.materials[material.material_index]);
+                material.meshes.iter().for_each(|model_mesh| {
+                    let mesh = &model_mesh.mesh;
                     // rpass.push_debug_group("Bind Mesh");
                     mesh.bind(device, command_buffer);
                     // rpass.pop_debug_group();
                     // rpass.insert_debug_marker("Draw!");
-                    mesh.draw(device, command_buffer);
+                    for node in self.scene.mesh_instances(model_mesh.mesh_index) {
+                        mesh.draw_lod(device, command_buffer, prepare_mesh(mesh, node));
+                    }
                 });
             });
         })
@@ -92,17 +118,28 @@ impl Model3D {
 
     pub fn destroy(&mut self, device: &ash::Device) {
         self.materials.drain(..).for_each(|mut material| {
-            material.diffuse_texture.destroy(device);
+            material.destroy(device);
         });
         self.pipelines.drain(..).for_each(|mut pipeline| {
             unsafe { device.destroy_pipeline(pipeline.pipeline, None) };
             pipeline.materials.drain(..).for_each(|mut model_material| {
-                model_material.meshes.drain(..).for_each(|mut mesh| {
-                    mesh.destroy(device);
+                model_material.meshes.drain(..).for_each(|mut model_mesh| {
+                    model_mesh.mesh.destroy(device);
                 });
             });
         });
-        // We are getting an Validation error when we try to free an descriptor set, They will all automatily freed when the Descriptor pool is destroyed
-        unsafe { device.destroy_descriptor_pool(self.descriptor_pool, None) };
+        if let Some(morph_targets) = &mut self.morph_targets {
+            morph_targets.destroy(device);
+        }
     }
 }
+
+/// Writes an Test Fixture into the Temp Directory, The Importers only read Files
+#[cfg(test)]
+fn fixture(name: &str, contents: &[u8]) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join("vent-assets-importers");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
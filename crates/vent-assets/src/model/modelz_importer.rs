@@ -0,0 +1,154 @@
+use std::{io, path::Path};
+
+use ash::vk;
+use vent_rendering::DEFAULT_TEXTURE_FILTER;
+
+use crate::AssetError;
+
+use super::data::{
+    AlphaMode, MaterialData, MeshData, ModelData, PrimitiveData, PrimitiveMode, TextureData,
+    TextureImage, TextureTransform,
+};
+
+/// Fallback for Extensions without an own Importer, modelz only gives us Triangle Lists
+pub(crate) struct ModelzImporter {}
+
+impl ModelzImporter {
+    pub fn import(path: &Path) -> Result<ModelData, AssetError> {
+        // modelz does not tell us why it failed to open an File
+        if !path.exists() {
+            return Err(AssetError::Io(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} does not exist", path.display()),
+            )));
+        }
+        // Everything modelz can not read is an Format we do not support
+        let model = modelz::Model3D::load(path)
+            .map_err(|err| AssetError::Unsupported(format!("{}: {:?}", path.display(), err)))?;
+
+        let materials = model
+            .materials
+            .into_iter()
+            .map(Self::convert_material)
+            .collect();
+        let meshes = model.meshes.into_iter().map(Self::convert_mesh).collect();
+
+        Ok(ModelData {
+            meshes,
+            materials,
+            ..Default::default()
+        })
+    }
+
+    fn convert_mesh(mesh: modelz::Mesh) -> MeshData {
+        // Attributes are either there for every Vertex or for none
+        let normals = mesh
+            .vertices
+            .iter()
+            .map(|vertex| vertex.normal)
+            .collect::<Option<Vec<_>>>();
+        let tex_coords = mesh
+            .vertices
+            .iter()
+            .map(|vertex| vertex.tex_coord)
+            .collect::<Option<Vec<_>>>();
+        let positions: Vec<[f32; 3]> = mesh.vertices.iter().map(|vertex| vertex.position).collect();
+
+        let indices = mesh.indices.map_or_else(
+            || PrimitiveData::generate_indices(positions.len()),
+            |indices| match indices {
+                modelz::Indices::U8(d) => d.into_iter().map(u32::from).collect(),
+                modelz::Indices::U16(d) => d.into_iter().map(u32::from).collect(),
+                modelz::Indices::U32(d) => d,
+            },
+        );
+
+        MeshData {
+            name: mesh.name,
+            weights: Vec::new(),
+            primitives: vec![PrimitiveData {
+                positions,
+                normals,
+                tex_coords,
+                tex_coords1: None,
+                tangents: None,
+                joints: None,
+                weights: None,
+                morph_targets: Vec::new(),
+                indices,
+                mode: PrimitiveMode::Triangles,
+                material_index: mesh.material_index,
+                flat_normals: false,
+            }],
+        }
+    }
+
+    fn convert_material(material: modelz::Material) -> MaterialData {
+        MaterialData {
+            name: material.name,
+            base_color_texture: material.diffuse_texture.map(|texture| TextureData {
+                image: TextureImage::Decoded(texture.image),
+                sampler: Self::convert_sampler(texture.sampler),
+                tex_coord: 0,
+                transform: TextureTransform::default(),
+            }),
+            base_color: material.base_color.unwrap_or([1.0, 1.0, 1.0, 1.0]),
+            alpha_mode: match material.alpha_mode {
+                modelz::AlphaMode::Opaque => AlphaMode::Opaque,
+                modelz::AlphaMode::Mask => AlphaMode::Mask,
+                modelz::AlphaMode::Blend => AlphaMode::Blend,
+            },
+            alpha_cutoff: material.alpha_cutoff.unwrap_or(0.5),
+            double_sided: material.double_sided,
+            ..Default::default()
+        }
+    }
+
+    /// Converts an modelz Texture Sampler into Vulkan Sampler Info
+    fn convert_sampler(sampler: modelz::Sampler) -> vk::SamplerCreateInfo<'static> {
+        let mag_filter = sampler
+            .mag_filter
+            .map_or(DEFAULT_TEXTURE_FILTER, |filter| match filter {
+                modelz::MagFilter::Nearest => vk::Filter::NEAREST,
+                modelz::MagFilter::Linear => vk::Filter::LINEAR,
+            });
+
+        let (min_filter, mipmap_filter) = sampler.min_filter.map_or(
+            (DEFAULT_TEXTURE_FILTER, vk::SamplerMipmapMode::LINEAR),
+            |filter| match filter {
+                modelz::MinFilter::Nearest => (vk::Filter::NEAREST, vk::SamplerMipmapMode::NEAREST),
+                modelz::MinFilter::Linear => (vk::Filter::LINEAR, vk::SamplerMipmapMode::NEAREST),
+                modelz::MinFilter::NearestMipmapNearest => {
+                    (vk::Filter::NEAREST, vk::SamplerMipmapMode::NEAREST)
+                }
+                modelz::MinFilter::LinearMipmapNearest => {
+                    (vk::Filter::LINEAR, vk::SamplerMipmapMode::NEAREST)
+                }
+                modelz::MinFilter::NearestMipmapLinear => {
+                    (vk::Filter::NEAREST, vk::SamplerMipmapMode::LINEAR)
+                }
+                modelz::MinFilter::LinearMipmapLinear => {
+                    (vk::Filter::LINEAR, vk::SamplerMipmapMode::LINEAR)
+                }
+            },
+        );
+
+        vk::SamplerCreateInfo {
+            mag_filter,
+            min_filter,
+            mipmap_mode: mipmap_filter,
+            address_mode_u: Self::convert_wrapping_mode(&sampler.wrap_s),
+            address_mode_v: Self::convert_wrapping_mode(&sampler.wrap_t),
+            ..Default::default()
+        }
+    }
+
+    #[must_use]
+    const fn convert_wrapping_mode(mode: &modelz::WrappingMode) -> vk::SamplerAddressMode {
+        match mode {
+            modelz::WrappingMode::ClampToEdge => vk::SamplerAddressMode::CLAMP_TO_EDGE,
+            modelz::WrappingMode::MirroredRepeat => vk::SamplerAddressMode::MIRRORED_REPEAT,
+            modelz::WrappingMode::Repeat => vk::SamplerAddressMode::REPEAT,
+        }
+    }
+}
@@ -0,0 +1,476 @@
+use std::{collections::HashMap, path::Path};
+
+use ash::vk;
+use vent_rendering::DEFAULT_TEXTURE_FILTER;
+
+use crate::{texture, AssetError};
+
+use super::data::{
+    self, AlphaMode, MaterialData, MeshData, ModelData, PrimitiveData, PrimitiveMode, TextureData,
+    TextureImage, TextureTransform,
+};
+
+/// Imports Wavefront OBJ Files together with their MTL Materials.
+/// Every Object (`o`) becomes an Mesh, Every Material used inside it an Primitive. Polygons are triangulated as Fans
+pub(crate) struct ObjImporter {}
+
+/// Position, Texture Coordinate and Normal Index of an Face Corner, Already resolved to 0 based Indices
+type Corner = (usize, Option<usize>, Option<usize>);
+
+/// Collects the Faces of one Material inside an Object
+#[derive(Default)]
+struct PrimitiveBuilder {
+    corners: HashMap<Corner, u32>,
+    positions: Vec<[f32; 3]>,
+    tex_coords: Vec<Option<[f32; 2]>>,
+    normals: Vec<Option<[f32; 3]>>,
+    indices: Vec<u32>,
+}
+
+struct MeshBuilder {
+    name: Option<String>,
+    /// Per Material Index, `None` is the Default Material
+    primitives: Vec<(Option<usize>, PrimitiveBuilder)>,
+}
+
+impl ObjImporter {
+    pub fn import(path: &Path) -> Result<ModelData, AssetError> {
+        let source = std::fs::read_to_string(path)?;
+        let base = path.parent().unwrap_or_else(|| Path::new("./"));
+
+        let mut positions: Vec<[f32; 3]> = Vec::new();
+        let mut tex_coords: Vec<[f32; 2]> = Vec::new();
+        let mut normals: Vec<[f32; 3]> = Vec::new();
+        let mut materials: Vec<MaterialData> = Vec::new();
+        let mut material_indices: HashMap<String, usize> = HashMap::new();
+        let mut meshes = vec![MeshBuilder {
+            name: None,
+            primitives: Vec::new(),
+        }];
+        let mut material = None;
+
+        for (line_number, line) in source.lines().enumerate() {
+            let parse_error =
+                |what: &str| AssetError::Parse(format!("OBJ Line {}: {}", line_number + 1, what));
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let mut parts = line.splitn(2, char::is_whitespace);
+            let keyword = parts.next().unwrap_or_default();
+            let arguments = parts.next().unwrap_or_default().trim();
+
+            match keyword {
+                "v" => positions.push(parse_floats(arguments).ok_or_else(|| parse_error("v"))?),
+                "vt" => {
+                    let mut values = arguments.split_whitespace().map(str::parse::<f32>);
+                    let u = values.next().and_then(Result::ok);
+                    // V and W are optional
+                    let v = values.next().unwrap_or(Ok(0.0)).ok();
+                    let (Some(u), Some(v)) = (u, v) else {
+                        return Err(parse_error("vt"));
+                    };
+                    // OBJ has its Origin at the bottom left
+                    tex_coords.push([u, 1.0 - v]);
+                }
+                "vn" => normals.push(parse_floats(arguments).ok_or_else(|| parse_error("vn"))?),
+                "f" => {
+                    let corners = arguments
+                        .split_whitespace()
+                        .map(|corner| {
+                            parse_corner(corner, positions.len(), tex_coords.len(), normals.len())
+                        })
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(|| parse_error("f"))?;
+                    if corners.len() < 3 {
+                        return Err(parse_error("Faces need at least 3 Corners"));
+                    }
+                    let mesh = meshes.last_mut().unwrap();
+                    let primitive = match mesh
+                        .primitives
+                        .iter_mut()
+                        .position(|(index, _)| *index == material)
+                    {
+                        Some(i) => &mut mesh.primitives[i].1,
+                        None => {
+                            mesh.primitives
+                                .push((material, PrimitiveBuilder::default()));
+                            &mut mesh.primitives.last_mut().unwrap().1
+                        }
+                    };
+                    let indices: Vec<u32> = corners
+                        .into_iter()
+                        .map(|corner| primitive.vertex(corner, &positions, &tex_coords, &normals))
+                        .collect();
+                    for i in 1..indices.len() - 1 {
+                        primitive
+                            .indices
+                            .extend([indices[0], indices[i], indices[i + 1]]);
+                    }
+                }
+                "o" | "g" => {
+                    let name = (!arguments.is_empty()).then(|| arguments.to_owned());
+                    let current = meshes.last_mut().unwrap();
+                    if current.primitives.is_empty() {
+                        current.name = name;
+                    } else {
+                        meshes.push(MeshBuilder {
+                            name,
+                            primitives: Vec::new(),
+                        });
+                    }
+                }
+                "usemtl" => {
+                    material = material_indices.get(arguments).copied();
+                    if material.is_none() {
+                        log::warn!("OBJ uses unknown Material {}", arguments);
+                    }
+                }
+                "mtllib" => {
+                    // A missing Material Library should not fail the whole Model
+                    let mtl_path = base.join(arguments);
+                    match Self::import_materials(&mtl_path) {
+                        Ok(library) => {
+                            for material in library {
+                                if let Some(name) = &material.name {
+                                    material_indices.insert(name.clone(), materials.len());
+                                }
+                                materials.push(material);
+                            }
+                        }
+                        Err(err) => log::warn!(
+                            "Failed to Load Material Library {}: {}",
+                            mtl_path.display(),
+                            err
+                        ),
+                    }
+                }
+                // Lines, Points, Smoothing Groups and Free Form Geometry are not supported
+                _ => {}
+            }
+        }
+
+        let meshes = meshes
+            .into_iter()
+            .filter(|mesh| !mesh.primitives.is_empty())
+            .map(|mesh| MeshData {
+                name: mesh.name,
+                primitives: mesh
+                    .primitives
+                    .into_iter()
+                    .map(|(material_index, primitive)| primitive.build(material_index))
+                    .collect(),
+                weights: Vec::new(),
+            })
+            .collect();
+        Ok(ModelData {
+            meshes,
+            materials,
+            ..Default::default()
+        })
+    }
+
+    /// Converts the Phong Materials of an MTL File, The PBR Extension (Pr, Pm, Ke, norm) is used when it is there
+    fn import_materials(path: &Path) -> Result<Vec<MaterialData>, AssetError> {
+        let source = std::fs::read_to_string(path)?;
+        let base = path.parent().unwrap_or_else(|| Path::new("./"));
+        let mut materials: Vec<MaterialData> = Vec::new();
+
+        for line in source.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((keyword, arguments)) = line.split_once(char::is_whitespace) else {
+                continue;
+            };
+            let arguments = arguments.trim();
+            if keyword == "newmtl" {
+                materials.push(MaterialData {
+                    name: Some(arguments.to_owned()),
+                    // OBJ Materials are not metallic unless they say so
+                    metallic_factor: 0.0,
+                    ..Default::default()
+                });
+                continue;
+            }
+            let Some(material) = materials.last_mut() else {
+                continue;
+            };
+            let float = || arguments.parse::<f32>().ok();
+            match keyword {
+                "Kd" => {
+                    if let Some([r, g, b]) = parse_floats(arguments) {
+                        material.base_color = [r, g, b, material.base_color[3]];
+                    }
+                }
+                "d" => {
+                    if let Some(alpha) = float() {
+                        material.base_color[3] = alpha;
+                    }
+                }
+                "Tr" => {
+                    if let Some(transparency) = float() {
+                        material.base_color[3] = 1.0 - transparency;
+                    }
+                }
+                "Ke" => {
+                    if let Some(emissive) = parse_floats(arguments) {
+                        material.emissive_factor = emissive;
+                    }
+                }
+                // Blinn-Phong Exponent to Roughness
+                "Ns" => {
+                    if let Some(shininess) = float() {
+                        material.roughness_factor = (2.0 / (shininess.max(0.0) + 2.0)).sqrt();
+                    }
+                }
+                "Pr" => {
+                    if let Some(roughness) = float() {
+                        material.roughness_factor = roughness;
+                    }
+                }
+                "Pm" => {
+                    if let Some(metallic) = float() {
+                        material.metallic_factor = metallic;
+                    }
+                }
+                "map_Kd" => {
+                    material.base_color_texture = Some(Self::import_texture(base, arguments))
+                }
+                "map_Ke" => material.emissive_texture = Some(Self::import_texture(base, arguments)),
+                "map_Bump" | "map_bump" | "bump" | "norm" => {
+                    material.normal_texture = Some(Self::import_texture(base, arguments))
+                }
+                _ => {}
+            }
+        }
+        for material in &mut materials {
+            if material.base_color[3] < 1.0 {
+                material.alpha_mode = AlphaMode::Blend;
+            }
+            if material.emissive_texture.is_some() && material.emissive_factor == [0.0; 3] {
+                material.emissive_factor = [1.0; 3];
+            }
+        }
+        Ok(materials)
+    }
+
+    /// Texture Options (e.g. `-bm 1.0`) come before the File Name, So we only use the last Argument
+    fn import_texture(base: &Path, arguments: &str) -> TextureData {
+        let file = arguments.split_whitespace().last().unwrap_or_default();
+        let path = base.join(file.replace('\\', "/"));
+        let bytes = std::fs::read(&path);
+        let image = bytes
+            .map_err(AssetError::from)
+            .and_then(|bytes| {
+                if texture::is_compressed(&bytes) {
+                    texture::load_compressed(&bytes).map(TextureImage::Compressed)
+                } else {
+                    Ok(TextureImage::Decoded(image::load_from_memory(&bytes)?))
+                }
+            })
+            .unwrap_or_else(|err| {
+                log::warn!("Failed to Load Texture {}: {}", path.display(), err);
+                TextureImage::Decoded(data::missing_texture_image())
+            });
+        TextureData {
+            image,
+            sampler: vk::SamplerCreateInfo {
+                mag_filter: DEFAULT_TEXTURE_FILTER,
+                min_filter: DEFAULT_TEXTURE_FILTER,
+                mipmap_mode: vk::SamplerMipmapMode::LINEAR,
+                address_mode_u: vk::SamplerAddressMode::REPEAT,
+                address_mode_v: vk::SamplerAddressMode::REPEAT,
+                ..Default::default()
+            },
+            tex_coord: 0,
+            transform: TextureTransform::default(),
+        }
+    }
+}
+
+impl PrimitiveBuilder {
+    /// OBJ indexes every Attribute on its own, So every unique Combination becomes an Vertex
+    fn vertex(
+        &mut self,
+        corner: Corner,
+        positions: &[[f32; 3]],
+        tex_coords: &[[f32; 2]],
+        normals: &[[f32; 3]],
+    ) -> u32 {
+        *self.corners.entry(corner).or_insert_with(|| {
+            let (position, tex_coord, normal) = corner;
+            self.positions.push(positions[position]);
+            self.tex_coords.push(tex_coord.map(|i| tex_coords[i]));
+            self.normals.push(normal.map(|i| normals[i]));
+            self.positions.len() as u32 - 1
+        })
+    }
+
+    fn build(self, material_index: Option<usize>) -> PrimitiveData {
+        // Attributes which some Corners do not have are generated by the Loader
+        PrimitiveData {
+            positions: self.positions,
+            normals: self.normals.into_iter().collect(),
+            tex_coords: self.tex_coords.into_iter().collect(),
+            tex_coords1: None,
+            tangents: None,
+            joints: None,
+            weights: None,
+            morph_targets: Vec::new(),
+            indices: self.indices,
+            mode: PrimitiveMode::Triangles,
+            material_index,
+            flat_normals: false,
+        }
+    }
+}
+
+fn parse_floats<const N: usize>(arguments: &str) -> Option<[f32; N]> {
+    let mut values = arguments.split_whitespace().map(str::parse::<f32>);
+    let mut result = [0.0; N];
+    for value in &mut result {
+        *value = values.next()?.ok()?;
+    }
+    Some(result)
+}
+
+/// `v`, `v/vt`, `v//vn` or `v/vt/vn`, Negative Indices count from the End
+fn parse_corner(
+    corner: &str,
+    position_count: usize,
+    tex_coord_count: usize,
+    normal_count: usize,
+) -> Option<Corner> {
+    let resolve = |index: Option<&str>, count: usize| -> Option<Option<usize>> {
+        match index.filter(|index| !index.is_empty()) {
+            None => Some(None),
+            Some(index) => {
+                let index: i64 = index.parse().ok()?;
+                let resolved = if index < 0 {
+                    count as i64 + index
+                } else {
+                    index - 1
+                };
+                (0..count as i64)
+                    .contains(&resolved)
+                    .then_some(Some(resolved as usize))
+            }
+        }
+    };
+    let mut parts = corner.split('/');
+    let position = resolve(parts.next(), position_count)??;
+    let tex_coord = resolve(parts.next(), tex_coord_count)?;
+    let normal = resolve(parts.next(), normal_count)?;
+    Some((position, tex_coord, normal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MTL: &str = "newmtl red
+Kd 1 0 0
+Ns 0
+newmtl glass
+Kd 0 0 1
+d 0.25
+Pm 0.5
+";
+
+    /// An Quad with the red Material and an Triangle with glass, Both use negative Indices
+    const OBJ: &str = "mtllib negative.mtl
+o quad
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 1
+vn 0 0 1
+usemtl red
+f -4/-2/-1 -3/-2/-1 -2/-1/-1 -1/-1/-1
+o triangle
+v 0 0 1
+v 1 0 1
+v 0 1 1
+usemtl glass
+f -3 -2 -1 # Positions only
+usemtl missing
+f 1 2 3
+";
+
+    #[test]
+    fn import_with_materials_and_negative_indices() {
+        crate::model::fixture("negative.mtl", MTL.as_bytes());
+        let path = crate::model::fixture("negative.obj", OBJ.as_bytes());
+        let model = ObjImporter::import(&path).unwrap();
+
+        assert_eq!(model.materials.len(), 2);
+        let red = &model.materials[0];
+        assert_eq!(red.name.as_deref(), Some("red"));
+        assert_eq!(red.base_color, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(red.metallic_factor, 0.0);
+        // Shininess 0 is fully rough
+        assert_eq!(red.roughness_factor, 1.0);
+        assert_eq!(red.alpha_mode, AlphaMode::Opaque);
+        let glass = &model.materials[1];
+        assert_eq!(glass.base_color, [0.0, 0.0, 1.0, 0.25]);
+        assert_eq!(glass.metallic_factor, 0.5);
+        assert_eq!(glass.alpha_mode, AlphaMode::Blend);
+
+        assert_eq!(model.meshes.len(), 2);
+        let quad = &model.meshes[0];
+        assert_eq!(quad.name.as_deref(), Some("quad"));
+        let primitive = &quad.primitives[0];
+        assert_eq!(primitive.material_index, Some(0));
+        assert_eq!(
+            primitive.positions,
+            [
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 1.0, 0.0],
+                [0.0, 1.0, 0.0]
+            ]
+        );
+        // V is flipped
+        assert_eq!(
+            primitive.tex_coords,
+            Some(vec![[0.0, 1.0], [0.0, 1.0], [1.0, 0.0], [1.0, 0.0]])
+        );
+        assert_eq!(primitive.normals, Some(vec![[0.0, 0.0, 1.0]; 4]));
+        assert_eq!(primitive.indices, [0, 1, 2, 0, 2, 3]);
+
+        let triangle = &model.meshes[1];
+        assert_eq!(triangle.name.as_deref(), Some("triangle"));
+        assert_eq!(triangle.primitives.len(), 2);
+        let (glass, default) = (&triangle.primitives[0], &triangle.primitives[1]);
+        assert_eq!(glass.material_index, Some(1));
+        assert_eq!(
+            glass.positions,
+            [[0.0, 0.0, 1.0], [1.0, 0.0, 1.0], [0.0, 1.0, 1.0]]
+        );
+        assert_eq!(glass.tex_coords, None);
+        assert_eq!(glass.normals, None);
+        // An unknown Material falls back to the Default one
+        assert_eq!(default.material_index, None);
+        assert_eq!(default.positions, primitive.positions[..3]);
+    }
+
+    #[test]
+    fn missing_material_library() {
+        let source = "mtllib does_not_exist.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n";
+        let path = crate::model::fixture("no_library.obj", source.as_bytes());
+        let model = ObjImporter::import(&path).unwrap();
+        assert!(model.materials.is_empty());
+        assert_eq!(model.meshes[0].primitives[0].indices, [0, 1, 2]);
+    }
+
+    #[test]
+    fn rejects_out_of_range_indices() {
+        for face in ["f 1 2 4", "f -4 -2 -1", "f 1 2", "f 0 1 2"] {
+            let source = format!("v 0 0 0\nv 1 0 0\nv 0 1 0\n{}\n", face);
+            let path = crate::model::fixture("out_of_range.obj", source.as_bytes());
+            assert!(
+                matches!(ObjImporter::import(&path), Err(AssetError::Parse(_))),
+                "{}",
+                face
+            );
+        }
+    }
+}
@@ -0,0 +1,465 @@
+use std::path::Path;
+
+use crate::AssetError;
+
+use super::data::{MeshData, ModelData, PrimitiveData, PrimitiveMode};
+
+/// Imports ASCII and binary PLY Files. Files without Faces are Point Clouds and are drawn as Points
+pub(crate) struct PlyImporter {}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Ascii,
+    LittleEndian,
+    BigEndian,
+}
+
+#[derive(Clone, Copy)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+enum Property {
+    Scalar(String, Scalar),
+    /// Count Type, Item Type
+    List(String, Scalar, Scalar),
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Reads Values from the Body, ASCII Values are separated by Whitespace
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    encoding: Encoding,
+}
+
+impl PlyImporter {
+    pub fn import(path: &Path) -> Result<ModelData, AssetError> {
+        let bytes = std::fs::read(path)?;
+        let (encoding, elements, body) = Self::parse_header(&bytes)?;
+        let mut reader = Reader {
+            bytes: &bytes[body..],
+            offset: 0,
+            encoding,
+        };
+
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut tex_coords = Vec::new();
+        let mut indices = Vec::new();
+        for element in &elements {
+            for _ in 0..element.count {
+                match element.name.as_str() {
+                    "vertex" => {
+                        let mut position = [0.0; 3];
+                        let mut normal = [f32::NAN; 3];
+                        let mut tex_coord = [f32::NAN; 2];
+                        for property in &element.properties {
+                            let Property::Scalar(name, ty) = property else {
+                                reader.skip(property)?;
+                                continue;
+                            };
+                            let value = reader.read(*ty)? as f32;
+                            match name.as_str() {
+                                "x" => position[0] = value,
+                                "y" => position[1] = value,
+                                "z" => position[2] = value,
+                                "nx" => normal[0] = value,
+                                "ny" => normal[1] = value,
+                                "nz" => normal[2] = value,
+                                "u" | "s" | "texture_u" | "texture_s" => tex_coord[0] = value,
+                                // PLY has its Texture Origin at the bottom left like OBJ
+                                "v" | "t" | "texture_v" | "texture_t" => tex_coord[1] = 1.0 - value,
+                                // Colors and everything else are not supported
+                                _ => {}
+                            }
+                        }
+                        positions.push(position);
+                        normals.push(normal);
+                        tex_coords.push(tex_coord);
+                    }
+                    "face" => {
+                        for property in &element.properties {
+                            match property {
+                                Property::List(name, count_ty, item_ty)
+                                    if name == "vertex_indices" || name == "vertex_index" =>
+                                {
+                                    let count = reader.read(*count_ty)? as usize;
+                                    let face = (0..count)
+                                        .map(|_| reader.read(*item_ty).map(|index| index as u32))
+                                        .collect::<Result<Vec<_>, _>>()?;
+                                    // Polygons are triangulated as Fans
+                                    for i in 1..face.len().saturating_sub(1) {
+                                        indices.extend([face[0], face[i], face[i + 1]]);
+                                    }
+                                }
+                                _ => reader.skip(property)?,
+                            }
+                        }
+                    }
+                    _ => {
+                        for property in &element.properties {
+                            reader.skip(property)?;
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(&index) = indices.iter().find(|&&i| i as usize >= positions.len()) {
+            return Err(AssetError::Parse(format!(
+                "PLY Face uses Vertex {} but there are only {}",
+                index,
+                positions.len()
+            )));
+        }
+
+        let is_point_cloud = indices.is_empty();
+        let normals = if normals.iter().flatten().all(|n| !n.is_nan()) {
+            Some(normals)
+        } else if is_point_cloud {
+            // Points have no Surface to generate Normals from, So they face up
+            Some(vec![[0.0, 1.0, 0.0]; positions.len()])
+        } else {
+            None
+        };
+        let tex_coords = tex_coords
+            .iter()
+            .flatten()
+            .all(|c| !c.is_nan())
+            .then_some(tex_coords);
+        let (indices, mode) = if is_point_cloud {
+            (
+                PrimitiveData::generate_indices(positions.len()),
+                PrimitiveMode::Points,
+            )
+        } else {
+            (indices, PrimitiveMode::Triangles)
+        };
+
+        let name = path
+            .file_stem()
+            .map(|name| name.to_string_lossy().into_owned());
+        Ok(ModelData {
+            meshes: vec![MeshData {
+                name,
+                primitives: vec![PrimitiveData {
+                    positions,
+                    normals,
+                    tex_coords,
+                    tex_coords1: None,
+                    tangents: None,
+                    joints: None,
+                    weights: None,
+                    morph_targets: Vec::new(),
+                    indices,
+                    mode,
+                    material_index: None,
+                    flat_normals: false,
+                }],
+                weights: Vec::new(),
+            }],
+            ..Default::default()
+        })
+    }
+
+    /// Returns the Encoding, the Elements and the Offset of the Body
+    fn parse_header(bytes: &[u8]) -> Result<(Encoding, Vec<Element>, usize), AssetError> {
+        const END: &[u8] = b"end_header";
+        let end = bytes
+            .windows(END.len())
+            .position(|window| window == END)
+            .ok_or_else(|| AssetError::Parse("PLY has no end_header".to_owned()))?;
+        // The Body starts after the Line Break of end_header
+        let body = bytes[end..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(bytes.len(), |i| end + i + 1);
+        let header = std::str::from_utf8(&bytes[..end])
+            .map_err(|_| AssetError::Parse("PLY Header is not ASCII".to_owned()))?;
+
+        let mut lines = header.lines();
+        if lines.next().map(str::trim) != Some("ply") {
+            return Err(AssetError::Parse("Not an PLY File".to_owned()));
+        }
+        let mut encoding = None;
+        let mut elements: Vec<Element> = Vec::new();
+        for line in lines {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let parse_error = || AssetError::Parse(format!("PLY Header: {}", line.trim()));
+            match words.as_slice() {
+                ["format", format, ..] => {
+                    encoding = Some(match *format {
+                        "ascii" => Encoding::Ascii,
+                        "binary_little_endian" => Encoding::LittleEndian,
+                        "binary_big_endian" => Encoding::BigEndian,
+                        _ => return Err(AssetError::Unsupported(format!("PLY Format {}", format))),
+                    });
+                }
+                ["element", name, count] => elements.push(Element {
+                    name: (*name).to_owned(),
+                    count: count.parse().map_err(|_| parse_error())?,
+                    properties: Vec::new(),
+                }),
+                ["property", "list", count_ty, item_ty, name] => {
+                    let element = elements.last_mut().ok_or_else(parse_error)?;
+                    element.properties.push(Property::List(
+                        (*name).to_owned(),
+                        parse_scalar(count_ty).ok_or_else(parse_error)?,
+                        parse_scalar(item_ty).ok_or_else(parse_error)?,
+                    ));
+                }
+                ["property", ty, name] => {
+                    let element = elements.last_mut().ok_or_else(parse_error)?;
+                    element.properties.push(Property::Scalar(
+                        (*name).to_owned(),
+                        parse_scalar(ty).ok_or_else(parse_error)?,
+                    ));
+                }
+                // comment, obj_info
+                _ => {}
+            }
+        }
+        let encoding =
+            encoding.ok_or_else(|| AssetError::Parse("PLY Header has no format".to_owned()))?;
+        Ok((encoding, elements, body))
+    }
+}
+
+fn parse_scalar(ty: &str) -> Option<Scalar> {
+    Some(match ty {
+        "char" | "int8" => Scalar::I8,
+        "uchar" | "uint8" => Scalar::U8,
+        "short" | "int16" => Scalar::I16,
+        "ushort" | "uint16" => Scalar::U16,
+        "int" | "int32" => Scalar::I32,
+        "uint" | "uint32" => Scalar::U32,
+        "float" | "float32" => Scalar::F32,
+        "double" | "float64" => Scalar::F64,
+        _ => return None,
+    })
+}
+
+impl Scalar {
+    const fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+}
+
+impl Reader<'_> {
+    fn read(&mut self, ty: Scalar) -> Result<f64, AssetError> {
+        if self.encoding == Encoding::Ascii {
+            return self.read_ascii();
+        }
+        let size = ty.size();
+        let data = self
+            .bytes
+            .get(self.offset..self.offset + size)
+            .ok_or_else(|| AssetError::Parse("PLY Body is truncated".to_owned()))?;
+        self.offset += size;
+        let mut buffer = [0; 8];
+        buffer[..size].copy_from_slice(data);
+        if self.encoding == Encoding::BigEndian {
+            buffer[..size].reverse();
+        }
+        let [a, b, c, d, ..] = buffer;
+        Ok(match ty {
+            Scalar::I8 => a as i8 as f64,
+            Scalar::U8 => a as f64,
+            Scalar::I16 => i16::from_le_bytes([a, b]) as f64,
+            Scalar::U16 => u16::from_le_bytes([a, b]) as f64,
+            Scalar::I32 => i32::from_le_bytes([a, b, c, d]) as f64,
+            Scalar::U32 => u32::from_le_bytes([a, b, c, d]) as f64,
+            Scalar::F32 => f32::from_le_bytes([a, b, c, d]) as f64,
+            Scalar::F64 => f64::from_le_bytes(buffer),
+        })
+    }
+
+    fn read_ascii(&mut self) -> Result<f64, AssetError> {
+        let rest = &self.bytes[self.offset..];
+        let start = rest
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .ok_or_else(|| AssetError::Parse("PLY Body is truncated".to_owned()))?;
+        let len = rest[start..]
+            .iter()
+            .position(u8::is_ascii_whitespace)
+            .unwrap_or(rest.len() - start);
+        let word = &rest[start..start + len];
+        self.offset += start + len;
+        std::str::from_utf8(word)
+            .ok()
+            .and_then(|word| word.parse().ok())
+            .ok_or_else(|| {
+                AssetError::Parse(format!(
+                    "PLY Value {} is not a Number",
+                    String::from_utf8_lossy(word)
+                ))
+            })
+    }
+
+    fn skip(&mut self, property: &Property) -> Result<(), AssetError> {
+        match property {
+            Property::Scalar(_, ty) => {
+                self.read(*ty)?;
+            }
+            Property::List(_, count_ty, item_ty) => {
+                let count = self.read(*count_ty)? as usize;
+                for _ in 0..count {
+                    self.read(*item_ty)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_ascii_point_cloud() {
+        let source = "ply
+format ascii 1.0
+comment exported by a Scanner
+element vertex 3
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+end_header
+0 0 0 255 0 0
+1 2 3 0 255 0
+-1 0.5 2 0 0 255
+";
+        let path = crate::model::fixture("points.ply", source.as_bytes());
+        let model = PlyImporter::import(&path).unwrap();
+        let primitive = &model.meshes[0].primitives[0];
+        assert_eq!(
+            primitive.positions,
+            [[0.0, 0.0, 0.0], [1.0, 2.0, 3.0], [-1.0, 0.5, 2.0]]
+        );
+        assert_eq!(primitive.mode, PrimitiveMode::Points);
+        assert_eq!(primitive.indices, [0, 1, 2]);
+        // Points face up since there is no Surface
+        assert_eq!(primitive.normals, Some(vec![[0.0, 1.0, 0.0]; 3]));
+        assert_eq!(primitive.tex_coords, None);
+    }
+
+    #[test]
+    fn import_binary_point_cloud() {
+        let points = [
+            ([1.0f32, 2.0, 3.0], [0.0f32, 0.0, 1.0]),
+            ([4.0, 5.0, 6.0], [1.0, 0.0, 0.0]),
+        ];
+        let mut bytes = b"ply
+format binary_big_endian 1.0
+element vertex 2
+property float x
+property float y
+property float z
+property float nx
+property float ny
+property float nz
+property list uchar int unused
+end_header
+"
+        .to_vec();
+        for (position, normal) in points {
+            for value in position.iter().chain(&normal) {
+                bytes.extend(value.to_be_bytes());
+            }
+            // An List nobody reads, With one Item
+            bytes.push(1);
+            bytes.extend(7i32.to_be_bytes());
+        }
+        let path = crate::model::fixture("binary_points.ply", &bytes);
+        let model = PlyImporter::import(&path).unwrap();
+        let primitive = &model.meshes[0].primitives[0];
+        assert_eq!(primitive.positions, points.map(|(position, _)| position));
+        assert_eq!(
+            primitive.normals,
+            Some(points.map(|(_, normal)| normal).to_vec())
+        );
+        assert_eq!(primitive.mode, PrimitiveMode::Points);
+    }
+
+    #[test]
+    fn import_binary_faces() {
+        let mut bytes = b"ply
+format binary_little_endian 1.0
+element vertex 4
+property float x
+property float y
+property float z
+element face 1
+property list uchar uint vertex_indices
+end_header
+"
+        .to_vec();
+        for position in [
+            [0.0f32, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0],
+        ] {
+            for value in position {
+                bytes.extend(value.to_le_bytes());
+            }
+        }
+        bytes.push(4);
+        for index in [0u32, 1, 2, 3] {
+            bytes.extend(index.to_le_bytes());
+        }
+        let path = crate::model::fixture("quad.ply", &bytes);
+        let model = PlyImporter::import(&path).unwrap();
+        let primitive = &model.meshes[0].primitives[0];
+        assert_eq!(primitive.mode, PrimitiveMode::Triangles);
+        // The Quad is triangulated as an Fan
+        assert_eq!(primitive.indices, [0, 1, 2, 0, 2, 3]);
+        assert_eq!(primitive.normals, None);
+    }
+
+    #[test]
+    fn rejects_broken_files() {
+        let truncated = "ply\nformat ascii 1.0\nelement vertex 2\nproperty float x\nproperty float y\nproperty float z\nend_header\n0 0 0\n1 1\n";
+        let path = crate::model::fixture("truncated.ply", truncated.as_bytes());
+        assert!(matches!(
+            PlyImporter::import(&path),
+            Err(AssetError::Parse(_))
+        ));
+
+        let out_of_range = "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nproperty float y\nproperty float z\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n0 0 0\n3 0 1 2\n";
+        let path = crate::model::fixture("out_of_range.ply", out_of_range.as_bytes());
+        assert!(matches!(
+            PlyImporter::import(&path),
+            Err(AssetError::Parse(_))
+        ));
+
+        let path = crate::model::fixture("no_header.ply", b"ply\nformat ascii 1.0\n");
+        assert!(matches!(
+            PlyImporter::import(&path),
+            Err(AssetError::Parse(_))
+        ));
+    }
+}
@@ -0,0 +1,106 @@
+use std::{collections::HashMap, ffi::OsStr, path::Path};
+
+use crate::AssetError;
+
+use super::{
+    data::ModelData, gltf_importer::GltfImporter, modelz_importer::ModelzImporter,
+    obj_importer::ObjImporter, ply_importer::PlyImporter, stl_importer::StlImporter,
+};
+
+/// Imports an File into CPU side Model Data
+pub type ImportFn = fn(&Path) -> Result<ModelData, AssetError>;
+
+/// Maps File Extensions to Importers, So Games can add their own Formats.
+/// Extensions without an Importer are tried with modelz, Formats it can not read (e.g. FBX) fail with `AssetError::Unsupported`
+pub struct ImporterRegistry {
+    importers: HashMap<String, ImportFn>,
+}
+
+impl Default for ImporterRegistry {
+    /// Creates an Registry with the built in Importers: glTF, OBJ, STL and PLY
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register("gltf", GltfImporter::import);
+        registry.register("glb", GltfImporter::import);
+        registry.register("obj", ObjImporter::import);
+        registry.register("stl", StlImporter::import);
+        registry.register("ply", PlyImporter::import);
+        registry
+    }
+}
+
+impl ImporterRegistry {
+    /// Creates an Registry without any Importers
+    pub fn empty() -> Self {
+        Self {
+            importers: HashMap::new(),
+        }
+    }
+
+    /// Registers an Importer for an Extension (without the Dot), Replacing the previous one
+    pub fn register(&mut self, extension: &str, importer: ImportFn) {
+        self.importers
+            .insert(extension.to_ascii_lowercase(), importer);
+    }
+
+    pub fn importer(&self, extension: &str) -> Option<ImportFn> {
+        self.importers.get(&extension.to_ascii_lowercase()).copied()
+    }
+
+    /// All Extensions which have an Importer
+    pub fn extensions(&self) -> impl Iterator<Item = &str> {
+        self.importers.keys().map(String::as_str)
+    }
+
+    pub fn import(&self, path: &Path) -> Result<ModelData, AssetError> {
+        let Some(extension) = path.extension().and_then(OsStr::to_str) else {
+            return Err(AssetError::Unsupported(format!(
+                "{} has no File Extension",
+                path.display()
+            )));
+        };
+        match self.importer(extension) {
+            Some(importer) => importer(path),
+            None => ModelzImporter::import(path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_extension_is_unsupported() {
+        // Neither an own Importer nor modelz can read this
+        let path = crate::model::fixture("model.unknown", b"\0not a Model\0");
+        let registry = ImporterRegistry::default();
+        assert!(registry.importer("unknown").is_none());
+        assert!(matches!(
+            registry.import(&path),
+            Err(AssetError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn missing_extension_is_unsupported() {
+        let registry = ImporterRegistry::default();
+        assert!(matches!(
+            registry.import(Path::new("model")),
+            Err(AssetError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn register_replaces_importer() {
+        fn empty(_: &Path) -> Result<ModelData, AssetError> {
+            Ok(ModelData::default())
+        }
+        let mut registry = ImporterRegistry::default();
+        registry.register("OBJ", empty);
+        // The File does not exist, So only the new Importer can succeed
+        let model = registry.import(Path::new("missing.obj")).unwrap();
+        assert!(model.meshes.is_empty());
+        assert!(registry.extensions().any(|extension| extension == "stl"));
+    }
+}
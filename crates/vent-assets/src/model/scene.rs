@@ -0,0 +1,91 @@
+//! The Node Hierarchy of an Model File, Together with the Meshes, Cameras and Lights attached to its Nodes
+
+use super::data::{CameraData, LightData, NodeData};
+
+/// Node Indices are the same as in `Skeleton::nodes`, Which holds the Names and Transforms
+#[derive(Default)]
+pub struct Scene {
+    pub nodes: Vec<SceneNode>,
+    /// Nodes the shown Scene starts at
+    pub roots: Vec<usize>,
+    pub cameras: Vec<CameraData>,
+    pub lights: Vec<LightData>,
+    /// Every Node of the shown Scene which draws an Mesh, Per Mesh
+    mesh_nodes: Vec<Vec<usize>>,
+}
+
+pub struct SceneNode {
+    pub children: Vec<usize>,
+    pub mesh: Option<usize>,
+    pub skin: Option<usize>,
+    pub camera: Option<usize>,
+    pub light: Option<usize>,
+}
+
+impl Scene {
+    pub fn new(
+        nodes: &[NodeData],
+        roots: Vec<usize>,
+        cameras: Vec<CameraData>,
+        lights: Vec<LightData>,
+        mesh_count: usize,
+    ) -> Self {
+        let mut scene_nodes: Vec<SceneNode> = nodes
+            .iter()
+            .map(|node| SceneNode {
+                children: Vec::new(),
+                mesh: node.mesh.filter(|&mesh| mesh < mesh_count),
+                skin: node.skin,
+                camera: node.camera.filter(|&camera| camera < cameras.len()),
+                light: node.light.filter(|&light| light < lights.len()),
+            })
+            .collect();
+        for (i, node) in nodes.iter().enumerate() {
+            if let Some(parent) = node.parent.filter(|&parent| parent < nodes.len()) {
+                scene_nodes[parent].children.push(i);
+            }
+        }
+        let roots: Vec<usize> = roots
+            .into_iter()
+            .filter(|&root| root < nodes.len())
+            .collect();
+
+        let mut mesh_nodes = vec![Vec::new(); mesh_count];
+        let mut visited = vec![false; nodes.len()];
+        let mut stack = roots.clone();
+        while let Some(node) = stack.pop() {
+            if std::mem::replace(&mut visited[node], true) {
+                continue;
+            }
+            if let Some(mesh) = scene_nodes[node].mesh {
+                mesh_nodes[mesh].push(node);
+            }
+            stack.extend(&scene_nodes[node].children);
+        }
+
+        Self {
+            nodes: scene_nodes,
+            roots,
+            cameras,
+            lights,
+            mesh_nodes,
+        }
+    }
+
+    /// The Nodes an Mesh has to be drawn at, `None` means at the Origin of the Model.
+    /// Models without Nodes draw every Mesh once at their Origin
+    pub fn mesh_instances(&self, mesh: usize) -> impl Iterator<Item = Option<usize>> + '_ {
+        let origin = self.nodes.is_empty().then_some(None);
+        self.mesh_nodes
+            .get(mesh)
+            .map_or(&[][..], Vec::as_slice)
+            .iter()
+            .map(|&node| Some(node))
+            .chain(origin)
+    }
+
+    /// Skinned Meshes are placed by their Joints, The Transform of their own Node is ignored
+    pub fn is_skinned(&self, node: usize) -> bool {
+        self.nodes.get(node).is_some_and(|node| node.skin.is_some())
+    }
+}
@@ -0,0 +1,239 @@
+use std::path::Path;
+
+use crate::AssetError;
+
+use super::data::{MeshData, ModelData, PrimitiveData, PrimitiveMode};
+
+/// 80 Byte Header and the Triangle Count
+const BINARY_HEADER_SIZE: usize = 84;
+/// Normal, 3 Vertices and an Attribute Byte Count
+const BINARY_TRIANGLE_SIZE: usize = 50;
+
+/// Face Normal and the 3 Vertices
+type Triangle = ([f32; 3], [[f32; 3]; 3]);
+
+/// Imports binary and ASCII STL Files. STL only stores Triangles with an Face Normal,
+/// So every Triangle gets its own Vertices and the Model is flat shaded
+pub(crate) struct StlImporter {}
+
+impl StlImporter {
+    pub fn import(path: &Path) -> Result<ModelData, AssetError> {
+        let bytes = std::fs::read(path)?;
+        let triangles = if Self::is_binary(&bytes) {
+            Self::parse_binary(&bytes)
+        } else {
+            let source = std::str::from_utf8(&bytes)
+                .map_err(|_| AssetError::Parse("STL is neither binary nor ASCII".to_owned()))?;
+            Self::parse_ascii(source)?
+        };
+
+        let mut positions = Vec::with_capacity(triangles.len() * 3);
+        let mut normals = Vec::with_capacity(triangles.len() * 3);
+        for (normal, vertices) in triangles {
+            positions.extend(vertices);
+            normals.extend([normal; 3]);
+        }
+        // Many Exporters write zero Normals, The Loader generates flat ones since no Vertex is shared
+        let normals = normals
+            .iter()
+            .all(|&normal| normal != [0.0; 3])
+            .then_some(normals);
+
+        let name = path
+            .file_stem()
+            .map(|name| name.to_string_lossy().into_owned());
+        Ok(ModelData {
+            meshes: vec![MeshData {
+                name,
+                primitives: vec![PrimitiveData {
+                    indices: PrimitiveData::generate_indices(positions.len()),
+                    positions,
+                    normals,
+                    tex_coords: None,
+                    tex_coords1: None,
+                    tangents: None,
+                    joints: None,
+                    weights: None,
+                    morph_targets: Vec::new(),
+                    mode: PrimitiveMode::Triangles,
+                    material_index: None,
+                    // STL only knows Facets
+                    flat_normals: true,
+                }],
+                weights: Vec::new(),
+            }],
+            ..Default::default()
+        })
+    }
+
+    /// Binary Files can also start with "solid", So we check if the Size matches the Triangle Count
+    fn is_binary(bytes: &[u8]) -> bool {
+        bytes.len() >= BINARY_HEADER_SIZE && {
+            let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+            BINARY_HEADER_SIZE + count * BINARY_TRIANGLE_SIZE == bytes.len()
+                || !bytes.starts_with(b"solid")
+        }
+    }
+
+    fn parse_binary(bytes: &[u8]) -> Vec<Triangle> {
+        let vec3 = |data: &[u8]| -> [f32; 3] {
+            std::array::from_fn(|i| {
+                f32::from_le_bytes([
+                    data[i * 4],
+                    data[i * 4 + 1],
+                    data[i * 4 + 2],
+                    data[i * 4 + 3],
+                ])
+            })
+        };
+        // A truncated File loses its last Triangles
+        bytes[BINARY_HEADER_SIZE..]
+            .chunks_exact(BINARY_TRIANGLE_SIZE)
+            .map(|triangle| {
+                (
+                    vec3(&triangle[0..12]),
+                    [
+                        vec3(&triangle[12..24]),
+                        vec3(&triangle[24..36]),
+                        vec3(&triangle[36..48]),
+                    ],
+                )
+            })
+            .collect()
+    }
+
+    fn parse_ascii(source: &str) -> Result<Vec<Triangle>, AssetError> {
+        let mut triangles = Vec::new();
+        let mut normal = [0.0; 3];
+        let mut vertices = Vec::with_capacity(3);
+        for line in source.lines() {
+            let mut words = line.split_whitespace();
+            let vec3 = |words: &mut std::str::SplitWhitespace| -> Result<[f32; 3], AssetError> {
+                let mut value = [0.0; 3];
+                for component in &mut value {
+                    *component = words
+                        .next()
+                        .and_then(|word| word.parse().ok())
+                        .ok_or_else(|| AssetError::Parse(format!("STL: {}", line.trim())))?;
+                }
+                Ok(value)
+            };
+            match words.next() {
+                Some("facet") => {
+                    words.next(); // "normal"
+                    normal = vec3(&mut words)?;
+                    vertices.clear();
+                }
+                Some("vertex") => vertices.push(vec3(&mut words)?),
+                Some("endfacet") => {
+                    let [a, b, c] = vertices[..] else {
+                        return Err(AssetError::Parse(format!(
+                            "STL Facet has {} Vertices, Expected 3",
+                            vertices.len()
+                        )));
+                    };
+                    triangles.push((normal, [a, b, c]));
+                }
+                _ => {}
+            }
+        }
+        Ok(triangles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASCII: &str = "solid quad
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 1 1 0
+    endloop
+  endfacet
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 1 0
+      vertex 0 1 0
+    endloop
+  endfacet
+endsolid quad
+";
+
+    fn binary(header: &[u8], triangles: &[Triangle]) -> Vec<u8> {
+        let mut bytes = vec![0; 80];
+        bytes[..header.len()].copy_from_slice(header);
+        bytes.extend((triangles.len() as u32).to_le_bytes());
+        for (normal, vertices) in triangles {
+            for value in normal.iter().chain(vertices.iter().flatten()) {
+                bytes.extend(value.to_le_bytes());
+            }
+            // Attribute Byte Count
+            bytes.extend([0, 0]);
+        }
+        bytes
+    }
+
+    #[test]
+    fn import_ascii() {
+        let path = crate::model::fixture("ascii.stl", ASCII.as_bytes());
+        let model = StlImporter::import(&path).unwrap();
+        assert_eq!(model.meshes.len(), 1);
+        assert_eq!(model.meshes[0].name.as_deref(), Some("ascii"));
+        let primitive = &model.meshes[0].primitives[0];
+        assert_eq!(
+            primitive.positions,
+            [
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0],
+                [1.0, 1.0, 0.0],
+                [0.0, 1.0, 0.0],
+            ]
+        );
+        assert_eq!(primitive.normals, Some(vec![[0.0, 0.0, 1.0]; 6]));
+        assert_eq!(primitive.indices, [0, 1, 2, 3, 4, 5]);
+        assert_eq!(primitive.mode, PrimitiveMode::Triangles);
+        assert!(primitive.flat_normals);
+    }
+
+    #[test]
+    fn import_binary() {
+        let triangle = (
+            [0.0, 0.0, -1.0],
+            [[0.0; 3], [0.0, 2.0, 0.0], [2.0, 0.0, 0.0]],
+        );
+        let path = crate::model::fixture("binary.stl", &binary(b"", &[triangle]));
+        let model = StlImporter::import(&path).unwrap();
+        let primitive = &model.meshes[0].primitives[0];
+        assert_eq!(primitive.positions, triangle.1);
+        assert_eq!(primitive.normals, Some(vec![triangle.0; 3]));
+        assert_eq!(primitive.indices, [0, 1, 2]);
+    }
+
+    #[test]
+    fn import_binary_with_solid_header() {
+        // Some Exporters start the binary Header with "solid" too, And leave the Normals zero
+        let triangle = ([0.0; 3], [[0.0; 3], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]);
+        let bytes = binary(b"solid exported", &[triangle, triangle]);
+        let path = crate::model::fixture("solid_header.stl", &bytes);
+        let model = StlImporter::import(&path).unwrap();
+        let primitive = &model.meshes[0].primitives[0];
+        assert_eq!(primitive.positions.len(), 6);
+        assert_eq!(primitive.normals, None);
+    }
+
+    #[test]
+    fn rejects_broken_facets() {
+        let source = "solid broken\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nendloop\nendfacet\nendsolid broken\n";
+        let path = crate::model::fixture("broken.stl", source.as_bytes());
+        assert!(matches!(
+            StlImporter::import(&path),
+            Err(AssetError::Parse(_))
+        ));
+    }
+}
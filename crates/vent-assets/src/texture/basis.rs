@@ -0,0 +1,571 @@
+//! Basis Universal Textures inside KTX2, See https://github.khronos.org/KTX-Specification/ktxspec.v2.html#basislz_gd
+//! ETC1S (BasisLZ) and UASTC can not be sampled directly, So they are transcoded into BC1 or BC3 while loading
+
+use ash::vk;
+use basis_universal::{
+    DecodeFlags, LowLevelUastcTranscoder, SliceParametersUastc, TranscodeParameters, Transcoder,
+    TranscoderBlockFormat, TranscoderTextureFormat,
+};
+use vent_rendering::image::CompressedImage;
+
+use crate::AssetError;
+
+use super::ktx2::{
+    self, read_u32, SUPERCOMPRESSION_BASIS_LZ, SUPERCOMPRESSION_NONE, SUPERCOMPRESSION_ZLIB,
+    SUPERCOMPRESSION_ZSTD,
+};
+
+/// Color Models of the Data Format Descriptor
+const MODEL_ETC1S: u8 = 163;
+const MODEL_UASTC: u8 = 166;
+const TRANSFER_SRGB: u8 = 2;
+
+/// Channel IDs of the Samples which say that an Texture has Alpha
+const CHANNEL_ETC1S_AAA: u8 = 15;
+const CHANNEL_UASTC_RGBA: u8 = 3;
+const CHANNEL_UASTC_RRRG: u8 = 5;
+
+/// Endpoint and Selector Count, Then the Byte Lengths of Endpoints, Selectors, Tables and Extended Data
+const GLOBAL_HEADER_SIZE: usize = 20;
+/// Image Flags, RGB Slice Offset and Length, Alpha Slice Offset and Length
+const IMAGE_DESC_SIZE: usize = 20;
+
+/// Sizes of the `.basis` File Header and Slice Descriptions, All Fields are packed
+const BASIS_HEADER_SIZE: usize = 77;
+const BASIS_SLICE_DESC_SIZE: usize = 23;
+const BASIS_SIGNATURE: u32 = (b'B' as u32) << 8 | b's' as u32;
+const BASIS_VERSION: u32 = 0x13;
+const BASIS_FLAG_ETC1S: u32 = 1;
+const BASIS_FLAG_HAS_ALPHA_SLICES: u32 = 4;
+const BASIS_FLAG_SRGB: u32 = 16;
+const BASIS_SLICE_HAS_ALPHA: u32 = 1;
+
+/// How the Texture was encoded, Read from the Data Format Descriptor
+struct Descriptor {
+    model: u8,
+    srgb: bool,
+    alpha: bool,
+}
+
+impl Descriptor {
+    fn parse(dfd: &[u8]) -> Result<Self, AssetError> {
+        // Total Size, Vendor & Type, Version and Block Size come before the Color Model
+        let header = dfd.get(4..28).ok_or_else(|| {
+            AssetError::Parse("KTX2 Data Format Descriptor is too short".to_owned())
+        })?;
+        let model = header[8];
+        let block_size = u16::from_le_bytes([header[6], header[7]]) as usize;
+        let sample_count = block_size.saturating_sub(24) / 16;
+        let alpha = (0..sample_count)
+            .filter_map(|sample| dfd.get(28 + sample * 16 + 3))
+            .map(|channel| channel & 0xF)
+            .any(|channel| match model {
+                MODEL_ETC1S => channel == CHANNEL_ETC1S_AAA,
+                _ => channel == CHANNEL_UASTC_RGBA || channel == CHANNEL_UASTC_RRRG,
+            });
+        Ok(Self {
+            model,
+            srgb: header[10] == TRANSFER_SRGB,
+            alpha,
+        })
+    }
+
+    /// BC3 when the Texture needs Alpha, BC1 otherwise
+    fn target(&self) -> (vk::Format, TranscoderTextureFormat, TranscoderBlockFormat) {
+        match (self.alpha, self.srgb) {
+            (true, false) => (
+                vk::Format::BC3_UNORM_BLOCK,
+                TranscoderTextureFormat::BC3_RGBA,
+                TranscoderBlockFormat::BC3,
+            ),
+            (true, true) => (
+                vk::Format::BC3_SRGB_BLOCK,
+                TranscoderTextureFormat::BC3_RGBA,
+                TranscoderBlockFormat::BC3,
+            ),
+            (false, false) => (
+                vk::Format::BC1_RGB_UNORM_BLOCK,
+                TranscoderTextureFormat::BC1_RGB,
+                TranscoderBlockFormat::BC1,
+            ),
+            (false, true) => (
+                vk::Format::BC1_RGB_SRGB_BLOCK,
+                TranscoderTextureFormat::BC1_RGB,
+                TranscoderBlockFormat::BC1,
+            ),
+        }
+    }
+}
+
+/// Transcodes the Levels of an KTX2 File with `vkFormat` UNDEFINED, `levels` are still supercompressed
+pub fn transcode(
+    width: u32,
+    height: u32,
+    dfd: &[u8],
+    supercompression: u32,
+    global_data: &[u8],
+    levels: &[&[u8]],
+) -> Result<CompressedImage, AssetError> {
+    let descriptor = Descriptor::parse(dfd)?;
+    let levels = match (descriptor.model, supercompression) {
+        (MODEL_UASTC, SUPERCOMPRESSION_NONE | SUPERCOMPRESSION_ZSTD | SUPERCOMPRESSION_ZLIB) => {
+            transcode_uastc(width, height, &descriptor, supercompression, levels)?
+        }
+        (MODEL_ETC1S, SUPERCOMPRESSION_BASIS_LZ) => {
+            transcode_etc1s(width, height, &descriptor, global_data, levels)?
+        }
+        (MODEL_ETC1S | MODEL_UASTC, scheme) => {
+            return Err(AssetError::Parse(format!(
+                "KTX2 Supercompression {} does not fit Color Model {}",
+                scheme, descriptor.model
+            )))
+        }
+        (model, _) => {
+            return Err(AssetError::Unsupported(format!(
+                "KTX2 without Vulkan Format and Color Model {}",
+                model
+            )))
+        }
+    };
+    Ok(CompressedImage {
+        format: descriptor.target().0,
+        width,
+        height,
+        levels,
+    })
+}
+
+/// UASTC Levels are just 4x4 Blocks of 16 Bytes, Every Level can be transcoded on its own
+fn transcode_uastc(
+    width: u32,
+    height: u32,
+    descriptor: &Descriptor,
+    supercompression: u32,
+    levels: &[&[u8]],
+) -> Result<Vec<Vec<u8>>, AssetError> {
+    let (_, _, block_format) = descriptor.target();
+    let transcoder = LowLevelUastcTranscoder::new();
+    levels
+        .iter()
+        .zip(0..)
+        .map(|(data, level)| {
+            let data = ktx2::decompress(data, supercompression)?;
+            let (level_width, level_height) = ((width >> level).max(1), (height >> level).max(1));
+            let (blocks_x, blocks_y) = (level_width.div_ceil(4), level_height.div_ceil(4));
+            let expected = (blocks_x * blocks_y) as usize * 16;
+            if data.len() != expected {
+                return Err(AssetError::Parse(format!(
+                    "UASTC Level {} has {} Bytes, Expected {}",
+                    level,
+                    data.len(),
+                    expected
+                )));
+            }
+            transcoder
+                .transcode_slice(
+                    &data,
+                    SliceParametersUastc {
+                        num_blocks_x: blocks_x,
+                        num_blocks_y: blocks_y,
+                        has_alpha: descriptor.alpha,
+                        original_width: level_width,
+                        original_height: level_height,
+                    },
+                    DecodeFlags::HIGH_QUALITY,
+                    block_format,
+                )
+                .map_err(|err| {
+                    AssetError::Parse(format!(
+                        "Failed to transcode UASTC Level {}: {:?}",
+                        level, err
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// ETC1S Slices share the Codebooks and Huffman Tables of the Supercompression Global Data.
+/// The Transcoder only reads `.basis` Files, So the Parts are put back together into one
+fn transcode_etc1s(
+    width: u32,
+    height: u32,
+    descriptor: &Descriptor,
+    global_data: &[u8],
+    levels: &[&[u8]],
+) -> Result<Vec<Vec<u8>>, AssetError> {
+    let basis = etc1s_to_basis(width, height, descriptor, global_data, levels)?;
+    let (_, texture_format, _) = descriptor.target();
+
+    let mut transcoder = Transcoder::new();
+    transcoder
+        .prepare_transcoding(&basis)
+        .map_err(|()| AssetError::Parse("Invalid ETC1S Codebooks".to_owned()))?;
+    let levels = (0..levels.len() as u32)
+        .map(|level| {
+            transcoder
+                .transcode_image_level(
+                    &basis,
+                    texture_format,
+                    TranscodeParameters {
+                        image_index: 0,
+                        level_index: level,
+                        ..Default::default()
+                    },
+                )
+                .map_err(|err| {
+                    AssetError::Parse(format!(
+                        "Failed to transcode ETC1S Level {}: {:?}",
+                        level, err
+                    ))
+                })
+        })
+        .collect();
+    transcoder.end_transcoding();
+    levels
+}
+
+fn etc1s_to_basis(
+    width: u32,
+    height: u32,
+    descriptor: &Descriptor,
+    global_data: &[u8],
+    levels: &[&[u8]],
+) -> Result<Vec<u8>, AssetError> {
+    let out_of_bounds = || AssetError::Parse("BasisLZ Global Data is out of Bounds".to_owned());
+    let read_u16 = |offset: usize| -> Result<u32, AssetError> {
+        let bytes = global_data
+            .get(offset..offset + 2)
+            .ok_or_else(out_of_bounds)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]) as u32)
+    };
+    if width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err(AssetError::Unsupported(format!(
+            "ETC1S Image size {}x{}",
+            width, height
+        )));
+    }
+    let endpoint_count = read_u16(0)?;
+    let selector_count = read_u16(2)?;
+    let endpoints_length = read_u32(global_data, 4)? as usize;
+    let selectors_length = read_u32(global_data, 8)? as usize;
+    let tables_length = read_u32(global_data, 12)? as usize;
+
+    // One Image Description per Level, The Codebooks follow them
+    let codebooks = GLOBAL_HEADER_SIZE + levels.len() * IMAGE_DESC_SIZE;
+    let codebooks = global_data
+        .get(codebooks..codebooks + endpoints_length + selectors_length + tables_length)
+        .ok_or_else(out_of_bounds)?;
+
+    // RGB and optional Alpha Slice of every Level
+    let mut slices = Vec::with_capacity(levels.len() * 2);
+    for (level, data) in levels.iter().enumerate() {
+        let desc = GLOBAL_HEADER_SIZE + level * IMAGE_DESC_SIZE;
+        let slice = |offset: usize| -> Result<&[u8], AssetError> {
+            let start = read_u32(global_data, desc + offset)? as usize;
+            let length = read_u32(global_data, desc + offset + 4)? as usize;
+            data.get(start..start + length).ok_or_else(|| {
+                AssetError::Parse(format!("ETC1S Slice of Level {} is out of Bounds", level))
+            })
+        };
+        slices.push((level, slice(4)?, 0));
+        if descriptor.alpha {
+            slices.push((level, slice(12)?, BASIS_SLICE_HAS_ALPHA));
+        }
+    }
+
+    let slices_offset = BASIS_HEADER_SIZE + slices.len() * BASIS_SLICE_DESC_SIZE;
+    let data_offset = slices_offset + codebooks.len();
+    let size = data_offset + slices.iter().map(|(_, data, _)| data.len()).sum::<usize>();
+    let mut basis = Vec::with_capacity(size);
+    let mut put = |value: usize, bytes: usize| {
+        basis.extend_from_slice(&(value as u64).to_le_bytes()[..bytes]);
+    };
+
+    let mut flags = BASIS_FLAG_ETC1S;
+    if descriptor.alpha {
+        flags |= BASIS_FLAG_HAS_ALPHA_SLICES;
+    }
+    if descriptor.srgb {
+        flags |= BASIS_FLAG_SRGB;
+    }
+    put(BASIS_SIGNATURE as usize, 2);
+    put(BASIS_VERSION as usize, 2);
+    put(BASIS_HEADER_SIZE, 2);
+    put(0, 2); // Header CRC16, Only checked when validating the Checksums
+    put(size - BASIS_HEADER_SIZE, 4);
+    put(0, 2); // Data CRC16
+    put(slices.len(), 3);
+    put(1, 3); // Image Count
+    put(0, 1); // ETC1S
+    put(flags as usize, 2);
+    put(0, 1); // 2D Texture
+    put(0, 3); // Microseconds per Frame
+    put(0, 4); // Reserved
+    put(0, 8); // User Data
+    put(endpoint_count as usize, 2);
+    put(slices_offset, 4);
+    put(endpoints_length, 3);
+    put(selector_count as usize, 2);
+    put(slices_offset + endpoints_length, 4);
+    put(selectors_length, 3);
+    put(slices_offset + endpoints_length + selectors_length, 4);
+    put(tables_length, 4);
+    put(BASIS_HEADER_SIZE, 4); // Slice Descriptions follow the Header
+    put(0, 8); // Extended Data is only used by Videos
+
+    let mut offset = data_offset;
+    for &(level, data, slice_flags) in &slices {
+        let (level_width, level_height) = ((width >> level).max(1), (height >> level).max(1));
+        put(0, 3); // Image Index
+        put(level, 1);
+        put(slice_flags as usize, 1);
+        put(level_width as usize, 2);
+        put(level_height as usize, 2);
+        put(level_width.div_ceil(4) as usize, 2);
+        put(level_height.div_ceil(4) as usize, 2);
+        put(offset, 4);
+        put(data.len(), 4);
+        put(0, 2); // Slice CRC16
+        offset += data.len();
+    }
+    basis.extend_from_slice(codebooks);
+    for (_, data, _) in slices {
+        basis.extend_from_slice(data);
+    }
+    Ok(basis)
+}
+
+#[cfg(test)]
+mod tests {
+    use basis_universal::{BasisTextureFormat, Compressor, CompressorParams, UASTC_QUALITY_MIN};
+
+    use super::*;
+    use crate::texture::bcn;
+
+    /// 4 flat Quadrants, Alpha only differs when `alpha` is set
+    fn quadrants(alpha: bool) -> image::RgbaImage {
+        image::RgbaImage::from_fn(32, 32, |x, y| {
+            let quadrant = (x / 16 + y / 16 * 2) as usize;
+            let [r, g, b] =
+                [[200, 40, 40], [40, 200, 40], [40, 40, 200], [220, 220, 220]][quadrant];
+            image::Rgba([r, g, b, if alpha { 60 * quadrant as u8 + 50 } else { 255 }])
+        })
+    }
+
+    fn compress(image: &image::RgbaImage, format: BasisTextureFormat) -> Vec<u8> {
+        let mut params = CompressorParams::new();
+        params.set_basis_format(format);
+        params.set_uastc_quality_level(UASTC_QUALITY_MIN);
+        params.set_generate_mipmaps(true);
+        params
+            .source_image_mut(0)
+            .init(image.as_raw(), image.width(), image.height(), 4);
+        let mut compressor = Compressor::new(1);
+        unsafe {
+            assert!(compressor.init(&params));
+            compressor.process().unwrap();
+        }
+        compressor.basis_file().to_vec()
+    }
+
+    /// Puts the Slices of an `.basis` File into KTX2 like `basisu -ktx2` does
+    fn basis_to_ktx2(basis: &[u8], zstd: bool) -> Vec<u8> {
+        let transcoder = Transcoder::new();
+        let info = transcoder.file_info(basis).unwrap();
+        let level_count = transcoder.image_level_count(basis, 0);
+        let level_info: Vec<_> = (0..level_count)
+            .map(|level| transcoder.image_level_info(basis, 0, level).unwrap())
+            .collect();
+        let part = |offset: u32, length: u32| &basis[offset as usize..(offset + length) as usize];
+        let alpha = info.m_has_alpha_slices;
+
+        let (model, channels, supercompression, global_data, levels) = if info.m_etc1s {
+            let mut global_data = Vec::new();
+            global_data.extend_from_slice(&(info.m_total_endpoints as u16).to_le_bytes());
+            global_data.extend_from_slice(&(info.m_total_selectors as u16).to_le_bytes());
+            for length in [
+                info.m_endpoint_codebook_size,
+                info.m_selector_codebook_size,
+                info.m_tables_size,
+                0,
+            ] {
+                global_data.extend_from_slice(&length.to_le_bytes());
+            }
+            let mut levels = Vec::new();
+            for level in &level_info {
+                let alpha_length = if alpha { level.m_alpha_file_len } else { 0 };
+                for value in [
+                    0,
+                    0,
+                    level.m_rgb_file_len,
+                    level.m_rgb_file_len,
+                    alpha_length,
+                ] {
+                    global_data.extend_from_slice(&value.to_le_bytes());
+                }
+                let mut data = part(level.m_rgb_file_ofs, level.m_rgb_file_len).to_vec();
+                if alpha {
+                    data.extend_from_slice(part(level.m_alpha_file_ofs, level.m_alpha_file_len));
+                }
+                levels.push(data);
+            }
+            global_data.extend_from_slice(part(
+                info.m_endpoint_codebook_ofs,
+                info.m_endpoint_codebook_size,
+            ));
+            global_data.extend_from_slice(part(
+                info.m_selector_codebook_ofs,
+                info.m_selector_codebook_size,
+            ));
+            global_data.extend_from_slice(part(info.m_tables_ofs, info.m_tables_size));
+            let channels = if alpha {
+                vec![0, CHANNEL_ETC1S_AAA]
+            } else {
+                vec![0]
+            };
+            (
+                MODEL_ETC1S,
+                channels,
+                SUPERCOMPRESSION_BASIS_LZ,
+                global_data,
+                levels,
+            )
+        } else {
+            let levels = level_info
+                .iter()
+                .map(|level| {
+                    let data = part(level.m_rgb_file_ofs, level.m_rgb_file_len);
+                    if zstd {
+                        ruzstd::encoding::compress_to_vec(
+                            data,
+                            ruzstd::encoding::CompressionLevel::Fastest,
+                        )
+                    } else {
+                        data.to_vec()
+                    }
+                })
+                .collect();
+            let channel = if alpha { CHANNEL_UASTC_RGBA } else { 0 };
+            let supercompression = if zstd {
+                SUPERCOMPRESSION_ZSTD
+            } else {
+                SUPERCOMPRESSION_NONE
+            };
+            (
+                MODEL_UASTC,
+                vec![channel],
+                supercompression,
+                Vec::new(),
+                levels,
+            )
+        };
+
+        let mut dfd = Vec::new();
+        let block_size = 24 + 16 * channels.len();
+        dfd.extend_from_slice(&(4 + block_size as u32).to_le_bytes());
+        dfd.extend_from_slice(&0u32.to_le_bytes());
+        dfd.extend_from_slice(&2u16.to_le_bytes());
+        dfd.extend_from_slice(&(block_size as u16).to_le_bytes());
+        dfd.extend_from_slice(&[model, 1, 1, 0, 3, 3, 0, 0]);
+        dfd.extend_from_slice(&[0; 8]);
+        for channel in channels {
+            dfd.extend_from_slice(&[0, 0, 127, channel, 0, 0, 0, 0]);
+            dfd.extend_from_slice(&[0, 0, 0, 0, 255, 255, 255, 255]);
+        }
+
+        let dfd_offset = 80 + levels.len() * 24;
+        let global_offset = dfd_offset + dfd.len();
+        let mut bytes = vec![
+            0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n',
+        ];
+        let first = &level_info[0];
+        for value in [
+            0, // UNDEFINED
+            1,
+            first.m_orig_width,
+            first.m_orig_height,
+            0,
+            0,
+            1,
+            level_count,
+            supercompression,
+            dfd_offset as u32,
+            dfd.len() as u32,
+            0,
+            0,
+        ] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&(global_offset as u64).to_le_bytes());
+        bytes.extend_from_slice(&(global_data.len() as u64).to_le_bytes());
+        let mut offset = global_offset + global_data.len();
+        for data in &levels {
+            for value in [offset, data.len(), data.len()] {
+                bytes.extend_from_slice(&(value as u64).to_le_bytes());
+            }
+            offset += data.len();
+        }
+        bytes.extend_from_slice(&dfd);
+        bytes.extend_from_slice(&global_data);
+        levels.iter().for_each(|data| bytes.extend_from_slice(data));
+        bytes
+    }
+
+    /// Transcodes the KTX2 File and decodes the first Level again
+    fn round_trip(format: BasisTextureFormat, alpha: bool, zstd: bool) {
+        let source = quadrants(alpha);
+        let ktx2 = basis_to_ktx2(&compress(&source, format), zstd);
+        let image = ktx2::parse(&ktx2).unwrap();
+        let expected = if alpha {
+            vk::Format::BC3_UNORM_BLOCK
+        } else {
+            vk::Format::BC1_RGB_UNORM_BLOCK
+        };
+        assert_eq!(image.format, expected);
+        assert_eq!((image.width, image.height), (32, 32));
+        assert_eq!(image.levels.len(), 6);
+
+        let decoded = bcn::decode(&image).unwrap();
+        for (source, decoded) in source.pixels().zip(decoded.pixels()) {
+            for c in 0..4 {
+                assert!(
+                    source[c].abs_diff(decoded[c]) <= 16,
+                    "{:?} {:?} became {:?}",
+                    format,
+                    source,
+                    decoded
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn transcode_etc1s() {
+        round_trip(BasisTextureFormat::ETC1S, false, false);
+    }
+
+    #[test]
+    fn transcode_etc1s_alpha() {
+        round_trip(BasisTextureFormat::ETC1S, true, false);
+    }
+
+    #[test]
+    fn transcode_uastc() {
+        round_trip(BasisTextureFormat::UASTC4x4, false, false);
+    }
+
+    #[test]
+    fn transcode_uastc_zstd_alpha() {
+        round_trip(BasisTextureFormat::UASTC4x4, true, true);
+    }
+
+    #[test]
+    fn rejects_wrong_supercompression() {
+        let mut ktx2 = basis_to_ktx2(
+            &compress(&quadrants(false), BasisTextureFormat::ETC1S),
+            false,
+        );
+        ktx2[44..48].copy_from_slice(&SUPERCOMPRESSION_ZSTD.to_le_bytes());
+        assert!(matches!(ktx2::parse(&ktx2), Err(AssetError::Parse(_))));
+    }
+}
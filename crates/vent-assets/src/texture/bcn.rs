@@ -0,0 +1,288 @@
+//! BC1 - BC5 Block Compression. Decoding is used when the GPU can not sample an Format, Encoding by the offline `TextureEncoder`
+
+use ash::vk;
+use vent_rendering::image::CompressedImage;
+
+/// The 16 Pixels of an 4x4 Block, Row by Row
+pub type Block<T> = [T; 16];
+
+type DecodeBlock = fn(&[u8]) -> Block<[u8; 4]>;
+
+/// Decodes the first Level into RGBA8, `None` when the Format is not BC1 - BC5
+pub fn decode(image: &CompressedImage) -> Option<image::RgbaImage> {
+    use vk::Format as F;
+
+    let (block_bytes, decode_block): (usize, DecodeBlock) = match image.format {
+        F::BC1_RGB_UNORM_BLOCK | F::BC1_RGB_SRGB_BLOCK => {
+            (8, |block| decode_color(block, ColorMode::Opaque))
+        }
+        F::BC1_RGBA_UNORM_BLOCK | F::BC1_RGBA_SRGB_BLOCK => {
+            (8, |block| decode_color(block, ColorMode::Transparent))
+        }
+        F::BC2_UNORM_BLOCK | F::BC2_SRGB_BLOCK => (16, |block| {
+            let mut pixels = decode_color(&block[8..], ColorMode::FourColor);
+            for (i, pixel) in pixels.iter_mut().enumerate() {
+                // 4 Bit explicit Alpha
+                pixel[3] = ((block[i / 2] >> (4 * (i % 2))) & 0xF) * 17;
+            }
+            pixels
+        }),
+        F::BC3_UNORM_BLOCK | F::BC3_SRGB_BLOCK => (16, |block| {
+            let mut pixels = decode_color(&block[8..], ColorMode::FourColor);
+            let alpha = decode_alpha(block);
+            for (pixel, alpha) in pixels.iter_mut().zip(alpha) {
+                pixel[3] = alpha;
+            }
+            pixels
+        }),
+        F::BC4_UNORM_BLOCK => (8, |block| decode_alpha(block).map(|r| [r, r, r, 255])),
+        F::BC5_UNORM_BLOCK => (16, |block| {
+            let red = decode_alpha(block);
+            let green = decode_alpha(&block[8..]);
+            std::array::from_fn(|i| [red[i], green[i], 0, 255])
+        }),
+        _ => return None,
+    };
+
+    let data = image.levels.first()?;
+    let blocks_x = image.width.div_ceil(4);
+    let blocks_y = image.height.div_ceil(4);
+    if data.len() < (blocks_x * blocks_y) as usize * block_bytes {
+        return None;
+    }
+    let mut decoded = image::RgbaImage::new(image.width, image.height);
+    for (i, block) in data
+        .chunks_exact(block_bytes)
+        .take((blocks_x * blocks_y) as usize)
+        .enumerate()
+    {
+        let (block_x, block_y) = (i as u32 % blocks_x * 4, i as u32 / blocks_x * 4);
+        for (j, pixel) in decode_block(block).into_iter().enumerate() {
+            let (x, y) = (block_x + j as u32 % 4, block_y + j as u32 / 4);
+            if x < image.width && y < image.height {
+                decoded.put_pixel(x, y, image::Rgba(pixel));
+            }
+        }
+    }
+    Some(decoded)
+}
+
+/// Copies an 4x4 Block out of the Image, Pixels outside of the Image repeat the Edge
+pub fn extract_block(image: &image::RgbaImage, block_x: u32, block_y: u32) -> Block<[u8; 4]> {
+    std::array::from_fn(|i| {
+        let x = (block_x * 4 + i as u32 % 4).min(image.width() - 1);
+        let y = (block_y * 4 + i as u32 / 4).min(image.height() - 1);
+        image.get_pixel(x, y).0
+    })
+}
+
+fn unpack_565(color: u16) -> [u8; 3] {
+    let r = (color >> 11) & 0x1F;
+    let g = (color >> 5) & 0x3F;
+    let b = color & 0x1F;
+    [
+        ((r << 3) | (r >> 2)) as u8,
+        ((g << 2) | (g >> 4)) as u8,
+        ((b << 3) | (b >> 2)) as u8,
+    ]
+}
+
+fn pack_565(color: [u8; 3]) -> u16 {
+    let [r, g, b] = color.map(u16::from);
+    ((r * 31 + 127) / 255) << 11 | ((g * 63 + 127) / 255) << 5 | ((b * 31 + 127) / 255)
+}
+
+/// How an Color Block with `color0 <= color1` is read
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum ColorMode {
+    /// BC1 without Alpha, 3 Colors and Black
+    Opaque,
+    /// BC1 with Alpha, 3 Colors and transparent Black
+    Transparent,
+    /// BC2 and BC3 store Alpha separately, Their Color Blocks always have 4 Colors
+    FourColor,
+}
+
+/// The 4 Colors of an Color Block, In 3 Color Mode the last one is (transparent) Black
+fn color_palette(color0: u16, color1: u16, mode: ColorMode) -> [[u8; 4]; 4] {
+    let [c0, c1] = [color0, color1].map(unpack_565);
+    let mix = |a: u8, b: u8, wa: u16, wb: u16| ((a as u16 * wa + b as u16 * wb) / (wa + wb)) as u8;
+    let blend = |wa: u16, wb: u16| -> [u8; 4] {
+        [
+            mix(c0[0], c1[0], wa, wb),
+            mix(c0[1], c1[1], wa, wb),
+            mix(c0[2], c1[2], wa, wb),
+            255,
+        ]
+    };
+    let [c0, c1] = [c0, c1].map(|[r, g, b]| [r, g, b, 255]);
+    match mode {
+        _ if color0 > color1 => [c0, c1, blend(2, 1), blend(1, 2)],
+        ColorMode::FourColor => [c0, c1, blend(2, 1), blend(1, 2)],
+        ColorMode::Opaque => [c0, c1, blend(1, 1), [0, 0, 0, 255]],
+        ColorMode::Transparent => [c0, c1, blend(1, 1), [0, 0, 0, 0]],
+    }
+}
+
+/// BC1 Color Block, Also the Color Part of BC2 and BC3
+fn decode_color(block: &[u8], mode: ColorMode) -> Block<[u8; 4]> {
+    let color0 = u16::from_le_bytes([block[0], block[1]]);
+    let color1 = u16::from_le_bytes([block[2], block[3]]);
+    let palette = color_palette(color0, color1, mode);
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    std::array::from_fn(|i| palette[((indices >> (2 * i)) & 0x3) as usize])
+}
+
+fn alpha_palette(alpha0: u8, alpha1: u8) -> [u8; 8] {
+    let [a0, a1] = [alpha0, alpha1].map(u16::from);
+    let mix = |w0: u16, w1: u16, d: u16| ((a0 * w0 + a1 * w1) / d) as u8;
+    if alpha0 > alpha1 {
+        [
+            alpha0,
+            alpha1,
+            mix(6, 1, 7),
+            mix(5, 2, 7),
+            mix(4, 3, 7),
+            mix(3, 4, 7),
+            mix(2, 5, 7),
+            mix(1, 6, 7),
+        ]
+    } else {
+        [
+            alpha0,
+            alpha1,
+            mix(4, 1, 5),
+            mix(3, 2, 5),
+            mix(2, 3, 5),
+            mix(1, 4, 5),
+            0,
+            255,
+        ]
+    }
+}
+
+/// BC3 Alpha Block, Also used for every Channel of BC4 and BC5
+fn decode_alpha(block: &[u8]) -> Block<u8> {
+    let palette = alpha_palette(block[0], block[1]);
+    let mut bits = [0; 8];
+    bits[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(bits);
+    std::array::from_fn(|i| palette[((indices >> (3 * i)) & 0x7) as usize])
+}
+
+/// Encodes an opaque BC1 Color Block from the Bounding Box of the Colors
+pub fn encode_color(pixels: &Block<[u8; 4]>) -> [u8; 8] {
+    let mut min = [255u8; 3];
+    let mut max = [0u8; 3];
+    for pixel in pixels {
+        for c in 0..3 {
+            min[c] = min[c].min(pixel[c]);
+            max[c] = max[c].max(pixel[c]);
+        }
+    }
+    // Moving the Endpoints inside a bit reduces the Error for most Blocks
+    for c in 0..3 {
+        let inset = (max[c] - min[c]) / 16;
+        min[c] += inset;
+        max[c] -= inset;
+    }
+
+    let (mut color0, mut color1) = (pack_565(max), pack_565(min));
+    if color0 < color1 {
+        std::mem::swap(&mut color0, &mut color1);
+    }
+    let mut indices = 0u32;
+    // Equal Endpoints are in 3 Color Mode, Index 0 is still the Color we want
+    if color0 != color1 {
+        let palette = color_palette(color0, color1, ColorMode::Opaque);
+        for (i, pixel) in pixels.iter().enumerate() {
+            indices |= (nearest(&palette, |color| color_distance(color, pixel)) as u32) << (2 * i);
+        }
+    }
+
+    let mut block = [0; 8];
+    block[0..2].copy_from_slice(&color0.to_le_bytes());
+    block[2..4].copy_from_slice(&color1.to_le_bytes());
+    block[4..8].copy_from_slice(&indices.to_le_bytes());
+    block
+}
+
+/// Encodes an BC3 Alpha (or BC4 Channel) Block in 8 Value Mode
+pub fn encode_alpha(values: &Block<u8>) -> [u8; 8] {
+    let alpha0 = *values.iter().max().unwrap();
+    let alpha1 = *values.iter().min().unwrap();
+    let mut indices = 0u64;
+    if alpha0 != alpha1 {
+        let palette = alpha_palette(alpha0, alpha1);
+        for (i, &value) in values.iter().enumerate() {
+            indices |= (nearest(&palette, |&a| a.abs_diff(value) as u32) as u64) << (3 * i);
+        }
+    }
+
+    let mut block = [0; 8];
+    block[0] = alpha0;
+    block[1] = alpha1;
+    block[2..8].copy_from_slice(&indices.to_le_bytes()[..6]);
+    block
+}
+
+fn color_distance(a: &[u8; 4], b: &[u8; 4]) -> u32 {
+    (0..3)
+        .map(|c| (a[c] as i32 - b[c] as i32).pow(2) as u32)
+        .sum()
+}
+
+fn nearest<T>(palette: &[T], distance: impl Fn(&T) -> u32) -> usize {
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, value)| distance(value))
+        .map_or(0, |(i, _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bc3_image(color0: u16, color1: u16) -> CompressedImage {
+        let mut block = vec![255, 255, 0, 0, 0, 0, 0, 0];
+        block.extend_from_slice(&color0.to_le_bytes());
+        block.extend_from_slice(&color1.to_le_bytes());
+        // Every Pixel uses Index 3
+        block.extend_from_slice(&u32::MAX.to_le_bytes());
+        CompressedImage {
+            format: vk::Format::BC3_UNORM_BLOCK,
+            width: 4,
+            height: 4,
+            levels: vec![block],
+        }
+    }
+
+    #[test]
+    fn bc3_color_ignores_endpoint_order() {
+        let black = pack_565([0, 0, 0]);
+        let white = pack_565([255, 255, 255]);
+        // color0 <= color1 would be 3 Color Mode in BC1, Index 3 must still be an blend of the Endpoints
+        let decoded = decode(&bc3_image(black, white)).unwrap();
+        assert!(decoded
+            .pixels()
+            .all(|pixel| pixel.0 == [170, 170, 170, 255]));
+        let decoded = decode(&bc3_image(white, black)).unwrap();
+        assert!(decoded.pixels().all(|pixel| pixel.0 == [85, 85, 85, 255]));
+    }
+
+    #[test]
+    fn bc1_three_color_mode() {
+        let block = [0, 0, 255, 255, 255, 255, 255, 255];
+        let image = |format| CompressedImage {
+            format,
+            width: 4,
+            height: 4,
+            levels: vec![block.to_vec()],
+        };
+        let opaque = decode(&image(vk::Format::BC1_RGB_UNORM_BLOCK)).unwrap();
+        assert_eq!(opaque.get_pixel(0, 0).0, [0, 0, 0, 255]);
+        let transparent = decode(&image(vk::Format::BC1_RGBA_UNORM_BLOCK)).unwrap();
+        assert_eq!(transparent.get_pixel(0, 0).0, [0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,362 @@
+//! DirectDraw Surface, Only 2D Textures with Block compressed or 32 Bit RGBA Data are supported.
+//! Files are written with an DX10 Header, So the glTF Exporter can store BCn Textures with MSFT_texture_dds
+
+use ash::vk;
+use vent_rendering::image::CompressedImage;
+
+use crate::AssetError;
+
+use super::{format, ktx2::read_u32};
+
+const MAGIC: &[u8; 4] = b"DDS ";
+/// Magic and Header
+const HEADER_SIZE: usize = 128;
+/// Follows the Header when the FourCC is DX10
+const DX10_HEADER_SIZE: usize = 20;
+
+/// Caps, Height, Width and Pixel Format
+const FLAGS_REQUIRED: u32 = 0x1 | 0x2 | 0x4 | 0x1000;
+const FLAG_MIPMAP_COUNT: u32 = 0x20000;
+const FLAG_LINEAR_SIZE: u32 = 0x80000;
+const PIXEL_FORMAT_FOURCC: u32 = 0x4;
+const PIXEL_FORMAT_RGB: u32 = 0x40;
+const CAPS_COMPLEX: u32 = 0x8;
+const CAPS_TEXTURE: u32 = 0x1000;
+const CAPS_MIPMAP: u32 = 0x400000;
+const CAPS2_CUBEMAP: u32 = 0x200;
+const CAPS2_VOLUME: u32 = 0x200000;
+const DX10_MISC_TEXTURECUBE: u32 = 0x4;
+const DX10_DIMENSION_TEXTURE2D: u32 = 3;
+/// DXGI has no BC1 without Alpha, The Alpha Mode in the second Misc Flags tells them apart
+const DX10_ALPHA_MODE_OPAQUE: u32 = 3;
+
+pub fn is_dds(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+pub fn parse(bytes: &[u8]) -> Result<CompressedImage, AssetError> {
+    if !is_dds(bytes) {
+        return Err(AssetError::Parse("Not an DDS File".to_owned()));
+    }
+    let flags = read_u32(bytes, 8)?;
+    let height = read_u32(bytes, 12)?;
+    let width = read_u32(bytes, 16)?;
+    let level_count = if flags & FLAG_MIPMAP_COUNT != 0 {
+        read_u32(bytes, 28)?.max(1)
+    } else {
+        1
+    };
+    let pixel_flags = read_u32(bytes, 80)?;
+    let four_cc = read_u32(bytes, 84)?.to_le_bytes();
+    let caps2 = read_u32(bytes, 112)?;
+    if caps2 & (CAPS2_CUBEMAP | CAPS2_VOLUME) != 0 {
+        return Err(AssetError::Unsupported(
+            "DDS Cubemap and Volume Textures".to_owned(),
+        ));
+    }
+
+    let mut data_offset = HEADER_SIZE;
+    let format = if pixel_flags & PIXEL_FORMAT_FOURCC != 0 {
+        if &four_cc == b"DX10" {
+            data_offset += DX10_HEADER_SIZE;
+            let misc_flags = read_u32(bytes, HEADER_SIZE + 8)?;
+            let array_size = read_u32(bytes, HEADER_SIZE + 12)?;
+            if array_size > 1 || misc_flags & DX10_MISC_TEXTURECUBE != 0 {
+                return Err(AssetError::Unsupported(
+                    "DDS Texture Arrays and Cubemaps".to_owned(),
+                ));
+            }
+            let dxgi_format = read_u32(bytes, HEADER_SIZE)?;
+            let format = dxgi_format_to_vk(dxgi_format).ok_or_else(|| {
+                AssetError::Unsupported(format!("DDS DXGI Format {}", dxgi_format))
+            })?;
+            match (format, read_u32(bytes, HEADER_SIZE + 16)? & 0x7) {
+                (vk::Format::BC1_RGBA_UNORM_BLOCK, DX10_ALPHA_MODE_OPAQUE) => {
+                    vk::Format::BC1_RGB_UNORM_BLOCK
+                }
+                (vk::Format::BC1_RGBA_SRGB_BLOCK, DX10_ALPHA_MODE_OPAQUE) => {
+                    vk::Format::BC1_RGB_SRGB_BLOCK
+                }
+                (format, _) => format,
+            }
+        } else {
+            four_cc_to_vk(&four_cc).ok_or_else(|| {
+                AssetError::Unsupported(format!("DDS FourCC {}", String::from_utf8_lossy(&four_cc)))
+            })?
+        }
+    } else if pixel_flags & PIXEL_FORMAT_RGB != 0 && read_u32(bytes, 88)? == 32 {
+        // Only the Position of Red matters, Alpha is always in the highest Byte
+        match read_u32(bytes, 92)? {
+            0x0000_00FF => vk::Format::R8G8B8A8_UNORM,
+            0x00FF_0000 => vk::Format::B8G8R8A8_UNORM,
+            mask => return Err(AssetError::Unsupported(format!("DDS Red Mask {:#x}", mask))),
+        }
+    } else {
+        return Err(AssetError::Unsupported("DDS Pixel Format".to_owned()));
+    };
+    // Every Format we map has an known Layout
+    let block = format::block_info(format).expect("Missing Block Info");
+    format::check_size(width, height, level_count)?;
+
+    let mut offset = data_offset;
+    let levels = (0..level_count)
+        .map(|level| {
+            let size = block.level_size(width, height, level);
+            let data = bytes.get(offset..offset + size).ok_or_else(|| {
+                AssetError::Parse(format!("DDS Level {} is out of Bounds", level))
+            })?;
+            offset += size;
+            Ok(data.to_vec())
+        })
+        .collect::<Result<_, AssetError>>()?;
+
+    Ok(CompressedImage {
+        format,
+        width,
+        height,
+        levels,
+    })
+}
+
+/// Writes an DDS File with an DX10 Header, Only Formats which have an DXGI Format are supported
+pub fn write(image: &CompressedImage) -> Result<Vec<u8>, AssetError> {
+    let unsupported = || AssetError::Unsupported(format!("Writing DDS {:?}", image.format));
+    let (dxgi_format, alpha_mode) = match image.format {
+        vk::Format::BC1_RGB_UNORM_BLOCK => (71, DX10_ALPHA_MODE_OPAQUE),
+        vk::Format::BC1_RGB_SRGB_BLOCK => (72, DX10_ALPHA_MODE_OPAQUE),
+        format => (vk_to_dxgi_format(format).ok_or_else(unsupported)?, 0),
+    };
+    let block = format::block_info(image.format).ok_or_else(unsupported)?;
+    let level_count = image.levels.len() as u32;
+    format::check_size(image.width, image.height, level_count)?;
+
+    let mipmaps = level_count > 1;
+    let mut header = [0u32; (HEADER_SIZE + DX10_HEADER_SIZE) / 4];
+    header[0] = u32::from_le_bytes(*MAGIC);
+    header[1] = 124; // Header Size
+    header[2] = FLAGS_REQUIRED | FLAG_LINEAR_SIZE | if mipmaps { FLAG_MIPMAP_COUNT } else { 0 };
+    header[3] = image.height;
+    header[4] = image.width;
+    header[5] = block.level_size(image.width, image.height, 0) as u32;
+    header[7] = level_count;
+    header[19] = 32; // Pixel Format Size
+    header[20] = PIXEL_FORMAT_FOURCC;
+    header[21] = u32::from_le_bytes(*b"DX10");
+    header[27] = CAPS_TEXTURE
+        | if mipmaps {
+            CAPS_COMPLEX | CAPS_MIPMAP
+        } else {
+            0
+        };
+    header[32] = dxgi_format;
+    header[33] = DX10_DIMENSION_TEXTURE2D;
+    header[35] = 1; // Array Size
+    header[36] = alpha_mode;
+
+    let mut bytes =
+        Vec::with_capacity(header.len() * 4 + image.levels.iter().map(Vec::len).sum::<usize>());
+    for value in header {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    // Unlike KTX2 the largest Level comes first
+    for (level, data) in image.levels.iter().enumerate() {
+        if data.len() != block.level_size(image.width, image.height, level as u32) {
+            return Err(AssetError::Parse(format!(
+                "Level {} has the wrong Size for {:?}",
+                level, image.format
+            )));
+        }
+        bytes.extend_from_slice(data);
+    }
+    Ok(bytes)
+}
+
+/// Legacy DDS Files only know the DXT and ATI Formats
+fn four_cc_to_vk(four_cc: &[u8; 4]) -> Option<vk::Format> {
+    Some(match four_cc {
+        b"DXT1" => vk::Format::BC1_RGBA_UNORM_BLOCK,
+        b"DXT2" | b"DXT3" => vk::Format::BC2_UNORM_BLOCK,
+        b"DXT4" | b"DXT5" => vk::Format::BC3_UNORM_BLOCK,
+        b"ATI1" | b"BC4U" => vk::Format::BC4_UNORM_BLOCK,
+        b"BC4S" => vk::Format::BC4_SNORM_BLOCK,
+        b"ATI2" | b"BC5U" => vk::Format::BC5_UNORM_BLOCK,
+        b"BC5S" => vk::Format::BC5_SNORM_BLOCK,
+        _ => return None,
+    })
+}
+
+fn dxgi_format_to_vk(dxgi_format: u32) -> Option<vk::Format> {
+    Some(match dxgi_format {
+        2 => vk::Format::R32G32B32A32_SFLOAT,
+        10 => vk::Format::R16G16B16A16_SFLOAT,
+        28 => vk::Format::R8G8B8A8_UNORM,
+        29 => vk::Format::R8G8B8A8_SRGB,
+        49 => vk::Format::R8G8_UNORM,
+        61 => vk::Format::R8_UNORM,
+        71 => vk::Format::BC1_RGBA_UNORM_BLOCK,
+        72 => vk::Format::BC1_RGBA_SRGB_BLOCK,
+        74 => vk::Format::BC2_UNORM_BLOCK,
+        75 => vk::Format::BC2_SRGB_BLOCK,
+        77 => vk::Format::BC3_UNORM_BLOCK,
+        78 => vk::Format::BC3_SRGB_BLOCK,
+        80 => vk::Format::BC4_UNORM_BLOCK,
+        81 => vk::Format::BC4_SNORM_BLOCK,
+        83 => vk::Format::BC5_UNORM_BLOCK,
+        84 => vk::Format::BC5_SNORM_BLOCK,
+        87 => vk::Format::B8G8R8A8_UNORM,
+        91 => vk::Format::B8G8R8A8_SRGB,
+        95 => vk::Format::BC6H_UFLOAT_BLOCK,
+        96 => vk::Format::BC6H_SFLOAT_BLOCK,
+        98 => vk::Format::BC7_UNORM_BLOCK,
+        99 => vk::Format::BC7_SRGB_BLOCK,
+        _ => return None,
+    })
+}
+
+fn vk_to_dxgi_format(format: vk::Format) -> Option<u32> {
+    (0..=99).find(|&dxgi_format| dxgi_format_to_vk(dxgi_format) == Some(format))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Header of an DDS File, The Pixel Format is either an FourCC or 32 Bit RGBA with the given Red Mask
+    fn header(
+        width: u32,
+        height: u32,
+        levels: u32,
+        four_cc: Option<&[u8; 4]>,
+        red_mask: u32,
+    ) -> Vec<u8> {
+        let mut bytes = vec![0; HEADER_SIZE];
+        bytes[..4].copy_from_slice(MAGIC);
+        let mut set = |offset: usize, value: u32| {
+            bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes())
+        };
+        set(4, 124);
+        set(
+            8,
+            FLAGS_REQUIRED | if levels > 1 { FLAG_MIPMAP_COUNT } else { 0 },
+        );
+        set(12, height);
+        set(16, width);
+        set(28, levels);
+        set(76, 32);
+        match four_cc {
+            Some(four_cc) => {
+                set(80, PIXEL_FORMAT_FOURCC);
+                set(84, u32::from_le_bytes(*four_cc));
+            }
+            None => {
+                set(80, PIXEL_FORMAT_RGB);
+                set(88, 32);
+                set(92, red_mask);
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn parse_four_cc() {
+        let mut bytes = header(8, 4, 2, Some(b"DXT5"), 0);
+        // 2 Blocks and 1 Block
+        bytes.extend((0..48).map(|i| i as u8));
+        let image = parse(&bytes).unwrap();
+        assert_eq!(image.format, vk::Format::BC3_UNORM_BLOCK);
+        assert_eq!((image.width, image.height), (8, 4));
+        assert_eq!(image.levels.len(), 2);
+        assert_eq!(image.levels[0], (0..32).collect::<Vec<u8>>());
+        assert_eq!(image.levels[1], (32..48).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn parse_dx10() {
+        let mut bytes = header(4, 4, 1, Some(b"DX10"), 0);
+        let mut dx10 = [0; DX10_HEADER_SIZE];
+        dx10[..4].copy_from_slice(&99u32.to_le_bytes());
+        dx10[12..16].copy_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&dx10);
+        bytes.extend_from_slice(&[7; 16]);
+        let image = parse(&bytes).unwrap();
+        assert_eq!(image.format, vk::Format::BC7_SRGB_BLOCK);
+        assert_eq!(image.levels, vec![vec![7; 16]]);
+    }
+
+    #[test]
+    fn parse_rgba() {
+        let mut bytes = header(2, 2, 1, None, 0x00FF_0000);
+        bytes.extend_from_slice(&[1; 16]);
+        let image = parse(&bytes).unwrap();
+        assert_eq!(image.format, vk::Format::B8G8R8A8_UNORM);
+        assert_eq!(image.levels[0].len(), 16);
+
+        let mut bytes = header(2, 2, 1, None, 0x0000_FF00);
+        bytes.extend_from_slice(&[1; 16]);
+        assert!(matches!(parse(&bytes), Err(AssetError::Unsupported(_))));
+    }
+
+    #[test]
+    fn write_and_parse() {
+        for format in [
+            vk::Format::BC1_RGB_SRGB_BLOCK,
+            vk::Format::BC1_RGBA_UNORM_BLOCK,
+            vk::Format::BC3_SRGB_BLOCK,
+            vk::Format::BC7_UNORM_BLOCK,
+        ] {
+            let block = format::block_info(format).unwrap();
+            let image = CompressedImage {
+                format,
+                width: 8,
+                height: 4,
+                levels: (0..3)
+                    .map(|level| {
+                        let size = block.level_size(8, 4, level);
+                        (0..size).map(|i| (i + level as usize) as u8).collect()
+                    })
+                    .collect(),
+            };
+            let parsed = parse(&write(&image).unwrap()).unwrap();
+            assert_eq!(parsed.format, image.format);
+            assert_eq!((parsed.width, parsed.height), (8, 4));
+            assert_eq!(parsed.levels, image.levels);
+        }
+    }
+
+    #[test]
+    fn write_rejects_unsupported_formats() {
+        let image = CompressedImage {
+            format: vk::Format::ETC2_R8G8B8_UNORM_BLOCK,
+            width: 4,
+            height: 4,
+            levels: vec![vec![0; 8]],
+        };
+        assert!(matches!(write(&image), Err(AssetError::Unsupported(_))));
+    }
+
+    #[test]
+    fn rejects_empty_images() {
+        for (width, height) in [(0, 4), (4, 0)] {
+            let mut bytes = header(width, height, 1, Some(b"DXT1"), 0);
+            bytes.extend_from_slice(&[0; 8]);
+            assert!(matches!(parse(&bytes), Err(AssetError::Parse(_))));
+        }
+    }
+
+    #[test]
+    fn rejects_broken_files() {
+        // The Level needs 8 Bytes
+        let mut bytes = header(4, 4, 1, Some(b"DXT1"), 0);
+        bytes.extend_from_slice(&[0; 7]);
+        assert!(matches!(parse(&bytes), Err(AssetError::Parse(_))));
+        assert!(matches!(parse(&bytes[..64]), Err(AssetError::Parse(_))));
+        // An 4x4 Image has 3 Levels at most
+        let mut bytes = header(4, 4, 33, Some(b"DXT1"), 0);
+        bytes.extend_from_slice(&[0; 64]);
+        assert!(matches!(parse(&bytes), Err(AssetError::Parse(_))));
+        assert!(matches!(
+            parse(&header(4, 4, 1, Some(b"ETC1"), 0)),
+            Err(AssetError::Unsupported(_))
+        ));
+    }
+}
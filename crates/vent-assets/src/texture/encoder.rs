@@ -0,0 +1,209 @@
+use std::path::Path;
+
+use ash::vk;
+use image::{imageops::FilterType, DynamicImage};
+use vent_rendering::image::CompressedImage;
+
+use crate::AssetError;
+
+use super::{bcn, ktx2};
+
+/// Block Compressed Formats our Encoder can create
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BlockFormat {
+    /// RGB with 4 Bits per Pixel, Alpha is dropped
+    Bc1,
+    /// RGBA with 8 Bits per Pixel
+    Bc3,
+    /// Only the Red Channel, e.g. Occlusion
+    Bc4,
+    /// Red and Green Channel, e.g. Tangent Space Normal Maps
+    Bc5,
+}
+
+/// Compresses Images on the CPU, So Textures can be loaded with prebuilt Mip Levels and uploaded without Transcoding.
+/// This is an offline Import Step, Encoding is too slow to do it while Loading
+pub struct TextureEncoder {
+    pub format: BlockFormat,
+    /// Only used for BC1 and BC3, Set this for Base Color and Emissive Textures
+    pub srgb: bool,
+    /// Creates the full Mip Chain, Otherwise only the first Level
+    pub mipmaps: bool,
+}
+
+impl Default for TextureEncoder {
+    fn default() -> Self {
+        Self {
+            format: BlockFormat::Bc3,
+            srgb: false,
+            mipmaps: true,
+        }
+    }
+}
+
+impl TextureEncoder {
+    pub fn vk_format(&self) -> vk::Format {
+        match (self.format, self.srgb) {
+            (BlockFormat::Bc1, false) => vk::Format::BC1_RGB_UNORM_BLOCK,
+            (BlockFormat::Bc1, true) => vk::Format::BC1_RGB_SRGB_BLOCK,
+            (BlockFormat::Bc3, false) => vk::Format::BC3_UNORM_BLOCK,
+            (BlockFormat::Bc3, true) => vk::Format::BC3_SRGB_BLOCK,
+            (BlockFormat::Bc4, _) => vk::Format::BC4_UNORM_BLOCK,
+            (BlockFormat::Bc5, _) => vk::Format::BC5_UNORM_BLOCK,
+        }
+    }
+
+    pub fn encode(&self, image: &DynamicImage) -> Result<CompressedImage, AssetError> {
+        let (width, height) = (image.width(), image.height());
+        if width == 0 || height == 0 {
+            return Err(AssetError::Unsupported(format!(
+                "Encoding an {}x{} Image",
+                width, height
+            )));
+        }
+        let level_count = if self.mipmaps {
+            width.max(height).ilog2() + 1
+        } else {
+            1
+        };
+
+        let mut level = image.to_rgba8();
+        let mut levels = Vec::with_capacity(level_count as usize);
+        for i in 0..level_count {
+            if i > 0 {
+                // Downsampling the previous Level is much faster than the full Image each Time
+                level = image::imageops::resize(
+                    &level,
+                    (width >> i).max(1),
+                    (height >> i).max(1),
+                    FilterType::Triangle,
+                );
+            }
+            levels.push(self.encode_level(&level));
+        }
+
+        Ok(CompressedImage {
+            format: self.vk_format(),
+            width,
+            height,
+            levels,
+        })
+    }
+
+    /// Encodes any Image the `image` Crate can read into an KTX2 File
+    pub fn encode_file(&self, source: &Path, destination: &Path) -> Result<(), AssetError> {
+        let image = image::open(source)?;
+        let bytes = ktx2::write(&self.encode(&image)?)?;
+        std::fs::write(destination, bytes)?;
+        Ok(())
+    }
+
+    fn encode_level(&self, image: &image::RgbaImage) -> Vec<u8> {
+        let blocks_x = image.width().div_ceil(4);
+        let blocks_y = image.height().div_ceil(4);
+        let block_bytes = match self.format {
+            BlockFormat::Bc1 | BlockFormat::Bc4 => 8,
+            BlockFormat::Bc3 | BlockFormat::Bc5 => 16,
+        };
+
+        let mut data = Vec::with_capacity((blocks_x * blocks_y) as usize * block_bytes);
+        for block_y in 0..blocks_y {
+            for block_x in 0..blocks_x {
+                let pixels = bcn::extract_block(image, block_x, block_y);
+                let channel = |c: usize| pixels.map(|pixel| pixel[c]);
+                match self.format {
+                    BlockFormat::Bc1 => data.extend_from_slice(&bcn::encode_color(&pixels)),
+                    BlockFormat::Bc3 => {
+                        data.extend_from_slice(&bcn::encode_alpha(&channel(3)));
+                        data.extend_from_slice(&bcn::encode_color(&pixels));
+                    }
+                    BlockFormat::Bc4 => data.extend_from_slice(&bcn::encode_alpha(&channel(0))),
+                    BlockFormat::Bc5 => {
+                        data.extend_from_slice(&bcn::encode_alpha(&channel(0)));
+                        data.extend_from_slice(&bcn::encode_alpha(&channel(1)));
+                    }
+                }
+            }
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Smooth Gradients, Which Block Compression keeps close to the Source
+    fn gradient(width: u32, height: u32) -> image::RgbaImage {
+        image::RgbaImage::from_fn(width, height, |x, y| {
+            image::Rgba([
+                (x * 8) as u8,
+                (y * 8) as u8,
+                ((x + y) * 4) as u8,
+                255 - (x * 4) as u8,
+            ])
+        })
+    }
+
+    /// Largest Difference of the given Channels
+    fn max_error(a: &image::RgbaImage, b: &image::RgbaImage, channels: &[usize]) -> u8 {
+        a.pixels()
+            .zip(b.pixels())
+            .flat_map(|(a, b)| channels.iter().map(|&c| a[c].abs_diff(b[c])))
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        // Not an Multiple of 4, So the Edge Blocks are partial
+        let source = gradient(30, 18);
+        for (format, channels) in [
+            (BlockFormat::Bc1, &[0, 1, 2][..]),
+            (BlockFormat::Bc3, &[0, 1, 2, 3][..]),
+            (BlockFormat::Bc4, &[0][..]),
+            (BlockFormat::Bc5, &[0, 1][..]),
+        ] {
+            let encoder = TextureEncoder {
+                format,
+                srgb: false,
+                mipmaps: true,
+            };
+            let compressed = encoder
+                .encode(&DynamicImage::ImageRgba8(source.clone()))
+                .unwrap();
+            assert_eq!(compressed.levels.len(), 5);
+            let decoded = bcn::decode(&compressed).unwrap();
+            assert_eq!(decoded.dimensions(), source.dimensions());
+            // The Bounding Box Endpoints only fit Gradients along the Diagonal of the Block exactly
+            let error = max_error(&source, &decoded, channels);
+            assert!(error <= 24, "{:?} Error {}", format, error);
+        }
+    }
+
+    #[test]
+    fn encode_solid_color() {
+        let source = image::RgbaImage::from_pixel(4, 4, image::Rgba([255, 0, 0, 255]));
+        let compressed = TextureEncoder {
+            format: BlockFormat::Bc1,
+            srgb: false,
+            mipmaps: false,
+        }
+        .encode(&DynamicImage::ImageRgba8(source))
+        .unwrap();
+        let decoded = bcn::decode(&compressed).unwrap();
+        assert!(decoded.pixels().all(|pixel| pixel.0 == [255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn rejects_empty_images() {
+        let encoder = TextureEncoder::default();
+        for (width, height) in [(0, 4), (4, 0)] {
+            let image = DynamicImage::ImageRgba8(image::RgbaImage::new(width, height));
+            assert!(matches!(
+                encoder.encode(&image),
+                Err(AssetError::Unsupported(_))
+            ));
+        }
+    }
+}
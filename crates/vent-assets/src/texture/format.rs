@@ -0,0 +1,180 @@
+use ash::vk;
+
+use crate::AssetError;
+
+/// Size of the smallest Unit an Format stores, 1x1 for uncompressed Formats
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BlockInfo {
+    pub width: u32,
+    pub height: u32,
+    pub bytes: usize,
+}
+
+impl BlockInfo {
+    const fn new(width: u32, height: u32, bytes: usize) -> Self {
+        Self {
+            width,
+            height,
+            bytes,
+        }
+    }
+
+    /// Bytes of an Mip Level, Partial Blocks at the Edges are stored as whole Blocks
+    pub fn level_size(&self, width: u32, height: u32, level: u32) -> usize {
+        let width = (width >> level).max(1);
+        let height = (height >> level).max(1);
+        width.div_ceil(self.width) as usize * height.div_ceil(self.height) as usize * self.bytes
+    }
+}
+
+/// Rejects Images without Pixels and more Mip Levels than the Size allows
+pub fn check_size(width: u32, height: u32, level_count: u32) -> Result<(), AssetError> {
+    if width == 0 || height == 0 {
+        return Err(AssetError::Parse(format!(
+            "Image size {}x{} has no Pixels",
+            width, height
+        )));
+    }
+    let max_levels = width.max(height).ilog2() + 1;
+    if level_count > max_levels {
+        return Err(AssetError::Parse(format!(
+            "{} Mip Levels, An {}x{} Image has at most {}",
+            level_count, width, height, max_levels
+        )));
+    }
+    Ok(())
+}
+
+/// ASTC Block Sizes in the Order of the Vulkan Formats, Every Size has an UNORM and SRGB Format
+const ASTC_BLOCKS: [(u32, u32); 14] = [
+    (4, 4),
+    (5, 4),
+    (5, 5),
+    (6, 5),
+    (6, 6),
+    (8, 5),
+    (8, 6),
+    (8, 8),
+    (10, 5),
+    (10, 6),
+    (10, 8),
+    (10, 10),
+    (12, 10),
+    (12, 12),
+];
+
+/// Every Format we can load from KTX2 or DDS, `None` when we do not know its Layout
+pub fn block_info(format: vk::Format) -> Option<BlockInfo> {
+    use vk::Format as F;
+
+    if is_astc(format) {
+        let (width, height) =
+            ASTC_BLOCKS[((format.as_raw() - F::ASTC_4X4_UNORM_BLOCK.as_raw()) / 2) as usize];
+        return Some(BlockInfo::new(width, height, 16));
+    }
+    let info = match format {
+        F::R8_UNORM | F::R8_SRGB => BlockInfo::new(1, 1, 1),
+        F::R8G8_UNORM | F::R8G8_SRGB | F::R16_SFLOAT => BlockInfo::new(1, 1, 2),
+        F::R8G8B8A8_UNORM
+        | F::R8G8B8A8_SRGB
+        | F::B8G8R8A8_UNORM
+        | F::B8G8R8A8_SRGB
+        | F::R16G16_SFLOAT => BlockInfo::new(1, 1, 4),
+        F::R16G16B16A16_SFLOAT => BlockInfo::new(1, 1, 8),
+        F::R32G32B32A32_SFLOAT => BlockInfo::new(1, 1, 16),
+        F::BC1_RGB_UNORM_BLOCK
+        | F::BC1_RGB_SRGB_BLOCK
+        | F::BC1_RGBA_UNORM_BLOCK
+        | F::BC1_RGBA_SRGB_BLOCK
+        | F::BC4_UNORM_BLOCK
+        | F::BC4_SNORM_BLOCK
+        | F::ETC2_R8G8B8_UNORM_BLOCK
+        | F::ETC2_R8G8B8_SRGB_BLOCK
+        | F::ETC2_R8G8B8A1_UNORM_BLOCK
+        | F::ETC2_R8G8B8A1_SRGB_BLOCK
+        | F::EAC_R11_UNORM_BLOCK
+        | F::EAC_R11_SNORM_BLOCK => BlockInfo::new(4, 4, 8),
+        F::BC2_UNORM_BLOCK
+        | F::BC2_SRGB_BLOCK
+        | F::BC3_UNORM_BLOCK
+        | F::BC3_SRGB_BLOCK
+        | F::BC5_UNORM_BLOCK
+        | F::BC5_SNORM_BLOCK
+        | F::BC6H_UFLOAT_BLOCK
+        | F::BC6H_SFLOAT_BLOCK
+        | F::BC7_UNORM_BLOCK
+        | F::BC7_SRGB_BLOCK
+        | F::ETC2_R8G8B8A8_UNORM_BLOCK
+        | F::ETC2_R8G8B8A8_SRGB_BLOCK
+        | F::EAC_R11G11_UNORM_BLOCK
+        | F::EAC_R11G11_SNORM_BLOCK => BlockInfo::new(4, 4, 16),
+        _ => return None,
+    };
+    Some(info)
+}
+
+/// UNORM and SRGB Variants of the same Format
+const SRGB_PAIRS: [(vk::Format, vk::Format); 12] = [
+    (vk::Format::R8_UNORM, vk::Format::R8_SRGB),
+    (vk::Format::R8G8_UNORM, vk::Format::R8G8_SRGB),
+    (vk::Format::R8G8B8A8_UNORM, vk::Format::R8G8B8A8_SRGB),
+    (vk::Format::B8G8R8A8_UNORM, vk::Format::B8G8R8A8_SRGB),
+    (
+        vk::Format::BC1_RGB_UNORM_BLOCK,
+        vk::Format::BC1_RGB_SRGB_BLOCK,
+    ),
+    (
+        vk::Format::BC1_RGBA_UNORM_BLOCK,
+        vk::Format::BC1_RGBA_SRGB_BLOCK,
+    ),
+    (vk::Format::BC2_UNORM_BLOCK, vk::Format::BC2_SRGB_BLOCK),
+    (vk::Format::BC3_UNORM_BLOCK, vk::Format::BC3_SRGB_BLOCK),
+    (vk::Format::BC7_UNORM_BLOCK, vk::Format::BC7_SRGB_BLOCK),
+    (
+        vk::Format::ETC2_R8G8B8_UNORM_BLOCK,
+        vk::Format::ETC2_R8G8B8_SRGB_BLOCK,
+    ),
+    (
+        vk::Format::ETC2_R8G8B8A1_UNORM_BLOCK,
+        vk::Format::ETC2_R8G8B8A1_SRGB_BLOCK,
+    ),
+    (
+        vk::Format::ETC2_R8G8B8A8_UNORM_BLOCK,
+        vk::Format::ETC2_R8G8B8A8_SRGB_BLOCK,
+    ),
+];
+
+/// The Variant of the Format with the given Color Space, Formats without an SRGB Variant (e.g. BC4, BC5 or Floats) are returned as they are
+pub fn with_color_space(format: vk::Format, srgb: bool) -> vk::Format {
+    if is_astc(format) {
+        // ASTC alternates between UNORM and SRGB
+        let unorm = if is_unorm_astc(format) {
+            format.as_raw()
+        } else {
+            format.as_raw() - 1
+        };
+        return vk::Format::from_raw(unorm + srgb as i32);
+    }
+    SRGB_PAIRS
+        .iter()
+        .find(|&&(unorm, srgb_format)| unorm == format || srgb_format == format)
+        .map_or(
+            format,
+            |&(unorm, srgb_format)| {
+                if srgb {
+                    srgb_format
+                } else {
+                    unorm
+                }
+            },
+        )
+}
+
+fn is_astc(format: vk::Format) -> bool {
+    (vk::Format::ASTC_4X4_UNORM_BLOCK.as_raw()..=vk::Format::ASTC_12X12_SRGB_BLOCK.as_raw())
+        .contains(&format.as_raw())
+}
+
+fn is_unorm_astc(format: vk::Format) -> bool {
+    (format.as_raw() - vk::Format::ASTC_4X4_UNORM_BLOCK.as_raw()) % 2 == 0
+}
@@ -0,0 +1,344 @@
+//! KTX 2.0 Container, See https://registry.khronos.org/KTX/specs/2.0/ktxspec.v2.html
+
+use std::{fmt::Display, io::Read};
+
+use ash::vk;
+use ruzstd::decoding::StreamingDecoder;
+use vent_rendering::image::CompressedImage;
+
+use crate::AssetError;
+
+use super::{
+    basis,
+    format::{self, BlockInfo},
+};
+
+const IDENTIFIER: [u8; 12] = [
+    0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n',
+];
+/// Identifier, Header and Index
+const HEADER_SIZE: usize = 80;
+/// Byte Offset, Byte Length and Uncompressed Byte Length of every Level
+const LEVEL_INDEX_SIZE: usize = 24;
+
+pub(super) const SUPERCOMPRESSION_NONE: u32 = 0;
+pub(super) const SUPERCOMPRESSION_BASIS_LZ: u32 = 1;
+pub(super) const SUPERCOMPRESSION_ZSTD: u32 = 2;
+pub(super) const SUPERCOMPRESSION_ZLIB: u32 = 3;
+
+pub fn is_ktx2(bytes: &[u8]) -> bool {
+    bytes.starts_with(&IDENTIFIER)
+}
+
+pub fn parse(bytes: &[u8]) -> Result<CompressedImage, AssetError> {
+    if !is_ktx2(bytes) {
+        return Err(AssetError::Parse("Not an KTX2 File".to_owned()));
+    }
+    let format = vk::Format::from_raw(read_u32(bytes, 12)? as i32);
+    let width = read_u32(bytes, 20)?;
+    let height = read_u32(bytes, 24)?;
+    let depth = read_u32(bytes, 28)?;
+    let layer_count = read_u32(bytes, 32)?;
+    let face_count = read_u32(bytes, 36)?;
+    // 0 means the Mip Levels should be generated, We just use the first one
+    let level_count = read_u32(bytes, 40)?.max(1);
+    let supercompression = read_u32(bytes, 44)?;
+
+    if depth > 1 || layer_count > 1 || face_count != 1 {
+        return Err(AssetError::Unsupported(
+            "KTX2 3D, Array and Cubemap Textures".to_owned(),
+        ));
+    }
+    format::check_size(width, height, level_count)?;
+    let level_data = (0..level_count)
+        .map(|level| {
+            let index = HEADER_SIZE + level as usize * LEVEL_INDEX_SIZE;
+            section(bytes, read_u64(bytes, index)?, read_u64(bytes, index + 8)?)
+                .ok_or_else(|| AssetError::Parse(format!("KTX2 Level {} is out of Bounds", level)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Basis Universal has no Vulkan Format, The Data Format Descriptor says whether it is ETC1S or UASTC
+    if format == vk::Format::UNDEFINED {
+        let out_of_bounds = || AssetError::Parse("KTX2 Header is out of Bounds".to_owned());
+        let dfd = section(
+            bytes,
+            read_u32(bytes, 48)? as u64,
+            read_u32(bytes, 52)? as u64,
+        )
+        .ok_or_else(out_of_bounds)?;
+        let global_data =
+            section(bytes, read_u64(bytes, 64)?, read_u64(bytes, 72)?).ok_or_else(out_of_bounds)?;
+        return basis::transcode(
+            width,
+            height,
+            dfd,
+            supercompression,
+            global_data,
+            &level_data,
+        );
+    }
+    let Some(block) = format::block_info(format) else {
+        return Err(AssetError::Unsupported(format!("KTX2 Format {:?}", format)));
+    };
+
+    let levels = level_data
+        .into_iter()
+        .zip(0..)
+        .map(|(data, level)| {
+            let data = decompress(data, supercompression)?;
+            let expected = block.level_size(width, height, level);
+            if data.len() != expected {
+                return Err(AssetError::Parse(format!(
+                    "KTX2 Level {} has {} Bytes, Expected {}",
+                    level,
+                    data.len(),
+                    expected
+                )));
+            }
+            Ok(data)
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(CompressedImage {
+        format,
+        width,
+        height,
+        levels,
+    })
+}
+
+/// `length` Bytes at `offset`, `None` when they are not inside the File
+fn section(bytes: &[u8], offset: u64, length: u64) -> Option<&[u8]> {
+    let offset = usize::try_from(offset).ok()?;
+    let end = offset.checked_add(usize::try_from(length).ok()?)?;
+    bytes.get(offset..end)
+}
+
+pub(super) fn decompress(data: &[u8], supercompression: u32) -> Result<Vec<u8>, AssetError> {
+    match supercompression {
+        SUPERCOMPRESSION_NONE => Ok(data.to_vec()),
+        SUPERCOMPRESSION_ZLIB => miniz_oxide::inflate::decompress_to_vec_zlib(data)
+            .map_err(|err| AssetError::Parse(format!("KTX2 ZLIB: {}", err))),
+        SUPERCOMPRESSION_ZSTD => {
+            let zstd_error =
+                |err: &dyn Display| AssetError::Parse(format!("KTX2 Zstandard: {}", err));
+            let mut decoder = StreamingDecoder::new(data).map_err(|err| zstd_error(&err))?;
+            let mut decompressed = Vec::new();
+            decoder
+                .read_to_end(&mut decompressed)
+                .map_err(|err| zstd_error(&err))?;
+            Ok(decompressed)
+        }
+        // Only used by ETC1S, Which has to be transcoded anyway
+        SUPERCOMPRESSION_BASIS_LZ => Err(AssetError::Parse(
+            "KTX2 BasisLZ Supercompression without an Basis Universal Format".to_owned(),
+        )),
+        scheme => Err(AssetError::Parse(format!(
+            "Unknown KTX2 Supercompression {}",
+            scheme
+        ))),
+    }
+}
+
+/// Writes an KTX2 File without Supercompression, Only Formats we can describe in the Data Format Descriptor are supported
+pub fn write(image: &CompressedImage) -> Result<Vec<u8>, AssetError> {
+    let unsupported = || AssetError::Unsupported(format!("Writing KTX2 {:?}", image.format));
+    let block = format::block_info(image.format).ok_or_else(unsupported)?;
+    let dfd = data_format_descriptor(image.format, block).ok_or_else(unsupported)?;
+
+    let level_count = image.levels.len();
+    let dfd_offset = HEADER_SIZE + level_count * LEVEL_INDEX_SIZE;
+    // Levels must be aligned to the least common multiple of the Block Size and 4
+    let alignment = match block.bytes % 4 {
+        0 => block.bytes,
+        2 => block.bytes * 2,
+        _ => block.bytes * 4,
+    };
+
+    let mut bytes = Vec::with_capacity(
+        dfd_offset + dfd.len() + image.levels.iter().map(Vec::len).sum::<usize>(),
+    );
+    bytes.extend_from_slice(&IDENTIFIER);
+    for value in [
+        image.format.as_raw() as u32,
+        1, // Type Size, 1 for Block compressed Formats
+        image.width,
+        image.height,
+        0, // Depth
+        0, // Layer Count
+        1, // Face Count
+        level_count as u32,
+        SUPERCOMPRESSION_NONE,
+        dfd_offset as u32,
+        dfd.len() as u32,
+        0, // Key/Value Data Offset
+        0, // Key/Value Data Length
+    ] {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    // Supercompression Global Data Offset and Length
+    bytes.extend_from_slice(&[0; 16]);
+    // Level Index, Filled in when the Levels are written
+    bytes.resize(dfd_offset, 0);
+    bytes.extend_from_slice(&dfd);
+
+    // The smallest Level comes first in the File
+    for (level, data) in image.levels.iter().enumerate().rev() {
+        bytes.resize(bytes.len().next_multiple_of(alignment), 0);
+        let index = HEADER_SIZE + level * LEVEL_INDEX_SIZE;
+        for (i, value) in [bytes.len(), data.len(), data.len()]
+            .into_iter()
+            .enumerate()
+        {
+            bytes[index + i * 8..index + i * 8 + 8].copy_from_slice(&(value as u64).to_le_bytes());
+        }
+        bytes.extend_from_slice(data);
+    }
+    Ok(bytes)
+}
+
+/// Basic Data Format Descriptor Block for the Formats our Encoder creates
+fn data_format_descriptor(format: vk::Format, block: BlockInfo) -> Option<Vec<u8>> {
+    use vk::Format as F;
+
+    const MODEL_BC1A: u8 = 128;
+    const MODEL_BC3: u8 = 130;
+    const MODEL_BC4: u8 = 131;
+    const MODEL_BC5: u8 = 132;
+    const CHANNEL_COLOR: u8 = 0;
+    const CHANNEL_BC1A_ALPHA: u8 = 1;
+    const CHANNEL_BC3_ALPHA: u8 = 15;
+    const CHANNEL_GREEN: u8 = 1;
+
+    // Color Model and Channel of every 64 Bit Sample
+    let (model, channels): (u8, &[u8]) = match format {
+        F::BC1_RGB_UNORM_BLOCK | F::BC1_RGB_SRGB_BLOCK => (MODEL_BC1A, &[CHANNEL_COLOR]),
+        F::BC1_RGBA_UNORM_BLOCK | F::BC1_RGBA_SRGB_BLOCK => (MODEL_BC1A, &[CHANNEL_BC1A_ALPHA]),
+        F::BC3_UNORM_BLOCK | F::BC3_SRGB_BLOCK => (MODEL_BC3, &[CHANNEL_BC3_ALPHA, CHANNEL_COLOR]),
+        F::BC4_UNORM_BLOCK => (MODEL_BC4, &[CHANNEL_COLOR]),
+        F::BC5_UNORM_BLOCK => (MODEL_BC5, &[CHANNEL_COLOR, CHANNEL_GREEN]),
+        _ => return None,
+    };
+    let srgb = matches!(
+        format,
+        F::BC1_RGB_SRGB_BLOCK | F::BC1_RGBA_SRGB_BLOCK | F::BC3_SRGB_BLOCK
+    );
+
+    let block_size = 24 + 16 * channels.len();
+    let mut dfd = Vec::with_capacity(4 + block_size);
+    dfd.extend_from_slice(&((4 + block_size) as u32).to_le_bytes());
+    // Khronos Vendor, Basic Descriptor Type
+    dfd.extend_from_slice(&0u32.to_le_bytes());
+    dfd.extend_from_slice(&2u16.to_le_bytes());
+    dfd.extend_from_slice(&(block_size as u16).to_le_bytes());
+    dfd.extend_from_slice(&[
+        model,
+        1, // BT.709 Primaries
+        if srgb { 2 } else { 1 },
+        0, // Straight Alpha
+        block.width as u8 - 1,
+        block.height as u8 - 1,
+        0,
+        0,
+    ]);
+    dfd.extend_from_slice(&[block.bytes as u8, 0, 0, 0, 0, 0, 0, 0]);
+    for (i, &channel) in channels.iter().enumerate() {
+        dfd.extend_from_slice(&(i as u16 * 64).to_le_bytes());
+        dfd.extend_from_slice(&[63, channel, 0, 0, 0, 0]);
+        dfd.extend_from_slice(&0u32.to_le_bytes());
+        dfd.extend_from_slice(&u32::MAX.to_le_bytes());
+    }
+    Some(dfd)
+}
+
+pub(super) fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, AssetError> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| AssetError::Parse("Unexpected End of File".to_owned()))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Result<u64, AssetError> {
+    Ok(read_u32(bytes, offset)? as u64 | (read_u32(bytes, offset + 4)? as u64) << 32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::texture::encoder::{BlockFormat, TextureEncoder};
+
+    fn encoded(width: u32, height: u32) -> CompressedImage {
+        let image = image::RgbaImage::from_fn(width, height, |x, y| {
+            image::Rgba([(x * 16) as u8, (y * 16) as u8, 64, 255])
+        });
+        TextureEncoder {
+            format: BlockFormat::Bc3,
+            srgb: true,
+            mipmaps: true,
+        }
+        .encode(&image::DynamicImage::ImageRgba8(image))
+        .unwrap()
+    }
+
+    fn set_u32(bytes: &mut [u8], offset: usize, value: u32) {
+        bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn write_and_parse() {
+        let image = encoded(12, 8);
+        let parsed = parse(&write(&image).unwrap()).unwrap();
+        assert_eq!(parsed.format, vk::Format::BC3_SRGB_BLOCK);
+        assert_eq!((parsed.width, parsed.height), (12, 8));
+        assert_eq!(parsed.levels.len(), 4);
+        assert_eq!(parsed.levels, image.levels);
+    }
+
+    #[test]
+    fn zlib_supercompression() {
+        let image = encoded(4, 4);
+        let mut bytes = write(&image).unwrap();
+        set_u32(&mut bytes, 44, SUPERCOMPRESSION_ZLIB);
+        // Moves the Levels to the End of the File, The old Data is just unused
+        for (level, data) in image.levels.iter().enumerate() {
+            let compressed = miniz_oxide::deflate::compress_to_vec_zlib(data, 6);
+            let index = HEADER_SIZE + level * LEVEL_INDEX_SIZE;
+            let offset = bytes.len() as u64;
+            bytes[index..index + 8].copy_from_slice(&offset.to_le_bytes());
+            bytes[index + 8..index + 16].copy_from_slice(&(compressed.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(&compressed);
+        }
+        assert_eq!(parse(&bytes).unwrap().levels, image.levels);
+    }
+
+    #[test]
+    fn rejects_empty_images() {
+        let mut bytes = write(&encoded(4, 4)).unwrap();
+        set_u32(&mut bytes, 20, 0);
+        assert!(matches!(parse(&bytes), Err(AssetError::Parse(_))));
+
+        let mut bytes = write(&encoded(4, 4)).unwrap();
+        set_u32(&mut bytes, 24, 0);
+        assert!(matches!(parse(&bytes), Err(AssetError::Parse(_))));
+    }
+
+    #[test]
+    fn rejects_broken_files() {
+        let bytes = write(&encoded(8, 8)).unwrap();
+        // Not even the Header
+        assert!(matches!(parse(&bytes[..40]), Err(AssetError::Parse(_))));
+        // The first Level is stored last
+        assert!(matches!(
+            parse(&bytes[..bytes.len() - 1]),
+            Err(AssetError::Parse(_))
+        ));
+        // An 8x8 Image has 4 Levels at most
+        let mut too_many_levels = bytes.clone();
+        set_u32(&mut too_many_levels, 40, 40);
+        assert!(matches!(parse(&too_many_levels), Err(AssetError::Parse(_))));
+        let mut cubemap = bytes;
+        set_u32(&mut cubemap, 36, 6);
+        assert!(matches!(parse(&cubemap), Err(AssetError::Unsupported(_))));
+    }
+}
@@ -0,0 +1,92 @@
+//! Textures which are already in an GPU Format, Loaded from KTX2 or DDS Files with all their Mip Levels.
+//! Basis Universal KTX2 Files are transcoded into BC1 or BC3 first
+
+use std::path::Path;
+
+use ash::vk;
+use vent_rendering::{
+    image::{CompressedImage, VulkanImage},
+    instance::VulkanInstance,
+};
+
+use crate::AssetError;
+
+mod basis;
+pub mod bcn;
+pub mod dds;
+pub mod encoder;
+pub mod format;
+pub mod ktx2;
+
+/// KTX2 or DDS, Detected by the Magic at the Start of the File
+pub fn is_compressed(bytes: &[u8]) -> bool {
+    ktx2::is_ktx2(bytes) || dds::is_dds(bytes)
+}
+
+pub fn load_compressed(bytes: &[u8]) -> Result<CompressedImage, AssetError> {
+    if ktx2::is_ktx2(bytes) {
+        ktx2::parse(bytes)
+    } else if dds::is_dds(bytes) {
+        dds::parse(bytes)
+    } else {
+        Err(AssetError::Unsupported(
+            "Texture Container, Only KTX2 and DDS are supported".to_owned(),
+        ))
+    }
+}
+
+pub fn load_compressed_file(path: &Path) -> Result<CompressedImage, AssetError> {
+    load_compressed(&std::fs::read(path)?)
+}
+
+/// Uploads the Image in its own Format when the GPU can sample it,
+/// Otherwise BC1 - BC5 are decoded on the CPU and uploaded as RGBA8 (Losing the prebuilt Mip Levels).
+/// `srgb` overrides the Color Space of the File, Because the Texture Role decides how it has to be sampled
+pub fn upload(
+    instance: &VulkanInstance,
+    mut image: CompressedImage,
+    srgb: bool,
+    sampler_info: Option<vk::SamplerCreateInfo>,
+    name: Option<&str>,
+) -> Result<VulkanImage, AssetError> {
+    image.format = format::with_color_space(image.format, srgb);
+    let supported = VulkanInstance::get_supported_format(
+        &instance.instance,
+        instance.physical_device,
+        &[image.format],
+        vk::ImageTiling::OPTIMAL,
+        vk::FormatFeatureFlags::SAMPLED_IMAGE | vk::FormatFeatureFlags::TRANSFER_DST,
+    );
+    if supported.is_some() {
+        return Ok(VulkanImage::from_compressed(
+            instance,
+            &image,
+            sampler_info,
+            name,
+        )?);
+    }
+
+    let decoded = bcn::decode(&image).ok_or_else(|| {
+        AssetError::Unsupported(format!(
+            "Texture Format {:?} is not supported by the GPU",
+            image.format
+        ))
+    })?;
+    log::debug!(
+        "GPU can not sample {:?}, Decoding Texture {:?} on the CPU",
+        image.format,
+        name
+    );
+    Ok(VulkanImage::from_image(
+        instance,
+        image::DynamicImage::ImageRgba8(decoded),
+        if srgb {
+            vk::Format::R8G8B8A8_SRGB
+        } else {
+            vk::Format::R8G8B8A8_UNORM
+        },
+        true,
+        sampler_info,
+        name,
+    )?)
+}
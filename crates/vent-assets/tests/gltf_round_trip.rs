@@ -0,0 +1,521 @@
+//! Loads Models, Exports them with the glTF Exporter and checks that Reloading gives the same Data
+
+use std::path::{Path, PathBuf};
+
+use ash::vk;
+use vent_assets::{
+    animation::{AnimationClip, Channel, ChannelValues, Interpolation},
+    model::{
+        data::{
+            AlphaMode, CameraData, LightData, LightKind, MaterialData, MeshData, ModelData,
+            MorphTargetData, NodeData, PrimitiveData, PrimitiveMode, Projection, SkinData,
+            TextureData, TextureImage, TextureTransform,
+        },
+        gltf_exporter::GltfExporter,
+        registry::ImporterRegistry,
+    },
+    texture::encoder::{BlockFormat, TextureEncoder},
+};
+use vent_math::{scalar::quat::Quat, vec::vec3::Vec3};
+
+fn temp_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join("vent-assets-gltf-round-trip");
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join(name)
+}
+
+fn round_trip(model: &ModelData, name: &str) -> ModelData {
+    let path = temp_path(name);
+    GltfExporter::export(model, &path).unwrap();
+    ImporterRegistry::default().import(&path).unwrap()
+}
+
+fn assert_primitives_eq(expected: &PrimitiveData, actual: &PrimitiveData) {
+    assert_eq!(expected.positions, actual.positions);
+    assert_eq!(expected.normals, actual.normals);
+    assert_eq!(expected.tex_coords, actual.tex_coords);
+    assert_eq!(expected.tex_coords1, actual.tex_coords1);
+    assert_eq!(expected.tangents, actual.tangents);
+    assert_eq!(expected.joints, actual.joints);
+    assert_eq!(expected.weights, actual.weights);
+    assert_eq!(expected.indices, actual.indices);
+    assert_eq!(expected.mode, actual.mode);
+    assert_eq!(expected.material_index, actual.material_index);
+    assert_eq!(expected.morph_targets.len(), actual.morph_targets.len());
+    for (expected, actual) in expected.morph_targets.iter().zip(&actual.morph_targets) {
+        assert_eq!(expected.positions, actual.positions);
+        assert_eq!(expected.normals, actual.normals);
+        assert_eq!(expected.tangents, actual.tangents);
+    }
+}
+
+fn assert_textures_eq(expected: &Option<TextureData>, actual: &Option<TextureData>) {
+    let (Some(expected), Some(actual)) = (expected, actual) else {
+        assert_eq!(expected.is_some(), actual.is_some());
+        return;
+    };
+    assert_eq!(expected.tex_coord, actual.tex_coord);
+    assert_eq!(expected.transform, actual.transform);
+    assert_eq!(expected.sampler.mag_filter, actual.sampler.mag_filter);
+    assert_eq!(expected.sampler.min_filter, actual.sampler.min_filter);
+    assert_eq!(expected.sampler.mipmap_mode, actual.sampler.mipmap_mode);
+    assert_eq!(
+        expected.sampler.address_mode_u,
+        actual.sampler.address_mode_u
+    );
+    assert_eq!(
+        expected.sampler.address_mode_v,
+        actual.sampler.address_mode_v
+    );
+    match (&expected.image, &actual.image) {
+        (TextureImage::Decoded(expected), TextureImage::Decoded(actual)) => {
+            assert_eq!(expected.to_rgba8(), actual.to_rgba8())
+        }
+        (TextureImage::Compressed(expected), TextureImage::Compressed(actual)) => {
+            assert_eq!(expected.format, actual.format);
+            assert_eq!(
+                (expected.width, expected.height),
+                (actual.width, actual.height)
+            );
+            assert_eq!(expected.levels, actual.levels);
+        }
+        _ => panic!("Texture changed between Decoded and Compressed"),
+    }
+}
+
+fn assert_materials_eq(expected: &MaterialData, actual: &MaterialData) {
+    assert_eq!(expected.name, actual.name);
+    assert_eq!(expected.base_color, actual.base_color);
+    assert_eq!(expected.metallic_factor, actual.metallic_factor);
+    assert_eq!(expected.roughness_factor, actual.roughness_factor);
+    assert_eq!(expected.normal_scale, actual.normal_scale);
+    assert_eq!(expected.occlusion_strength, actual.occlusion_strength);
+    assert_eq!(expected.emissive_factor, actual.emissive_factor);
+    assert_eq!(expected.alpha_mode, actual.alpha_mode);
+    assert_eq!(expected.alpha_cutoff, actual.alpha_cutoff);
+    assert_eq!(expected.double_sided, actual.double_sided);
+    assert_textures_eq(&expected.base_color_texture, &actual.base_color_texture);
+    assert_textures_eq(
+        &expected.metallic_roughness_texture,
+        &actual.metallic_roughness_texture,
+    );
+    assert_textures_eq(&expected.normal_texture, &actual.normal_texture);
+    assert_textures_eq(&expected.occlusion_texture, &actual.occlusion_texture);
+    assert_textures_eq(&expected.emissive_texture, &actual.emissive_texture);
+}
+
+fn assert_models_eq(expected: &ModelData, actual: &ModelData) {
+    assert_eq!(expected.meshes.len(), actual.meshes.len());
+    for (expected, actual) in expected.meshes.iter().zip(&actual.meshes) {
+        assert_eq!(expected.name, actual.name);
+        assert_eq!(expected.weights, actual.weights);
+        assert_eq!(expected.primitives.len(), actual.primitives.len());
+        for (expected, actual) in expected.primitives.iter().zip(&actual.primitives) {
+            assert_primitives_eq(expected, actual);
+        }
+    }
+
+    assert_eq!(expected.materials.len(), actual.materials.len());
+    for (expected, actual) in expected.materials.iter().zip(&actual.materials) {
+        assert_materials_eq(expected, actual);
+    }
+
+    assert_eq!(expected.nodes.len(), actual.nodes.len());
+    for (expected, actual) in expected.nodes.iter().zip(&actual.nodes) {
+        assert_eq!(expected.name, actual.name);
+        assert_eq!(expected.parent, actual.parent);
+        assert_eq!(expected.translation, actual.translation);
+        assert_eq!(expected.rotation, actual.rotation);
+        assert_eq!(expected.scale, actual.scale);
+        assert_eq!(expected.mesh, actual.mesh);
+        assert_eq!(expected.skin, actual.skin);
+        assert_eq!(expected.camera, actual.camera);
+        assert_eq!(expected.light, actual.light);
+    }
+    assert_eq!(expected.scene_roots, actual.scene_roots);
+
+    assert_eq!(expected.skins.len(), actual.skins.len());
+    for (expected, actual) in expected.skins.iter().zip(&actual.skins) {
+        assert_eq!(expected.name, actual.name);
+        assert_eq!(expected.joints, actual.joints);
+        assert_eq!(expected.inverse_bind_matrices, actual.inverse_bind_matrices);
+    }
+
+    assert_eq!(expected.animations.len(), actual.animations.len());
+    for (expected, actual) in expected.animations.iter().zip(&actual.animations) {
+        assert_eq!(expected.name, actual.name);
+        assert_eq!(expected.duration, actual.duration);
+        assert_eq!(expected.channels.len(), actual.channels.len());
+        for (expected, actual) in expected.channels.iter().zip(&actual.channels) {
+            assert_eq!(expected.node, actual.node);
+            assert_eq!(expected.interpolation, actual.interpolation);
+            assert_eq!(expected.times, actual.times);
+            assert_eq!(channel_values(expected), channel_values(actual));
+        }
+    }
+
+    assert_eq!(expected.cameras.len(), actual.cameras.len());
+    for (expected, actual) in expected.cameras.iter().zip(&actual.cameras) {
+        assert_eq!(expected.name, actual.name);
+        assert_eq!(expected.projection, actual.projection);
+    }
+
+    assert_eq!(expected.lights.len(), actual.lights.len());
+    for (expected, actual) in expected.lights.iter().zip(&actual.lights) {
+        assert_eq!(expected.name, actual.name);
+        assert_eq!(expected.kind, actual.kind);
+        assert_eq!(expected.color, actual.color);
+        assert_eq!(expected.intensity, actual.intensity);
+        assert_eq!(expected.range, actual.range);
+    }
+}
+
+/// The Path and every Value as Floats, So Channels can be compared
+fn channel_values(channel: &Channel) -> (&'static str, Vec<f32>) {
+    match &channel.values {
+        ChannelValues::Translation(values) => (
+            "translation",
+            values.iter().flat_map(|&v| <[f32; 3]>::from(v)).collect(),
+        ),
+        ChannelValues::Rotation(values) => (
+            "rotation",
+            values.iter().flat_map(|v| v.to_array()).collect(),
+        ),
+        ChannelValues::Scale(values) => (
+            "scale",
+            values.iter().flat_map(|&v| <[f32; 3]>::from(v)).collect(),
+        ),
+        ChannelValues::MorphWeights(values) => ("weights", values.clone()),
+    }
+}
+
+fn texture(image: TextureImage, tex_coord: u32, transform: TextureTransform) -> TextureData {
+    TextureData {
+        image,
+        sampler: vk::SamplerCreateInfo {
+            mag_filter: vk::Filter::NEAREST,
+            min_filter: vk::Filter::LINEAR,
+            mipmap_mode: vk::SamplerMipmapMode::LINEAR,
+            address_mode_u: vk::SamplerAddressMode::CLAMP_TO_EDGE,
+            address_mode_v: vk::SamplerAddressMode::MIRRORED_REPEAT,
+            ..Default::default()
+        },
+        tex_coord,
+        transform,
+    }
+}
+
+/// Uses every Part of the Model Data the Exporter writes
+fn generated_model() -> ModelData {
+    let positions = vec![
+        [0.0, 0.0, 0.0],
+        [1.0, 0.0, 0.0],
+        [1.0, 1.0, 0.0],
+        [0.0, 1.0, -0.5],
+    ];
+    let primitive = PrimitiveData {
+        normals: Some(vec![[0.0, 0.0, 1.0]; 4]),
+        tex_coords: Some(vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]),
+        tex_coords1: Some(vec![[0.5, 0.25]; 4]),
+        tangents: Some(vec![[1.0, 0.0, 0.0, -1.0]; 4]),
+        joints: Some(vec![[0, 1, 0, 0], [1, 0, 0, 0], [1, 0, 0, 0], [0, 0, 0, 0]]),
+        weights: Some(vec![
+            [0.75, 0.25, 0.0, 0.0],
+            [1.0, 0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0, 0.0],
+        ]),
+        morph_targets: vec![MorphTargetData {
+            positions: Some(vec![[0.0, 0.0, 0.5]; 4]),
+            normals: None,
+            tangents: Some(vec![[0.0, 0.1, 0.0]; 4]),
+        }],
+        indices: vec![0, 1, 2, 0, 2, 3],
+        positions,
+        mode: PrimitiveMode::Triangles,
+        material_index: Some(0),
+        flat_normals: true,
+    };
+    // More than 65535 Vertices need 32 Bit Indices
+    let point_count = 70_000;
+    let points = PrimitiveData {
+        positions: (0..point_count).map(|i| [i as f32, 0.0, 0.0]).collect(),
+        normals: None,
+        tex_coords: None,
+        tex_coords1: None,
+        tangents: None,
+        joints: None,
+        weights: None,
+        morph_targets: Vec::new(),
+        indices: PrimitiveData::generate_indices(point_count),
+        mode: PrimitiveMode::Points,
+        material_index: None,
+        flat_normals: true,
+    };
+
+    let checker = image::DynamicImage::ImageRgba8(image::RgbaImage::from_fn(8, 4, |x, y| {
+        image::Rgba([(x * 30) as u8, (y * 60) as u8, 128, 255 - x as u8])
+    }));
+    let compressed = TextureEncoder {
+        format: BlockFormat::Bc1,
+        srgb: true,
+        mipmaps: true,
+    }
+    .encode(&checker)
+    .unwrap();
+    let material = MaterialData {
+        name: Some("Everything".to_owned()),
+        base_color_texture: Some(texture(
+            TextureImage::Compressed(compressed),
+            0,
+            TextureTransform::default(),
+        )),
+        normal_texture: Some(texture(
+            TextureImage::Decoded(checker.clone()),
+            1,
+            TextureTransform {
+                offset: [0.5, 0.25],
+                rotation: 0.5,
+                scale: [2.0, 4.0],
+            },
+        )),
+        occlusion_texture: Some(texture(
+            TextureImage::Decoded(checker),
+            0,
+            TextureTransform::default(),
+        )),
+        base_color: [0.5, 0.25, 1.0, 0.5],
+        metallic_factor: 0.25,
+        roughness_factor: 0.75,
+        normal_scale: 0.5,
+        occlusion_strength: 0.25,
+        // Brighter than 1, Needs KHR_materials_emissive_strength
+        emissive_factor: [4.0, 2.0, 1.0],
+        alpha_mode: AlphaMode::Mask,
+        alpha_cutoff: 0.25,
+        double_sided: true,
+        ..Default::default()
+    };
+
+    let node = |name: &str, parent: Option<usize>| NodeData {
+        name: Some(name.to_owned()),
+        parent,
+        translation: [1.0, 2.0, 3.0],
+        rotation: [0.0, 0.0, 0.0, 1.0],
+        scale: [1.0, 1.0, 1.0],
+        mesh: None,
+        skin: None,
+        camera: None,
+        light: None,
+    };
+    let nodes = vec![
+        NodeData {
+            mesh: Some(0),
+            skin: Some(0),
+            ..node("Mesh", None)
+        },
+        node("Root Joint", None),
+        NodeData {
+            rotation: [0.0, 0.70710677, 0.0, 0.70710677],
+            scale: [2.0, 2.0, 2.0],
+            ..node("Child Joint", Some(1))
+        },
+        NodeData {
+            camera: Some(0),
+            light: Some(1),
+            ..node("Camera", Some(2))
+        },
+        NodeData {
+            mesh: Some(1),
+            light: Some(0),
+            ..node("Points", None)
+        },
+    ];
+
+    ModelData {
+        meshes: vec![
+            MeshData {
+                name: Some("Quad".to_owned()),
+                primitives: vec![primitive],
+                weights: vec![0.5],
+            },
+            MeshData {
+                name: None,
+                primitives: vec![points],
+                weights: Vec::new(),
+            },
+        ],
+        materials: vec![material],
+        nodes,
+        skins: vec![SkinData {
+            name: Some("Skin".to_owned()),
+            joints: vec![1, 2],
+            inverse_bind_matrices: vec![
+                [
+                    [1.0, 0.0, 0.0, 0.0],
+                    [0.0, 1.0, 0.0, 0.0],
+                    [0.0, 0.0, 1.0, 0.0],
+                    [-1.0, -2.0, -3.0, 1.0],
+                ],
+                [
+                    [0.5, 0.0, 0.0, 0.0],
+                    [0.0, 0.5, 0.0, 0.0],
+                    [0.0, 0.0, 0.5, 0.0],
+                    [0.0, -1.0, 0.0, 1.0],
+                ],
+            ],
+        }],
+        animations: vec![AnimationClip::new(
+            Some("Wave".to_owned()),
+            vec![
+                Channel {
+                    node: 2,
+                    interpolation: Interpolation::Linear,
+                    times: vec![0.0, 0.5, 1.25],
+                    values: ChannelValues::Rotation(vec![
+                        Quat::new(0.0, 0.0, 0.0, 1.0),
+                        Quat::new(0.0, 0.70710677, 0.0, 0.70710677),
+                        Quat::new(0.0, 1.0, 0.0, 0.0),
+                    ]),
+                },
+                Channel {
+                    node: 1,
+                    interpolation: Interpolation::Step,
+                    times: vec![0.0, 1.0],
+                    values: ChannelValues::Translation(vec![
+                        Vec3::new(0.0, 0.0, 0.0),
+                        Vec3::new(0.0, 1.0, 0.0),
+                    ]),
+                },
+                Channel {
+                    node: 0,
+                    interpolation: Interpolation::CubicSpline,
+                    times: vec![0.0, 2.0],
+                    values: ChannelValues::MorphWeights(vec![0.0, 0.0, 0.0, 0.0, 1.0, 0.0]),
+                },
+            ],
+        )],
+        cameras: vec![
+            CameraData {
+                name: Some("Perspective".to_owned()),
+                projection: Projection::Perspective {
+                    yfov: 0.8,
+                    aspect_ratio: Some(1.5),
+                    znear: 0.1,
+                    zfar: None,
+                },
+            },
+            CameraData {
+                name: None,
+                projection: Projection::Orthographic {
+                    xmag: 2.0,
+                    ymag: 1.0,
+                    znear: 0.5,
+                    zfar: 100.0,
+                },
+            },
+        ],
+        lights: vec![
+            LightData {
+                name: Some("Sun".to_owned()),
+                kind: LightKind::Directional,
+                color: [1.0, 0.9, 0.8],
+                intensity: 3.0,
+                range: None,
+            },
+            LightData {
+                name: None,
+                kind: LightKind::Spot {
+                    inner_cone_angle: 0.25,
+                    outer_cone_angle: 0.5,
+                },
+                color: [0.0, 0.5, 1.0],
+                intensity: 100.0,
+                range: Some(20.0),
+            },
+        ],
+        scene_roots: vec![0, 1, 4],
+    }
+}
+
+fn cube_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../vent-runtime/assets/models/basic/cube.glb")
+}
+
+#[test]
+fn cube_round_trips_as_glb_and_gltf() {
+    let cube = ImporterRegistry::default().import(&cube_path()).unwrap();
+    assert!(!cube.meshes.is_empty());
+
+    assert_models_eq(&cube, &round_trip(&cube, "cube.glb"));
+    assert_models_eq(&cube, &round_trip(&cube, "cube.gltf"));
+}
+
+#[test]
+fn generated_model_round_trips() {
+    let model = generated_model();
+    assert_models_eq(&model, &round_trip(&model, "generated.glb"));
+    assert_models_eq(&model, &round_trip(&model, "generated.gltf"));
+}
+
+#[test]
+fn exported_model_can_be_exported_again() {
+    let model = round_trip(&generated_model(), "first.glb");
+    assert_models_eq(&model, &round_trip(&model, "second.glb"));
+}
+
+#[test]
+fn models_without_nodes_get_one_node_per_mesh() {
+    let mut model = generated_model();
+    model.nodes.clear();
+    model.skins.clear();
+    model.animations.clear();
+    model.scene_roots.clear();
+
+    let exported = round_trip(&model, "no_nodes.glb");
+    assert_eq!(exported.nodes.len(), model.meshes.len());
+    assert_eq!(exported.scene_roots, vec![0, 1]);
+    for (index, node) in exported.nodes.iter().enumerate() {
+        assert_eq!(node.mesh, Some(index));
+        assert_eq!(node.parent, None);
+    }
+}
+
+#[test]
+fn unknown_extensions_are_unsupported() {
+    let result = GltfExporter::export(&generated_model(), &temp_path("model.fbx"));
+    assert!(matches!(
+        result,
+        Err(vent_assets::AssetError::Unsupported(_))
+    ));
+}
+
+#[test]
+fn broken_primitives_fail_to_import() {
+    let import = |model: &ModelData, name: &str| {
+        let path = temp_path(name);
+        GltfExporter::export(model, &path).unwrap();
+        ImporterRegistry::default().import(&path)
+    };
+
+    let mut out_of_range = generated_model();
+    out_of_range.meshes[0].primitives[0].indices[2] = 4;
+    assert!(matches!(
+        import(&out_of_range, "out_of_range.glb"),
+        Err(vent_assets::AssetError::Parse(_))
+    ));
+
+    let mut short_normals = generated_model();
+    short_normals.meshes[0].primitives[0].normals = Some(vec![[0.0, 0.0, 1.0]; 3]);
+    assert!(matches!(
+        import(&short_normals, "short_normals.glb"),
+        Err(vent_assets::AssetError::Parse(_))
+    ));
+
+    let mut too_few_indices = generated_model();
+    too_few_indices.meshes[0].primitives[0].indices.truncate(2);
+    assert!(matches!(
+        import(&too_few_indices, "too_few_indices.glb"),
+        Err(vent_assets::AssetError::Parse(_))
+    ));
+}
@@ -55,6 +55,17 @@ impl Mat4 {
         }
     }
 
+    #[inline]
+    #[must_use]
+    pub const fn from_cols_array_2d(m: &[[f32; 4]; 4]) -> Self {
+        Self::from_cols(
+            Vec4::from_array(m[0]),
+            Vec4::from_array(m[1]),
+            Vec4::from_array(m[2]),
+            Vec4::from_array(m[3]),
+        )
+    }
+
     #[inline]
     #[must_use]
     fn quat_to_axes(rotation: Quat) -> (Vec4, Vec4, Vec4) {
@@ -1,6 +1,6 @@
 use std::{
     arch::x86_64::__m128,
-    ops::{Deref, DerefMut},
+    ops::{Deref, DerefMut, Neg},
 };
 
 use crate::vec::{vec3::Vec3, vec4::Vec4};
@@ -57,6 +57,83 @@ impl Quat {
         let v = axis * s;
         Self::new(v.x, v.y, v.z, c)
     }
+
+    #[inline]
+    #[must_use]
+    pub fn to_array(self) -> [f32; 4] {
+        [self.x, self.y, self.z, self.w]
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn dot(self, rhs: Self) -> f32 {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z + self.w * rhs.w
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn normalize(self) -> Self {
+        let length_recip = self.length().recip();
+        Self::new(
+            self.x * length_recip,
+            self.y * length_recip,
+            self.z * length_recip,
+            self.w * length_recip,
+        )
+    }
+
+    /// Normalized linear interpolation, Always takes the shortest Path
+    #[must_use]
+    pub fn lerp(self, end: Self, s: f32) -> Self {
+        let end = if self.dot(end) < 0.0 { -end } else { end };
+        Self::new(
+            self.x + (end.x - self.x) * s,
+            self.y + (end.y - self.y) * s,
+            self.z + (end.z - self.z) * s,
+            self.w + (end.w - self.w) * s,
+        )
+        .normalize()
+    }
+
+    /// Spherical linear interpolation, Always takes the shortest Path
+    #[must_use]
+    pub fn slerp(self, end: Self, s: f32) -> Self {
+        let mut dot = self.dot(end);
+        let end = if dot < 0.0 {
+            dot = -dot;
+            -end
+        } else {
+            end
+        };
+        // Nearly the same Rotation, sin(theta) would be close to zero
+        if dot > 0.9995 {
+            return self.lerp(end, s);
+        }
+        let theta = dot.acos();
+        let sin_theta = theta.sin();
+        let a = ((1.0 - s) * theta).sin() / sin_theta;
+        let b = (s * theta).sin() / sin_theta;
+        Self::new(
+            self.x * a + end.x * b,
+            self.y * a + end.y * b,
+            self.z * a + end.z * b,
+            self.w * a + end.w * b,
+        )
+    }
+}
+
+impl Neg for Quat {
+    type Output = Self;
+    #[inline]
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y, -self.z, -self.w)
+    }
 }
 
 impl From<Quat> for Vec4 {
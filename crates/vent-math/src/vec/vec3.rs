@@ -83,6 +83,13 @@ impl Vec3 {
         self.dot(self).sqrt()
     }
 
+    /// Linear interpolation between `self` and `rhs`, `s` = 0.0 returns `self`
+    #[inline]
+    #[must_use]
+    pub fn lerp(self, rhs: Self, s: f32) -> Self {
+        self + (rhs - self) * s
+    }
+
     #[inline]
     #[must_use]
     pub fn cross(self, rhs: Self) -> Self {
@@ -0,0 +1,97 @@
+use ash::vk::DeviceSize;
+
+use super::{align_up, SubAllocator};
+
+/// Bump Allocator, Allocating is only an Addition. Freed Memory is reused once every Allocation of the Block was freed,
+/// So it fits Data which is freed together like Staging Buffers or per Frame Data
+pub struct LinearAllocator {
+    size: DeviceSize,
+    offset: DeviceSize,
+    allocations: usize,
+    used: DeviceSize,
+}
+
+impl LinearAllocator {
+    pub fn new(size: DeviceSize) -> Self {
+        Self {
+            size,
+            offset: 0,
+            allocations: 0,
+            used: 0,
+        }
+    }
+}
+
+impl SubAllocator for LinearAllocator {
+    fn allocate(&mut self, size: DeviceSize, alignment: DeviceSize) -> Option<DeviceSize> {
+        let offset = align_up(self.offset, alignment);
+        let end = offset.checked_add(size)?;
+        if end > self.size {
+            return None;
+        }
+        self.offset = end;
+        self.allocations += 1;
+        self.used += size;
+        Some(offset)
+    }
+
+    fn free(&mut self, _offset: DeviceSize, size: DeviceSize) {
+        self.allocations -= 1;
+        self.used -= size;
+        if self.allocations == 0 {
+            self.offset = 0;
+        }
+    }
+
+    fn allocation_count(&self) -> usize {
+        self.allocations
+    }
+
+    fn used(&self) -> DeviceSize {
+        self.used
+    }
+
+    fn largest_free_region(&self) -> DeviceSize {
+        self.size - self.offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alignment() {
+        let mut allocator = LinearAllocator::new(1024);
+        assert_eq!(allocator.allocate(10, 1), Some(0));
+        assert_eq!(allocator.allocate(10, 64), Some(64));
+        assert_eq!(allocator.allocate(1, 4), Some(76));
+        assert_eq!(allocator.used(), 21);
+        assert_eq!(allocator.largest_free_region(), 1024 - 77);
+    }
+
+    #[test]
+    fn reuses_memory_once_everything_is_freed() {
+        let mut allocator = LinearAllocator::new(1024);
+        let first = allocator.allocate(512, 1).unwrap();
+        let second = allocator.allocate(512, 1).unwrap();
+        allocator.free(first, 512);
+        // Freeing does not give Memory back while an Allocation is alive
+        assert_eq!(allocator.allocate(1, 1), None);
+        allocator.free(second, 512);
+        assert_eq!(allocator.allocation_count(), 0);
+        assert_eq!(allocator.used(), 0);
+        assert_eq!(allocator.allocate(1024, 1), Some(0));
+    }
+
+    #[test]
+    fn out_of_space() {
+        let mut allocator = LinearAllocator::new(1024);
+        assert_eq!(allocator.allocate(1025, 1), None);
+        assert_eq!(allocator.allocate(1000, 1), Some(0));
+        assert_eq!(allocator.allocate(16, 256), None);
+        assert_eq!(allocator.allocate(DeviceSize::MAX, 1), None);
+        assert_eq!(allocator.allocate(24, 1), Some(1000));
+        assert_eq!(allocator.allocation_count(), 2);
+    }
+}
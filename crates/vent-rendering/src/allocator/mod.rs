@@ -0,0 +1,632 @@
+//! Sub-allocates GPU Memory. Drivers only allow a few thousand `vkAllocateMemory` Calls (`maxMemoryAllocationCount` is often 4096)
+//! and every Call is slow, So Buffers and Images share big Memory Blocks instead of getting their own Memory
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    os::raw::c_void,
+    ptr::NonNull,
+    sync::{Arc, Mutex},
+};
+
+use ash::{prelude::VkResult, vk};
+
+use linear::LinearAllocator;
+use tlsf::TlsfAllocator;
+
+mod linear;
+mod tlsf;
+
+/// Size of the Blocks of the Default Pools
+pub const DEFAULT_BLOCK_SIZE: vk::DeviceSize = 64 * 1024 * 1024;
+/// Heaps smaller than this get Blocks of an eighth of their Size, So small Heaps (e.g. Resizable BAR without ReBAR) are not used up by one Block
+const SMALL_HEAP_SIZE: vk::DeviceSize = 1024 * 1024 * 1024;
+
+/// Places Allocations inside of an Memory Block, Only works with Offsets so it does not touch Vulkan
+trait SubAllocator: Send {
+    /// Returns the Offset of the new Allocation or `None` if it does not fit
+    fn allocate(
+        &mut self,
+        size: vk::DeviceSize,
+        alignment: vk::DeviceSize,
+    ) -> Option<vk::DeviceSize>;
+    fn free(&mut self, offset: vk::DeviceSize, size: vk::DeviceSize);
+    fn allocation_count(&self) -> usize;
+    fn used(&self) -> vk::DeviceSize;
+    /// Largest Allocation which would still fit, Used to detect Fragmentation
+    fn largest_free_region(&self) -> vk::DeviceSize;
+}
+
+fn align_up(value: vk::DeviceSize, alignment: vk::DeviceSize) -> vk::DeviceSize {
+    value.next_multiple_of(alignment)
+}
+
+/// How an Pool places Allocations inside its Blocks
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PoolStrategy {
+    /// Two-Level Segregated Fit, Allocations can be freed in any Order. Used by the Default Pools
+    Tlsf,
+    /// Bump Allocation, Memory is only reused once every Allocation of an Block was freed
+    Linear,
+}
+
+/// Vulkan requires Buffers and linear Images to be `bufferImageGranularity` apart from optimal Images
+/// when they share an Memory Block, So we have to know what an Allocation is used for
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ResourceKind {
+    /// Buffers and Images with linear Tiling
+    Linear,
+    /// Images with optimal Tiling
+    Optimal,
+}
+
+/// Describes an own Pool, e.g. an Linear Pool for Staging Buffers
+#[derive(Clone, Copy, Debug)]
+pub struct PoolInfo {
+    pub flags: vk::MemoryPropertyFlags,
+    pub strategy: PoolStrategy,
+    pub block_size: vk::DeviceSize,
+    /// 0 means the Pool can grow without Limit
+    pub max_blocks: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PoolId(usize);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct AllocationId(u64);
+
+/// Pointer to persistently mapped Memory
+#[derive(Clone, Copy)]
+struct MappedPtr(NonNull<u8>);
+
+// The Pointer is only handed out, The Allocator itself never reads or writes through it
+unsafe impl Send for MappedPtr {}
+unsafe impl Sync for MappedPtr {}
+
+/// One `vkAllocateMemory` Call, Host visible Blocks stay mapped for their whole Lifetime
+/// since Vulkan does not allow mapping the same Memory twice
+struct MemoryBlock {
+    memory: vk::DeviceMemory,
+    size: vk::DeviceSize,
+    mapped: Option<MappedPtr>,
+    allocator: Box<dyn SubAllocator>,
+    /// Offset to the Id and Size of every Allocation, Used for Defragmentation
+    allocations: BTreeMap<vk::DeviceSize, (AllocationId, vk::DeviceSize)>,
+}
+
+struct Pool {
+    memory_type: u32,
+    strategy: PoolStrategy,
+    block_size: vk::DeviceSize,
+    max_blocks: usize,
+    /// Own Pools do not know what they are used for, So every Allocation is aligned to `bufferImageGranularity`
+    granularity: vk::DeviceSize,
+    blocks: Vec<Option<MemoryBlock>>,
+}
+
+#[derive(Default)]
+struct AllocatorState {
+    pools: Vec<Pool>,
+    default_pools: HashMap<(u32, ResourceKind), usize>,
+    dedicated_allocations: usize,
+    dedicated_bytes: vk::DeviceSize,
+    next_id: u64,
+}
+
+enum Location {
+    Block { pool: usize, block: usize },
+    Dedicated,
+    Freed,
+}
+
+/// An Range of GPU Memory, Must be freed with `free` before the Allocator is destroyed
+pub struct Allocation {
+    memory: vk::DeviceMemory,
+    offset: vk::DeviceSize,
+    size: vk::DeviceSize,
+    mapped: Option<MappedPtr>,
+    id: AllocationId,
+    location: Location,
+    state: Arc<Mutex<AllocatorState>>,
+}
+
+/// An Allocation which should move to an other Block, So its current Block can be freed
+#[derive(Clone, Copy, Debug)]
+pub struct DefragmentationMove {
+    pub allocation: AllocationId,
+    pub memory_type: u32,
+    pub memory: vk::DeviceMemory,
+    pub offset: vk::DeviceSize,
+    pub size: vk::DeviceSize,
+}
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct MemoryStats {
+    /// `vkAllocateMemory` Calls which are still alive, Blocks and dedicated Allocations
+    pub device_allocations: usize,
+    pub blocks: usize,
+    pub block_bytes: vk::DeviceSize,
+    /// Sub-Allocations inside of Blocks
+    pub allocations: usize,
+    pub used_bytes: vk::DeviceSize,
+    pub dedicated_allocations: usize,
+    pub dedicated_bytes: vk::DeviceSize,
+    /// Largest Allocation which would fit into an existing Block
+    pub largest_free_region: vk::DeviceSize,
+}
+
+impl fmt::Display for MemoryStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const MIB: f64 = 1024.0 * 1024.0;
+        write!(
+            f,
+            "{} Device Allocations, {} Allocations using {:.1} MiB of {} Blocks ({:.1} MiB), {} Dedicated ({:.1} MiB)",
+            self.device_allocations,
+            self.allocations,
+            self.used_bytes as f64 / MIB,
+            self.blocks,
+            self.block_bytes as f64 / MIB,
+            self.dedicated_allocations,
+            self.dedicated_bytes as f64 / MIB,
+        )
+    }
+}
+
+pub struct MemoryAllocator {
+    memory_props: vk::PhysicalDeviceMemoryProperties,
+    buffer_image_granularity: vk::DeviceSize,
+    state: Arc<Mutex<AllocatorState>>,
+}
+
+impl MemoryAllocator {
+    pub fn new(
+        memory_props: vk::PhysicalDeviceMemoryProperties,
+        buffer_image_granularity: vk::DeviceSize,
+    ) -> Self {
+        Self {
+            memory_props,
+            buffer_image_granularity: buffer_image_granularity.max(1),
+            state: Arc::default(),
+        }
+    }
+
+    /// Allocates memory for an Buffer and binds it
+    pub fn allocate_buffer(
+        &self,
+        device: &ash::Device,
+        buffer: vk::Buffer,
+        flags: vk::MemoryPropertyFlags,
+    ) -> VkResult<Allocation> {
+        let memory_req = unsafe { device.get_buffer_memory_requirements(buffer) };
+        let mut allocation =
+            self.allocate(device, memory_req, flags, ResourceKind::Linear, None)?;
+        if let Err(err) =
+            unsafe { device.bind_buffer_memory(buffer, allocation.memory, allocation.offset) }
+        {
+            allocation.free(device);
+            return Err(err);
+        }
+        Ok(allocation)
+    }
+
+    /// Allocates memory for an Image with optimal Tiling and binds it
+    pub fn allocate_image(
+        &self,
+        device: &ash::Device,
+        image: vk::Image,
+        flags: vk::MemoryPropertyFlags,
+    ) -> VkResult<Allocation> {
+        let memory_req = unsafe { device.get_image_memory_requirements(image) };
+        let mut allocation =
+            self.allocate(device, memory_req, flags, ResourceKind::Optimal, None)?;
+        if let Err(err) =
+            unsafe { device.bind_image_memory(image, allocation.memory, allocation.offset) }
+        {
+            allocation.free(device);
+            return Err(err);
+        }
+        Ok(allocation)
+    }
+
+    /// Creates an own Pool, Its Memory Type is picked out of `info.flags` when it is created
+    pub fn create_pool(&self, info: PoolInfo) -> Option<PoolId> {
+        let memory_type = self.find_memorytype_index(!0, info.flags)?;
+        let mut state = self.state.lock().unwrap();
+        state.pools.push(Pool {
+            memory_type,
+            strategy: info.strategy,
+            block_size: info.block_size,
+            max_blocks: info.max_blocks,
+            granularity: self.buffer_image_granularity,
+            blocks: Vec::new(),
+        });
+        Some(PoolId(state.pools.len() - 1))
+    }
+
+    /// Allocates without binding, `pool` is `None` for the Default Pools.
+    /// Allocations bigger than half an Block of the Default Pools get their own `vkAllocateMemory`
+    pub fn allocate(
+        &self,
+        device: &ash::Device,
+        memory_req: vk::MemoryRequirements,
+        flags: vk::MemoryPropertyFlags,
+        kind: ResourceKind,
+        pool: Option<PoolId>,
+    ) -> VkResult<Allocation> {
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        let id = AllocationId(state.next_id);
+        state.next_id += 1;
+
+        let pool_index = match pool {
+            Some(PoolId(pool)) => {
+                if (1 << state.pools[pool].memory_type) & memory_req.memory_type_bits == 0 {
+                    return Err(vk::Result::ERROR_FEATURE_NOT_PRESENT);
+                }
+                pool
+            }
+            None => {
+                let memory_type = self
+                    .find_memorytype_index(memory_req.memory_type_bits, flags)
+                    .ok_or(vk::Result::ERROR_FEATURE_NOT_PRESENT)?;
+                let block_size = self.default_block_size(memory_type);
+                if memory_req.size > block_size / 2 {
+                    drop(guard);
+                    return self.allocate_dedicated(device, memory_req.size, memory_type, id);
+                }
+                // Separate Pools for both Kinds keep Buffers and optimal Images out of each others Granularity Pages
+                let kind = if self.buffer_image_granularity > 1 {
+                    kind
+                } else {
+                    ResourceKind::Linear
+                };
+                *state
+                    .default_pools
+                    .entry((memory_type, kind))
+                    .or_insert_with(|| {
+                        state.pools.push(Pool {
+                            memory_type,
+                            strategy: PoolStrategy::Tlsf,
+                            block_size,
+                            max_blocks: 0,
+                            granularity: 1,
+                            blocks: Vec::new(),
+                        });
+                        state.pools.len() - 1
+                    })
+            }
+        };
+
+        let pool = &mut state.pools[pool_index];
+        let alignment = memory_req.alignment.max(pool.granularity).max(1);
+        let size = align_up(memory_req.size, pool.granularity);
+
+        let existing = pool
+            .blocks
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, block)| Some((index, block.as_mut()?)))
+            .find_map(|(index, block)| Some((index, block.allocator.allocate(size, alignment)?)));
+        let (block_index, offset) = match existing {
+            Some(found) => found,
+            None => {
+                let block_count = pool.blocks.iter().flatten().count();
+                if pool.max_blocks != 0 && block_count >= pool.max_blocks {
+                    return Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY);
+                }
+                let mut block = self.create_block(
+                    device,
+                    pool.memory_type,
+                    pool.block_size.max(size),
+                    pool.strategy,
+                )?;
+                let offset = block
+                    .allocator
+                    .allocate(size, alignment)
+                    .ok_or(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY)?;
+                // Reuse Slots of freed Blocks, So Block Indices of living Allocations stay valid
+                let index = match pool.blocks.iter().position(Option::is_none) {
+                    Some(index) => {
+                        pool.blocks[index] = Some(block);
+                        index
+                    }
+                    None => {
+                        pool.blocks.push(Some(block));
+                        pool.blocks.len() - 1
+                    }
+                };
+                (index, offset)
+            }
+        };
+
+        let block = pool.blocks[block_index].as_mut().unwrap();
+        block.allocations.insert(offset, (id, size));
+        Ok(Allocation {
+            memory: block.memory,
+            offset,
+            size,
+            mapped: block.mapped.map(|ptr| {
+                MappedPtr(unsafe { NonNull::new_unchecked(ptr.0.as_ptr().add(offset as usize)) })
+            }),
+            id,
+            location: Location::Block {
+                pool: pool_index,
+                block: block_index,
+            },
+            state: self.state.clone(),
+        })
+    }
+
+    fn allocate_dedicated(
+        &self,
+        device: &ash::Device,
+        size: vk::DeviceSize,
+        memory_type: u32,
+        id: AllocationId,
+    ) -> VkResult<Allocation> {
+        let (memory, mapped) = self.allocate_memory(device, size, memory_type)?;
+        let mut state = self.state.lock().unwrap();
+        state.dedicated_allocations += 1;
+        state.dedicated_bytes += size;
+        Ok(Allocation {
+            memory,
+            offset: 0,
+            size,
+            mapped,
+            id,
+            location: Location::Dedicated,
+            state: self.state.clone(),
+        })
+    }
+
+    fn create_block(
+        &self,
+        device: &ash::Device,
+        memory_type: u32,
+        size: vk::DeviceSize,
+        strategy: PoolStrategy,
+    ) -> VkResult<MemoryBlock> {
+        let (memory, mapped) = self.allocate_memory(device, size, memory_type)?;
+        let allocator: Box<dyn SubAllocator> = match strategy {
+            PoolStrategy::Tlsf => Box::new(TlsfAllocator::new(size)),
+            PoolStrategy::Linear => Box::new(LinearAllocator::new(size)),
+        };
+        log::debug!(
+            "Allocated Memory Block of {} MiB, Memory Type {}",
+            size / (1024 * 1024),
+            memory_type
+        );
+        Ok(MemoryBlock {
+            memory,
+            size,
+            mapped,
+            allocator,
+            allocations: BTreeMap::new(),
+        })
+    }
+
+    /// Maps Host visible Memory right away, It stays mapped until it is freed
+    fn allocate_memory(
+        &self,
+        device: &ash::Device,
+        size: vk::DeviceSize,
+        memory_type: u32,
+    ) -> VkResult<(vk::DeviceMemory, Option<MappedPtr>)> {
+        let memory_info = vk::MemoryAllocateInfo::default()
+            .allocation_size(size)
+            .memory_type_index(memory_type);
+        let memory = unsafe { device.allocate_memory(&memory_info, None) }?;
+
+        let flags = self.memory_props.memory_types[memory_type as usize].property_flags;
+        if !flags.contains(vk::MemoryPropertyFlags::HOST_VISIBLE) {
+            return Ok((memory, None));
+        }
+        match unsafe { device.map_memory(memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty()) } {
+            Ok(ptr) => Ok((memory, NonNull::new(ptr.cast()).map(MappedPtr))),
+            Err(err) => {
+                unsafe { device.free_memory(memory, None) };
+                Err(err)
+            }
+        }
+    }
+
+    fn default_block_size(&self, memory_type: u32) -> vk::DeviceSize {
+        let heap = self.memory_props.memory_types[memory_type as usize].heap_index;
+        let heap_size = self.memory_props.memory_heaps[heap as usize].size;
+        if heap_size < SMALL_HEAP_SIZE {
+            heap_size / 8
+        } else {
+            DEFAULT_BLOCK_SIZE
+        }
+    }
+
+    pub fn stats(&self) -> MemoryStats {
+        self.memory_type_stats()
+            .into_iter()
+            .fold(MemoryStats::default(), |total, (_, stats)| MemoryStats {
+                device_allocations: total.device_allocations + stats.device_allocations,
+                blocks: total.blocks + stats.blocks,
+                block_bytes: total.block_bytes + stats.block_bytes,
+                allocations: total.allocations + stats.allocations,
+                used_bytes: total.used_bytes + stats.used_bytes,
+                dedicated_allocations: total.dedicated_allocations + stats.dedicated_allocations,
+                dedicated_bytes: total.dedicated_bytes + stats.dedicated_bytes,
+                largest_free_region: total.largest_free_region.max(stats.largest_free_region),
+            })
+    }
+
+    /// Stats of every Memory Type which has Blocks, Dedicated Allocations are not tracked per Type
+    /// and are reported under `u32::MAX`
+    pub fn memory_type_stats(&self) -> Vec<(u32, MemoryStats)> {
+        let state = self.state.lock().unwrap();
+        let mut per_type: BTreeMap<u32, MemoryStats> = BTreeMap::new();
+        for pool in &state.pools {
+            let stats = per_type.entry(pool.memory_type).or_default();
+            for block in pool.blocks.iter().flatten() {
+                stats.device_allocations += 1;
+                stats.blocks += 1;
+                stats.block_bytes += block.size;
+                stats.allocations += block.allocator.allocation_count();
+                stats.used_bytes += block.allocator.used();
+                stats.largest_free_region = stats
+                    .largest_free_region
+                    .max(block.allocator.largest_free_region());
+            }
+        }
+        if state.dedicated_allocations > 0 {
+            per_type.insert(
+                u32::MAX,
+                MemoryStats {
+                    device_allocations: state.dedicated_allocations,
+                    dedicated_allocations: state.dedicated_allocations,
+                    dedicated_bytes: state.dedicated_bytes,
+                    ..Default::default()
+                },
+            );
+        }
+        per_type.into_iter().collect()
+    }
+
+    /// Defragmentation Hook, Returns the Allocations of the least used Blocks of every TLSF Pool
+    /// when they would fit into the free Space of the other Blocks. The Allocator can not move Resources itself,
+    /// The Owner recreates each Resource with an new Allocation, Copies the Content and frees the old one.
+    /// Blocks which become empty are released
+    pub fn defragmentation_moves(&self) -> Vec<DefragmentationMove> {
+        let state = self.state.lock().unwrap();
+        let mut moves = Vec::new();
+        for pool in &state.pools {
+            if pool.strategy != PoolStrategy::Tlsf {
+                continue;
+            }
+            let mut blocks: Vec<&MemoryBlock> = pool
+                .blocks
+                .iter()
+                .flatten()
+                .filter(|block| !block.allocations.is_empty())
+                .collect();
+            if blocks.len() < 2 {
+                continue;
+            }
+            blocks.sort_by_key(|block| block.allocator.used());
+
+            let mut free_space: vk::DeviceSize = blocks
+                .iter()
+                .map(|block| block.size - block.allocator.used())
+                .sum();
+            for block in blocks.iter().take(blocks.len() - 1) {
+                let used = block.allocator.used();
+                // The Block we empty can not hold its own Allocations
+                free_space -= block.size - used;
+                if used > free_space {
+                    break;
+                }
+                free_space -= used;
+                moves.extend(
+                    block
+                        .allocations
+                        .iter()
+                        .map(|(&offset, &(allocation, size))| DefragmentationMove {
+                            allocation,
+                            memory_type: pool.memory_type,
+                            memory: block.memory,
+                            offset,
+                            size,
+                        }),
+                );
+            }
+        }
+        moves
+    }
+
+    /// Frees every Block, Allocations which are still alive are leaked and reported
+    pub fn destroy(&mut self, device: &ash::Device) {
+        let stats = self.stats();
+        if stats.allocations > 0 || stats.dedicated_allocations > 0 {
+            log::warn!(
+                "Destroying Memory Allocator with living Allocations: {}",
+                stats
+            );
+        }
+        let mut state = self.state.lock().unwrap();
+        for pool in &mut state.pools {
+            for block in pool.blocks.drain(..).flatten() {
+                unsafe { device.free_memory(block.memory, None) };
+            }
+        }
+    }
+
+    fn find_memorytype_index(
+        &self,
+        memory_type_bits: u32,
+        flags: vk::MemoryPropertyFlags,
+    ) -> Option<u32> {
+        self.memory_props.memory_types[..self.memory_props.memory_type_count as _]
+            .iter()
+            .enumerate()
+            .find(|(index, memory_type)| {
+                (1 << index) & memory_type_bits != 0 && memory_type.property_flags & flags == flags
+            })
+            .map(|(index, _memory_type)| index as _)
+    }
+}
+
+impl Allocation {
+    pub fn memory(&self) -> vk::DeviceMemory {
+        self.memory
+    }
+
+    pub fn offset(&self) -> vk::DeviceSize {
+        self.offset
+    }
+
+    pub fn size(&self) -> vk::DeviceSize {
+        self.size
+    }
+
+    pub fn id(&self) -> AllocationId {
+        self.id
+    }
+
+    /// Pointer to the Start of the Allocation, `None` if the Memory is not Host visible
+    pub fn mapped_ptr(&self) -> Option<*mut c_void> {
+        self.mapped.map(|ptr| ptr.0.as_ptr().cast())
+    }
+
+    /// Returns the Range to its Block, An Block which becomes empty is released when the Pool has an other empty Block.
+    /// Freeing twice does nothing
+    pub fn free(&mut self, device: &ash::Device) {
+        match std::mem::replace(&mut self.location, Location::Freed) {
+            Location::Freed => {}
+            Location::Dedicated => {
+                unsafe { device.free_memory(self.memory, None) };
+                let mut state = self.state.lock().unwrap();
+                state.dedicated_allocations -= 1;
+                state.dedicated_bytes -= self.size;
+            }
+            Location::Block { pool, block } => {
+                let mut state = self.state.lock().unwrap();
+                let pool = &mut state.pools[pool];
+                // The Allocator may already have been destroyed
+                let Some(memory_block) = pool.blocks.get_mut(block).and_then(Option::as_mut) else {
+                    return;
+                };
+                memory_block.allocator.free(self.offset, self.size);
+                memory_block.allocations.remove(&self.offset);
+                if memory_block.allocations.is_empty() {
+                    let empty_blocks = pool
+                        .blocks
+                        .iter()
+                        .flatten()
+                        .filter(|block| block.allocations.is_empty())
+                        .count();
+                    // Keeping one empty Block avoids allocating again right after freeing
+                    if empty_blocks > 1 {
+                        let memory_block = pool.blocks[block].take().unwrap();
+                        unsafe { device.free_memory(memory_block.memory, None) };
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+
+use ash::vk::DeviceSize;
+
+use super::{align_up, SubAllocator};
+
+/// Every First Level (Power of Two) is split into this many Second Level Lists
+const SL_BITS: u32 = 4;
+const SL_COUNT: usize = 1 << SL_BITS;
+const FL_COUNT: usize = 64;
+
+/// An Range of the Block, Either free or allocated. Physical Neighbours are linked so freed Ranges can be merged
+struct Node {
+    offset: DeviceSize,
+    size: DeviceSize,
+    free: bool,
+    prev_physical: Option<usize>,
+    next_physical: Option<usize>,
+    prev_free: Option<usize>,
+    next_free: Option<usize>,
+}
+
+/// Two-Level Segregated Fit, Finds an fitting free Range in constant Time and merges Neighbours on free.
+/// See "TLSF: a New Dynamic Memory Allocator for Real-Time Systems" (Masmano et al.)
+pub struct TlsfAllocator {
+    nodes: Vec<Node>,
+    unused_nodes: Vec<usize>,
+    fl_bitmap: u64,
+    sl_bitmaps: [u32; FL_COUNT],
+    free_lists: [[Option<usize>; SL_COUNT]; FL_COUNT],
+    /// Offset of an Allocation to its Node
+    allocated: HashMap<DeviceSize, usize>,
+    used: DeviceSize,
+}
+
+impl TlsfAllocator {
+    pub fn new(size: DeviceSize) -> Self {
+        let mut allocator = Self {
+            nodes: Vec::new(),
+            unused_nodes: Vec::new(),
+            fl_bitmap: 0,
+            sl_bitmaps: [0; FL_COUNT],
+            free_lists: [[None; SL_COUNT]; FL_COUNT],
+            allocated: HashMap::new(),
+            used: 0,
+        };
+        let node = allocator.new_node(0, size, None, None);
+        allocator.insert_free(node);
+        allocator
+    }
+
+    /// The List an Range of `size` belongs to
+    fn mapping(size: DeviceSize) -> (usize, usize) {
+        if size < SL_COUNT as DeviceSize {
+            (0, size as usize)
+        } else {
+            let fl = size.ilog2();
+            let sl = (size >> (fl - SL_BITS)) as usize & (SL_COUNT - 1);
+            ((fl - SL_BITS + 1) as usize, sl)
+        }
+    }
+
+    /// Rounds up to the next List, So every Range in the returned List is big enough
+    fn mapping_search(size: DeviceSize) -> Option<(usize, usize)> {
+        let size = if size < SL_COUNT as DeviceSize {
+            size
+        } else {
+            size.checked_add((1 << (size.ilog2() - SL_BITS)) - 1)?
+        };
+        Some(Self::mapping(size))
+    }
+
+    fn new_node(
+        &mut self,
+        offset: DeviceSize,
+        size: DeviceSize,
+        prev_physical: Option<usize>,
+        next_physical: Option<usize>,
+    ) -> usize {
+        let node = Node {
+            offset,
+            size,
+            free: false,
+            prev_physical,
+            next_physical,
+            prev_free: None,
+            next_free: None,
+        };
+        match self.unused_nodes.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    fn insert_free(&mut self, index: usize) {
+        let (fl, sl) = Self::mapping(self.nodes[index].size);
+        let head = self.free_lists[fl][sl];
+        let node = &mut self.nodes[index];
+        node.free = true;
+        node.prev_free = None;
+        node.next_free = head;
+        if let Some(head) = head {
+            self.nodes[head].prev_free = Some(index);
+        }
+        self.free_lists[fl][sl] = Some(index);
+        self.fl_bitmap |= 1 << fl;
+        self.sl_bitmaps[fl] |= 1 << sl;
+    }
+
+    fn remove_free(&mut self, index: usize) {
+        let (fl, sl) = Self::mapping(self.nodes[index].size);
+        let Node {
+            prev_free,
+            next_free,
+            ..
+        } = self.nodes[index];
+        match prev_free {
+            Some(prev) => self.nodes[prev].next_free = next_free,
+            None => self.free_lists[fl][sl] = next_free,
+        }
+        if let Some(next) = next_free {
+            self.nodes[next].prev_free = prev_free;
+        }
+        if self.free_lists[fl][sl].is_none() {
+            self.sl_bitmaps[fl] &= !(1 << sl);
+            if self.sl_bitmaps[fl] == 0 {
+                self.fl_bitmap &= !(1 << fl);
+            }
+        }
+        self.nodes[index].free = false;
+    }
+
+    fn find_free(&self, size: DeviceSize) -> Option<usize> {
+        let (mut fl, sl) = Self::mapping_search(size)?;
+        if fl >= FL_COUNT {
+            return None;
+        }
+        let mut sl_map = self.sl_bitmaps[fl] & (!0 << sl);
+        if sl_map == 0 {
+            let fl_map = self.fl_bitmap & (!0u64).checked_shl(fl as u32 + 1).unwrap_or(0);
+            if fl_map == 0 {
+                return None;
+            }
+            fl = fl_map.trailing_zeros() as usize;
+            sl_map = self.sl_bitmaps[fl];
+        }
+        self.free_lists[fl][sl_map.trailing_zeros() as usize]
+    }
+
+    /// Splits `size` Bytes off the Front of `index`, The Rest becomes an new free Node
+    fn split(&mut self, index: usize, size: DeviceSize) {
+        let node = &self.nodes[index];
+        let (rest_offset, rest_size) = (node.offset + size, node.size - size);
+        let next = node.next_physical;
+        let rest = self.new_node(rest_offset, rest_size, Some(index), next);
+        if let Some(next) = next {
+            self.nodes[next].prev_physical = Some(rest);
+        }
+        self.nodes[index].size = size;
+        self.nodes[index].next_physical = Some(rest);
+        self.insert_free(rest);
+    }
+
+    /// Merges `next` into `index`, Both must be physical Neighbours
+    fn merge(&mut self, index: usize, next: usize) {
+        let Node {
+            size,
+            next_physical,
+            ..
+        } = self.nodes[next];
+        self.nodes[index].size += size;
+        self.nodes[index].next_physical = next_physical;
+        if let Some(after) = next_physical {
+            self.nodes[after].prev_physical = Some(index);
+        }
+        self.unused_nodes.push(next);
+    }
+}
+
+impl SubAllocator for TlsfAllocator {
+    fn allocate(&mut self, size: DeviceSize, alignment: DeviceSize) -> Option<DeviceSize> {
+        // Searching for the Size plus the worst Case Padding guarantees the aligned Range fits
+        let index = self.find_free(size.checked_add(alignment - 1)?)?;
+        self.remove_free(index);
+
+        let offset = self.nodes[index].offset;
+        let padding = align_up(offset, alignment) - offset;
+        let index = if padding > 0 {
+            // The Padding stays free as its own Node, Its previous Neighbour is never free
+            self.split(index, padding);
+            let aligned = self.nodes[index].next_physical.unwrap();
+            self.remove_free(aligned);
+            self.insert_free(index);
+            aligned
+        } else {
+            index
+        };
+        if self.nodes[index].size > size {
+            self.split(index, size);
+        }
+
+        let offset = self.nodes[index].offset;
+        self.allocated.insert(offset, index);
+        self.used += size;
+        Some(offset)
+    }
+
+    fn free(&mut self, offset: DeviceSize, _size: DeviceSize) {
+        let Some(mut index) = self.allocated.remove(&offset) else {
+            log::error!("Freeing unknown Allocation at Offset {}", offset);
+            return;
+        };
+        self.used -= self.nodes[index].size;
+
+        if let Some(next) = self.nodes[index].next_physical {
+            if self.nodes[next].free {
+                self.remove_free(next);
+                self.merge(index, next);
+            }
+        }
+        if let Some(prev) = self.nodes[index].prev_physical {
+            if self.nodes[prev].free {
+                self.remove_free(prev);
+                self.merge(prev, index);
+                index = prev;
+            }
+        }
+        self.insert_free(index);
+    }
+
+    fn allocation_count(&self) -> usize {
+        self.allocated.len()
+    }
+
+    fn used(&self) -> DeviceSize {
+        self.used
+    }
+
+    fn largest_free_region(&self) -> DeviceSize {
+        if self.fl_bitmap == 0 {
+            return 0;
+        }
+        // Only the highest List can contain the biggest Range
+        let fl = 63 - self.fl_bitmap.leading_zeros() as usize;
+        let sl = 31 - self.sl_bitmaps[fl].leading_zeros() as usize;
+        let mut largest = 0;
+        let mut next = self.free_lists[fl][sl];
+        while let Some(index) = next {
+            largest = largest.max(self.nodes[index].size);
+            next = self.nodes[index].next_free;
+        }
+        largest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mapping_buckets() {
+        // Small Sizes get an List each
+        assert_eq!(TlsfAllocator::mapping(0), (0, 0));
+        assert_eq!(TlsfAllocator::mapping(15), (0, 15));
+        // Every Power of Two is split into 16 Lists
+        assert_eq!(TlsfAllocator::mapping(16), (1, 0));
+        assert_eq!(TlsfAllocator::mapping(31), (1, 15));
+        assert_eq!(TlsfAllocator::mapping(32), (2, 0));
+        assert_eq!(TlsfAllocator::mapping(35), (2, 1));
+        assert_eq!(TlsfAllocator::mapping(1 << 20), (17, 0));
+        assert_eq!(
+            TlsfAllocator::mapping(DeviceSize::MAX),
+            (FL_COUNT - 4, SL_COUNT - 1)
+        );
+
+        let mut previous = (0, 0);
+        for size in 1..4096 {
+            let mapping = TlsfAllocator::mapping(size);
+            assert!(mapping >= previous);
+            previous = mapping;
+            // The searched List only contains Ranges which are big enough
+            let (fl, sl) = TlsfAllocator::mapping_search(size).unwrap();
+            let smallest = (size..).find(|&s| TlsfAllocator::mapping(s) == (fl, sl));
+            assert!(smallest.is_some_and(|smallest| smallest >= size));
+            assert!((fl, sl) >= mapping);
+        }
+        assert_eq!(TlsfAllocator::mapping_search(DeviceSize::MAX), None);
+    }
+
+    #[test]
+    fn alignment() {
+        let mut allocator = TlsfAllocator::new(4096);
+        assert_eq!(allocator.allocate(10, 1), Some(0));
+        let aligned = allocator.allocate(64, 256).unwrap();
+        assert_eq!(aligned % 256, 0);
+        // The Padding in front of the aligned Allocation is still free
+        let padding = allocator.allocate(100, 4).unwrap();
+        assert!(padding >= 10 && padding + 100 <= aligned);
+        assert_eq!(padding % 4, 0);
+        assert_eq!(allocator.used(), 174);
+        assert_eq!(allocator.allocation_count(), 3);
+    }
+
+    #[test]
+    fn merges_free_blocks() {
+        let mut allocator = TlsfAllocator::new(1024);
+        let offsets: Vec<_> = (0..4)
+            .map(|_| allocator.allocate(256, 1).unwrap())
+            .collect();
+        assert_eq!(offsets, [0, 256, 512, 768]);
+        assert_eq!(allocator.largest_free_region(), 0);
+
+        // Merges with the next Neighbour, Then with the previous one
+        allocator.free(512, 256);
+        allocator.free(256, 256);
+        assert_eq!(allocator.largest_free_region(), 512);
+        assert_eq!(allocator.allocate(512, 1), Some(256));
+        allocator.free(256, 512);
+
+        allocator.free(0, 256);
+        allocator.free(768, 256);
+        assert_eq!(allocator.allocation_count(), 0);
+        assert_eq!(allocator.used(), 0);
+        assert_eq!(allocator.largest_free_region(), 1024);
+        assert_eq!(allocator.allocate(1024, 1), Some(0));
+    }
+
+    #[test]
+    fn out_of_space() {
+        let mut allocator = TlsfAllocator::new(1024);
+        assert_eq!(allocator.allocate(1025, 1), None);
+        assert_eq!(allocator.allocate(DeviceSize::MAX, 1), None);
+        assert_eq!(allocator.allocate(1000, 1), Some(0));
+        // 24 Bytes are left, But not aligned to 256
+        assert_eq!(allocator.allocate(16, 256), None);
+        assert_eq!(allocator.allocate(16, 8), Some(1000));
+        assert_eq!(allocator.allocate(16, 1), None);
+
+        // Freeing an unknown Offset is logged and ignored
+        allocator.free(1, 16);
+        assert_eq!(allocator.allocation_count(), 2);
+    }
+}
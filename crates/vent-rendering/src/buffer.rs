@@ -1,15 +1,16 @@
 use std::{mem::align_of, os::raw::c_void};
 
-use ash::vk;
+use ash::{prelude::VkResult, vk};
 
 use crate::{
-    allocator::MemoryAllocator, begin_single_time_command, debug, end_single_time_command,
+    allocator::{Allocation, MemoryAllocator},
+    debug,
     instance::VulkanInstance,
 };
 
 pub struct VulkanBuffer {
     pub buffer: vk::Buffer,
-    pub buffer_memory: vk::DeviceMemory,
+    pub allocation: Allocation,
 }
 
 impl VulkanBuffer {
@@ -23,18 +24,18 @@ impl VulkanBuffer {
         usage: vk::BufferUsageFlags,
         flags: vk::MemoryPropertyFlags,
         name: Option<&str>,
-    ) -> Self {
+    ) -> VkResult<Self> {
         let buffer_info = vk::BufferCreateInfo::default()
             .size(size)
             .usage(usage)
             .sharing_mode(vk::SharingMode::EXCLUSIVE);
 
-        let buffer = unsafe { instance.device.create_buffer(&buffer_info, None) }.unwrap();
+        let buffer = unsafe { instance.device.create_buffer(&buffer_info, None) }?;
 
-        let buffer_memory =
-            instance
-                .memory_allocator
-                .allocate_buffer(&instance.device, buffer, flags);
+        let allocation = instance
+            .memory_allocator
+            .allocate_buffer(&instance.device, buffer, flags)
+            .inspect_err(|_| unsafe { instance.device.destroy_buffer(buffer, None) })?;
 
         if instance.validation {
             if let Some(name) = name {
@@ -42,14 +43,11 @@ impl VulkanBuffer {
             }
         }
 
-        Self {
-            buffer,
-            buffer_memory,
-        }
+        Ok(Self { buffer, allocation })
     }
 
     /**
-     * Allocates & Binds an Staging buffer for CPU and then uploading it to the GPU
+     * Allocates & Binds Device local Memory, The Data is copied with the next Frame (See `UploadManager`)
      */
     pub fn cpu_to_gpu<T: Copy>(
         instance: &VulkanInstance,
@@ -57,47 +55,21 @@ impl VulkanBuffer {
         size: vk::DeviceSize,
         usage: vk::BufferUsageFlags,
         name: Option<&str>,
-    ) -> Self {
-        let buffer = VulkanBuffer::new(
+    ) -> VkResult<Self> {
+        let mut buffer = VulkanBuffer::new(
             instance,
             size,
             usage | vk::BufferUsageFlags::TRANSFER_DST,
             vk::MemoryPropertyFlags::DEVICE_LOCAL,
             name,
-        );
-        let mut staging_buf = VulkanBuffer::new(
-            instance,
-            size,
-            vk::BufferUsageFlags::TRANSFER_SRC,
-            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
-            name,
-        );
-
-        let memory = staging_buf.map(&instance.device, size);
-
-        unsafe { staging_buf.upload_data(memory, data, size) };
-
-        let command_buffer =
-            begin_single_time_command(&instance.device, instance.global_command_pool);
-
-        unsafe {
-            let buffer_info = vk::BufferCopy::default().size(size);
-
-            instance
-                .device
-                .cmd_copy_buffer(command_buffer, *staging_buf, *buffer, &[buffer_info]);
-        }
-        staging_buf.unmap(&instance.device);
-
-        end_single_time_command(
-            &instance.device,
-            instance.global_command_pool,
-            instance.graphics_queue,
-            command_buffer,
-        );
-
-        staging_buf.destroy(&instance.device);
-        buffer
+        )?;
+        instance
+            .upload_manager
+            .upload_buffer_with(instance, *buffer, size, |staging, memory| unsafe {
+                staging.upload_data(memory, data, size)
+            })
+            .inspect_err(|_| buffer.destroy(&instance.device))?;
+        Ok(buffer)
     }
 
     /**
@@ -107,7 +79,7 @@ impl VulkanBuffer {
         device: &ash::Device,
         allocator: &MemoryAllocator,
         image: vk::Image,
-    ) -> vk::DeviceMemory {
+    ) -> VkResult<Allocation> {
         allocator.allocate_image(device, image, vk::MemoryPropertyFlags::DEVICE_LOCAL)
     }
 
@@ -121,12 +93,12 @@ impl VulkanBuffer {
         data: &[T],
         flags: vk::MemoryPropertyFlags,
         name: Option<&str>,
-    ) -> Self {
-        let buffer = Self::new(instance, size, usage, flags, name);
+    ) -> VkResult<Self> {
+        let buffer = Self::new(instance, size, usage, flags, name)?;
         let memory = buffer.map(&instance.device, size);
         unsafe { buffer.upload_data(memory, data, size) };
         buffer.unmap(&instance.device);
-        buffer
+        Ok(buffer)
     }
 
     /// # Safety
@@ -142,22 +114,20 @@ impl VulkanBuffer {
         align.copy_from_slice(data);
     }
 
-    pub fn map(&self, device: &ash::Device, size: vk::DeviceSize) -> *mut c_void {
-        unsafe {
-            device
-                .map_memory(self.buffer_memory, 0, size, vk::MemoryMapFlags::empty())
-                .unwrap()
-        }
+    /// Host visible Memory is mapped by the Allocator as long as it lives, So this only returns the Pointer
+    pub fn map(&self, _device: &ash::Device, _size: vk::DeviceSize) -> *mut c_void {
+        self.allocation
+            .mapped_ptr()
+            .expect("Buffer memory is not Host visible")
     }
 
-    pub fn unmap(&self, device: &ash::Device) {
-        unsafe { device.unmap_memory(self.buffer_memory) };
-    }
+    /// Does nothing, Kept so Uploads read the same as before
+    pub fn unmap(&self, _device: &ash::Device) {}
 
     pub fn destroy(&mut self, device: &ash::Device) {
         unsafe {
             device.destroy_buffer(self.buffer, None);
-            device.free_memory(self.buffer_memory, None); // Free Memory after buffer destruction!
+            self.allocation.free(device); // Free Memory after buffer destruction!
         }
     }
 }
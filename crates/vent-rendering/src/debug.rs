@@ -54,18 +54,21 @@ pub fn get_layer_names_and_pointers() -> (Vec<CString>, Vec<*const c_char>) {
 ///
 /// Panic if at least one on the layer is not supported.
 pub fn check_validation_layer_support(entry: &Entry) {
-    for required in REQUIRED_LAYERS.iter() {
-        let properties = unsafe { entry.enumerate_instance_layer_properties().unwrap() }; // TODO: Cache
-        let layers = properties.iter().find(|layer| {
+    if let Some(required) = missing_validation_layer(entry) {
+        panic!("Layer not supported: {}", required);
+    }
+}
+
+/// Returns the first Layer of `REQUIRED_LAYERS` which is not installed, Software Drivers in CI often come without them
+pub fn missing_validation_layer(entry: &Entry) -> Option<&'static str> {
+    let properties = unsafe { entry.enumerate_instance_layer_properties().unwrap() };
+    REQUIRED_LAYERS.iter().copied().find(|required| {
+        !properties.iter().any(|layer| {
             let name = unsafe { CStr::from_ptr(layer.layer_name.as_ptr()) };
             let name = name.to_str().expect("Failed to get layer name pointer");
             required == &name
-        });
-
-        if layers.is_none() {
-            panic!("Layer not supported: {}", required);
-        }
-    }
+        })
+    })
 }
 pub fn set_object_name<H: Handle>(
     debug_utils_device: &Option<debug_utils::Device>,
@@ -0,0 +1,158 @@
+use std::sync::{Arc, Mutex};
+
+use ash::vk;
+
+use crate::instance::VulkanInstance;
+
+///
+/// One big Array of Textures at Binding 0 which Shaders index, So Materials do not need their own Descriptor Set.
+/// Needs Descriptor Indexing, See `DeviceCapabilities::descriptor_indexing`.
+///
+/// Textures can be added and removed while Frames using the Set are in Flight (Update After Bind),
+/// An removed Index is only reused once the Frames in Flight finished
+///
+pub struct BindlessTextures {
+    pub layout: vk::DescriptorSetLayout,
+    pub descriptor_set: vk::DescriptorSet,
+    /// Has its own Pool, Update After Bind Sets can not come from the `DescriptorAllocator`
+    pool: vk::DescriptorPool,
+    capacity: u32,
+    next_index: u32,
+    /// Shared with the Deletion Queue, Which gives removed Indices back
+    free_indices: Arc<Mutex<Vec<u32>>>,
+}
+
+impl BindlessTextures {
+    /// `None` when the Device does not support Descriptor Indexing, `capacity` is clamped to the Device Limits
+    pub fn new(instance: &VulkanInstance, capacity: u32) -> Option<Self> {
+        if !instance.capabilities.descriptor_indexing {
+            return None;
+        }
+        let mut properties_1_2 = vk::PhysicalDeviceVulkan12Properties::default();
+        let mut properties =
+            vk::PhysicalDeviceProperties2::default().push_next(&mut properties_1_2);
+        unsafe {
+            instance
+                .instance
+                .get_physical_device_properties2(instance.physical_device, &mut properties)
+        };
+        let capacity = capacity
+            .min(properties_1_2.max_descriptor_set_update_after_bind_sampled_images)
+            .min(properties_1_2.max_per_stage_descriptor_update_after_bind_sampled_images)
+            .min(properties_1_2.max_descriptor_set_update_after_bind_samplers)
+            .min(properties_1_2.max_per_stage_descriptor_update_after_bind_samplers);
+
+        let device = &instance.device;
+        let binding_flags = [vk::DescriptorBindingFlags::PARTIALLY_BOUND
+            | vk::DescriptorBindingFlags::UPDATE_AFTER_BIND
+            | vk::DescriptorBindingFlags::VARIABLE_DESCRIPTOR_COUNT];
+        let mut flags_info =
+            vk::DescriptorSetLayoutBindingFlagsCreateInfo::default().binding_flags(&binding_flags);
+        let bindings = [vk::DescriptorSetLayoutBinding::default()
+            .binding(0)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .descriptor_count(capacity)
+            .stage_flags(vk::ShaderStageFlags::FRAGMENT)];
+        let layout_info = vk::DescriptorSetLayoutCreateInfo::default()
+            .flags(vk::DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL)
+            .bindings(&bindings)
+            .push_next(&mut flags_info);
+        let layout = unsafe { device.create_descriptor_set_layout(&layout_info, None) }.unwrap();
+
+        let pool_sizes = [vk::DescriptorPoolSize {
+            ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            descriptor_count: capacity,
+        }];
+        let pool_info = vk::DescriptorPoolCreateInfo::default()
+            .flags(vk::DescriptorPoolCreateFlags::UPDATE_AFTER_BIND)
+            .pool_sizes(&pool_sizes)
+            .max_sets(1);
+        let pool = unsafe { device.create_descriptor_pool(&pool_info, None) }.unwrap();
+
+        let counts = [capacity];
+        let mut count_info = vk::DescriptorSetVariableDescriptorCountAllocateInfo::default()
+            .descriptor_counts(&counts);
+        let layouts = [layout];
+        let allocate_info = vk::DescriptorSetAllocateInfo::default()
+            .descriptor_pool(pool)
+            .set_layouts(&layouts)
+            .push_next(&mut count_info);
+        let descriptor_set = unsafe { device.allocate_descriptor_sets(&allocate_info) }.unwrap()[0];
+
+        Some(Self {
+            layout,
+            descriptor_set,
+            pool,
+            capacity,
+            next_index: 0,
+            free_indices: Arc::default(),
+        })
+    }
+
+    /// Writes the Texture into an free Slot and returns its Index, `None` when the Array is full
+    pub fn add(
+        &mut self,
+        device: &ash::Device,
+        image_view: vk::ImageView,
+        sampler: vk::Sampler,
+    ) -> Option<u32> {
+        let free_index = self.free_indices.lock().unwrap().pop();
+        let index = match free_index {
+            Some(index) => index,
+            None if self.next_index < self.capacity => {
+                self.next_index += 1;
+                self.next_index - 1
+            }
+            None => return None,
+        };
+        let image_info = [vk::DescriptorImageInfo::default()
+            .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .image_view(image_view)
+            .sampler(sampler)];
+        let write = vk::WriteDescriptorSet::default()
+            .dst_set(self.descriptor_set)
+            .dst_binding(0)
+            .dst_array_element(index)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .image_info(&image_info);
+        unsafe { device.update_descriptor_sets(&[write], &[]) };
+        Some(index)
+    }
+
+    /// The Index is reused once the current Frame finished, So Frames in Flight can still sample it
+    pub fn remove(&self, instance: &VulkanInstance, index: u32) {
+        debug_assert!(index < self.next_index);
+        let free_indices = self.free_indices.clone();
+        instance.destroy_later(move |_| free_indices.lock().unwrap().push(index));
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    pub fn bind(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        pipeline_layout: vk::PipelineLayout,
+        set: u32,
+    ) {
+        unsafe {
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline_layout,
+                set,
+                &[self.descriptor_set],
+                &[],
+            )
+        }
+    }
+
+    pub fn destroy(&mut self, device: &ash::Device) {
+        unsafe {
+            device.destroy_descriptor_pool(self.pool, None);
+            device.destroy_descriptor_set_layout(self.layout, None);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! Allocates Descriptor Sets out of shared Pools. Pools used to be created per Model with an fixed Size,
+//! Now new Pools are created when the old ones are full and freed Sets are reused for their Layout
+
+use std::{collections::HashMap, sync::Mutex};
+
+use ash::{prelude::VkResult, vk};
+
+mod bindless;
+
+pub use bindless::BindlessTextures;
+
+/// Sets of the first Pool, Every following Pool gets more Sets until `MAX_POOL_SETS`
+const INITIAL_POOL_SETS: u32 = 64;
+const MAX_POOL_SETS: u32 = 4096;
+
+/// Descriptors per Set of every Type used by the Engine, Material Sets have five Textures and one Uniform Buffer.
+/// Per Frame Data like Joint Matrices is bound with dynamic Offsets into the `UniformRing`
+pub const DEFAULT_POOL_RATIOS: &[(vk::DescriptorType, f32)] = &[
+    (vk::DescriptorType::COMBINED_IMAGE_SAMPLER, 4.0),
+    (vk::DescriptorType::UNIFORM_BUFFER, 1.0),
+    (vk::DescriptorType::STORAGE_BUFFER, 1.0),
+    (vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC, 1.0),
+    (vk::DescriptorType::STORAGE_BUFFER_DYNAMIC, 2.0),
+];
+
+#[derive(Default)]
+struct AllocatorState {
+    /// Pools which may still have Space, Allocations always try the last one
+    ready_pools: Vec<vk::DescriptorPool>,
+    full_pools: Vec<vk::DescriptorPool>,
+    sets_per_pool: u32,
+    /// Sets given back by `free`, Reused by the next Allocation with the same Layout
+    free_sets: HashMap<vk::DescriptorSetLayout, Vec<vk::DescriptorSet>>,
+    /// Layout of every Set handed out, So `free` only needs the Sets
+    layouts: HashMap<vk::DescriptorSet, vk::DescriptorSetLayout>,
+}
+
+///
+/// Hands out Descriptor Sets of any Layout. Sets are never freed back to their Pool,
+/// Instead `free` keeps them for the next Allocation with the same Layout which avoids Pool Fragmentation.
+///
+/// Can be shared between Threads, Every Method only takes `&self`
+///
+pub struct DescriptorAllocator {
+    ratios: Vec<(vk::DescriptorType, f32)>,
+    state: Mutex<AllocatorState>,
+}
+
+impl Default for DescriptorAllocator {
+    fn default() -> Self {
+        Self::new(DEFAULT_POOL_RATIOS)
+    }
+}
+
+impl DescriptorAllocator {
+    /// `ratios` are the Descriptors per Set of each Type, An Set which needs more than an new Pool has fails to allocate
+    pub fn new(ratios: &[(vk::DescriptorType, f32)]) -> Self {
+        Self {
+            ratios: ratios.to_vec(),
+            state: Mutex::new(AllocatorState {
+                sets_per_pool: INITIAL_POOL_SETS,
+                ..Default::default()
+            }),
+        }
+    }
+
+    pub fn allocate(
+        &self,
+        device: &ash::Device,
+        layout: vk::DescriptorSetLayout,
+    ) -> VkResult<vk::DescriptorSet> {
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        if let Some(set) = state.free_sets.get_mut(&layout).and_then(Vec::pop) {
+            return Ok(set);
+        }
+
+        let layouts = [layout];
+        let mut new_pool = false;
+        loop {
+            let pool = match state.ready_pools.last() {
+                Some(pool) => *pool,
+                None => {
+                    let pool = self.create_pool(device, state.sets_per_pool)?;
+                    state.sets_per_pool = (state.sets_per_pool * 3 / 2).min(MAX_POOL_SETS);
+                    state.ready_pools.push(pool);
+                    new_pool = true;
+                    pool
+                }
+            };
+            let info = vk::DescriptorSetAllocateInfo::default()
+                .descriptor_pool(pool)
+                .set_layouts(&layouts);
+            match unsafe { device.allocate_descriptor_sets(&info) } {
+                Ok(sets) => {
+                    state.layouts.insert(sets[0], layout);
+                    return Ok(sets[0]);
+                }
+                Err(vk::Result::ERROR_OUT_OF_POOL_MEMORY | vk::Result::ERROR_FRAGMENTED_POOL) => {
+                    state.ready_pools.pop();
+                    state.full_pools.push(pool);
+                    // Does not even fit into an empty Pool, Trying again would create Pools forever
+                    if new_pool {
+                        return Err(vk::Result::ERROR_OUT_OF_POOL_MEMORY);
+                    }
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Allocates `count` Sets, e.g. one for every Frame
+    pub fn allocate_many(
+        &self,
+        device: &ash::Device,
+        layout: vk::DescriptorSetLayout,
+        count: usize,
+    ) -> VkResult<Vec<vk::DescriptorSet>> {
+        (0..count).map(|_| self.allocate(device, layout)).collect()
+    }
+
+    /// Gives Sets back for Reuse. The GPU must be done with them and they have to be written again before the next Use,
+    /// See `forget_layout` when their Layout is destroyed
+    pub fn free(&self, sets: &[vk::DescriptorSet]) {
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        for set in sets {
+            match state.layouts.get(set) {
+                Some(layout) => state.free_sets.entry(*layout).or_default().push(*set),
+                None => log::warn!("Freed Descriptor Set {:?} was not allocated here", set),
+            }
+        }
+    }
+
+    /// Has to be called before an Layout is destroyed, Otherwise an new Layout with the same Handle would get its freed Sets
+    pub fn forget_layout(&self, layout: vk::DescriptorSetLayout) {
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        // The Sets stay in their Pool until it is destroyed
+        state.free_sets.remove(&layout);
+        state.layouts.retain(|_, set_layout| *set_layout != layout);
+    }
+
+    /// Number of Pools and of Sets which are handed out
+    pub fn stats(&self) -> (usize, usize) {
+        let state = self.state.lock().unwrap();
+        let free: usize = state.free_sets.values().map(Vec::len).sum();
+        (
+            state.ready_pools.len() + state.full_pools.len(),
+            state.layouts.len() - free,
+        )
+    }
+
+    fn create_pool(&self, device: &ash::Device, max_sets: u32) -> VkResult<vk::DescriptorPool> {
+        let pool_sizes: Vec<_> = self
+            .ratios
+            .iter()
+            .map(|(ty, ratio)| vk::DescriptorPoolSize {
+                ty: *ty,
+                descriptor_count: (ratio * max_sets as f32).ceil() as u32,
+            })
+            .collect();
+        let create_info = vk::DescriptorPoolCreateInfo::default()
+            .pool_sizes(&pool_sizes)
+            .max_sets(max_sets);
+        unsafe { device.create_descriptor_pool(&create_info, None) }
+    }
+
+    /// Destroys every Pool and with them every Set
+    pub fn destroy(&mut self, device: &ash::Device) {
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        for pool in state
+            .ready_pools
+            .drain(..)
+            .chain(state.full_pools.drain(..))
+        {
+            unsafe { device.destroy_descriptor_pool(pool, None) };
+        }
+        state.free_sets.clear();
+        state.layouts.clear();
+    }
+}
@@ -0,0 +1,448 @@
+//! Picks the Physical Device to render with. Every Device is checked for what the Engine needs,
+//! The usable ones are scored and the Reason why an Device was rejected is logged
+
+use std::{ffi::CStr, fmt};
+
+use ash::{ext, khr, vk};
+
+/// Forces an Device by its Index or an Part of its Name, Overrides the Device of the Project Settings
+pub const DEVICE_ENV: &str = "VENT_DEVICE";
+
+/// Formats checked for `DeviceCapabilities::formats`, Textures and Attachments the Engine may create
+const CANDIDATE_FORMATS: &[vk::Format] = &[
+    vk::Format::R8G8B8A8_UNORM,
+    vk::Format::R8G8B8A8_SRGB,
+    vk::Format::B8G8R8A8_UNORM,
+    vk::Format::B8G8R8A8_SRGB,
+    vk::Format::R16G16B16A16_SFLOAT,
+    vk::Format::R32G32B32A32_SFLOAT,
+    vk::Format::BC1_RGBA_UNORM_BLOCK,
+    vk::Format::BC1_RGBA_SRGB_BLOCK,
+    vk::Format::BC3_UNORM_BLOCK,
+    vk::Format::BC3_SRGB_BLOCK,
+    vk::Format::BC4_UNORM_BLOCK,
+    vk::Format::BC5_UNORM_BLOCK,
+    vk::Format::BC7_UNORM_BLOCK,
+    vk::Format::BC7_SRGB_BLOCK,
+    vk::Format::ETC2_R8G8B8A8_UNORM_BLOCK,
+    vk::Format::ETC2_R8G8B8A8_SRGB_BLOCK,
+    vk::Format::ASTC_4X4_UNORM_BLOCK,
+    vk::Format::ASTC_4X4_SRGB_BLOCK,
+    vk::Format::D16_UNORM,
+    vk::Format::D24_UNORM_S8_UINT,
+    vk::Format::D32_SFLOAT,
+    vk::Format::D32_SFLOAT_S8_UINT,
+];
+
+/// Why an Physical Device can not be used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceRejection {
+    /// The Engine needs Vulkan 1.3
+    ApiVersion(u32),
+    MissingExtension(&'static CStr),
+    MissingFeature(&'static str),
+    NoGraphicsQueue,
+    NoPresentQueue,
+}
+
+impl fmt::Display for DeviceRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ApiVersion(version) => write!(
+                f,
+                "Supports only Vulkan {}.{}, Vulkan 1.3 is required",
+                vk::api_version_major(*version),
+                vk::api_version_minor(*version)
+            ),
+            Self::MissingExtension(name) => {
+                write!(f, "Missing Extension {}", name.to_string_lossy())
+            }
+            Self::MissingFeature(name) => write!(f, "Missing Feature {}", name),
+            Self::NoGraphicsQueue => write!(f, "No Graphics Queue"),
+            Self::NoPresentQueue => write!(f, "No Queue can present to the Surface"),
+        }
+    }
+}
+
+///
+/// What the selected Device supports and what was enabled on it.
+/// Optional Features like `index_type_uint8` are enabled whenever the Device supports them
+///
+pub struct DeviceCapabilities {
+    pub name: String,
+    pub device_type: vk::PhysicalDeviceType,
+    pub api_version: u32,
+    pub limits: vk::PhysicalDeviceLimits,
+    /// Candidate Formats with their optimal Tiling Features, Formats without any Feature are left out
+    pub formats: Vec<(vk::Format, vk::FormatFeatureFlags)>,
+    /// Extensions enabled on the Device
+    pub extensions: Vec<&'static CStr>,
+    /// `Indices::U8` can be bound directly, Otherwise they are widened to 16 Bit when the Mesh is created
+    pub index_type_uint8: bool,
+    /// Descriptor Indexing is supported and enabled, So `BindlessTextures` can be created
+    pub descriptor_indexing: bool,
+}
+
+impl DeviceCapabilities {
+    pub(crate) fn new(
+        instance: &ash::Instance,
+        pdevice: vk::PhysicalDevice,
+        headless: bool,
+    ) -> Self {
+        let properties = unsafe { instance.get_physical_device_properties(pdevice) };
+        let supported = supported_extensions(instance, pdevice);
+        let has = |name: &'static CStr| supported.contains(&name);
+
+        let mut extensions = Vec::new();
+        if !headless {
+            extensions.push(khr::swapchain::NAME);
+        }
+        // Has to be enabled when present, e.g. on MoltenVK
+        if has(khr::portability_subset::NAME) {
+            extensions.push(khr::portability_subset::NAME);
+        }
+        let index_type_uint8_extension = [khr::index_type_uint8::NAME, ext::index_type_uint8::NAME]
+            .into_iter()
+            .find(|name| has(name));
+
+        let core_features = unsafe { instance.get_physical_device_features(pdevice) };
+        let mut features_1_2 = vk::PhysicalDeviceVulkan12Features::default();
+        let mut features_uint8 = vk::PhysicalDeviceIndexTypeUint8FeaturesKHR::default();
+        let mut features = vk::PhysicalDeviceFeatures2::default().push_next(&mut features_1_2);
+        if index_type_uint8_extension.is_some() {
+            features = features.push_next(&mut features_uint8);
+        }
+        unsafe { instance.get_physical_device_features2(pdevice, &mut features) };
+
+        let index_type_uint8 = match index_type_uint8_extension {
+            Some(name) if features_uint8.index_type_uint8 == vk::TRUE => {
+                extensions.push(name);
+                true
+            }
+            _ => false,
+        };
+        // Everything `BindlessTextures` needs, Most Desktop GPUs support it
+        let descriptor_indexing = core_features.shader_sampled_image_array_dynamic_indexing
+            == vk::TRUE
+            && features_1_2.runtime_descriptor_array == vk::TRUE
+            && features_1_2.descriptor_binding_partially_bound == vk::TRUE
+            && features_1_2.descriptor_binding_sampled_image_update_after_bind == vk::TRUE
+            && features_1_2.descriptor_binding_variable_descriptor_count == vk::TRUE;
+
+        let formats = CANDIDATE_FORMATS
+            .iter()
+            .map(|format| {
+                let properties =
+                    unsafe { instance.get_physical_device_format_properties(pdevice, *format) };
+                (*format, properties.optimal_tiling_features)
+            })
+            .filter(|(_, features)| !features.is_empty())
+            .collect();
+
+        Self {
+            name: device_name(&properties),
+            device_type: properties.device_type,
+            api_version: properties.api_version,
+            limits: properties.limits,
+            formats,
+            extensions,
+            index_type_uint8,
+            descriptor_indexing,
+        }
+    }
+
+    /// Whether Images with optimal Tiling of `format` support all of `features`, Only knows the Candidate Formats
+    pub fn supports_format(&self, format: vk::Format, features: vk::FormatFeatureFlags) -> bool {
+        self.formats
+            .iter()
+            .any(|(candidate, supported)| *candidate == format && supported.contains(features))
+    }
+}
+
+/// The usable Device with the highest Score and the Queue Families to render and present with
+pub(crate) struct SelectedDevice {
+    pub pdevice: vk::PhysicalDevice,
+    pub graphics_family: u32,
+    pub present_family: u32,
+}
+
+struct Candidate {
+    name: String,
+    device_type: vk::PhysicalDeviceType,
+    /// Size of the biggest Device Local Heap, Decides between Devices of the same Type
+    local_memory: vk::DeviceSize,
+    /// Graphics and Present Queue Family
+    checked: Result<(u32, u32), DeviceRejection>,
+}
+
+///
+/// Checks every Device and picks the best usable one, Discrete over Integrated over Virtual over CPU.
+/// `preferred` is an Device Index or an case insensitive Part of its Name, `DEVICE_ENV` overrides it.
+/// An preferred Device which does not exist or can not be used is logged and ignored
+///
+pub(crate) fn select_physical_device(
+    instance: &ash::Instance,
+    surface_loader: &khr::surface::Instance,
+    surface: vk::SurfaceKHR,
+    preferred: Option<&str>,
+) -> SelectedDevice {
+    let pdevices = unsafe {
+        instance
+            .enumerate_physical_devices()
+            .expect("Physical device error")
+    };
+    let candidates: Vec<_> = pdevices
+        .iter()
+        .map(|pdevice| {
+            let properties = unsafe { instance.get_physical_device_properties(*pdevice) };
+            let memory = unsafe { instance.get_physical_device_memory_properties(*pdevice) };
+            let candidate = Candidate {
+                name: device_name(&properties),
+                device_type: properties.device_type,
+                local_memory: memory
+                    .memory_heaps_as_slice()
+                    .iter()
+                    .filter(|heap| heap.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL))
+                    .map(|heap| heap.size)
+                    .max()
+                    .unwrap_or_default(),
+                checked: check_device(instance, surface_loader, surface, *pdevice, &properties),
+            };
+            match &candidate.checked {
+                Ok(_) => log::debug!("Found `{}` ({:?})", candidate.name, candidate.device_type),
+                Err(reason) => log::info!("Rejected `{}`: {}", candidate.name, reason),
+            }
+            candidate
+        })
+        .collect();
+
+    let forced = std::env::var(DEVICE_ENV).ok();
+    let preferred = forced.as_deref().or(preferred);
+    let Some(index) = choose(&candidates, preferred) else {
+        let reasons: Vec<_> = candidates
+            .iter()
+            .filter_map(|c| {
+                c.checked
+                    .as_ref()
+                    .err()
+                    .map(|r| format!("`{}`: {}", c.name, r))
+            })
+            .collect();
+        panic!("No suitable Vulkan Device found [{}]", reasons.join(", "));
+    };
+
+    let candidate = &candidates[index];
+    log::info!(
+        "Selected `{}` ({:?})",
+        candidate.name,
+        candidate.device_type
+    );
+    let (graphics_family, present_family) = candidate.checked.clone().unwrap();
+    SelectedDevice {
+        pdevice: pdevices[index],
+        graphics_family,
+        present_family,
+    }
+}
+
+fn check_device(
+    instance: &ash::Instance,
+    surface_loader: &khr::surface::Instance,
+    surface: vk::SurfaceKHR,
+    pdevice: vk::PhysicalDevice,
+    properties: &vk::PhysicalDeviceProperties,
+) -> Result<(u32, u32), DeviceRejection> {
+    if properties.api_version < vk::API_VERSION_1_3 {
+        return Err(DeviceRejection::ApiVersion(properties.api_version));
+    }
+
+    // Headless Instances have no Surface to present to
+    let headless = surface == vk::SurfaceKHR::null();
+    if !headless && !supported_extensions(instance, pdevice).contains(&khr::swapchain::NAME) {
+        return Err(DeviceRejection::MissingExtension(khr::swapchain::NAME));
+    }
+
+    let mut features_1_2 = vk::PhysicalDeviceVulkan12Features::default();
+    let mut features_1_3 = vk::PhysicalDeviceVulkan13Features::default();
+    let mut features = vk::PhysicalDeviceFeatures2::default()
+        .push_next(&mut features_1_2)
+        .push_next(&mut features_1_3);
+    unsafe { instance.get_physical_device_features2(pdevice, &mut features) };
+    let required = [
+        ("timelineSemaphore", features_1_2.timeline_semaphore),
+        ("synchronization2", features_1_3.synchronization2),
+        ("dynamicRendering", features_1_3.dynamic_rendering),
+        ("maintenance4", features_1_3.maintenance4),
+    ];
+    if let Some((name, _)) = required.iter().find(|(_, enabled)| *enabled != vk::TRUE) {
+        return Err(DeviceRejection::MissingFeature(name));
+    }
+
+    let families = unsafe { instance.get_physical_device_queue_family_properties(pdevice) };
+    let graphics = families
+        .iter()
+        .position(|p| p.queue_flags.contains(vk::QueueFlags::GRAPHICS))
+        .map(|i| i as u32)
+        .ok_or(DeviceRejection::NoGraphicsQueue)?;
+    if headless {
+        return Ok((graphics, graphics));
+    }
+
+    let present = (0..families.len() as u32)
+        .find(|index| {
+            unsafe { surface_loader.get_physical_device_surface_support(pdevice, *index, surface) }
+                .unwrap_or(false)
+        })
+        .ok_or(DeviceRejection::NoPresentQueue)?;
+    Ok((graphics, present))
+}
+
+/// Index of the Device to use, `None` when no Device is usable
+fn choose(candidates: &[Candidate], preferred: Option<&str>) -> Option<usize> {
+    if let Some(preferred) = preferred {
+        match find_preferred(candidates, preferred) {
+            Some(index) => match &candidates[index].checked {
+                Ok(_) => return Some(index),
+                Err(reason) => log::warn!(
+                    "Preferred Device `{}` can not be used: {}",
+                    candidates[index].name,
+                    reason
+                ),
+            },
+            None => log::warn!("No Device matches the preferred Device `{}`", preferred),
+        }
+    }
+
+    // Reversed, So the first Device wins between equal Scores
+    candidates
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, candidate)| candidate.checked.is_ok())
+        .max_by_key(|(_, candidate)| (type_score(candidate.device_type), candidate.local_memory))
+        .map(|(index, _)| index)
+}
+
+fn find_preferred(candidates: &[Candidate], preferred: &str) -> Option<usize> {
+    if let Ok(index) = preferred.trim().parse::<usize>() {
+        return (index < candidates.len()).then_some(index);
+    }
+    let preferred = preferred.to_lowercase();
+    candidates
+        .iter()
+        .position(|candidate| candidate.name.to_lowercase().contains(&preferred))
+}
+
+fn type_score(device_type: vk::PhysicalDeviceType) -> u32 {
+    match device_type {
+        vk::PhysicalDeviceType::DISCRETE_GPU => 4,
+        vk::PhysicalDeviceType::INTEGRATED_GPU => 3,
+        vk::PhysicalDeviceType::VIRTUAL_GPU => 2,
+        vk::PhysicalDeviceType::CPU => 1,
+        _ => 0,
+    }
+}
+
+fn supported_extensions(
+    instance: &ash::Instance,
+    pdevice: vk::PhysicalDevice,
+) -> Vec<&'static CStr> {
+    let properties =
+        unsafe { instance.enumerate_device_extension_properties(pdevice) }.unwrap_or_default();
+    // Only the Extensions the Engine knows about are needed, So they can be compared against the static Names
+    [
+        khr::swapchain::NAME,
+        khr::portability_subset::NAME,
+        khr::index_type_uint8::NAME,
+        ext::index_type_uint8::NAME,
+    ]
+    .into_iter()
+    .filter(|name| {
+        properties
+            .iter()
+            .any(|ext| ext.extension_name_as_c_str() == Ok(*name))
+    })
+    .collect()
+}
+
+fn device_name(properties: &vk::PhysicalDeviceProperties) -> String {
+    properties
+        .device_name_as_c_str()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(
+        name: &str,
+        device_type: vk::PhysicalDeviceType,
+        local_memory: vk::DeviceSize,
+        checked: Result<(u32, u32), DeviceRejection>,
+    ) -> Candidate {
+        Candidate {
+            name: name.to_owned(),
+            device_type,
+            local_memory,
+            checked,
+        }
+    }
+
+    fn devices() -> Vec<Candidate> {
+        vec![
+            candidate("llvmpipe", vk::PhysicalDeviceType::CPU, 0, Ok((0, 0))),
+            candidate(
+                "Intel UHD",
+                vk::PhysicalDeviceType::INTEGRATED_GPU,
+                1 << 30,
+                Ok((0, 0)),
+            ),
+            candidate(
+                "NVIDIA GeForce",
+                vk::PhysicalDeviceType::DISCRETE_GPU,
+                8 << 30,
+                Ok((0, 1)),
+            ),
+            candidate(
+                "Old Radeon",
+                vk::PhysicalDeviceType::DISCRETE_GPU,
+                16 << 30,
+                Err(DeviceRejection::ApiVersion(vk::API_VERSION_1_2)),
+            ),
+        ]
+    }
+
+    #[test]
+    fn prefers_discrete_over_integrated_over_cpu() {
+        let mut devices = devices();
+        assert_eq!(choose(&devices, None), Some(2));
+        devices.remove(2);
+        assert_eq!(choose(&devices, None), Some(1));
+        devices.remove(1);
+        assert_eq!(choose(&devices, None), Some(0));
+    }
+
+    #[test]
+    fn preferred_device() {
+        let devices = devices();
+        assert_eq!(choose(&devices, Some("intel")), Some(1));
+        assert_eq!(choose(&devices, Some("0")), Some(0));
+        // Rejected or unknown Devices fall back to the Score
+        assert_eq!(choose(&devices, Some("radeon")), Some(2));
+        assert_eq!(choose(&devices, Some("Mali")), Some(2));
+        assert_eq!(choose(&devices, Some("7")), Some(2));
+    }
+
+    #[test]
+    fn nothing_usable() {
+        let devices = vec![candidate(
+            "Old Radeon",
+            vk::PhysicalDeviceType::DISCRETE_GPU,
+            0,
+            Err(DeviceRejection::NoGraphicsQueue),
+        )];
+        assert_eq!(choose(&devices, None), None);
+    }
+}
@@ -0,0 +1,155 @@
+//! Resources which exist once for every Frame in Flight. There are fewer Frames in Flight than Swapchain Images,
+//! So everything the CPU records or writes per Frame only has to exist `MAX_FRAMES_IN_FLIGHT` times
+
+use std::sync::Mutex;
+
+use ash::vk;
+
+use crate::{allocator::MemoryAllocator, buffer::VulkanBuffer};
+
+/// Size of the Slice every Frame has in the `UniformRing`
+pub const UNIFORM_RING_FRAME_SIZE: vk::DeviceSize = 4 * 1024 * 1024;
+
+type Deletion = Box<dyn FnOnce(&ash::Device) + Send>;
+
+/// Command Pool, Command Buffer and Deletion Queue of one Frame in Flight
+pub struct FrameContext {
+    /// Reset when the Frame starts, So the Command Buffer can be recorded again
+    pub command_pool: vk::CommandPool,
+    pub command_buffer: vk::CommandBuffer,
+    /// Queued while this Frame is recorded
+    deletions: Mutex<Vec<Deletion>>,
+    /// Queued the last Time this Frame was recorded, Run once its Fence signaled
+    retired: Vec<Deletion>,
+}
+
+impl FrameContext {
+    pub(crate) fn new(device: &ash::Device, queue_family_index: u32) -> Self {
+        let create_info = vk::CommandPoolCreateInfo::default()
+            .queue_family_index(queue_family_index)
+            .flags(vk::CommandPoolCreateFlags::TRANSIENT);
+        let command_pool = unsafe { device.create_command_pool(&create_info, None) }.unwrap();
+        let allocate_info = vk::CommandBufferAllocateInfo::default()
+            .command_pool(command_pool)
+            .level(vk::CommandBufferLevel::PRIMARY)
+            .command_buffer_count(1);
+        let command_buffer = unsafe { device.allocate_command_buffers(&allocate_info) }.unwrap()[0];
+        Self {
+            command_pool,
+            command_buffer,
+            deletions: Mutex::default(),
+            retired: Vec::new(),
+        }
+    }
+
+    pub(crate) fn destroy_later(&self, deletion: Deletion) {
+        self.deletions.lock().unwrap().push(deletion);
+    }
+
+    /// Called when the Frame is submitted, Everything queued so far may be used by this or an earlier Submission
+    pub(crate) fn retire(&mut self) {
+        let deletions = self.deletions.get_mut().unwrap();
+        self.retired.append(deletions);
+    }
+
+    /// Called after waiting for the Fence of the Frame
+    pub(crate) fn run_retired(&mut self, device: &ash::Device) {
+        self.retired.drain(..).for_each(|deletion| deletion(device));
+    }
+
+    /// The Device has to be idle, Runs every queued Deletion
+    pub(crate) fn destroy(&mut self, device: &ash::Device) {
+        self.retire();
+        self.run_retired(device);
+        unsafe { device.destroy_command_pool(self.command_pool, None) };
+    }
+}
+
+struct RingCursor {
+    frame: usize,
+    offset: vk::DeviceSize,
+}
+
+///
+/// One Buffer with an Slice for every Frame in Flight. Data which changes every Frame is pushed into the Slice of the current Frame
+/// and bound with an dynamic Offset (`UNIFORM_BUFFER_DYNAMIC` or `STORAGE_BUFFER_DYNAMIC`), So it is never overwritten while the GPU reads it.
+///
+/// The Memory is only HOST_VISIBLE | HOST_COHERENT, Every Device has such an Memory Type
+///
+pub struct UniformRing {
+    pub buffer: VulkanBuffer,
+    frame_size: vk::DeviceSize,
+    alignment: vk::DeviceSize,
+    cursor: Mutex<RingCursor>,
+}
+
+impl UniformRing {
+    pub(crate) fn new(
+        device: &ash::Device,
+        allocator: &MemoryAllocator,
+        limits: &vk::PhysicalDeviceLimits,
+        frames: usize,
+        frame_size: vk::DeviceSize,
+    ) -> Self {
+        let buffer_info = vk::BufferCreateInfo::default()
+            .size(frame_size * frames as vk::DeviceSize)
+            .usage(vk::BufferUsageFlags::UNIFORM_BUFFER | vk::BufferUsageFlags::STORAGE_BUFFER)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+        let buffer = unsafe { device.create_buffer(&buffer_info, None) }.unwrap();
+        let allocation = allocator
+            .allocate_buffer(
+                device,
+                buffer,
+                vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+            )
+            .expect("Failed to allocate Uniform Ring");
+        Self {
+            buffer: VulkanBuffer { buffer, allocation },
+            frame_size,
+            alignment: limits
+                .min_uniform_buffer_offset_alignment
+                .max(limits.min_storage_buffer_offset_alignment)
+                .max(1),
+            cursor: Mutex::new(RingCursor {
+                frame: 0,
+                offset: 0,
+            }),
+        }
+    }
+
+    /// Starts writing into the Slice of `frame`, The GPU must be done with it
+    pub(crate) fn begin_frame(&self, frame: usize) {
+        let mut cursor = self.cursor.lock().unwrap();
+        cursor.frame = frame;
+        cursor.offset = frame as vk::DeviceSize * self.frame_size;
+    }
+
+    /// Copies `data` into the Slice of the current Frame and returns its dynamic Offset, `None` when the Slice is full.
+    /// Only valid between `VulkanInstance::next_image` and `VulkanInstance::submit`
+    pub fn push<T: Copy>(&self, data: &[T]) -> Option<u32> {
+        let size = size_of_val(data) as vk::DeviceSize;
+        let mut cursor = self.cursor.lock().unwrap();
+        let offset = cursor.offset.next_multiple_of(self.alignment);
+        if offset + size > (cursor.frame as vk::DeviceSize + 1) * self.frame_size {
+            return None;
+        }
+        let memory = self
+            .buffer
+            .allocation
+            .mapped_ptr()
+            .expect("Uniform Ring is not Host visible");
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                data.as_ptr() as *const u8,
+                (memory as *mut u8).add(offset as usize),
+                size as usize,
+            )
+        };
+        cursor.offset = offset + size;
+        Some(offset as u32)
+    }
+
+    pub fn destroy(&mut self, device: &ash::Device) {
+        self.buffer.destroy(device);
+    }
+}
@@ -1,18 +1,21 @@
 use std::path::PathBuf;
 
-use ash::vk::{self, Extent2D};
+use ash::{
+    prelude::VkResult,
+    vk::{self, Extent2D},
+};
 
 use crate::{
-    allocator::MemoryAllocator, begin_single_time_command, buffer::VulkanBuffer, debug,
-    end_single_time_command, instance::VulkanInstance,
+    allocator::{Allocation, MemoryAllocator},
+    buffer::VulkanBuffer,
+    debug,
+    instance::VulkanInstance,
 };
 
-// TODO: Implement Compression/Decompression (e.g KTX)
-
 pub struct DepthImage {
     pub image: vk::Image,
     pub image_view: vk::ImageView,
-    pub memory: vk::DeviceMemory,
+    pub memory: Allocation,
 }
 
 impl DepthImage {
@@ -20,7 +23,24 @@ impl DepthImage {
         unsafe {
             device.destroy_image_view(self.image_view, None);
             device.destroy_image(self.image, None);
-            device.free_memory(self.memory, None);
+            self.memory.free(device);
+        }
+    }
+}
+
+/// An Color Attachment which is rendered into instead of an Swapchain Image and can be copied back to the CPU
+pub struct ColorImage {
+    pub image: vk::Image,
+    pub image_view: vk::ImageView,
+    pub memory: Allocation,
+}
+
+impl ColorImage {
+    pub fn destroy(&mut self, device: &ash::Device) {
+        unsafe {
+            device.destroy_image_view(self.image_view, None);
+            device.destroy_image(self.image, None);
+            self.memory.free(device);
         }
     }
 }
@@ -34,114 +54,104 @@ pub struct SkyBoxImages {
     pub back: PathBuf,
 }
 
+/// An Image which is already in its GPU Format with all its Mip Levels, e.g. Block compressed Data from KTX2 or DDS
+#[derive(Clone)]
+pub struct CompressedImage {
+    pub format: vk::Format,
+    pub width: u32,
+    pub height: u32,
+    /// Largest Level first
+    pub levels: Vec<Vec<u8>>,
+}
+
+/// Vulkan requires Buffer Offsets of Copies to be aligned to the Texel Block Size, 16 works for every Format
+const LEVEL_ALIGNMENT: usize = 16;
+
 pub struct VulkanImage {
     pub image: vk::Image,
     pub image_view: vk::ImageView,
     pub sampler: vk::Sampler,
-    pub memory: vk::DeviceMemory,
+    pub memory: Allocation,
 }
 
 impl VulkanImage {
+    /// `data` holds 4 Bytes per Pixel, The Copy is batched by the `UploadManager` and the Frame waits for it
     pub fn new(
-        instance: &mut VulkanInstance,
+        instance: &VulkanInstance,
         data: &[u8],
         image_size: Extent2D,
         format: vk::Format,
         sampler_info: Option<vk::SamplerCreateInfo>,
         name: Option<&str>,
-    ) -> Self {
-        let image_data_size = (image_size.width * image_size.height * 4) as vk::DeviceSize;
-
-        let mut staging_buffer = VulkanBuffer::new_init(
-            instance,
-            image_data_size,
-            vk::BufferUsageFlags::TRANSFER_SRC,
-            data,
-            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
-            Some(&format!("Staging of {}", name.unwrap_or("Unknown"))),
+    ) -> VkResult<Self> {
+        debug_assert_eq!(
+            data.len(),
+            (image_size.width * image_size.height * 4) as usize
         );
-
         let image = Self::create_image(
             &instance.device,
             format,
             image_size,
             1,
             vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED,
-        );
-        if instance.validation {
-            if let Some(name) = name {
-                debug::set_object_name(&instance.debug_utils_device, image, name)
-            }
-        }
-        let memory = VulkanBuffer::new_image(&instance.device, &instance.memory_allocator, image);
-        Self::copy_buffer_to_image(
+        )?;
+        let memory = Self::allocate(instance, image)?;
+        let mut created = Self::finish(
             instance,
             image,
-            &staging_buffer,
-            instance.global_command_pool,
-            image_size,
-            1,
-            0,
-            1,
-            true,
-        );
-        staging_buffer.destroy(&instance.device);
-        let image_view = Self::create_image_view(
-            image,
-            &instance.device,
+            memory,
             format,
             1,
             1,
-            vk::ImageAspectFlags::COLOR,
             vk::ImageViewType::TYPE_2D,
-        );
-        if instance.validation {
-            if let Some(name) = name {
-                debug::set_object_name(&instance.debug_utils_device, image_view, name)
-            }
-        }
-
-        let sampler_info = sampler_info.unwrap_or_default();
-        let sampler = unsafe { instance.device.create_sampler(&sampler_info, None).unwrap() };
+            &sampler_info.unwrap_or_default(),
+            name,
+        )?;
 
-        Self {
-            image,
-            image_view,
-            sampler,
-            memory,
-        }
+        let region = vk::BufferImageCopy2::default()
+            .image_subresource(
+                vk::ImageSubresourceLayers::default()
+                    .aspect_mask(vk::ImageAspectFlags::COLOR)
+                    .layer_count(1),
+            )
+            .image_extent(image_size.into());
+        instance
+            .upload_manager
+            .upload_image(
+                instance,
+                image,
+                data,
+                &[region],
+                vk::ImageSubresourceRange {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    level_count: 1,
+                    layer_count: 1,
+                    ..Default::default()
+                },
+                None,
+            )
+            .inspect_err(|_| created.destroy(&instance.device))?;
+        Ok(created)
     }
 
+    /// `format` must store 4 Bytes per Pixel in RGBA Order, Use `R8G8B8A8_SRGB` for Color Textures and `R8G8B8A8_UNORM` for Data like Normals
     pub fn from_image(
         instance: &VulkanInstance,
         image: image::DynamicImage,
+        format: vk::Format,
         mipmaps: bool,
         sampler_info: Option<vk::SamplerCreateInfo>,
         name: Option<&str>,
-    ) -> Self {
+    ) -> VkResult<Self> {
         let image_size = Extent2D {
             width: image.width(),
             height: image.height(),
         };
-        let image_data = match &image {
-            image::DynamicImage::ImageLuma8(_) | image::DynamicImage::ImageRgb8(_) => {
-                image.into_rgba8().into_raw()
-            }
-            image::DynamicImage::ImageLumaA8(_) | image::DynamicImage::ImageRgba8(_) => {
-                image.into_bytes()
-            }
-            _ => image.into_rgb8().into_raw(),
+        // We always upload 4 Bytes per Pixel
+        let image_data = match image {
+            image::DynamicImage::ImageRgba8(image) => image.into_raw(),
+            image => image.into_rgba8().into_raw(),
         };
-        let image_data_size = (image_size.width * image_size.height * 4) as vk::DeviceSize;
-
-        let mut staging_buffer = VulkanBuffer::new_init(
-            instance,
-            image_data_size,
-            vk::BufferUsageFlags::TRANSFER_SRC,
-            &image_data,
-            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
-            Some(&format!("Staging of {}", name.unwrap_or("Unknown"))),
-        );
 
         let mip_level = if mipmaps {
             (image_size.width.max(image_size.height) as f32)
@@ -152,8 +162,6 @@ impl VulkanImage {
             1
         };
 
-        let format = vk::Format::R8G8B8A8_UNORM;
-
         let image = Self::create_image(
             &instance.device,
             format,
@@ -162,154 +170,257 @@ impl VulkanImage {
             vk::ImageUsageFlags::TRANSFER_DST
                 | vk::ImageUsageFlags::TRANSFER_SRC
                 | vk::ImageUsageFlags::SAMPLED,
-        );
-        if instance.validation {
-            if let Some(name) = name {
-                debug::set_object_name(&instance.debug_utils_device, image, name)
-            }
-        }
-        let memory = VulkanBuffer::new_image(&instance.device, &instance.memory_allocator, image);
-
-        Self::copy_buffer_to_image(
+        )?;
+        let memory = Self::allocate(instance, image)?;
+        let mut created = Self::finish(
             instance,
             image,
-            &staging_buffer,
-            instance.global_command_pool,
-            image_size,
+            memory,
+            format,
             mip_level,
-            0,
             1,
-            !mipmaps,
-        );
-        staging_buffer.destroy(&instance.device);
+            vk::ImageViewType::TYPE_2D,
+            &sampler_info.unwrap_or_default(), // TODO
+            name,
+        )?;
 
-        if mipmaps {
-            Self::generate_mipmaps(
+        let region = vk::BufferImageCopy2::default()
+            .image_subresource(
+                vk::ImageSubresourceLayers::default()
+                    .aspect_mask(vk::ImageAspectFlags::COLOR)
+                    .layer_count(1),
+            )
+            .image_extent(image_size.into());
+        instance
+            .upload_manager
+            .upload_image(
                 instance,
                 image,
-                instance.global_command_pool,
-                image_size.width,
-                image_size.height,
-                mip_level,
-                1,
-            );
-        }
-        let image_view = Self::create_image_view(
-            image,
+                &image_data,
+                &[region],
+                vk::ImageSubresourceRange {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    level_count: mip_level,
+                    layer_count: 1,
+                    ..Default::default()
+                },
+                mipmaps.then_some(image_size),
+            )
+            .inspect_err(|_| created.destroy(&instance.device))?;
+        Ok(created)
+    }
+
+    /// Uploads every Mip Level as it is, The Format must support being sampled (See `VulkanInstance::get_supported_format`)
+    pub fn from_compressed(
+        instance: &VulkanInstance,
+        compressed: &CompressedImage,
+        sampler_info: Option<vk::SamplerCreateInfo>,
+        name: Option<&str>,
+    ) -> VkResult<Self> {
+        let image_size = Extent2D {
+            width: compressed.width,
+            height: compressed.height,
+        };
+        let mip_level = compressed.levels.len().max(1) as u32;
+
+        let (data, offsets) = Self::stage_levels(&compressed.levels);
+
+        let image = Self::create_image(
             &instance.device,
-            format,
+            compressed.format,
+            image_size,
+            mip_level,
+            vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED,
+        )?;
+        let memory = Self::allocate(instance, image)?;
+        let mut created = Self::finish(
+            instance,
+            image,
+            memory,
+            compressed.format,
             mip_level,
             1,
-            vk::ImageAspectFlags::COLOR,
             vk::ImageViewType::TYPE_2D,
-        );
-        if instance.validation {
-            if let Some(name) = name {
-                debug::set_object_name(&instance.debug_utils_device, image_view, name)
-            }
-        }
-
-        let sampler_info = sampler_info.unwrap_or_default(); // TODO
-        let sampler = unsafe { instance.device.create_sampler(&sampler_info, None).unwrap() };
+            &sampler_info.unwrap_or_default(),
+            name,
+        )?;
 
-        Self {
-            image,
-            image_view,
-            sampler,
-            memory,
-        }
+        Self::copy_levels_to_image(instance, image, &data, image_size, &offsets, 1)
+            .inspect_err(|_| created.destroy(&instance.device))?;
+        Ok(created)
     }
 
-    // skybox images:
-    // 1. LEFT
-    // 2. RIGHT
-    // 3. TOP
-    // 4. BOTTOM
-    // 5. FRONT
-    // 6. BACK
-    //
-    // Size: For CUBE_COMPATIABLE width and height must match
-    pub fn load_cubemap(
+    /// Uploads an Cubemap with all its Mip Levels, Every Level holds the 6 Faces (+X, -X, +Y, -Y, +Z, -Z) after another.
+    /// The Sampler uses all Levels, So Shaders can pick blurrier Levels with `textureLod`
+    pub fn from_cubemap(
         instance: &VulkanInstance,
-        skybox_images: [image::DynamicImage; 6],
-        image_size: Extent2D,
-    ) -> Self {
-        let mip_level = 1;
-        let format = vk::Format::R8G8B8A8_UNORM;
+        format: vk::Format,
+        size: u32,
+        levels: &[Vec<u8>],
+        name: Option<&str>,
+    ) -> VkResult<Self> {
+        let image_size = Extent2D {
+            width: size,
+            height: size,
+        };
+        let mip_level = levels.len().max(1) as u32;
+        let (data, offsets) = Self::stage_levels(levels);
 
         let image = Self::create_cubemap_image(
             &instance.device,
             format,
             image_size,
             mip_level,
-            vk::ImageUsageFlags::TRANSFER_DST
-                | vk::ImageUsageFlags::TRANSFER_SRC
-                | vk::ImageUsageFlags::SAMPLED,
-        );
-
-        let memory = VulkanBuffer::new_image(&instance.device, &instance.memory_allocator, image);
-        let image_data_size = (image_size.width * image_size.height * 4) as vk::DeviceSize;
-
-        for (i, layer_image) in skybox_images.into_iter().enumerate() {
-            let image_data = match &layer_image {
-                image::DynamicImage::ImageLuma8(_) | image::DynamicImage::ImageRgb8(_) => {
-                    layer_image.into_rgba8().into_raw()
-                }
-                image::DynamicImage::ImageLumaA8(_) | image::DynamicImage::ImageRgba8(_) => {
-                    layer_image.into_bytes()
-                }
-                _ => layer_image.into_rgb8().into_raw(),
-            };
-
-            let mut staging_buffer = VulkanBuffer::new_init(
-                instance,
-                image_data_size,
-                vk::BufferUsageFlags::TRANSFER_SRC,
-                &image_data,
-                vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
-                Some("Staging Cubemap"),
-            );
-
-            Self::copy_buffer_to_image(
-                instance,
-                image,
-                &staging_buffer,
-                instance.global_command_pool,
-                image_size,
-                mip_level,
-                i as u32,
-                6,
-                true,
-            );
-            staging_buffer.destroy(&instance.device);
-        }
+            vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED,
+        )?;
+        let memory = Self::allocate(instance, image)?;
 
-        let image_view = Self::create_image_view(
+        let sampler_info = vk::SamplerCreateInfo::default()
+            .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .mag_filter(vk::Filter::LINEAR)
+            .min_filter(vk::Filter::LINEAR)
+            .mipmap_mode(vk::SamplerMipmapMode::LINEAR)
+            .max_lod(mip_level as f32);
+        let mut created = Self::finish(
+            instance,
             image,
-            &instance.device,
+            memory,
             format,
             mip_level,
             6,
-            vk::ImageAspectFlags::COLOR,
             vk::ImageViewType::CUBE,
-        );
+            &sampler_info,
+            name,
+        )?;
 
-        let sampler_info = vk::SamplerCreateInfo::default()
-            .border_color(vk::BorderColor::FLOAT_OPAQUE_WHITE)
-            .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
-            .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
-            .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE)
-            .mag_filter(vk::Filter::LINEAR)
-            .min_filter(vk::Filter::LINEAR);
+        Self::copy_levels_to_image(instance, image, &data, image_size, &offsets, 6)
+            .inspect_err(|_| created.destroy(&instance.device))?;
+        Ok(created)
+    }
+
+    /// Allocates and binds the Memory of an sampled Image, The Image is destroyed when that fails
+    fn allocate(instance: &VulkanInstance, image: vk::Image) -> VkResult<Allocation> {
+        VulkanBuffer::new_image(&instance.device, &instance.memory_allocator, image)
+            .inspect_err(|_| unsafe { instance.device.destroy_image(image, None) })
+    }
 
-        let sampler = unsafe { instance.device.create_sampler(&sampler_info, None).unwrap() };
+    /// Creates the View and Sampler of an Image with bound Memory, The Image and its Memory are freed when that fails
+    #[allow(clippy::too_many_arguments)]
+    fn finish(
+        instance: &VulkanInstance,
+        image: vk::Image,
+        mut memory: Allocation,
+        format: vk::Format,
+        mip_level: u32,
+        layer_count: u32,
+        view_type: vk::ImageViewType,
+        sampler_info: &vk::SamplerCreateInfo,
+        name: Option<&str>,
+    ) -> VkResult<Self> {
+        let device = &instance.device;
+        let destroy_image = |memory: &mut Allocation| unsafe {
+            device.destroy_image(image, None);
+            memory.free(device);
+        };
+        let image_view = match Self::create_image_view(
+            image,
+            device,
+            format,
+            mip_level,
+            layer_count,
+            vk::ImageAspectFlags::COLOR,
+            view_type,
+        ) {
+            Ok(image_view) => image_view,
+            Err(err) => {
+                destroy_image(&mut memory);
+                return Err(err);
+            }
+        };
+        let sampler = match unsafe { device.create_sampler(sampler_info, None) } {
+            Ok(sampler) => sampler,
+            Err(err) => {
+                unsafe { device.destroy_image_view(image_view, None) };
+                destroy_image(&mut memory);
+                return Err(err);
+            }
+        };
+        if instance.validation {
+            if let Some(name) = name {
+                debug::set_object_name(&instance.debug_utils_device, image, name);
+                debug::set_object_name(&instance.debug_utils_device, image_view, name);
+            }
+        }
 
-        Self {
+        Ok(Self {
             image,
             image_view,
             sampler,
             memory,
+        })
+    }
+
+    /// All Levels are copied from one Staging Buffer, Returns its Data with the Offset of every Level
+    fn stage_levels(levels: &[Vec<u8>]) -> (Vec<u8>, Vec<vk::DeviceSize>) {
+        let mut data = Vec::new();
+        let mut offsets = Vec::with_capacity(levels.len());
+        for level in levels {
+            data.resize(data.len().next_multiple_of(LEVEL_ALIGNMENT), 0);
+            offsets.push(data.len() as vk::DeviceSize);
+            data.extend_from_slice(level);
         }
+        (data, offsets)
+    }
+
+    /// Copies every Mip Level and makes the Image ready to be sampled,
+    /// The Layers of an Level must follow each other in `data`
+    fn copy_levels_to_image(
+        instance: &VulkanInstance,
+        image: vk::Image,
+        data: &[u8],
+        size: Extent2D,
+        offsets: &[vk::DeviceSize],
+        layer_count: u32,
+    ) -> VkResult<()> {
+        let subresource_range = vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            level_count: offsets.len().max(1) as u32,
+            layer_count,
+            ..Default::default()
+        };
+        let regions: Vec<vk::BufferImageCopy2> = offsets
+            .iter()
+            .zip(0..)
+            .map(|(&offset, level)| {
+                vk::BufferImageCopy2::default()
+                    .buffer_offset(offset)
+                    .buffer_row_length(0)
+                    .buffer_image_height(0)
+                    .image_subresource(
+                        vk::ImageSubresourceLayers::default()
+                            .aspect_mask(vk::ImageAspectFlags::COLOR)
+                            .mip_level(level)
+                            .base_array_layer(0)
+                            .layer_count(layer_count),
+                    )
+                    .image_offset(vk::Offset3D::default())
+                    .image_extent(vk::Extent3D {
+                        width: (size.width >> level).max(1),
+                        height: (size.height >> level).max(1),
+                        depth: 1,
+                    })
+            })
+            .collect();
+        instance.upload_manager.upload_image(
+            instance,
+            image,
+            data,
+            &regions,
+            subresource_range,
+            None,
+        )
     }
 
     pub fn new_depth(
@@ -317,31 +428,68 @@ impl VulkanImage {
         allocator: &MemoryAllocator,
         format: vk::Format,
         size: Extent2D,
-    ) -> DepthImage {
-        let image = Self::create_image(
+    ) -> VkResult<DepthImage> {
+        let (image, image_view, memory) = Self::create_attachment(
             device,
+            allocator,
             format,
             size,
-            1,
             vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
-        );
+            vk::ImageAspectFlags::DEPTH,
+        )?;
+        Ok(DepthImage {
+            image,
+            image_view,
+            memory,
+        })
+    }
 
-        let memory = VulkanBuffer::new_image(device, allocator, image);
+    pub fn new_color_attachment(
+        device: &ash::Device,
+        allocator: &MemoryAllocator,
+        format: vk::Format,
+        size: Extent2D,
+    ) -> VkResult<ColorImage> {
+        let (image, image_view, memory) = Self::create_attachment(
+            device,
+            allocator,
+            format,
+            size,
+            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC,
+            vk::ImageAspectFlags::COLOR,
+        )?;
+        Ok(ColorImage {
+            image,
+            image_view,
+            memory,
+        })
+    }
+
+    fn create_attachment(
+        device: &ash::Device,
+        allocator: &MemoryAllocator,
+        format: vk::Format,
+        size: Extent2D,
+        usage: vk::ImageUsageFlags,
+        aspect: vk::ImageAspectFlags,
+    ) -> VkResult<(vk::Image, vk::ImageView, Allocation)> {
+        let image = Self::create_image(device, format, size, 1, usage)?;
+        let mut memory = VulkanBuffer::new_image(device, allocator, image)
+            .inspect_err(|_| unsafe { device.destroy_image(image, None) })?;
         let image_view = Self::create_image_view(
             image,
             device,
             format,
             1,
             1,
-            vk::ImageAspectFlags::DEPTH,
+            aspect,
             vk::ImageViewType::TYPE_2D,
-        );
-
-        DepthImage {
-            image,
-            image_view,
-            memory,
-        }
+        )
+        .inspect_err(|_| unsafe {
+            device.destroy_image(image, None);
+            memory.free(device);
+        })?;
+        Ok((image, image_view, memory))
     }
 
     pub fn from_color(
@@ -349,130 +497,32 @@ impl VulkanImage {
         color: [u8; 4],
         size: Extent2D,
         name: Option<&str>,
-    ) -> Self {
+    ) -> VkResult<Self> {
         let color_img = image::RgbaImage::from_pixel(size.width, size.height, image::Rgba(color));
         Self::from_image(
             instance,
             image::DynamicImage::ImageRgba8(color_img),
+            vk::Format::R8G8B8A8_UNORM,
             false,
             None,
             name,
         )
     }
 
-    #[allow(clippy::too_many_arguments)]
-    pub fn copy_buffer_to_image(
-        instance: &VulkanInstance,
-        image: vk::Image,
-        staging_buffer: &VulkanBuffer,
-        command_pool: vk::CommandPool,
-        size: Extent2D,
-        mip_level: u32,
-        layer_count: u32,
-        final_count: u32,
-        make_ready: bool,
-    ) {
-        let device = &instance.device;
-
-        let command_buffer = begin_single_time_command(device, command_pool);
-
-        let image_barrier = vk::ImageMemoryBarrier2::default()
-            .dst_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
-            .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
-            .image(image)
-            .src_stage_mask(vk::PipelineStageFlags2::TOP_OF_PIPE)
-            .dst_stage_mask(vk::PipelineStageFlags2::TRANSFER)
-            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
-            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
-            .subresource_range(vk::ImageSubresourceRange {
-                aspect_mask: vk::ImageAspectFlags::COLOR,
-                level_count: mip_level,
-                base_array_layer: 0,
-                layer_count: final_count,
-                ..Default::default()
-            });
-
-        let binding = [image_barrier];
-        let dep_info = vk::DependencyInfo::default()
-            .image_memory_barriers(&binding)
-            .dependency_flags(vk::DependencyFlags::BY_REGION);
-
-        unsafe { device.cmd_pipeline_barrier2(command_buffer, &dep_info) };
-
-        let subresource = vk::ImageSubresourceLayers::default()
-            .aspect_mask(vk::ImageAspectFlags::COLOR)
-            .mip_level(0)
-            .base_array_layer(layer_count)
-            .layer_count(1);
-
-        let region = vk::BufferImageCopy2::default()
-            .buffer_offset(0)
-            .buffer_row_length(0)
-            .buffer_image_height(0)
-            .image_subresource(subresource)
-            .image_offset(vk::Offset3D::default())
-            .image_extent(size.into());
-
-        let binding = [region];
-        let copy_image_info = vk::CopyBufferToImageInfo2::default()
-            .src_buffer(staging_buffer.buffer)
-            .dst_image(image)
-            .dst_image_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
-            .regions(&binding);
-
-        unsafe { device.cmd_copy_buffer_to_image2(command_buffer, &copy_image_info) };
-
-        if make_ready {
-            let image_barrier = vk::ImageMemoryBarrier2::default()
-                .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
-                .new_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
-                .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
-                .dst_access_mask(vk::AccessFlags2::SHADER_READ)
-                .src_stage_mask(vk::PipelineStageFlags2::TRANSFER)
-                .dst_stage_mask(vk::PipelineStageFlags2::FRAGMENT_SHADER)
-                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
-                .image(image)
-                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
-                .subresource_range(vk::ImageSubresourceRange {
-                    aspect_mask: vk::ImageAspectFlags::COLOR,
-                    level_count: mip_level,
-                    layer_count: final_count,
-                    ..Default::default()
-                });
-
-            let binding = [image_barrier];
-            let dep_info = vk::DependencyInfo::default()
-                .image_memory_barriers(&binding)
-                .dependency_flags(vk::DependencyFlags::BY_REGION);
-
-            unsafe { device.cmd_pipeline_barrier2(command_buffer, &dep_info) };
-        }
-
-        end_single_time_command(
-            device,
-            command_pool,
-            instance.graphics_queue,
-            command_buffer,
-        );
-    }
-
-    pub fn generate_mipmaps(
-        instance: &VulkanInstance,
+    /// Blits every Level from the one before, All Levels have to be in `TRANSFER_DST_OPTIMAL` and end up in `SHADER_READ_ONLY_OPTIMAL`
+    pub fn record_mipmaps(
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
         image: vk::Image,
-        command_pool: vk::CommandPool,
         width: u32,
         height: u32,
         mip_level: u32,
         layer_count: u32,
     ) {
-        let device = &instance.device;
-
-        let command_buffer = begin_single_time_command(device, command_pool);
-
         let subresource = vk::ImageSubresourceRange::default()
             .aspect_mask(vk::ImageAspectFlags::COLOR)
             .base_array_layer(0)
-            .layer_count(1)
+            .layer_count(layer_count)
             .level_count(1);
 
         let mut barrier = vk::ImageMemoryBarrier2::default()
@@ -504,7 +554,7 @@ impl VulkanImage {
                 .aspect_mask(vk::ImageAspectFlags::COLOR)
                 .mip_level(i - 1)
                 .base_array_layer(0)
-                .layer_count(1);
+                .layer_count(layer_count);
 
             let dst_subresource = vk::ImageSubresourceLayers::default()
                 .aspect_mask(vk::ImageAspectFlags::COLOR)
@@ -581,13 +631,6 @@ impl VulkanImage {
             .dependency_flags(vk::DependencyFlags::BY_REGION);
 
         unsafe { device.cmd_pipeline_barrier2(command_buffer, &dep_info) };
-
-        end_single_time_command(
-            device,
-            command_pool,
-            instance.graphics_queue,
-            command_buffer,
-        );
     }
 
     fn create_image_view(
@@ -598,7 +641,7 @@ impl VulkanImage {
         layer_count: u32, // Usally 1 for Standard images
         mask: vk::ImageAspectFlags,
         view_type: vk::ImageViewType,
-    ) -> vk::ImageView {
+    ) -> VkResult<vk::ImageView> {
         let image_view_info = vk::ImageViewCreateInfo::default()
             .subresource_range(
                 vk::ImageSubresourceRange::default()
@@ -610,7 +653,7 @@ impl VulkanImage {
             .format(format)
             .view_type(view_type);
 
-        unsafe { device.create_image_view(&image_view_info, None) }.unwrap()
+        unsafe { device.create_image_view(&image_view_info, None) }
     }
 
     fn create_image(
@@ -619,7 +662,7 @@ impl VulkanImage {
         size: Extent2D,
         mip_level: u32,
         usage: vk::ImageUsageFlags,
-    ) -> vk::Image {
+    ) -> VkResult<vk::Image> {
         let create_info = vk::ImageCreateInfo::default()
             .image_type(vk::ImageType::TYPE_2D)
             .format(format)
@@ -631,7 +674,7 @@ impl VulkanImage {
             .usage(usage)
             .sharing_mode(vk::SharingMode::EXCLUSIVE);
 
-        unsafe { device.create_image(&create_info, None) }.unwrap()
+        unsafe { device.create_image(&create_info, None) }
     }
 
     fn create_cubemap_image(
@@ -640,7 +683,7 @@ impl VulkanImage {
         size: Extent2D,
         mip_level: u32,
         usage: vk::ImageUsageFlags,
-    ) -> vk::Image {
+    ) -> VkResult<vk::Image> {
         let create_info = vk::ImageCreateInfo::default()
             .image_type(vk::ImageType::TYPE_2D)
             .format(format)
@@ -653,7 +696,7 @@ impl VulkanImage {
             .flags(vk::ImageCreateFlags::CUBE_COMPATIBLE)
             .sharing_mode(vk::SharingMode::EXCLUSIVE);
 
-        unsafe { device.create_image(&create_info, None) }.unwrap()
+        unsafe { device.create_image(&create_info, None) }
     }
 
     pub fn destroy(&mut self, device: &ash::Device) {
@@ -661,7 +704,7 @@ impl VulkanImage {
             device.destroy_image_view(self.image_view, None);
             device.destroy_image(self.image, None);
             device.destroy_sampler(self.sampler, None);
-            device.free_memory(self.memory, None);
+            self.memory.free(device);
         }
     }
 }
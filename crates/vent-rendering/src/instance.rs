@@ -1,5 +1,4 @@
 use ash::ext::{debug_utils, validation_features};
-use ash::khr::swapchain;
 use ash::prelude::VkResult;
 use ash::vk::{Extent2D, PushConstantRange, SwapchainKHR};
 use ash::{khr, vk, Entry};
@@ -13,9 +12,16 @@ use ash::vk::{
 };
 
 use crate::allocator::MemoryAllocator;
+use crate::buffer::VulkanBuffer;
 use crate::debug::{self, check_validation_layer_support, setup_debug_messenger};
-use crate::image::{DepthImage, VulkanImage};
+use crate::descriptor::DescriptorAllocator;
+use crate::device::{self, DeviceCapabilities};
+use crate::frame::{FrameContext, UniformRing, UNIFORM_RING_FRAME_SIZE};
+use crate::image::{ColorImage, DepthImage, VulkanImage};
+use crate::pipeline::{AttachmentFormats, PipelineCache};
 use crate::surface;
+use crate::upload::UploadManager;
+use crate::{begin_single_time_command, end_single_time_command};
 
 pub const MAX_FRAMES_IN_FLIGHT: u8 = 2;
 
@@ -37,30 +43,41 @@ pub struct VulkanInstance {
 
     pub swapchain_images: Vec<vk::Image>,
     pub swapchain_image_views: Vec<vk::ImageView>,
-    pub frame_buffers: Vec<vk::Framebuffer>,
     pub depth_format: vk::Format,
     pub depth_image: DepthImage,
     pub graphics_queue: vk::Queue,
     pub present_queue: vk::Queue,
 
-    pub render_pass: vk::RenderPass,
     pub global_command_pool: vk::CommandPool,
-    pub command_pools: Vec<vk::CommandPool>,
-    pub command_buffers: Vec<vk::CommandBuffer>,
+    /// One for every Frame in Flight, See `current_frame`
+    pub frames: Vec<FrameContext>,
+    /// Per Frame Uniform and Storage Data, Bound with dynamic Offsets
+    pub uniform_ring: UniformRing,
 
     pub image_available_semaphores: Vec<vk::Semaphore>,
     pub render_finished_semaphores: Vec<vk::Semaphore>,
 
     pub in_flight_fences: Vec<vk::Fence>,
-    pub images_in_flight: Vec<vk::Fence>,
 
     pub memory_allocator: MemoryAllocator,
+    pub descriptor_allocator: DescriptorAllocator,
+    /// Copies Buffers and Images to the GPU, Submitted with the next Frame
+    pub upload_manager: UploadManager,
+    /// Limits, Formats and optional Features of the selected Device
+    pub capabilities: DeviceCapabilities,
+    /// Used by every `PipelineBuilder`, Saved when the Instance is dropped
+    pub pipeline_cache: PipelineCache,
     pub vulkan_version: u32,
 
     pub vsync: bool,
 
     frame: usize,
 
+    /// Set by `new_headless`, Then there is no Surface or Swapchain and `swapchain_images` are Offscreen Images
+    pub headless: bool,
+    offscreen_images: Vec<ColorImage>,
+    last_submitted: Option<u32>,
+
     // debugging
     pub validation: bool,
     pub debug_utils: Option<debug_utils::Instance>,
@@ -74,6 +91,42 @@ impl VulkanInstance {
         application_version: u32,
         vsync: bool,
         window: &vent_window::Window,
+        preferred_device: Option<&str>,
+    ) -> Self {
+        Self::create(
+            application_name,
+            application_version,
+            vsync,
+            Some(window),
+            window.size(),
+            preferred_device,
+        )
+    }
+
+    /// Creates an Instance without an Window, Surface or Swapchain. Frames are rendered into Offscreen Images and can be copied back using `read_back`,
+    /// So Rendering can run in CI on an Software Driver like lavapipe or on an Server for Thumbnails
+    pub fn new_headless(
+        application_name: &String,
+        application_version: u32,
+        size: (u32, u32),
+    ) -> Self {
+        Self::create(
+            application_name,
+            application_version,
+            false,
+            None,
+            size,
+            None,
+        )
+    }
+
+    fn create(
+        application_name: &String,
+        application_version: u32,
+        vsync: bool,
+        window: Option<&vent_window::Window>,
+        size: (u32, u32),
+        preferred_device: Option<&str>,
     ) -> Self {
         let entry = unsafe { Entry::load().expect("Failed to load Vulkan Library") };
 
@@ -91,8 +144,17 @@ impl VulkanInstance {
             }
         };
 
+        let headless = window.is_none();
+
         // We now set validation at runtime, This will be usefull later, We should make this an startup flag so release builds for example could be debugged
-        let validation = cfg!(debug_assertions);
+        let mut validation = cfg!(debug_assertions);
+        if validation && headless {
+            // Headless is used for Tests, They should still run when the Layers are not installed
+            if let Some(layer) = debug::missing_validation_layer(&entry) {
+                log::warn!("Layer not supported: {}, Running without Validation", layer);
+                validation = false;
+            }
+        }
 
         let app_info = unsafe {
             vk::ApplicationInfo::default()
@@ -105,12 +167,21 @@ impl VulkanInstance {
                 .api_version(vk::API_VERSION_1_3)
         };
 
-        let display_handle = window.display_handle().expect("No Display Handle");
-        let window_handle = window.window_handle().expect("No Window Handle");
+        let handles = window.map(|window| {
+            (
+                window.display_handle().expect("No Display Handle"),
+                window.window_handle().expect("No Window Handle"),
+            )
+        });
 
-        let mut extension_names = surface::enumerate_required_extensions(display_handle.as_raw())
-            .expect("Unsupported Surface Extension")
-            .to_vec();
+        let mut extension_names = match handles {
+            Some((display_handle, _)) => {
+                surface::enumerate_required_extensions(display_handle.as_raw())
+                    .expect("Unsupported Surface Extension")
+                    .to_vec()
+            }
+            None => Vec::new(),
+        };
         if validation {
             extension_names.push(validation_features::NAME.as_ptr());
             extension_names.push(debug_utils::NAME.as_ptr());
@@ -155,35 +226,53 @@ impl VulkanInstance {
                 .expect("Failed Create Vulkan Instance")
         };
 
-        let surface = unsafe {
-            surface::create_surface(&entry, &instance, display_handle, window_handle, None)
-        }
-        .unwrap();
+        let surface = match handles {
+            Some((display_handle, window_handle)) => unsafe {
+                surface::create_surface(&entry, &instance, display_handle, window_handle, None)
+            }
+            .unwrap(),
+            None => vk::SurfaceKHR::null(),
+        };
         let surface_loader = khr::surface::Instance::new(&entry, &instance);
 
-        let (pdevice, graphics_queue_family_index, present_queue_family_index) =
-            Self::create_physical_device(&instance, &surface_loader, surface);
+        let selected =
+            device::select_physical_device(&instance, &surface_loader, surface, preferred_device);
+        let pdevice = selected.pdevice;
+        let graphics_queue_family_index = selected.graphics_family;
+        let present_queue_family_index = selected.present_family;
 
         let info = unsafe { instance.get_physical_device_properties(pdevice) };
-        unsafe {
-            log::debug!(
-                "Vulkan {}, Selected graphics device (`{}`)",
-                info.api_version,
-                CStr::from_ptr(info.device_name.as_ptr()).to_string_lossy()
-            );
-        }
+        let capabilities = DeviceCapabilities::new(&instance, pdevice, headless);
+        log::debug!(
+            "Vulkan {}, Device Extensions {:?}",
+            info.api_version,
+            capabilities.extensions
+        );
 
-        let surface_format =
-            unsafe { surface_loader.get_physical_device_surface_formats(pdevice, surface) }
-                .unwrap()[0];
+        let surface_format = if headless {
+            // RGBA Order so `read_back` does not have to swizzle
+            vk::SurfaceFormatKHR {
+                format: vk::Format::R8G8B8A8_SRGB,
+                color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
+            }
+        } else {
+            unsafe { surface_loader.get_physical_device_surface_formats(pdevice, surface) }.unwrap()
+                [0]
+        };
 
         let device_features = unsafe { instance.get_physical_device_features(pdevice) };
 
+        let transfer_queue_family_index = Self::find_transfer_queue_family(&instance, pdevice);
         let device = Self::create_device(
             &instance,
             pdevice,
-            graphics_queue_family_index,
+            &[
+                Some(graphics_queue_family_index),
+                Some(present_queue_family_index),
+                transfer_queue_family_index,
+            ],
             device_features,
+            &capabilities,
         );
 
         let (debug_utils, debug_utils_device, debug_messenger) = if validation {
@@ -196,54 +285,86 @@ impl VulkanInstance {
 
         let graphics_queue = unsafe { device.get_device_queue(graphics_queue_family_index, 0) };
         let present_queue = unsafe { device.get_device_queue(present_queue_family_index, 0) };
+        let transfer_queue = transfer_queue_family_index
+            .map(|family| (unsafe { device.get_device_queue(family, 0) }, family));
+        if transfer_queue.is_some() {
+            log::debug!("Using an dedicated Transfer Queue for Uploads");
+        }
+        let upload_manager = UploadManager::new(
+            &device,
+            graphics_queue,
+            graphics_queue_family_index,
+            transfer_queue,
+        );
 
         let swapchain_loader = khr::swapchain::Device::new(&instance, &device);
 
-        let (swapchain, surface_resolution) = Self::create_swapchain(
-            &swapchain_loader,
-            surface_format,
-            &surface_loader,
-            pdevice,
-            vsync,
-            surface,
-            (window.width(), window.height()),
-            None,
+        let memory_allocator = MemoryAllocator::new(
+            unsafe { instance.get_physical_device_memory_properties(pdevice) },
+            info.limits.buffer_image_granularity,
+        );
+        let uniform_ring = UniformRing::new(
+            &device,
+            &memory_allocator,
+            &info.limits,
+            MAX_FRAMES_IN_FLIGHT as usize,
+            UNIFORM_RING_FRAME_SIZE,
+        );
+        let pipeline_cache = PipelineCache::load(
+            &device,
+            &info,
+            PipelineCache::default_path(application_name),
         );
 
-        let (swapchain_image_views, swapchain_images) =
-            Self::create_image_views(&device, &swapchain_loader, swapchain, surface_format);
-
-        let depth_format = Self::get_depth_format(&instance, pdevice);
+        let (swapchain, surface_resolution, offscreen_images) = if headless {
+            let surface_resolution = vk::Extent2D {
+                width: size.0,
+                height: size.1,
+            };
+            let offscreen_images = Self::create_offscreen_images(
+                &device,
+                &memory_allocator,
+                surface_format.format,
+                surface_resolution,
+            );
+            (
+                vk::SwapchainKHR::null(),
+                surface_resolution,
+                offscreen_images,
+            )
+        } else {
+            let (swapchain, surface_resolution) = Self::create_swapchain(
+                &swapchain_loader,
+                surface_format,
+                &surface_loader,
+                pdevice,
+                vsync,
+                surface,
+                size,
+                None,
+            );
+            (swapchain, surface_resolution, Vec::new())
+        };
 
-        let render_pass = Self::create_render_pass(&device, surface_format, depth_format);
+        let (swapchain_image_views, swapchain_images) = if headless {
+            Self::offscreen_image_views(&offscreen_images)
+        } else {
+            Self::create_image_views(&device, &swapchain_loader, swapchain, surface_format)
+        };
 
-        let memory_allocator = MemoryAllocator::new(unsafe {
-            instance.get_physical_device_memory_properties(pdevice)
-        });
+        let depth_format = Self::get_depth_format(&instance, pdevice);
 
         let depth_image =
-            VulkanImage::new_depth(&device, &memory_allocator, depth_format, surface_resolution);
-        let frame_buffers = Self::create_frame_buffers(
-            &swapchain_image_views,
-            render_pass,
-            &device,
-            depth_image.image_view,
-            surface_resolution,
-        );
-        let (global_command_pool, command_pools) = Self::create_command_pools(
-            &device,
-            graphics_queue_family_index,
-            swapchain_images.len(),
-        );
-        let command_buffers =
-            Self::allocate_command_buffers(&device, &command_pools, swapchain_images.len());
+            VulkanImage::new_depth(&device, &memory_allocator, depth_format, surface_resolution)
+                .expect("Failed to create Depth Image");
+        let global_command_pool =
+            Self::create_global_command_pool(&device, graphics_queue_family_index);
+        let frames = (0..MAX_FRAMES_IN_FLIGHT)
+            .map(|_| FrameContext::new(&device, graphics_queue_family_index))
+            .collect();
 
-        let (
-            image_available_semaphores,
-            render_finished_semaphores,
-            in_flight_fences,
-            images_in_flight,
-        ) = Self::create_sync_objects(&device, &swapchain_images);
+        let (image_available_semaphores, render_finished_semaphores, in_flight_fences) =
+            Self::create_sync_objects(&device);
 
         Self {
             entry,
@@ -259,20 +380,21 @@ impl VulkanInstance {
             swapchain,
             swapchain_images,
             swapchain_image_views,
-            frame_buffers,
             depth_format,
             depth_image,
             graphics_queue,
             present_queue,
-            render_pass,
             global_command_pool,
-            command_pools,
-            command_buffers,
+            frames,
+            uniform_ring,
             image_available_semaphores,
             render_finished_semaphores,
             in_flight_fences,
-            images_in_flight,
             memory_allocator,
+            descriptor_allocator: DescriptorAllocator::default(),
+            upload_manager,
+            capabilities,
+            pipeline_cache,
             vulkan_version,
             vsync,
             frame: 0,
@@ -280,11 +402,15 @@ impl VulkanInstance {
             debug_utils,
             debug_utils_device,
             debug_messenger,
+            headless,
+            offscreen_images,
+            last_submitted: None,
         }
     }
 
     // returns the next image's index and whether the swapchain is suboptimal for the surface.
-    pub fn next_image(&self) -> VkResult<(u32, bool)> {
+    // Also starts the next Frame in Flight: Its Deletions are run and its Slice of the Uniform Ring can be written again
+    pub fn next_image(&mut self) -> VkResult<(u32, bool)> {
         let in_flight_fence = self.in_flight_fences[self.frame];
 
         unsafe {
@@ -293,6 +419,13 @@ impl VulkanInstance {
                 .unwrap();
             self.device.reset_fences(&[in_flight_fence]).unwrap();
         }
+        self.frames[self.frame].run_retired(&self.device);
+        self.upload_manager.collect(&self.device);
+        self.uniform_ring.begin_frame(self.frame);
+        if self.headless {
+            // Every Frame in Flight has its own Offscreen Image
+            return Ok((self.frame as u32, false));
+        }
         unsafe {
             self.swapchain_loader.acquire_next_image(
                 self.swapchain,
@@ -303,14 +436,28 @@ impl VulkanInstance {
         }
     }
 
+    /// Everything the Frames in Flight may still use is destroyed once they finished, So Resizing does not wait for the GPU
     pub fn recreate_swap_chain(&mut self, new_size: Option<(u32, u32)>) {
         let new_size = new_size.unwrap_or((
             self.surface_resolution.width,
             self.surface_resolution.height,
         ));
-        unsafe {
-            self.device.device_wait_idle().unwrap();
-
+        if self.headless {
+            self.clean_swapchain();
+            self.surface_resolution = vk::Extent2D {
+                width: new_size.0,
+                height: new_size.1,
+            };
+            self.offscreen_images = Self::create_offscreen_images(
+                &self.device,
+                &self.memory_allocator,
+                self.surface_format.format,
+                self.surface_resolution,
+            );
+            self.last_submitted = None;
+            (self.swapchain_image_views, self.swapchain_images) =
+                Self::offscreen_image_views(&self.offscreen_images);
+        } else {
             let (swapchain, surface_resolution) = Self::create_swapchain(
                 &self.swapchain_loader,
                 self.surface_format,
@@ -333,23 +480,33 @@ impl VulkanInstance {
                 self.swapchain,
                 self.surface_format,
             );
+        }
 
-            self.depth_image.destroy(&self.device);
-            self.depth_image = VulkanImage::new_depth(
-                &self.device,
-                &self.memory_allocator,
-                self.depth_format,
-                surface_resolution,
-            );
+        let depth_image = VulkanImage::new_depth(
+            &self.device,
+            &self.memory_allocator,
+            self.depth_format,
+            self.surface_resolution,
+        )
+        .expect("Failed to create Depth Image");
+        let mut old_depth_image = std::mem::replace(&mut self.depth_image, depth_image);
+        self.destroy_later(move |device| old_depth_image.destroy(device));
+    }
 
-            self.frame_buffers = Self::create_frame_buffers(
-                &self.swapchain_image_views,
-                self.render_pass,
-                &self.device,
-                self.depth_image.image_view,
-                self.surface_resolution,
-            );
-        }
+    /// Index of the current Frame in Flight, Always below `MAX_FRAMES_IN_FLIGHT`
+    pub fn frame_index(&self) -> usize {
+        self.frame
+    }
+
+    /// Command Pool and Buffer to record the current Frame with
+    pub fn current_frame(&self) -> &FrameContext {
+        &self.frames[self.frame]
+    }
+
+    /// Destroys something the current Frame may still use once the GPU finished it,
+    /// e.g. an Buffer which was replaced while recording
+    pub fn destroy_later(&self, destroy: impl FnOnce(&ash::Device) + Send + 'static) {
+        self.frames[self.frame].destroy_later(Box::new(destroy));
     }
 
     /**
@@ -357,20 +514,44 @@ impl VulkanInstance {
      */
     pub fn submit(&mut self, image_index: u32) -> VkResult<bool> {
         let in_flight_fence = self.in_flight_fences[self.frame];
+        let command_buffer = self.frames[self.frame].command_buffer;
+        self.frames[self.frame].retire();
+        // Everything uploaded while recording has to be copied before the Frame runs
+        let uploads = vk::SemaphoreSubmitInfo::default()
+            .semaphore(self.upload_manager.timeline())
+            .value(self.upload_manager.flush(&self.device))
+            .stage_mask(vk::PipelineStageFlags2::ALL_COMMANDS);
+
+        if self.headless {
+            // Nothing to present to
+            let command_infos =
+                [vk::CommandBufferSubmitInfo::default().command_buffer(command_buffer)];
+            let wait_infos = [uploads];
+            let submit_info = vk::SubmitInfo2::default()
+                .wait_semaphore_infos(&wait_infos)
+                .command_buffer_infos(&command_infos);
+            unsafe {
+                self.device
+                    .queue_submit2(self.graphics_queue, &[submit_info], in_flight_fence)
+                    .unwrap();
+            }
+            self.last_submitted = Some(image_index);
+            self.frame = (self.frame + 1) % MAX_FRAMES_IN_FLIGHT as usize;
+            return Ok(false);
+        }
 
         let wait_semaphores = vk::SemaphoreSubmitInfo::default()
             .semaphore(self.image_available_semaphores[self.frame])
             .stage_mask(vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT);
 
-        let command_buffers = vk::CommandBufferSubmitInfo::default()
-            .command_buffer(self.command_buffers[image_index as usize]);
+        let command_buffers = vk::CommandBufferSubmitInfo::default().command_buffer(command_buffer);
         let signal_semaphores = vk::SemaphoreSubmitInfo::default()
             .semaphore(self.render_finished_semaphores[self.frame])
             .stage_mask(vk::PipelineStageFlags2::ALL_GRAPHICS);
 
         let signal_infos = [signal_semaphores];
         let command_infos = [command_buffers];
-        let wait_infos = [wait_semaphores];
+        let wait_infos = [wait_semaphores, uploads];
         let submit_info = vk::SubmitInfo2::default()
             .wait_semaphore_infos(&wait_infos)
             .command_buffer_infos(&command_infos)
@@ -398,17 +579,136 @@ impl VulkanInstance {
         }
     }
 
-    unsafe fn clean_swapchain(&mut self) {
-        self.frame_buffers
-            .drain(..)
-            .for_each(|f| self.device.destroy_framebuffer(f, None));
+    /// Copies the last submitted Frame of an headless Instance into CPU Memory, Returns None when rendering into an Window or nothing was submitted yet
+    pub fn read_back(&self) -> Option<image::RgbaImage> {
+        if !self.headless {
+            return None;
+        }
+        let image = self.swapchain_images[self.last_submitted? as usize];
+        let extent = self.surface_resolution;
+        let size = (extent.width * extent.height * 4) as vk::DeviceSize;
+
+        let mut buffer = VulkanBuffer::new(
+            self,
+            size,
+            vk::BufferUsageFlags::TRANSFER_DST,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+            Some("Read Back"),
+        )
+        .ok()?;
+
+        let command_buffer = begin_single_time_command(&self.device, self.global_command_pool);
+        unsafe {
+            // The Render Pass already left the Image in TRANSFER_SRC_OPTIMAL, We only have to wait for its Writes
+            let image_barrier = vk::ImageMemoryBarrier2::default()
+                .src_stage_mask(vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT)
+                .src_access_mask(vk::AccessFlags2::COLOR_ATTACHMENT_WRITE)
+                .dst_stage_mask(vk::PipelineStageFlags2::COPY)
+                .dst_access_mask(vk::AccessFlags2::TRANSFER_READ)
+                .old_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+                .new_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+                .image(image)
+                .subresource_range(
+                    vk::ImageSubresourceRange::default()
+                        .aspect_mask(vk::ImageAspectFlags::COLOR)
+                        .level_count(1)
+                        .layer_count(1),
+                );
+            self.device.cmd_pipeline_barrier2(
+                command_buffer,
+                &vk::DependencyInfo::default().image_memory_barriers(&[image_barrier]),
+            );
 
-        self.swapchain_image_views
-            .drain(..)
-            .for_each(|v| self.device.destroy_image_view(v, None));
+            let region = vk::BufferImageCopy::default()
+                .image_subresource(
+                    vk::ImageSubresourceLayers::default()
+                        .aspect_mask(vk::ImageAspectFlags::COLOR)
+                        .layer_count(1),
+                )
+                .image_extent(extent.into());
+            self.device.cmd_copy_image_to_buffer(
+                command_buffer,
+                image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                *buffer,
+                &[region],
+            );
+
+            let buffer_barrier = vk::BufferMemoryBarrier2::default()
+                .src_stage_mask(vk::PipelineStageFlags2::COPY)
+                .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+                .dst_stage_mask(vk::PipelineStageFlags2::HOST)
+                .dst_access_mask(vk::AccessFlags2::HOST_READ)
+                .buffer(*buffer)
+                .size(vk::WHOLE_SIZE);
+            self.device.cmd_pipeline_barrier2(
+                command_buffer,
+                &vk::DependencyInfo::default().buffer_memory_barriers(&[buffer_barrier]),
+            );
+        }
+        end_single_time_command(
+            &self.device,
+            self.global_command_pool,
+            self.graphics_queue,
+            command_buffer,
+        );
 
-        self.swapchain_loader
-            .destroy_swapchain(self.swapchain, None);
+        let memory = buffer.map(&self.device, size);
+        let pixels =
+            unsafe { std::slice::from_raw_parts(memory as *const u8, size as usize) }.to_vec();
+        buffer.unmap(&self.device);
+        buffer.destroy(&self.device);
+
+        image::RgbaImage::from_raw(extent.width, extent.height, pixels)
+    }
+
+    fn create_offscreen_images(
+        device: &ash::Device,
+        allocator: &MemoryAllocator,
+        format: vk::Format,
+        size: Extent2D,
+    ) -> Vec<ColorImage> {
+        (0..MAX_FRAMES_IN_FLIGHT)
+            .map(|_| {
+                VulkanImage::new_color_attachment(device, allocator, format, size)
+                    .expect("Failed to create Offscreen Image")
+            })
+            .collect()
+    }
+
+    fn offscreen_image_views(
+        offscreen_images: &[ColorImage],
+    ) -> (Vec<vk::ImageView>, Vec<vk::Image>) {
+        offscreen_images
+            .iter()
+            .map(|image| (image.image_view, image.image))
+            .unzip()
+    }
+
+    /// Queues the Swapchain and its Views (or the Offscreen Images) for Deletion, The Frames in Flight may still render into them
+    fn clean_swapchain(&mut self) {
+        if self.headless {
+            // The Views are owned by the Offscreen Images
+            self.swapchain_image_views.clear();
+            self.swapchain_images.clear();
+            let offscreen_images = std::mem::take(&mut self.offscreen_images);
+            self.destroy_later(move |device| {
+                offscreen_images
+                    .into_iter()
+                    .for_each(|mut image| image.destroy(device))
+            });
+            return;
+        }
+
+        let image_views = std::mem::take(&mut self.swapchain_image_views);
+        let swapchain_loader = self.swapchain_loader.clone();
+        let swapchain = self.swapchain;
+        self.destroy_later(move |device| unsafe {
+            image_views
+                .into_iter()
+                .for_each(|view| device.destroy_image_view(view, None));
+            swapchain_loader.destroy_swapchain(swapchain, None);
+        });
     }
 
     fn get_depth_format(instance: &ash::Instance, pdevice: vk::PhysicalDevice) -> vk::Format {
@@ -452,108 +752,90 @@ impl VulkanInstance {
             .copied()
     }
 
-    fn create_frame_buffers(
-        swapchain_image_views: &[vk::ImageView],
-        render_pass: vk::RenderPass,
-        device: &ash::Device,
-        depth_image_view: vk::ImageView,
-        surface_resolution: Extent2D,
-    ) -> Vec<vk::Framebuffer> {
-        swapchain_image_views
-            .iter()
-            .map(|&present_image_view| {
-                let framebuffer_attachments = [present_image_view, depth_image_view];
-                let frame_buffer_create_info = vk::FramebufferCreateInfo::default()
-                    .render_pass(render_pass)
-                    .attachments(&framebuffer_attachments)
-                    .width(surface_resolution.width)
-                    .height(surface_resolution.height)
-                    .layers(1);
-
-                unsafe {
-                    device
-                        .create_framebuffer(&frame_buffer_create_info, None)
-                        .unwrap()
-                }
-            })
-            .collect::<Vec<vk::Framebuffer>>()
-    }
-
-    fn create_physical_device(
+    /// An Queue Family which can only transfer (and maybe compute), So Uploads do not wait behind Rendering.
+    /// Its Image Transfer Granularity must allow copying any Size
+    fn find_transfer_queue_family(
         instance: &ash::Instance,
-        surface_loader: &khr::surface::Instance,
-        surface: vk::SurfaceKHR,
-    ) -> (vk::PhysicalDevice, u32, u32) {
-        let pdevices = unsafe {
-            instance
-                .enumerate_physical_devices()
-                .expect("Physical device error")
+        pdevice: vk::PhysicalDevice,
+    ) -> Option<u32> {
+        let properties = unsafe { instance.get_physical_device_queue_family_properties(pdevice) };
+        let granularity = vk::Extent3D {
+            width: 1,
+            height: 1,
+            depth: 1,
         };
-        for pdevice in pdevices {
-            let properties =
-                unsafe { instance.get_physical_device_queue_family_properties(pdevice) };
-
-            let graphics = properties
-                .iter()
-                .position(|p| p.queue_flags.contains(vk::QueueFlags::GRAPHICS))
-                .map(|i| i as u32);
-
-            let mut present = None;
-            for (index, _properties) in properties.iter().enumerate() {
-                if unsafe {
-                    surface_loader.get_physical_device_surface_support(
-                        pdevice,
-                        index as u32,
-                        surface,
-                    )
-                }
-                .unwrap()
-                {
-                    present = Some(index as u32);
-                    break;
-                }
-            }
-
-            if let (Some(graphics), Some(present)) = (graphics, present) {
-                return (pdevice, graphics, present);
-            }
-        }
-        panic!("Missing required queue families")
+        properties
+            .iter()
+            .position(|p| {
+                p.queue_flags.contains(vk::QueueFlags::TRANSFER)
+                    && !p.queue_flags.contains(vk::QueueFlags::GRAPHICS)
+                    && p.queue_count > 0
+                    && p.min_image_transfer_granularity == granularity
+            })
+            .map(|i| i as u32)
     }
 
     fn create_device(
         instance: &ash::Instance,
         pdevice: vk::PhysicalDevice,
-        queue_family_index: u32,
+        queue_family_indices: &[Option<u32>],
         available_features: vk::PhysicalDeviceFeatures,
+        capabilities: &DeviceCapabilities,
     ) -> ash::Device {
-        let device_extension_names_raw = [
-            swapchain::NAME.as_ptr(),
-            #[cfg(any(target_os = "macos", target_os = "ios"))]
-            portability_subset::NAME.as_ptr(),
-        ];
+        let device_extension_names_raw: Vec<_> = capabilities
+            .extensions
+            .iter()
+            .map(|name| name.as_ptr())
+            .collect();
 
         let mut features_1_3 = vk::PhysicalDeviceVulkan13Features::default()
             .synchronization2(true)
+            .dynamic_rendering(true)
             .maintenance4(true);
 
+        let descriptor_indexing = capabilities.descriptor_indexing;
+        let mut features_1_2 = vk::PhysicalDeviceVulkan12Features::default()
+            .timeline_semaphore(true)
+            .runtime_descriptor_array(descriptor_indexing)
+            .descriptor_binding_partially_bound(descriptor_indexing)
+            .descriptor_binding_sampled_image_update_after_bind(descriptor_indexing)
+            .descriptor_binding_variable_descriptor_count(descriptor_indexing);
+
         let features = vk::PhysicalDeviceFeatures {
             sampler_anisotropy: available_features.sampler_anisotropy,
+            shader_sampled_image_array_dynamic_indexing: descriptor_indexing.into(),
             ..Default::default()
         };
         //        features.shader_clip_distance = available_features.shader_clip_distance;
 
         let priorities = [1.0];
 
-        let queue_info = vk::DeviceQueueCreateInfo::default()
-            .queue_family_index(queue_family_index)
-            .queue_priorities(&priorities);
+        // One Queue of every Family, Graphics and Present are often the same
+        let mut families: Vec<u32> = queue_family_indices.iter().flatten().copied().collect();
+        families.sort_unstable();
+        families.dedup();
+        let queue_infos: Vec<_> = families
+            .into_iter()
+            .map(|family| {
+                vk::DeviceQueueCreateInfo::default()
+                    .queue_family_index(family)
+                    .queue_priorities(&priorities)
+            })
+            .collect();
+
+        let mut features_uint8 =
+            vk::PhysicalDeviceIndexTypeUint8FeaturesKHR::default().index_type_uint8(true);
 
-        let device_create_info = vk::DeviceCreateInfo::default()
-            .queue_create_infos(std::slice::from_ref(&queue_info))
+        let mut device_create_info = vk::DeviceCreateInfo::default()
+            .queue_create_infos(&queue_infos)
             .enabled_extension_names(&device_extension_names_raw)
             .enabled_features(&features)
+            .push_next(&mut features_1_2)
             .push_next(&mut features_1_3);
+        // The Extension is only enabled when the Feature is supported
+        if capabilities.index_type_uint8 {
+            device_create_info = device_create_info.push_next(&mut features_uint8);
+        }
 
         unsafe { instance.create_device(pdevice, &device_create_info, None) }.unwrap()
     }
@@ -640,52 +922,19 @@ impl VulkanInstance {
         )
     }
 
-    fn create_command_pools(
+    fn create_global_command_pool(
         device: &ash::Device,
         queue_family_index: u32,
-        count: usize,
-    ) -> (vk::CommandPool, Vec<vk::CommandPool>) {
+    ) -> vk::CommandPool {
         let create_info = vk::CommandPoolCreateInfo::default()
             .queue_family_index(queue_family_index)
             .flags(vk::CommandPoolCreateFlags::TRANSIENT);
-        let global = unsafe { device.create_command_pool(&create_info, None) }.unwrap();
-
-        let mut pools: Vec<_> = Vec::with_capacity(count);
-        for _ in 0..count {
-            let create_info = vk::CommandPoolCreateInfo::default()
-                .queue_family_index(queue_family_index)
-                .flags(vk::CommandPoolCreateFlags::TRANSIENT);
-            pools.push(unsafe { device.create_command_pool(&create_info, None) }.unwrap());
-        }
-        (global, pools)
-    }
-
-    fn allocate_command_buffers(
-        device: &ash::Device,
-        command_pools: &[vk::CommandPool],
-        count: usize,
-    ) -> Vec<vk::CommandBuffer> {
-        let mut buffers = Vec::with_capacity(count);
-        for pool in command_pools.iter().take(count) {
-            let allocate_info = vk::CommandBufferAllocateInfo::default()
-                .command_pool(*pool)
-                .level(vk::CommandBufferLevel::PRIMARY)
-                .command_buffer_count(1);
-
-            buffers.push(unsafe { device.allocate_command_buffers(&allocate_info) }.unwrap()[0]);
-        }
-        buffers
+        unsafe { device.create_command_pool(&create_info, None) }.unwrap()
     }
 
     fn create_sync_objects(
         device: &ash::Device,
-        swapchain_images: &[vk::Image],
-    ) -> (
-        Vec<vk::Semaphore>,
-        Vec<vk::Semaphore>,
-        Vec<vk::Fence>,
-        Vec<vk::Fence>,
-    ) {
+    ) -> (Vec<vk::Semaphore>, Vec<vk::Semaphore>, Vec<vk::Fence>) {
         let (mut image_available_semaphores, mut render_finished_semaphores, mut in_flight_fences) =
             (Vec::new(), Vec::new(), Vec::new());
 
@@ -702,13 +951,10 @@ impl VulkanInstance {
             in_flight_fences.push(unsafe { device.create_fence(&fence_info, None) }.unwrap());
         }
 
-        let images_in_flight = swapchain_images.iter().map(|_| vk::Fence::null()).collect();
-
         (
             image_available_semaphores,
             render_finished_semaphores,
             in_flight_fences,
-            images_in_flight,
         )
     }
 
@@ -747,18 +993,12 @@ impl VulkanInstance {
         )
     }
 
-    pub fn allocate_descriptor_sets(
-        device: &ash::Device,
-        descriptor_pool: vk::DescriptorPool,
-        descriptor_set_layout: vk::DescriptorSetLayout,
-        size: usize,
-    ) -> Vec<vk::DescriptorSet> {
-        let layouts = (0..size).map(|_| descriptor_set_layout).collect::<Vec<_>>();
-        let info = vk::DescriptorSetAllocateInfo::default()
-            .descriptor_pool(descriptor_pool)
-            .set_layouts(&layouts);
-
-        unsafe { device.allocate_descriptor_sets(&info) }.unwrap()
+    /// Formats of the Swapchain and Depth Image, Used by Pipelines which render directly into the Swapchain
+    pub fn surface_attachment_formats(&self) -> AttachmentFormats {
+        AttachmentFormats {
+            color: vec![self.surface_format.format],
+            depth: Some(self.depth_format),
+        }
     }
 
     pub fn create_pipeline_layout(
@@ -772,79 +1012,6 @@ impl VulkanInstance {
 
         unsafe { self.device.create_pipeline_layout(&create_info, None) }.unwrap()
     }
-
-    fn create_render_pass(
-        device: &ash::Device,
-        surface_format: vk::SurfaceFormatKHR,
-        depth_format: vk::Format,
-    ) -> vk::RenderPass {
-        let renderpass_attachments = [
-            vk::AttachmentDescription2 {
-                format: surface_format.format,
-                samples: vk::SampleCountFlags::TYPE_1,
-                load_op: vk::AttachmentLoadOp::CLEAR,
-                store_op: vk::AttachmentStoreOp::STORE,
-                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
-                stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
-                initial_layout: vk::ImageLayout::UNDEFINED,
-                final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
-                ..Default::default()
-            },
-            vk::AttachmentDescription2 {
-                format: depth_format,
-                samples: vk::SampleCountFlags::TYPE_1,
-                load_op: vk::AttachmentLoadOp::CLEAR,
-                store_op: vk::AttachmentStoreOp::DONT_CARE,
-                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
-                stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
-                initial_layout: vk::ImageLayout::UNDEFINED,
-                final_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-                ..Default::default()
-            },
-        ];
-        let color_attachment_refs = [vk::AttachmentReference2 {
-            attachment: 0,
-            layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-            ..Default::default()
-        }];
-        let depth_attachment_ref = vk::AttachmentReference2::default()
-            .attachment(1)
-            .layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
-
-        let dependencies = [
-            vk::SubpassDependency2 {
-                src_subpass: vk::SUBPASS_EXTERNAL,
-                src_stage_mask: vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
-                    | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
-                dst_stage_mask: vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
-                    | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
-                src_access_mask: vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
-                dst_access_mask: vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ
-                    | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
-                ..Default::default()
-            },
-            vk::SubpassDependency2 {
-                src_subpass: vk::SUBPASS_EXTERNAL,
-                src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
-                dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
-                dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_READ
-                    | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
-                ..Default::default()
-            },
-        ];
-
-        let subpass = vk::SubpassDescription2::default()
-            .color_attachments(&color_attachment_refs)
-            .depth_stencil_attachment(&depth_attachment_ref)
-            .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS);
-
-        let create_info = vk::RenderPassCreateInfo2::default()
-            .attachments(&renderpass_attachments)
-            .subpasses(std::slice::from_ref(&subpass))
-            .dependencies(&dependencies);
-
-        unsafe { device.create_render_pass2(&create_info, None) }.unwrap()
-    }
 }
 
 impl Drop for VulkanInstance {
@@ -864,20 +1031,34 @@ impl Drop for VulkanInstance {
                 .drain(..)
                 .for_each(|s| self.device.destroy_semaphore(s, None));
 
-            self.device.destroy_render_pass(self.render_pass, None);
-
+            // Deletions may free Memory, So they run before the Allocator is destroyed
+            self.frames
+                .iter_mut()
+                .for_each(|frame| frame.destroy(&self.device));
+            self.uniform_ring.destroy(&self.device);
+            self.upload_manager.destroy(&self.device);
             self.depth_image.destroy(&self.device);
+            self.memory_allocator.destroy(&self.device);
+            self.descriptor_allocator.destroy(&self.device);
+
+            if let Err(err) = self.pipeline_cache.save(&self.device) {
+                log::warn!(
+                    "Failed to save Pipeline Cache {}: {}",
+                    self.pipeline_cache.path().display(),
+                    err
+                );
+            }
+            self.pipeline_cache.destroy(&self.device);
 
-            self.command_pools
-                .iter()
-                .for_each(|p| self.device.destroy_command_pool(*p, None));
             self.device
                 .destroy_command_pool(self.global_command_pool, None);
 
             // DEVICE DESTRUCTION
             self.device.destroy_device(None);
 
-            self.surface_loader.destroy_surface(self.surface, None);
+            if !self.headless {
+                self.surface_loader.destroy_surface(self.surface, None);
+            }
 
             if let Some(debug_messenger) = self.debug_messenger {
                 if let Some(debug_utils) = &self.debug_utils {
@@ -7,11 +7,17 @@ use ordered_float::OrderedFloat;
 pub mod allocator;
 pub mod buffer;
 mod debug;
+pub mod descriptor;
+pub mod device;
+pub mod frame;
 pub mod image;
 pub mod instance;
 pub mod mesh;
 pub mod pipeline;
+pub mod reflect;
+pub mod render_graph;
 mod surface;
+pub mod upload;
 pub mod vertex;
 
 pub const DEFAULT_TEXTURE_FILTER: vk::Filter = vk::Filter::LINEAR;
@@ -28,7 +34,8 @@ pub struct MaterialPipelineInfo {
 }
 
 pub enum Indices {
-    U8(Vec<u8>), // TODO: Enable uin8 vulkan feature
+    /// Needs `DeviceCapabilities::index_type_uint8`, Otherwise `Mesh3D` widens them to `U16`
+    U8(Vec<u8>),
     U16(Vec<u16>),
     U32(Vec<u32>),
 }
@@ -62,6 +69,15 @@ impl Indices {
         }
     }
 
+    /// Converts `U8` Indices to `U16`, Other Index Types are copied
+    pub fn widen_u8(&self) -> Self {
+        match self {
+            Indices::U8(vec) => Indices::U16(vec.iter().map(|i| u16::from(*i)).collect()),
+            Indices::U16(vec) => Indices::U16(vec.clone()),
+            Indices::U32(vec) => Indices::U32(vec.clone()),
+        }
+    }
+
     /// Returns `true` if there are no indices.
     pub fn is_empty(&self) -> bool {
         match self {
@@ -1,9 +1,6 @@
-use ash::vk;
+use ash::{prelude::VkResult, vk};
 
-use crate::{
-    begin_single_time_command, buffer::VulkanBuffer, end_single_time_command,
-    instance::VulkanInstance, Indices,
-};
+use crate::{buffer::VulkanBuffer, instance::VulkanInstance, Indices};
 
 /// This is a simple mesh that consists of vertices and indices. It is useful when you need to hard-code 3D data into your application.
 
@@ -15,7 +12,34 @@ pub struct Mesh3D {
     vertex_buf: VulkanBuffer,
     index_buf: VulkanBuffer,
     index_type: vk::IndexType,
-    index_count: u32,
+    lods: Vec<MeshLod>,
+    pub bounding_sphere: Option<BoundingSphere>,
+    /// Only set when the Mesh has Morph Targets
+    pub morph_targets: Option<MorphTargets>,
+}
+
+/// A Range inside the Index Buffer, LOD 0 is always the full detail Mesh
+#[derive(Clone, Copy)]
+pub struct MeshLod {
+    pub first_index: u32,
+    pub index_count: u32,
+}
+
+/// Where the Morph Targets of an Mesh are stored. Deltas are stored per Vertex,
+/// So the Delta of Target `t` for Vertex `v` is at `first_delta + v * target_count + t`
+#[derive(Clone, Copy)]
+pub struct MorphTargets {
+    pub first_delta: u32,
+    pub target_count: u32,
+    /// Where the Weights of the Mesh start inside the Weight Buffer
+    pub first_weight: u32,
+}
+
+/// Bounding Sphere in Model Space, Used to calculate the projected Screen size of an Mesh
+#[derive(Clone, Copy)]
+pub struct BoundingSphere {
+    pub center: [f32; 3],
+    pub radius: f32,
 }
 
 impl Mesh3D {
@@ -24,87 +48,105 @@ impl Mesh3D {
         vertices: &[V],
         indices: Indices,
         name: Option<&str>,
-    ) -> Self {
+    ) -> VkResult<Self> {
+        Self::new_with_lods(instance, vertices, &[indices], None, name)
+    }
+
+    /// Creates an Mesh with multiple Levels of Detail, All LODs share the same Vertex Buffer
+    /// and are stored one after the other in a single Index Buffer.
+    /// All Indices must have the same Index Type
+    pub fn new_with_lods<V: Copy>(
+        instance: &VulkanInstance,
+        vertices: &[V],
+        lods: &[Indices],
+        bounding_sphere: Option<BoundingSphere>,
+        name: Option<&str>,
+    ) -> VkResult<Self> {
+        assert!(!lods.is_empty(), "Mesh needs at least one LOD");
+        let widened: Vec<Indices>;
+        let lods = if !instance.capabilities.index_type_uint8 && matches!(lods[0], Indices::U8(_)) {
+            widened = lods.iter().map(Indices::widen_u8).collect();
+            &widened
+        } else {
+            lods
+        };
+        let index_type = lods[0].vk_type();
+        debug_assert!(lods.iter().all(|lod| lod.vk_type() == index_type));
+
         let vertex_size = std::mem::size_of_val(vertices) as vk::DeviceSize;
-        let index_size = indices.get_size() as vk::DeviceSize;
+        let index_size = lods.iter().map(|lod| lod.get_size()).sum::<usize>() as vk::DeviceSize;
 
-        let vertex_buf = VulkanBuffer::new(
+        let mut vertex_buf = VulkanBuffer::new(
             instance,
             vertex_size,
             vk::BufferUsageFlags::VERTEX_BUFFER | vk::BufferUsageFlags::TRANSFER_DST,
             vk::MemoryPropertyFlags::DEVICE_LOCAL,
             name,
-        );
+        )?;
 
-        let index_buf = VulkanBuffer::new(
+        let mut index_buf = match VulkanBuffer::new(
             instance,
             index_size,
             vk::BufferUsageFlags::INDEX_BUFFER | vk::BufferUsageFlags::TRANSFER_DST,
             vk::MemoryPropertyFlags::DEVICE_LOCAL,
             name,
-        );
-
-        let mut staging_buf = VulkanBuffer::new(
-            instance,
-            vertex_size + index_size,
-            vk::BufferUsageFlags::TRANSFER_SRC,
-            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
-            name,
-        );
-
-        let memory = staging_buf.map(&instance.device, vertex_size + index_size);
-
-        // copy vertex buffer
-        unsafe { staging_buf.upload_data(memory, vertices, vertex_size) };
-        // copy index buffer
-        unsafe {
-            indices.upload(
-                &staging_buf,
-                memory.wrapping_add(vertex_size as usize),
-                index_size,
-            )
+        ) {
+            Ok(buffer) => buffer,
+            Err(err) => {
+                vertex_buf.destroy(&instance.device);
+                return Err(err);
+            }
         };
 
-        let command_buffer =
-            begin_single_time_command(&instance.device, instance.global_command_pool);
-
-        unsafe {
-            let buffer_info = vk::BufferCopy::default().size(vertex_size);
-
-            instance.device.cmd_copy_buffer(
-                command_buffer,
-                *staging_buf,
-                *vertex_buf,
-                &[buffer_info],
-            );
-            let buffer_info = vk::BufferCopy::default()
-                .size(index_size)
-                .src_offset(vertex_size);
-
-            instance.device.cmd_copy_buffer(
-                command_buffer,
-                *staging_buf,
-                *index_buf,
-                &[buffer_info],
-            );
-        };
-        staging_buf.unmap(&instance.device);
-
-        end_single_time_command(
-            &instance.device,
-            instance.global_command_pool,
-            instance.graphics_queue,
-            command_buffer,
-        );
+        // Copied with the next Frame, Every LOD after another
+        let upload = &instance.upload_manager;
+        let uploaded = upload
+            .upload_buffer(instance, *vertex_buf, vertices)
+            .and_then(|()| {
+                upload.upload_buffer_with(instance, *index_buf, index_size, |staging, memory| {
+                    let mut offset = 0;
+                    for lod in lods {
+                        let lod_size = lod.get_size();
+                        unsafe {
+                            lod.upload(
+                                staging,
+                                memory.wrapping_add(offset),
+                                lod_size as vk::DeviceSize,
+                            )
+                        };
+                        offset += lod_size;
+                    }
+                })
+            });
+        if let Err(err) = uploaded {
+            vertex_buf.destroy(&instance.device);
+            index_buf.destroy(&instance.device);
+            return Err(err);
+        }
 
-        staging_buf.destroy(&instance.device);
+        let mut mesh_lods = Vec::with_capacity(lods.len());
+        let mut first_index = 0;
+        for lod in lods {
+            mesh_lods.push(MeshLod {
+                first_index,
+                index_count: lod.len() as u32,
+            });
+            first_index += lod.len() as u32;
+        }
 
-        Self {
+        Ok(Self {
             vertex_buf,
             index_buf,
-            index_type: indices.vk_type(),
-            index_count: indices.len() as u32,
-        }
+            index_type,
+            lods: mesh_lods,
+            bounding_sphere,
+            morph_targets: None,
+        })
+    }
+
+    /// Returns the number of Levels of Detail, Always at least 1
+    pub fn lod_count(&self) -> usize {
+        self.lods.len()
     }
 
     pub fn bind(&self, device: &ash::Device, command_buffer: vk::CommandBuffer) {
@@ -122,7 +164,15 @@ impl Mesh3D {
     }
 
     pub fn draw(&self, device: &ash::Device, command_buffer: vk::CommandBuffer) {
-        unsafe { device.cmd_draw_indexed(command_buffer, self.index_count, 1, 0, 0, 0) };
+        self.draw_lod(device, command_buffer, 0);
+    }
+
+    /// Draws the given Level of Detail, Clamps to the lowest available Detail
+    pub fn draw_lod(&self, device: &ash::Device, command_buffer: vk::CommandBuffer, lod: usize) {
+        let lod = &self.lods[lod.min(self.lods.len() - 1)];
+        unsafe {
+            device.cmd_draw_indexed(command_buffer, lod.index_count, 1, lod.first_index, 0, 0)
+        };
     }
 
     pub fn destroy(&mut self, device: &ash::Device) {
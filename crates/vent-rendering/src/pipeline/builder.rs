@@ -0,0 +1,193 @@
+use ash::{prelude::VkResult, vk};
+
+use crate::instance::VulkanInstance;
+
+use super::AttachmentFormats;
+
+/// How the Fragment Output is combined with what is already in the Color Attachment
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Blend {
+    /// Overwrites the Attachment
+    Opaque,
+    /// Blends by the Source Alpha, e.g. for Glass or Text
+    Alpha,
+    /// Adds the Source onto the Attachment, e.g. for Particles or Light Accumulation
+    Additive,
+}
+
+impl Blend {
+    fn attachment_state(self) -> vk::PipelineColorBlendAttachmentState {
+        let state = vk::PipelineColorBlendAttachmentState::default()
+            .color_write_mask(vk::ColorComponentFlags::RGBA);
+        match self {
+            Blend::Opaque => state,
+            Blend::Alpha => state
+                .blend_enable(true)
+                .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
+                .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+                .color_blend_op(vk::BlendOp::ADD)
+                .src_alpha_blend_factor(vk::BlendFactor::ONE)
+                .dst_alpha_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+                .alpha_blend_op(vk::BlendOp::ADD),
+            Blend::Additive => state
+                .blend_enable(true)
+                .src_color_blend_factor(vk::BlendFactor::ONE)
+                .dst_color_blend_factor(vk::BlendFactor::ONE)
+                .color_blend_op(vk::BlendOp::ADD)
+                .src_alpha_blend_factor(vk::BlendFactor::ONE)
+                .dst_alpha_blend_factor(vk::BlendFactor::ONE)
+                .alpha_blend_op(vk::BlendOp::ADD),
+        }
+    }
+}
+
+///
+/// Builds Graphics Pipelines, Viewport and Scissor are always dynamic.
+///
+/// Defaults:
+/// Topology: Triangle List,
+/// Cull: None,
+/// Front Face: CC,
+/// Depth: Disabled,
+/// Blend: Opaque
+///
+#[derive(Clone)]
+pub struct PipelineBuilder<'a> {
+    layout: vk::PipelineLayout,
+    formats: AttachmentFormats,
+    stages: Vec<vk::PipelineShaderStageCreateInfo<'a>>,
+    vertex_bindings: &'a [vk::VertexInputBindingDescription],
+    vertex_attributes: &'a [vk::VertexInputAttributeDescription],
+    topology: vk::PrimitiveTopology,
+    cull_mode: vk::CullModeFlags,
+    front_face: vk::FrontFace,
+    depth: Option<(vk::CompareOp, bool)>,
+    blend: Blend,
+}
+
+impl<'a> PipelineBuilder<'a> {
+    pub fn new(layout: vk::PipelineLayout, formats: AttachmentFormats) -> Self {
+        Self {
+            layout,
+            formats,
+            stages: Vec::new(),
+            vertex_bindings: &[],
+            vertex_attributes: &[],
+            topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+            cull_mode: vk::CullModeFlags::NONE,
+            front_face: vk::FrontFace::COUNTER_CLOCKWISE,
+            depth: None,
+            blend: Blend::Opaque,
+        }
+    }
+
+    /// Adds an Shader Stage with the Entry Point `main`
+    pub fn shader(mut self, stage: vk::ShaderStageFlags, module: vk::ShaderModule) -> Self {
+        self.stages.push(
+            vk::PipelineShaderStageCreateInfo::default()
+                .stage(stage)
+                .module(module)
+                .name(c"main"),
+        );
+        self
+    }
+
+    pub fn vertex_input(
+        mut self,
+        bindings: &'a [vk::VertexInputBindingDescription],
+        attributes: &'a [vk::VertexInputAttributeDescription],
+    ) -> Self {
+        self.vertex_bindings = bindings;
+        self.vertex_attributes = attributes;
+        self
+    }
+
+    pub fn topology(mut self, topology: vk::PrimitiveTopology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    pub fn cull_mode(mut self, cull_mode: vk::CullModeFlags) -> Self {
+        self.cull_mode = cull_mode;
+        self
+    }
+
+    pub fn front_face(mut self, front_face: vk::FrontFace) -> Self {
+        self.front_face = front_face;
+        self
+    }
+
+    /// Enables the Depth Test, `write` also writes the Depth of passing Fragments
+    pub fn depth(mut self, compare_op: vk::CompareOp, write: bool) -> Self {
+        self.depth = Some((compare_op, write));
+        self
+    }
+
+    /// Used for every Color Attachment
+    pub fn blend(mut self, blend: Blend) -> Self {
+        self.blend = blend;
+        self
+    }
+
+    /// Creates the Pipeline using the Pipeline Cache of the Instance
+    pub fn build(&self, instance: &VulkanInstance) -> VkResult<vk::Pipeline> {
+        let vertex_input_state_info = vk::PipelineVertexInputStateCreateInfo::default()
+            .vertex_binding_descriptions(self.vertex_bindings)
+            .vertex_attribute_descriptions(self.vertex_attributes);
+        let input_assembly_state_info =
+            vk::PipelineInputAssemblyStateCreateInfo::default().topology(self.topology);
+        // Set while recording
+        let viewport_state_info = vk::PipelineViewportStateCreateInfo::default()
+            .viewport_count(1)
+            .scissor_count(1);
+        let rasterization_info = vk::PipelineRasterizationStateCreateInfo::default()
+            .polygon_mode(vk::PolygonMode::FILL)
+            .cull_mode(self.cull_mode)
+            .front_face(self.front_face)
+            .line_width(1.0);
+        let multisample_state_info = vk::PipelineMultisampleStateCreateInfo::default()
+            .rasterization_samples(vk::SampleCountFlags::TYPE_1);
+
+        let depth_state_info = match self.depth {
+            Some((compare_op, write)) => vk::PipelineDepthStencilStateCreateInfo::default()
+                .depth_test_enable(true)
+                .depth_write_enable(write)
+                .depth_compare_op(compare_op)
+                .max_depth_bounds(1.0),
+            None => vk::PipelineDepthStencilStateCreateInfo::default(),
+        };
+        let color_blend_attachment_states =
+            vec![self.blend.attachment_state(); self.formats.color.len()];
+        let color_blend_state = vk::PipelineColorBlendStateCreateInfo::default()
+            .logic_op(vk::LogicOp::COPY)
+            .attachments(&color_blend_attachment_states);
+
+        let dynamic_state = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+        let dynamic_state_info =
+            vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&dynamic_state);
+
+        let mut rendering_info = self.formats.rendering_info();
+        let graphic_pipeline_info = vk::GraphicsPipelineCreateInfo::default()
+            .push_next(&mut rendering_info)
+            .stages(&self.stages)
+            .vertex_input_state(&vertex_input_state_info)
+            .input_assembly_state(&input_assembly_state_info)
+            .viewport_state(&viewport_state_info)
+            .rasterization_state(&rasterization_info)
+            .multisample_state(&multisample_state_info)
+            .depth_stencil_state(&depth_state_info)
+            .color_blend_state(&color_blend_state)
+            .dynamic_state(&dynamic_state_info)
+            .layout(self.layout);
+
+        let pipelines = unsafe {
+            instance.device.create_graphics_pipelines(
+                instance.pipeline_cache.cache,
+                &[graphic_pipeline_info],
+                None,
+            )
+        }
+        .map_err(|(_, err)| err)?;
+        Ok(pipelines[0])
+    }
+}
@@ -0,0 +1,101 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use ash::vk;
+
+/// Size of `VkPipelineCacheHeaderVersionOne`
+const HEADER_SIZE: usize = 32;
+
+/// An `vk::PipelineCache` which is loaded from and saved to an File, So Pipelines do not have to be compiled again on every Start
+pub struct PipelineCache {
+    pub cache: vk::PipelineCache,
+    path: PathBuf,
+}
+
+impl PipelineCache {
+    /// Loads the Cache at `path`, Data of an other Device or Driver is thrown away and an empty Cache is created
+    pub fn load(
+        device: &ash::Device,
+        properties: &vk::PhysicalDeviceProperties,
+        path: PathBuf,
+    ) -> Self {
+        let data = match fs::read(&path) {
+            Ok(data) if is_compatible(&data, properties) => {
+                log::debug!("Loaded Pipeline Cache {}", path.display());
+                data
+            }
+            Ok(_) => {
+                log::info!(
+                    "Pipeline Cache {} was created by an other Device or Driver, Ignoring it",
+                    path.display()
+                );
+                Vec::new()
+            }
+            Err(_) => Vec::new(),
+        };
+        let create_info = vk::PipelineCacheCreateInfo::default().initial_data(&data);
+        let cache = unsafe { device.create_pipeline_cache(&create_info, None) }
+            .or_else(|_| {
+                // Should not happen as we checked the Header, But Drivers may reject the Data anyway
+                unsafe {
+                    device.create_pipeline_cache(&vk::PipelineCacheCreateInfo::default(), None)
+                }
+            })
+            .unwrap();
+        Self { cache, path }
+    }
+
+    /// Default Location of the Cache for `application_name`, Inside the temp Directory as it can be recreated any time
+    pub fn default_path(application_name: &str) -> PathBuf {
+        let file_name: String = application_name
+            .trim_end_matches('\0')
+            .chars()
+            .map(|char| {
+                if char.is_ascii_alphanumeric() {
+                    char
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        std::env::temp_dir()
+            .join("vent-engine")
+            .join(format!("{file_name}.pipeline_cache"))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes the Cache to its File, Pipelines created since loading are included
+    pub fn save(&self, device: &ash::Device) -> io::Result<()> {
+        let data =
+            unsafe { device.get_pipeline_cache_data(self.cache) }.map_err(io::Error::other)?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Write to an other File first, So an Crash while saving does not leave an broken Cache behind
+        let temp_path = self.path.with_extension("pipeline_cache.tmp");
+        fs::write(&temp_path, data)?;
+        fs::rename(temp_path, &self.path)
+    }
+
+    pub fn destroy(&mut self, device: &ash::Device) {
+        unsafe { device.destroy_pipeline_cache(self.cache, None) };
+    }
+}
+
+/// Checks the `VkPipelineCacheHeaderVersionOne` Header against the Device, Some Drivers crash on Data of other Devices instead of ignoring it
+fn is_compatible(data: &[u8], properties: &vk::PhysicalDeviceProperties) -> bool {
+    if data.len() < HEADER_SIZE {
+        return false;
+    }
+    let read_u32 = |offset: usize| u32::from_ne_bytes(data[offset..offset + 4].try_into().unwrap());
+    read_u32(0) as usize >= HEADER_SIZE
+        && read_u32(4) == vk::PipelineCacheHeaderVersion::ONE.as_raw() as u32
+        && read_u32(8) == properties.vendor_id
+        && read_u32(12) == properties.device_id
+        && data[16..32] == properties.pipeline_cache_uuid
+}
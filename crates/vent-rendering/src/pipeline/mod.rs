@@ -0,0 +1,122 @@
+use std::{fs::File, path::Path};
+
+use ash::{util::read_spv, vk};
+
+pub use builder::{Blend, PipelineBuilder};
+pub use cache::PipelineCache;
+
+use crate::{instance::VulkanInstance, reflect::PipelineReflection};
+
+mod builder;
+mod cache;
+
+/// Formats of the Attachments an Pipeline renders into, Passes drawing with the Pipeline must render into the same Formats
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct AttachmentFormats {
+    pub color: Vec<vk::Format>,
+    pub depth: Option<vk::Format>,
+}
+
+impl AttachmentFormats {
+    /// Has to be chained into the `vk::GraphicsPipelineCreateInfo`
+    pub fn rendering_info(&self) -> vk::PipelineRenderingCreateInfo<'_> {
+        vk::PipelineRenderingCreateInfo::default()
+            .color_attachment_formats(&self.color)
+            .depth_attachment_format(self.depth.unwrap_or(vk::Format::UNDEFINED))
+    }
+}
+
+pub struct VulkanPipeline {
+    pub pipeline: vk::Pipeline,
+    pub pipeline_layout: vk::PipelineLayout,
+    pub descriptor_set_layout: vk::DescriptorSetLayout,
+
+    pub vertex_module: vk::ShaderModule,
+    pub fragment_module: vk::ShaderModule,
+}
+
+impl VulkanPipeline {
+    ///
+    /// Creates an Simple Pipeline from an Vertex & Fragment Shader
+    ///
+    /// Depth: Disabled,
+    /// Cull: None,
+    /// Front Face: CC,
+    /// Polygon Mode: Fill
+    ///
+    /// Renders into Attachments with `formats`. The Descriptor Set Layout (Set 0) and Push Constant Ranges are reflected from the Shaders
+    ///
+    pub fn create_simple_pipeline(
+        instance: &VulkanInstance,
+        vertex_file: &Path,
+        fragment_file: &Path,
+        binding_desc: &[vk::VertexInputBindingDescription],
+        attrib_desc: &[vk::VertexInputAttributeDescription],
+        formats: &AttachmentFormats,
+    ) -> Self {
+        let reflection = PipelineReflection::from_files(&[vertex_file, fragment_file])
+            .expect("Failed to reflect Shaders");
+        for mismatch in reflection.check_vertex_input(binding_desc, attrib_desc) {
+            log::error!("{}: {}", vertex_file.display(), mismatch);
+        }
+        debug_assert!(
+            reflection.set_count() <= 1,
+            "Simple Pipelines only support Descriptor Set 0"
+        );
+
+        let desc_layout_bindings = reflection.set_layout_bindings(0);
+        let info = vk::DescriptorSetLayoutCreateInfo::default().bindings(&desc_layout_bindings);
+
+        let descriptor_set_layout =
+            unsafe { instance.device.create_descriptor_set_layout(&info, None) }.unwrap();
+
+        let pipeline_layout = instance
+            .create_pipeline_layout(&reflection.push_constant_ranges(), &[descriptor_set_layout]);
+
+        let vertex_code =
+            read_spv(&mut File::open(vertex_file).expect("Failed to open Vertex File")).unwrap();
+        let vertex_module_info = vk::ShaderModuleCreateInfo::default().code(&vertex_code);
+        let fragment_code =
+            read_spv(&mut File::open(fragment_file).expect("Failed to open Fragment File"))
+                .unwrap();
+        let fragment_module_info = vk::ShaderModuleCreateInfo::default().code(&fragment_code);
+
+        let vertex_module = unsafe {
+            instance
+                .device
+                .create_shader_module(&vertex_module_info, None)
+        }
+        .unwrap();
+        let fragment_module = unsafe {
+            instance
+                .device
+                .create_shader_module(&fragment_module_info, None)
+        }
+        .unwrap();
+
+        let pipeline = PipelineBuilder::new(pipeline_layout, formats.clone())
+            .shader(vk::ShaderStageFlags::VERTEX, vertex_module)
+            .shader(vk::ShaderStageFlags::FRAGMENT, fragment_module)
+            .vertex_input(binding_desc, attrib_desc)
+            .build(instance)
+            .expect("Unable to create graphics pipeline");
+
+        Self {
+            pipeline,
+            descriptor_set_layout,
+            pipeline_layout,
+            vertex_module,
+            fragment_module,
+        }
+    }
+
+    pub fn destroy(&mut self, device: &ash::Device) {
+        unsafe {
+            device.destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+            device.destroy_pipeline_layout(self.pipeline_layout, None);
+            device.destroy_shader_module(self.vertex_module, None);
+            device.destroy_shader_module(self.fragment_module, None);
+            device.destroy_pipeline(self.pipeline, None)
+        }
+    }
+}
@@ -0,0 +1,808 @@
+//! SPIR-V Reflection. Descriptor Set Layouts and Push Constant Ranges are generated from the Shaders instead of being written by Hand,
+//! and the Vertex Input of an Pipeline is checked against what the Vertex Shader expects
+
+use std::{collections::HashMap, fmt, fs::File, io, path::Path};
+
+use ash::{util::read_spv, vk};
+use spirv::{Decoration, Dim, ExecutionModel, Op, StorageClass};
+
+#[derive(Debug)]
+pub enum ReflectError {
+    Io(io::Error),
+    /// The Code does not start with the SPIR-V Magic Number
+    InvalidMagic,
+    /// An Instruction reaches past the End of the Code or is missing Operands
+    Truncated,
+    NoEntryPoint,
+    /// OpenCL Kernels can not be used with Vulkan
+    UnsupportedStage(ExecutionModel),
+}
+
+impl fmt::Display for ReflectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReflectError::Io(err) => write!(f, "Failed to read Shader: {}", err),
+            ReflectError::InvalidMagic => write!(f, "Not an SPIR-V Module"),
+            ReflectError::Truncated => write!(f, "SPIR-V Module is truncated"),
+            ReflectError::NoEntryPoint => write!(f, "SPIR-V Module has no Entry Point"),
+            ReflectError::UnsupportedStage(model) => {
+                write!(f, "Unsupported Execution Model {:?}", model)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReflectError {}
+
+impl From<io::Error> for ReflectError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// An Resource the Shader reads through an Descriptor
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DescriptorBinding {
+    pub set: u32,
+    pub binding: u32,
+    pub descriptor_type: vk::DescriptorType,
+    /// Number of Array Elements, 0 for Runtime Arrays whose Size is decided when allocating the Set
+    pub count: u32,
+}
+
+/// How the Components of an Vertex Input are interpreted
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NumericType {
+    Float,
+    SInt,
+    UInt,
+}
+
+/// An `in` Variable of the Vertex Shader
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct VertexInput {
+    pub location: u32,
+    pub components: u32,
+    pub numeric: NumericType,
+}
+
+/// Everything the Pipeline Layout needs to know about one Shader Stage
+#[derive(Debug)]
+pub struct ShaderReflection {
+    pub stage: vk::ShaderStageFlags,
+    pub bindings: Vec<DescriptorBinding>,
+    /// Offset and Size of the Push Constant Block
+    pub push_constants: Option<(u32, u32)>,
+    /// Only filled for Vertex Shaders, Built-ins are skipped
+    pub inputs: Vec<VertexInput>,
+}
+
+enum Type {
+    Scalar { numeric: NumericType, width: u32 },
+    Vector { component: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    Image { dim: Dim, sampled: u32 },
+    Sampler,
+    SampledImage,
+    AccelerationStructure,
+    Array { element: u32, length: u32 },
+    RuntimeArray { element: u32 },
+    Struct { members: Vec<u32> },
+    Pointer { pointee: u32 },
+    Other,
+}
+
+#[derive(Default)]
+struct Decorations {
+    set: Option<u32>,
+    binding: Option<u32>,
+    location: Option<u32>,
+    built_in: bool,
+    buffer_block: bool,
+    array_stride: Option<u32>,
+    /// Offset and Matrix Stride of every Struct Member
+    member_offsets: HashMap<u32, u32>,
+    member_matrix_strides: HashMap<u32, u32>,
+}
+
+impl ShaderReflection {
+    pub fn from_file(path: &Path) -> Result<Self, ReflectError> {
+        let code = read_spv(&mut File::open(path)?)?;
+        Self::new(&code)
+    }
+
+    pub fn new(code: &[u32]) -> Result<Self, ReflectError> {
+        if code.len() < 5 || code[0] != spirv::MAGIC_NUMBER {
+            return Err(ReflectError::InvalidMagic);
+        }
+        let mut stage = None;
+        let mut interface = Vec::new();
+        let mut types: HashMap<u32, Type> = HashMap::new();
+        let mut constants: HashMap<u32, u32> = HashMap::new();
+        let mut decorations: HashMap<u32, Decorations> = HashMap::new();
+        let mut variables = Vec::new();
+
+        let mut words = &code[5..];
+        while let Some(&first) = words.first() {
+            let word_count = (first >> 16) as usize;
+            if word_count == 0 || word_count > words.len() {
+                return Err(ReflectError::Truncated);
+            }
+            let operands = &words[1..word_count];
+            // Broken Modules can have Instructions with too few Operands
+            let operand =
+                |index: usize| operands.get(index).copied().ok_or(ReflectError::Truncated);
+            words = &words[word_count..];
+            let Some(op) = Op::from_u32(first & 0xffff) else {
+                continue;
+            };
+            match op {
+                // Only the first Entry Point is reflected
+                Op::EntryPoint if stage.is_none() => {
+                    if let Some(model) = ExecutionModel::from_u32(operand(0)?) {
+                        stage = Some(conv_shader_stage(model)?);
+                    }
+                    // The Name is an nul terminated String packed into Words
+                    let name = operands.get(2..).ok_or(ReflectError::Truncated)?;
+                    let name_words = name
+                        .iter()
+                        .position(|word| word.to_le_bytes().contains(&0))
+                        .map_or(0, |index| index + 1);
+                    interface = name[name_words..].to_vec();
+                }
+                Op::Decorate => {
+                    let target = decorations.entry(operand(0)?).or_default();
+                    match Decoration::from_u32(operand(1)?) {
+                        Some(Decoration::DescriptorSet) => target.set = Some(operand(2)?),
+                        Some(Decoration::Binding) => target.binding = Some(operand(2)?),
+                        Some(Decoration::Location) => target.location = Some(operand(2)?),
+                        Some(Decoration::BuiltIn) => target.built_in = true,
+                        Some(Decoration::BufferBlock) => target.buffer_block = true,
+                        Some(Decoration::ArrayStride) => target.array_stride = Some(operand(2)?),
+                        _ => {}
+                    }
+                }
+                Op::MemberDecorate => {
+                    let target = decorations.entry(operand(0)?).or_default();
+                    match Decoration::from_u32(operand(2)?) {
+                        Some(Decoration::Offset) => {
+                            target.member_offsets.insert(operand(1)?, operand(3)?);
+                        }
+                        Some(Decoration::MatrixStride) => {
+                            target
+                                .member_matrix_strides
+                                .insert(operand(1)?, operand(3)?);
+                        }
+                        // Members of Input Blocks (gl_PerVertex) are Built-ins
+                        Some(Decoration::BuiltIn) => target.built_in = true,
+                        _ => {}
+                    }
+                }
+                Op::TypeInt => {
+                    let numeric = if operand(2)? == 1 {
+                        NumericType::SInt
+                    } else {
+                        NumericType::UInt
+                    };
+                    types.insert(
+                        operand(0)?,
+                        Type::Scalar {
+                            numeric,
+                            width: operand(1)?,
+                        },
+                    );
+                }
+                Op::TypeFloat => {
+                    types.insert(
+                        operand(0)?,
+                        Type::Scalar {
+                            numeric: NumericType::Float,
+                            width: operand(1)?,
+                        },
+                    );
+                }
+                Op::TypeVector => {
+                    types.insert(
+                        operand(0)?,
+                        Type::Vector {
+                            component: operand(1)?,
+                            count: operand(2)?,
+                        },
+                    );
+                }
+                Op::TypeMatrix => {
+                    types.insert(
+                        operand(0)?,
+                        Type::Matrix {
+                            column: operand(1)?,
+                            count: operand(2)?,
+                        },
+                    );
+                }
+                Op::TypeImage => {
+                    types.insert(
+                        operand(0)?,
+                        Type::Image {
+                            dim: Dim::from_u32(operand(2)?).unwrap_or(Dim::Dim2D),
+                            sampled: operand(6)?,
+                        },
+                    );
+                }
+                Op::TypeSampler => {
+                    types.insert(operand(0)?, Type::Sampler);
+                }
+                Op::TypeSampledImage => {
+                    types.insert(operand(0)?, Type::SampledImage);
+                }
+                Op::TypeAccelerationStructureKHR => {
+                    types.insert(operand(0)?, Type::AccelerationStructure);
+                }
+                Op::TypeArray => {
+                    types.insert(
+                        operand(0)?,
+                        Type::Array {
+                            element: operand(1)?,
+                            length: operand(2)?,
+                        },
+                    );
+                }
+                Op::TypeRuntimeArray => {
+                    types.insert(
+                        operand(0)?,
+                        Type::RuntimeArray {
+                            element: operand(1)?,
+                        },
+                    );
+                }
+                Op::TypeStruct => {
+                    types.insert(
+                        operand(0)?,
+                        Type::Struct {
+                            members: operands.get(1..).ok_or(ReflectError::Truncated)?.to_vec(),
+                        },
+                    );
+                }
+                Op::TypePointer => {
+                    types.insert(
+                        operand(0)?,
+                        Type::Pointer {
+                            pointee: operand(2)?,
+                        },
+                    );
+                }
+                Op::TypeVoid | Op::TypeBool | Op::TypeFunction => {
+                    types.insert(operand(0)?, Type::Other);
+                }
+                // Array Lengths are 32 bit Integer Constants
+                Op::Constant => {
+                    constants.insert(operand(1)?, operand(2)?);
+                }
+                Op::Variable => {
+                    variables.push((operand(0)?, operand(1)?, operand(2)?));
+                }
+                _ => {}
+            }
+        }
+        let stage = stage.ok_or(ReflectError::NoEntryPoint)?;
+
+        let module = Module {
+            types,
+            constants,
+            decorations,
+        };
+        let mut bindings = Vec::new();
+        let mut push_constants = None;
+        let mut inputs = Vec::new();
+        for (pointer_type, id, storage_class) in variables {
+            let Some(&Type::Pointer { pointee }) = module.types.get(&pointer_type) else {
+                continue;
+            };
+            let decoration = module.decorations.get(&id);
+            match StorageClass::from_u32(storage_class) {
+                Some(
+                    StorageClass::UniformConstant
+                    | StorageClass::Uniform
+                    | StorageClass::StorageBuffer,
+                ) => {
+                    let Some(binding) = decoration.and_then(|d| d.binding) else {
+                        continue;
+                    };
+                    let (element, count) = module.array_element(pointee);
+                    if let Some(descriptor_type) = module.descriptor_type(element, storage_class) {
+                        bindings.push(DescriptorBinding {
+                            set: decoration.and_then(|d| d.set).unwrap_or_default(),
+                            binding,
+                            descriptor_type,
+                            count,
+                        });
+                    }
+                }
+                Some(StorageClass::PushConstant) => {
+                    push_constants = module.struct_range(pointee);
+                }
+                Some(StorageClass::Input)
+                    if stage == vk::ShaderStageFlags::VERTEX && interface.contains(&id) =>
+                {
+                    let Some(location) =
+                        decoration.filter(|d| !d.built_in).and_then(|d| d.location)
+                    else {
+                        continue;
+                    };
+                    if let Some((components, numeric)) = module.components(pointee) {
+                        inputs.push(VertexInput {
+                            location,
+                            components,
+                            numeric,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        bindings.sort_by_key(|binding| (binding.set, binding.binding));
+        inputs.sort_by_key(|input| input.location);
+
+        Ok(Self {
+            stage,
+            bindings,
+            push_constants,
+            inputs,
+        })
+    }
+}
+
+struct Module {
+    types: HashMap<u32, Type>,
+    constants: HashMap<u32, u32>,
+    decorations: HashMap<u32, Decorations>,
+}
+
+impl Module {
+    /// Element Type and Length of an Descriptor Array, Non Arrays have an Length of 1
+    fn array_element(&self, type_id: u32) -> (u32, u32) {
+        match self.types.get(&type_id) {
+            Some(&Type::Array { element, length }) => {
+                (element, self.constants.get(&length).copied().unwrap_or(1))
+            }
+            Some(&Type::RuntimeArray { element }) => (element, 0),
+            _ => (type_id, 1),
+        }
+    }
+
+    fn descriptor_type(&self, type_id: u32, storage_class: u32) -> Option<vk::DescriptorType> {
+        let block = self.decorations.get(&type_id);
+        Some(
+            match (
+                StorageClass::from_u32(storage_class)?,
+                self.types.get(&type_id)?,
+            ) {
+                (StorageClass::StorageBuffer, Type::Struct { .. }) => {
+                    vk::DescriptorType::STORAGE_BUFFER
+                }
+                // Older GLSL Versions declare Storage Buffers as Uniform with BufferBlock
+                (StorageClass::Uniform, Type::Struct { .. })
+                    if block.is_some_and(|d| d.buffer_block) =>
+                {
+                    vk::DescriptorType::STORAGE_BUFFER
+                }
+                (StorageClass::Uniform, Type::Struct { .. }) => vk::DescriptorType::UNIFORM_BUFFER,
+                (_, Type::SampledImage) => vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                (_, Type::Sampler) => vk::DescriptorType::SAMPLER,
+                (_, Type::AccelerationStructure) => vk::DescriptorType::ACCELERATION_STRUCTURE_KHR,
+                (_, &Type::Image { dim, sampled }) => match (dim, sampled) {
+                    (Dim::DimSubpassData, _) => vk::DescriptorType::INPUT_ATTACHMENT,
+                    (Dim::DimBuffer, 2) => vk::DescriptorType::STORAGE_TEXEL_BUFFER,
+                    (Dim::DimBuffer, _) => vk::DescriptorType::UNIFORM_TEXEL_BUFFER,
+                    (_, 2) => vk::DescriptorType::STORAGE_IMAGE,
+                    _ => vk::DescriptorType::SAMPLED_IMAGE,
+                },
+                _ => return None,
+            },
+        )
+    }
+
+    /// Offset of the first Member and Size up to the End of the last Member
+    fn struct_range(&self, type_id: u32) -> Option<(u32, u32)> {
+        let Some(Type::Struct { members }) = self.types.get(&type_id) else {
+            return None;
+        };
+        let decorations = self.decorations.get(&type_id)?;
+        let mut start = u32::MAX;
+        let mut end = 0;
+        for (index, &member) in members.iter().enumerate() {
+            let offset = *decorations.member_offsets.get(&(index as u32))?;
+            let matrix_stride = decorations.member_matrix_strides.get(&(index as u32));
+            start = start.min(offset);
+            end = end.max(offset + self.size(member, matrix_stride.copied())?);
+        }
+        (start < end).then_some((start, end - start))
+    }
+
+    fn size(&self, type_id: u32, matrix_stride: Option<u32>) -> Option<u32> {
+        Some(match self.types.get(&type_id)? {
+            Type::Scalar { width, .. } => width / 8,
+            &Type::Vector { component, count } => self.size(component, None)? * count,
+            &Type::Matrix { column, count } => match matrix_stride {
+                Some(stride) => stride * count,
+                None => self.size(column, None)? * count,
+            },
+            &Type::Array { element, length } => {
+                let stride = match self.decorations.get(&type_id).and_then(|d| d.array_stride) {
+                    Some(stride) => stride,
+                    None => self.size(element, matrix_stride)?,
+                };
+                stride * self.constants.get(&length)?
+            }
+            Type::Struct { .. } => {
+                let (start, size) = self.struct_range(type_id)?;
+                start + size
+            }
+            _ => return None,
+        })
+    }
+
+    fn components(&self, type_id: u32) -> Option<(u32, NumericType)> {
+        match *self.types.get(&type_id)? {
+            Type::Scalar { numeric, .. } => Some((1, numeric)),
+            Type::Vector { component, count } => {
+                let (_, numeric) = self.components(component)?;
+                Some((count, numeric))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Merges the Reflection of every Stage of an Pipeline
+pub struct PipelineReflection {
+    pub stages: Vec<ShaderReflection>,
+}
+
+impl PipelineReflection {
+    pub fn new(stages: Vec<ShaderReflection>) -> Self {
+        Self { stages }
+    }
+
+    /// Reflects the Shader Files of an Pipeline
+    pub fn from_files(paths: &[&Path]) -> Result<Self, ReflectError> {
+        let stages = paths
+            .iter()
+            .map(|path| ShaderReflection::from_file(path))
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new(stages))
+    }
+
+    /// Number of Descriptor Sets, Including unused Sets in between
+    pub fn set_count(&self) -> u32 {
+        self.stages
+            .iter()
+            .flat_map(|stage| &stage.bindings)
+            .map(|binding| binding.set + 1)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Bindings of `set` used by any Stage, Every Binding is visible to all Stages using it
+    pub fn set_layout_bindings(&self, set: u32) -> Vec<vk::DescriptorSetLayoutBinding<'static>> {
+        let mut bindings: Vec<vk::DescriptorSetLayoutBinding> = Vec::new();
+        for stage in &self.stages {
+            for reflected in stage.bindings.iter().filter(|binding| binding.set == set) {
+                match bindings
+                    .iter_mut()
+                    .find(|binding| binding.binding == reflected.binding)
+                {
+                    Some(binding) => binding.stage_flags |= stage.stage,
+                    None => bindings.push(
+                        vk::DescriptorSetLayoutBinding::default()
+                            .binding(reflected.binding)
+                            .descriptor_type(reflected.descriptor_type)
+                            .descriptor_count(reflected.count)
+                            .stage_flags(stage.stage),
+                    ),
+                }
+            }
+        }
+        bindings.sort_by_key(|binding| binding.binding);
+        bindings
+    }
+
+    /// Makes the Uniform or Storage Buffer at `set` and `binding` dynamic in every Stage,
+    /// So it can be bound with an Offset into the `UniformRing`
+    pub fn make_dynamic(&mut self, set: u32, binding: u32) {
+        for reflected in self
+            .stages
+            .iter_mut()
+            .flat_map(|stage| &mut stage.bindings)
+            .filter(|reflected| reflected.set == set && reflected.binding == binding)
+        {
+            reflected.descriptor_type = match reflected.descriptor_type {
+                vk::DescriptorType::UNIFORM_BUFFER => vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC,
+                vk::DescriptorType::STORAGE_BUFFER => vk::DescriptorType::STORAGE_BUFFER_DYNAMIC,
+                other => other,
+            };
+        }
+    }
+
+    /// One Layout for every Set up to `set_count`
+    pub fn create_set_layouts(&self, device: &ash::Device) -> Vec<vk::DescriptorSetLayout> {
+        (0..self.set_count())
+            .map(|set| {
+                let bindings = self.set_layout_bindings(set);
+                let info = vk::DescriptorSetLayoutCreateInfo::default().bindings(&bindings);
+                unsafe { device.create_descriptor_set_layout(&info, None) }.unwrap()
+            })
+            .collect()
+    }
+
+    /// One Range for every Stage with Push Constants, `vkCmdPushConstants` needs the Flags of every Stage whose Range overlaps the pushed Bytes
+    pub fn push_constant_ranges(&self) -> Vec<vk::PushConstantRange> {
+        let mut ranges: Vec<vk::PushConstantRange> = Vec::new();
+        for stage in &self.stages {
+            let Some((offset, size)) = stage.push_constants else {
+                continue;
+            };
+            // An Stage may only appear in one Range
+            match ranges
+                .iter_mut()
+                .find(|range| range.stage_flags == stage.stage)
+            {
+                Some(range) => {
+                    let start = range.offset.min(offset);
+                    let end = (range.offset + range.size).max(offset + size);
+                    range.offset = start;
+                    range.size = end - start;
+                }
+                None => ranges.push(
+                    vk::PushConstantRange::default()
+                        .offset(offset)
+                        .size(size)
+                        .stage_flags(stage.stage),
+                ),
+            }
+        }
+        ranges
+    }
+
+    /// Compares the Vertex Input of the Pipeline with the Inputs of the Vertex Shader
+    pub fn check_vertex_input(
+        &self,
+        bindings: &[vk::VertexInputBindingDescription],
+        attributes: &[vk::VertexInputAttributeDescription],
+    ) -> Vec<VertexInputMismatch> {
+        let mut mismatches = Vec::new();
+        let Some(vertex) = self
+            .stages
+            .iter()
+            .find(|stage| stage.stage == vk::ShaderStageFlags::VERTEX)
+        else {
+            return mismatches;
+        };
+        for input in &vertex.inputs {
+            let Some(attribute) = attributes
+                .iter()
+                .find(|attribute| attribute.location == input.location)
+            else {
+                mismatches.push(VertexInputMismatch::MissingAttribute {
+                    location: input.location,
+                });
+                continue;
+            };
+            let Some((components, size, numeric)) = format_info(attribute.format) else {
+                continue;
+            };
+            if numeric != input.numeric {
+                mismatches.push(VertexInputMismatch::NumericType {
+                    location: input.location,
+                    shader: input.numeric,
+                    format: attribute.format,
+                });
+            }
+            if components != input.components {
+                mismatches.push(VertexInputMismatch::ComponentCount {
+                    location: input.location,
+                    shader: input.components,
+                    format: attribute.format,
+                });
+            }
+            // The Format must not read into the next Attribute or past the Vertex
+            let end = attribute.offset + size;
+            let stride = bindings
+                .iter()
+                .find(|binding| binding.binding == attribute.binding)
+                .map(|binding| binding.stride);
+            if stride.is_some_and(|stride| end > stride) {
+                mismatches.push(VertexInputMismatch::Overlap {
+                    location: input.location,
+                    other: None,
+                });
+            }
+            if let Some(other) = attributes.iter().find(|other| {
+                other.binding == attribute.binding
+                    && other.offset > attribute.offset
+                    && other.offset < end
+            }) {
+                mismatches.push(VertexInputMismatch::Overlap {
+                    location: input.location,
+                    other: Some(other.location),
+                });
+            }
+        }
+        mismatches
+    }
+}
+
+/// An Difference between the Vertex Input of an Pipeline and its Vertex Shader
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VertexInputMismatch {
+    /// The Shader reads an Location no Attribute provides
+    MissingAttribute { location: u32 },
+    NumericType {
+        location: u32,
+        shader: NumericType,
+        format: vk::Format,
+    },
+    ComponentCount {
+        location: u32,
+        shader: u32,
+        format: vk::Format,
+    },
+    /// The Format is larger than the Field, It reads into the Attribute at `other` or past the Vertex when `None`
+    Overlap { location: u32, other: Option<u32> },
+}
+
+impl fmt::Display for VertexInputMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VertexInputMismatch::MissingAttribute { location } => {
+                write!(f, "Location {}: No Vertex Attribute", location)
+            }
+            VertexInputMismatch::NumericType {
+                location,
+                shader,
+                format,
+            } => write!(
+                f,
+                "Location {}: Shader reads {:?}, Attribute Format is {:?}",
+                location, shader, format
+            ),
+            VertexInputMismatch::ComponentCount {
+                location,
+                shader,
+                format,
+            } => write!(
+                f,
+                "Location {}: Shader reads {} Components, Attribute Format is {:?}",
+                location, shader, format
+            ),
+            VertexInputMismatch::Overlap {
+                location,
+                other: Some(other),
+            } => write!(
+                f,
+                "Location {}: Attribute Format overlaps Location {}",
+                location, other
+            ),
+            VertexInputMismatch::Overlap {
+                location,
+                other: None,
+            } => write!(
+                f,
+                "Location {}: Attribute Format reaches past the Vertex Stride",
+                location
+            ),
+        }
+    }
+}
+
+/// Components, Size in Bytes and Numeric Type of the Vertex Formats we use
+fn format_info(format: vk::Format) -> Option<(u32, u32, NumericType)> {
+    Some(match format {
+        vk::Format::R32_SFLOAT => (1, 4, NumericType::Float),
+        vk::Format::R32G32_SFLOAT => (2, 8, NumericType::Float),
+        vk::Format::R32G32B32_SFLOAT => (3, 12, NumericType::Float),
+        vk::Format::R32G32B32A32_SFLOAT => (4, 16, NumericType::Float),
+        vk::Format::R32_UINT => (1, 4, NumericType::UInt),
+        vk::Format::R32G32_UINT => (2, 8, NumericType::UInt),
+        vk::Format::R32G32B32_UINT => (3, 12, NumericType::UInt),
+        vk::Format::R32G32B32A32_UINT => (4, 16, NumericType::UInt),
+        vk::Format::R32_SINT => (1, 4, NumericType::SInt),
+        vk::Format::R32G32_SINT => (2, 8, NumericType::SInt),
+        vk::Format::R32G32B32_SINT => (3, 12, NumericType::SInt),
+        vk::Format::R32G32B32A32_SINT => (4, 16, NumericType::SInt),
+        vk::Format::R16G16_UINT => (2, 4, NumericType::UInt),
+        vk::Format::R16G16B16A16_UINT => (4, 8, NumericType::UInt),
+        vk::Format::R8G8B8A8_UINT => (4, 4, NumericType::UInt),
+        vk::Format::R16G16_SFLOAT => (2, 4, NumericType::Float),
+        vk::Format::R16G16B16A16_SFLOAT => (4, 8, NumericType::Float),
+        // Normalized Formats are read as Floats
+        vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SNORM => (4, 4, NumericType::Float),
+        vk::Format::R16G16B16A16_UNORM | vk::Format::R16G16B16A16_SNORM => {
+            (4, 8, NumericType::Float)
+        }
+        _ => return None,
+    })
+}
+
+fn conv_shader_stage(model: ExecutionModel) -> Result<vk::ShaderStageFlags, ReflectError> {
+    Ok(match model {
+        ExecutionModel::Vertex => vk::ShaderStageFlags::VERTEX,
+        ExecutionModel::TessellationControl => vk::ShaderStageFlags::TESSELLATION_CONTROL,
+        ExecutionModel::TessellationEvaluation => vk::ShaderStageFlags::TESSELLATION_EVALUATION,
+        ExecutionModel::Geometry => vk::ShaderStageFlags::GEOMETRY,
+        ExecutionModel::Fragment => vk::ShaderStageFlags::FRAGMENT,
+        ExecutionModel::GLCompute => vk::ShaderStageFlags::COMPUTE,
+        ExecutionModel::Kernel => return Err(ReflectError::UnsupportedStage(model)),
+        ExecutionModel::TaskNV => vk::ShaderStageFlags::TASK_NV,
+        ExecutionModel::MeshNV => vk::ShaderStageFlags::MESH_NV,
+        ExecutionModel::RayGenerationNV => vk::ShaderStageFlags::RAYGEN_NV,
+        ExecutionModel::IntersectionNV => vk::ShaderStageFlags::INTERSECTION_NV,
+        ExecutionModel::AnyHitNV => vk::ShaderStageFlags::ANY_HIT_NV,
+        ExecutionModel::ClosestHitNV => vk::ShaderStageFlags::CLOSEST_HIT_NV,
+        ExecutionModel::MissNV => vk::ShaderStageFlags::MISS_NV,
+        ExecutionModel::CallableNV => vk::ShaderStageFlags::CALLABLE_NV,
+        ExecutionModel::TaskEXT => vk::ShaderStageFlags::TASK_EXT,
+        ExecutionModel::MeshEXT => vk::ShaderStageFlags::MESH_EXT,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An Module Header followed by `instructions`, Each is an Opcode and its Operands
+    fn module(instructions: &[(Op, &[u32])]) -> Vec<u32> {
+        let mut code = vec![spirv::MAGIC_NUMBER, 0x0001_0000, 0, 16, 0];
+        for (op, operands) in instructions {
+            code.push(((operands.len() as u32 + 1) << 16) | *op as u32);
+            code.extend_from_slice(operands);
+        }
+        code
+    }
+
+    const MAIN: [u32; 2] = [u32::from_le_bytes(*b"main"), 0];
+
+    #[test]
+    fn fragment_entry_point() {
+        let entry_point = [&[ExecutionModel::Fragment as u32, 1], &MAIN[..]].concat();
+        let reflection = ShaderReflection::new(&module(&[(Op::EntryPoint, &entry_point)])).unwrap();
+        assert_eq!(reflection.stage, vk::ShaderStageFlags::FRAGMENT);
+        assert!(reflection.bindings.is_empty());
+    }
+
+    #[test]
+    fn rejects_kernels() {
+        let entry_point = [&[ExecutionModel::Kernel as u32, 1], &MAIN[..]].concat();
+        assert!(matches!(
+            ShaderReflection::new(&module(&[(Op::EntryPoint, &entry_point)])),
+            Err(ReflectError::UnsupportedStage(ExecutionModel::Kernel))
+        ));
+    }
+
+    #[test]
+    fn rejects_missing_operands() {
+        let entry_point = [&[ExecutionModel::Vertex as u32, 1], &MAIN[..]].concat();
+        for instruction in [
+            (Op::EntryPoint, &[ExecutionModel::Vertex as u32][..]),
+            (Op::Decorate, &[1, Decoration::Binding as u32]),
+            (Op::MemberDecorate, &[1, 0, Decoration::Offset as u32]),
+            (Op::TypeImage, &[1, 2, 1]),
+            (Op::TypeStruct, &[]),
+            (Op::Variable, &[1, 2]),
+        ] {
+            // Only the first Entry Point is read, So the broken Instruction comes first
+            let code = module(&[instruction, (Op::EntryPoint, &entry_point)]);
+            assert!(
+                matches!(ShaderReflection::new(&code), Err(ReflectError::Truncated)),
+                "{:?}",
+                instruction.0
+            );
+        }
+        // The Word Count reaches past the End
+        let mut code = module(&[(Op::EntryPoint, &entry_point)]);
+        code.truncate(code.len() - 1);
+        assert!(matches!(
+            ShaderReflection::new(&code),
+            Err(ReflectError::Truncated)
+        ));
+    }
+}
@@ -0,0 +1,973 @@
+//! Frame Render Graph. Passes declare which Images and Buffers they read and write, The Graph then culls Passes whose Results are never used,
+//! inserts the synchronization2 Barriers and Layout Transitions between them and places Transient Attachments into shared (aliased) Memory.
+//!
+//! Passes run in the Order they were added, A Pass can only read what earlier Passes wrote so this Order already satisfies every Dependency.
+//! The Graph is built again every Frame, Transient Images live longer and are cached in `RenderGraphResources`
+
+use std::collections::HashSet;
+
+use ash::vk;
+
+pub use resources::RenderGraphResources;
+
+use crate::instance::VulkanInstance;
+
+mod resources;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ImageHandle(usize);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct BufferHandle(usize);
+
+/// Where an Resource was last used and in which Layout it is, The Graph synchronizes its first Use against this
+#[derive(Clone, Copy, Debug)]
+pub struct ResourceState {
+    pub layout: vk::ImageLayout,
+    pub stage: vk::PipelineStageFlags2,
+    pub access: vk::AccessFlags2,
+}
+
+impl ResourceState {
+    /// An Swapchain Image right after it was acquired, Waiting on the Acquire Semaphore happens at `COLOR_ATTACHMENT_OUTPUT`
+    pub const ACQUIRED: Self = Self {
+        layout: vk::ImageLayout::UNDEFINED,
+        stage: vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+        access: vk::AccessFlags2::NONE,
+    };
+
+    /// Content does not matter, But earlier Frames may still use the Resource
+    pub const UNDEFINED: Self = Self {
+        layout: vk::ImageLayout::UNDEFINED,
+        stage: vk::PipelineStageFlags2::ALL_COMMANDS,
+        access: vk::AccessFlags2::MEMORY_WRITE,
+    };
+}
+
+/// An Image which is owned outside of the Graph, e.g. the Swapchain Image
+pub struct ImportedImage {
+    pub image: vk::Image,
+    pub image_view: vk::ImageView,
+    pub format: vk::Format,
+    pub extent: vk::Extent2D,
+    pub initial_state: ResourceState,
+    /// Layout the Image is left in after the Graph, e.g. `PRESENT_SRC_KHR`. Images with an final Layout are Outputs of the Graph and are never culled
+    pub final_layout: Option<vk::ImageLayout>,
+}
+
+/// An Image which only lives during the Frame, Its Memory is shared with other Transient Images whose Lifetimes do not overlap
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct TransientImageInfo {
+    pub format: vk::Format,
+    pub extent: vk::Extent2D,
+}
+
+/// An Buffer which is owned outside of the Graph
+pub struct ImportedBuffer {
+    pub buffer: vk::Buffer,
+    pub initial_state: ResourceState,
+    /// Buffers marked as Output are never culled, e.g. when the CPU reads them back
+    pub output: bool,
+}
+
+#[derive(Clone, Copy)]
+pub enum AttachmentLoad {
+    Load,
+    Clear(vk::ClearValue),
+    /// Every Pixel gets written, The old Content is not needed
+    DontCare,
+}
+
+/// How an Pass uses an Image outside of its Attachments
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ImageUsage {
+    /// Sampled in an Fragment or Compute Shader
+    Sampled,
+    /// Depth sampled in an Shader, e.g. an Shadow Map
+    DepthRead,
+    Storage,
+    TransferSrc,
+    TransferDst,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BufferUsage {
+    Vertex,
+    Index,
+    Indirect,
+    Uniform,
+    Storage,
+    TransferSrc,
+    TransferDst,
+}
+
+/// What the Graph has to know about one Use of an Image
+#[derive(Clone, Copy)]
+struct Access {
+    layout: vk::ImageLayout,
+    stage: vk::PipelineStageFlags2,
+    access: vk::AccessFlags2,
+    usage: vk::ImageUsageFlags,
+    read: bool,
+    write: bool,
+}
+
+impl ImageUsage {
+    fn access(self) -> Access {
+        let (layout, stage, access, usage, write) = match self {
+            ImageUsage::Sampled => (
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                vk::PipelineStageFlags2::FRAGMENT_SHADER | vk::PipelineStageFlags2::COMPUTE_SHADER,
+                vk::AccessFlags2::SHADER_SAMPLED_READ,
+                vk::ImageUsageFlags::SAMPLED,
+                false,
+            ),
+            ImageUsage::DepthRead => (
+                vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+                vk::PipelineStageFlags2::EARLY_FRAGMENT_TESTS
+                    | vk::PipelineStageFlags2::LATE_FRAGMENT_TESTS
+                    | vk::PipelineStageFlags2::FRAGMENT_SHADER,
+                vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_READ
+                    | vk::AccessFlags2::SHADER_SAMPLED_READ,
+                vk::ImageUsageFlags::SAMPLED,
+                false,
+            ),
+            ImageUsage::Storage => (
+                vk::ImageLayout::GENERAL,
+                vk::PipelineStageFlags2::FRAGMENT_SHADER | vk::PipelineStageFlags2::COMPUTE_SHADER,
+                vk::AccessFlags2::SHADER_STORAGE_READ | vk::AccessFlags2::SHADER_STORAGE_WRITE,
+                vk::ImageUsageFlags::STORAGE,
+                true,
+            ),
+            ImageUsage::TransferSrc => (
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                vk::PipelineStageFlags2::TRANSFER,
+                vk::AccessFlags2::TRANSFER_READ,
+                vk::ImageUsageFlags::TRANSFER_SRC,
+                false,
+            ),
+            ImageUsage::TransferDst => (
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::PipelineStageFlags2::TRANSFER,
+                vk::AccessFlags2::TRANSFER_WRITE,
+                vk::ImageUsageFlags::TRANSFER_DST,
+                true,
+            ),
+        };
+        Access {
+            layout,
+            stage,
+            access,
+            usage,
+            // Storage Images are read and written
+            read: !write || self == ImageUsage::Storage,
+            write,
+        }
+    }
+}
+
+impl BufferUsage {
+    fn access(self) -> Access {
+        let (stage, access, write) = match self {
+            BufferUsage::Vertex => (
+                vk::PipelineStageFlags2::VERTEX_ATTRIBUTE_INPUT,
+                vk::AccessFlags2::VERTEX_ATTRIBUTE_READ,
+                false,
+            ),
+            BufferUsage::Index => (
+                vk::PipelineStageFlags2::INDEX_INPUT,
+                vk::AccessFlags2::INDEX_READ,
+                false,
+            ),
+            BufferUsage::Indirect => (
+                vk::PipelineStageFlags2::DRAW_INDIRECT,
+                vk::AccessFlags2::INDIRECT_COMMAND_READ,
+                false,
+            ),
+            BufferUsage::Uniform => (
+                vk::PipelineStageFlags2::VERTEX_SHADER
+                    | vk::PipelineStageFlags2::FRAGMENT_SHADER
+                    | vk::PipelineStageFlags2::COMPUTE_SHADER,
+                vk::AccessFlags2::UNIFORM_READ,
+                false,
+            ),
+            BufferUsage::Storage => (
+                vk::PipelineStageFlags2::VERTEX_SHADER
+                    | vk::PipelineStageFlags2::FRAGMENT_SHADER
+                    | vk::PipelineStageFlags2::COMPUTE_SHADER,
+                vk::AccessFlags2::SHADER_STORAGE_READ | vk::AccessFlags2::SHADER_STORAGE_WRITE,
+                true,
+            ),
+            BufferUsage::TransferSrc => (
+                vk::PipelineStageFlags2::TRANSFER,
+                vk::AccessFlags2::TRANSFER_READ,
+                false,
+            ),
+            BufferUsage::TransferDst => (
+                vk::PipelineStageFlags2::TRANSFER,
+                vk::AccessFlags2::TRANSFER_WRITE,
+                true,
+            ),
+        };
+        Access {
+            layout: vk::ImageLayout::UNDEFINED,
+            stage,
+            access,
+            usage: vk::ImageUsageFlags::empty(),
+            read: !write || self == BufferUsage::Storage,
+            write,
+        }
+    }
+}
+
+fn color_attachment_access(load: AttachmentLoad) -> Access {
+    Access {
+        layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        stage: vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+        access: vk::AccessFlags2::COLOR_ATTACHMENT_READ | vk::AccessFlags2::COLOR_ATTACHMENT_WRITE,
+        usage: vk::ImageUsageFlags::COLOR_ATTACHMENT,
+        read: matches!(load, AttachmentLoad::Load),
+        write: true,
+    }
+}
+
+fn depth_attachment_access(load: AttachmentLoad) -> Access {
+    Access {
+        layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+        stage: vk::PipelineStageFlags2::EARLY_FRAGMENT_TESTS
+            | vk::PipelineStageFlags2::LATE_FRAGMENT_TESTS,
+        access: vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_READ
+            | vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
+        usage: vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+        read: matches!(load, AttachmentLoad::Load),
+        write: true,
+    }
+}
+
+/// Gives the Pass Callbacks access to the Vulkan Objects behind the Handles, Transient Images only exist once the Graph executes
+pub struct PassContext<'g> {
+    images: &'g [(vk::Image, vk::ImageView)],
+    buffers: &'g [vk::Buffer],
+}
+
+impl PassContext<'_> {
+    pub fn image(&self, handle: ImageHandle) -> vk::Image {
+        self.images[handle.0].0
+    }
+
+    pub fn image_view(&self, handle: ImageHandle) -> vk::ImageView {
+        self.images[handle.0].1
+    }
+
+    pub fn buffer(&self, handle: BufferHandle) -> vk::Buffer {
+        self.buffers[handle.0]
+    }
+}
+
+type PassCallback<'a> = Box<dyn FnOnce(&PassContext, vk::CommandBuffer) + 'a>;
+
+/// Declares an Pass, Passes with Attachments are recorded inside `vkCmdBeginRendering` covering the whole Attachment
+pub struct GraphPass<'a> {
+    name: String,
+    color_attachments: Vec<(ImageHandle, AttachmentLoad)>,
+    depth_attachment: Option<(ImageHandle, AttachmentLoad)>,
+    images: Vec<(ImageHandle, ImageUsage)>,
+    buffers: Vec<(BufferHandle, BufferUsage)>,
+    side_effect: bool,
+    callback: Option<PassCallback<'a>>,
+}
+
+impl<'a> GraphPass<'a> {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            color_attachments: Vec::new(),
+            depth_attachment: None,
+            images: Vec::new(),
+            buffers: Vec::new(),
+            side_effect: false,
+            callback: None,
+        }
+    }
+
+    /// Attachments are bound in the Order they are added
+    pub fn color_attachment(mut self, image: ImageHandle, load: AttachmentLoad) -> Self {
+        self.color_attachments.push((image, load));
+        self
+    }
+
+    pub fn depth_attachment(mut self, image: ImageHandle, load: AttachmentLoad) -> Self {
+        self.depth_attachment = Some((image, load));
+        self
+    }
+
+    pub fn image(mut self, image: ImageHandle, usage: ImageUsage) -> Self {
+        self.images.push((image, usage));
+        self
+    }
+
+    pub fn buffer(mut self, buffer: BufferHandle, usage: BufferUsage) -> Self {
+        self.buffers.push((buffer, usage));
+        self
+    }
+
+    /// The Pass is never culled, e.g. when it writes Data the Graph does not know about
+    pub fn side_effect(mut self) -> Self {
+        self.side_effect = true;
+        self
+    }
+
+    pub fn execute(mut self, callback: impl FnOnce(&PassContext, vk::CommandBuffer) + 'a) -> Self {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Every Image the Pass touches with how it uses it, Attachments first
+    fn image_accesses(&self) -> impl Iterator<Item = (ImageHandle, Access)> + '_ {
+        self.color_attachments
+            .iter()
+            .map(|&(image, load)| (image, color_attachment_access(load)))
+            .chain(
+                self.depth_attachment
+                    .map(|(image, load)| (image, depth_attachment_access(load))),
+            )
+            .chain(
+                self.images
+                    .iter()
+                    .map(|&(image, usage)| (image, usage.access())),
+            )
+    }
+
+    fn has_attachments(&self) -> bool {
+        !self.color_attachments.is_empty() || self.depth_attachment.is_some()
+    }
+}
+
+enum ImageSource {
+    Imported {
+        image: vk::Image,
+        image_view: vk::ImageView,
+        final_layout: Option<vk::ImageLayout>,
+    },
+    Transient(TransientImageInfo),
+}
+
+struct GraphImage {
+    source: ImageSource,
+    format: vk::Format,
+    extent: vk::Extent2D,
+    state: State,
+}
+
+struct GraphBuffer {
+    buffer: vk::Buffer,
+    output: bool,
+    state: State,
+}
+
+/// Tracks the last Write and the Reads since then, So only real Hazards get an Barrier
+struct State {
+    layout: vk::ImageLayout,
+    write_stage: vk::PipelineStageFlags2,
+    write_access: vk::AccessFlags2,
+    /// Stages which already waited for the last Write
+    read_stages: vk::PipelineStageFlags2,
+}
+
+impl State {
+    fn new(state: ResourceState) -> Self {
+        Self {
+            layout: state.layout,
+            write_stage: state.stage,
+            write_access: state.access,
+            read_stages: vk::PipelineStageFlags2::NONE,
+        }
+    }
+
+    /// Returns the Source Stage, Source Access and old Layout if `access` needs an Barrier
+    fn transition(
+        &mut self,
+        access: &Access,
+        has_layout: bool,
+    ) -> Option<(vk::PipelineStageFlags2, vk::AccessFlags2, vk::ImageLayout)> {
+        let old_layout = self.layout;
+        // Reads already waited for the last Write, So Writes after them only need an Execution Dependency
+        let src_access = if self.read_stages.is_empty() {
+            self.write_access
+        } else {
+            vk::AccessFlags2::NONE
+        };
+        let barrier = (self.write_stage | self.read_stages, src_access, old_layout);
+        if has_layout && old_layout != access.layout {
+            // The Transition itself is an Write, Later Reads have to wait for it. Its Memory is made available by the Barrier,
+            // And an Read which caused it already waits in its own Stages
+            self.layout = access.layout;
+            self.write_stage = access.stage;
+            (self.write_access, self.read_stages) = if access.write {
+                (access.access, vk::PipelineStageFlags2::NONE)
+            } else {
+                (vk::AccessFlags2::NONE, access.stage)
+            };
+            return Some(barrier);
+        }
+        if access.write {
+            let hazard = !self.write_stage.is_empty() || !self.read_stages.is_empty();
+            self.write_stage = access.stage;
+            self.write_access = access.access;
+            self.read_stages = vk::PipelineStageFlags2::NONE;
+            return hazard.then_some(barrier);
+        }
+        if self.read_stages.contains(access.stage) {
+            return None;
+        }
+        self.read_stages |= access.stage;
+        (!self.write_stage.is_empty()).then_some((self.write_stage, self.write_access, old_layout))
+    }
+}
+
+#[derive(Default)]
+pub struct RenderGraph<'a> {
+    images: Vec<GraphImage>,
+    buffers: Vec<GraphBuffer>,
+    passes: Vec<GraphPass<'a>>,
+}
+
+impl<'a> RenderGraph<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn import_image(&mut self, image: ImportedImage) -> ImageHandle {
+        self.images.push(GraphImage {
+            source: ImageSource::Imported {
+                image: image.image,
+                image_view: image.image_view,
+                final_layout: image.final_layout,
+            },
+            format: image.format,
+            extent: image.extent,
+            state: State::new(image.initial_state),
+        });
+        ImageHandle(self.images.len() - 1)
+    }
+
+    pub fn create_image(&mut self, info: TransientImageInfo) -> ImageHandle {
+        self.images.push(GraphImage {
+            source: ImageSource::Transient(info),
+            format: info.format,
+            extent: info.extent,
+            // Aliased Memory was used by other Images before and earlier Frames may still use it
+            state: State::new(ResourceState::UNDEFINED),
+        });
+        ImageHandle(self.images.len() - 1)
+    }
+
+    pub fn import_buffer(&mut self, buffer: ImportedBuffer) -> BufferHandle {
+        self.buffers.push(GraphBuffer {
+            buffer: buffer.buffer,
+            output: buffer.output,
+            state: State::new(buffer.initial_state),
+        });
+        BufferHandle(self.buffers.len() - 1)
+    }
+
+    pub fn add_pass(&mut self, pass: GraphPass<'a>) {
+        self.passes.push(pass);
+    }
+
+    /// Walks the Passes backwards from the Outputs, A Pass is needed when it has Side Effects or writes something an needed Pass reads
+    fn cull(&self) -> Vec<bool> {
+        let mut needed_images: HashSet<usize> = self
+            .images
+            .iter()
+            .enumerate()
+            .filter(|(_, image)| {
+                matches!(
+                    image.source,
+                    ImageSource::Imported {
+                        final_layout: Some(_),
+                        ..
+                    }
+                )
+            })
+            .map(|(index, _)| index)
+            .collect();
+        let mut needed_buffers: HashSet<usize> = self
+            .buffers
+            .iter()
+            .enumerate()
+            .filter(|(_, buffer)| buffer.output)
+            .map(|(index, _)| index)
+            .collect();
+
+        let mut alive = vec![false; self.passes.len()];
+        for (index, pass) in self.passes.iter().enumerate().rev() {
+            let writes_needed = pass
+                .image_accesses()
+                .any(|(image, access)| access.write && needed_images.contains(&image.0))
+                || pass.buffers.iter().any(|(buffer, usage)| {
+                    usage.access().write && needed_buffers.contains(&buffer.0)
+                });
+            if !pass.side_effect && !writes_needed {
+                continue;
+            }
+            alive[index] = true;
+            // Overwritten Content is not needed from earlier Passes, Read Content is
+            for (image, access) in pass.image_accesses() {
+                if access.read {
+                    needed_images.insert(image.0);
+                } else if access.write {
+                    needed_images.remove(&image.0);
+                }
+            }
+            for &(buffer, usage) in pass.buffers.iter() {
+                let access = usage.access();
+                if access.read {
+                    needed_buffers.insert(buffer.0);
+                } else if access.write {
+                    needed_buffers.remove(&buffer.0);
+                }
+            }
+        }
+        alive
+    }
+
+    /// Records every needed Pass into `command_buffer`
+    pub fn execute(
+        mut self,
+        instance: &VulkanInstance,
+        resources: &mut RenderGraphResources,
+        command_buffer: vk::CommandBuffer,
+    ) {
+        let device = &instance.device;
+        let alive = self.cull();
+        let passes: Vec<GraphPass> = std::mem::take(&mut self.passes)
+            .into_iter()
+            .zip(alive)
+            .filter_map(|(pass, alive)| alive.then_some(pass))
+            .collect();
+
+        let physical_images = self.physical_images(instance, resources, &passes);
+        // Attachments are only stored when an later Pass reads them or they live on after the Graph
+        let mut last_read = vec![None; self.images.len()];
+        for (index, pass) in passes.iter().enumerate() {
+            for (image, access) in pass.image_accesses() {
+                if access.read {
+                    last_read[image.0] = Some(index);
+                }
+            }
+        }
+        let physical_buffers: Vec<vk::Buffer> =
+            self.buffers.iter().map(|buffer| buffer.buffer).collect();
+
+        for (index, mut pass) in passes.into_iter().enumerate() {
+            let mut image_barriers = Vec::new();
+            for (handle, access) in pass.image_accesses() {
+                let image = &mut self.images[handle.0];
+                if let Some((src_stage, src_access, old_layout)) =
+                    image.state.transition(&access, true)
+                {
+                    image_barriers.push(
+                        vk::ImageMemoryBarrier2::default()
+                            .src_stage_mask(src_stage)
+                            .src_access_mask(src_access)
+                            .dst_stage_mask(access.stage)
+                            .dst_access_mask(access.access)
+                            .old_layout(old_layout)
+                            .new_layout(access.layout)
+                            .image(physical_images[handle.0].0)
+                            .subresource_range(full_range(image.format)),
+                    );
+                }
+            }
+            let mut buffer_barriers = Vec::new();
+            for &(handle, usage) in pass.buffers.iter() {
+                let access = usage.access();
+                let buffer = &mut self.buffers[handle.0];
+                if let Some((src_stage, src_access, _)) = buffer.state.transition(&access, false) {
+                    buffer_barriers.push(
+                        vk::BufferMemoryBarrier2::default()
+                            .src_stage_mask(src_stage)
+                            .src_access_mask(src_access)
+                            .dst_stage_mask(access.stage)
+                            .dst_access_mask(access.access)
+                            .buffer(buffer.buffer)
+                            .size(vk::WHOLE_SIZE),
+                    );
+                }
+            }
+            if !image_barriers.is_empty() || !buffer_barriers.is_empty() {
+                let dependency_info = vk::DependencyInfo::default()
+                    .image_memory_barriers(&image_barriers)
+                    .buffer_memory_barriers(&buffer_barriers);
+                unsafe { device.cmd_pipeline_barrier2(command_buffer, &dependency_info) };
+            }
+
+            let context = PassContext {
+                images: &physical_images,
+                buffers: &physical_buffers,
+            };
+            let rendering = pass.has_attachments();
+            if rendering {
+                let store = |image: ImageHandle| {
+                    matches!(self.images[image.0].source, ImageSource::Imported { .. })
+                        || last_read[image.0].is_some_and(|last| last > index)
+                };
+                self.begin_rendering(device, &pass, store, &physical_images, command_buffer);
+            }
+            if let Some(callback) = pass.callback.take() {
+                callback(&context, command_buffer);
+            }
+            if rendering {
+                unsafe { device.cmd_end_rendering(command_buffer) };
+            }
+            log::trace!("Render Graph: Recorded Pass {}", pass.name);
+        }
+
+        // Leave the Outputs in their final Layout
+        let final_barriers: Vec<_> = self
+            .images
+            .iter()
+            .zip(physical_images.iter())
+            .filter_map(|(image, &(physical, _))| match image.source {
+                ImageSource::Imported {
+                    final_layout: Some(final_layout),
+                    ..
+                } if final_layout != image.state.layout => Some(
+                    vk::ImageMemoryBarrier2::default()
+                        .src_stage_mask(image.state.write_stage | image.state.read_stages)
+                        .src_access_mask(image.state.write_access)
+                        // Whatever comes next (Present or an Copy) waits for the Transition
+                        .dst_stage_mask(vk::PipelineStageFlags2::ALL_COMMANDS)
+                        .dst_access_mask(vk::AccessFlags2::NONE)
+                        .old_layout(image.state.layout)
+                        .new_layout(final_layout)
+                        .image(physical)
+                        .subresource_range(full_range(image.format)),
+                ),
+                _ => None,
+            })
+            .collect();
+        if !final_barriers.is_empty() {
+            let dependency_info =
+                vk::DependencyInfo::default().image_memory_barriers(&final_barriers);
+            unsafe { device.cmd_pipeline_barrier2(command_buffer, &dependency_info) };
+        }
+    }
+
+    /// Image and View of every Handle, Transient Images are taken from `resources`
+    fn physical_images(
+        &self,
+        instance: &VulkanInstance,
+        resources: &mut RenderGraphResources,
+        passes: &[GraphPass],
+    ) -> Vec<(vk::Image, vk::ImageView)> {
+        // First and last Pass using each Transient Image and every Usage, Unused Transients get no Memory
+        let mut transients = Vec::new();
+        for (handle, image) in self.images.iter().enumerate() {
+            let ImageSource::Transient(info) = image.source else {
+                continue;
+            };
+            let mut lifetime: Option<(usize, usize)> = None;
+            let mut usage = vk::ImageUsageFlags::empty();
+            for (index, pass) in passes.iter().enumerate() {
+                for (image, access) in pass.image_accesses() {
+                    if image.0 == handle {
+                        usage |= access.usage;
+                        lifetime =
+                            Some(lifetime.map_or((index, index), |(first, _)| (first, index)));
+                    }
+                }
+            }
+            if let Some((first, last)) = lifetime {
+                transients.push((
+                    handle,
+                    resources::TransientKey {
+                        info,
+                        usage,
+                        first,
+                        last,
+                    },
+                ));
+            }
+        }
+        let keys: Vec<_> = transients.iter().map(|(_, key)| *key).collect();
+        let transient_images = resources.transient_images(instance, &keys);
+
+        let mut physical = vec![(vk::Image::null(), vk::ImageView::null()); self.images.len()];
+        for (image, physical) in self.images.iter().zip(physical.iter_mut()) {
+            if let ImageSource::Imported {
+                image, image_view, ..
+            } = image.source
+            {
+                *physical = (image, image_view);
+            }
+        }
+        for ((handle, _), &transient) in transients.iter().zip(transient_images.iter()) {
+            physical[*handle] = transient;
+        }
+        physical
+    }
+
+    fn begin_rendering(
+        &self,
+        device: &ash::Device,
+        pass: &GraphPass,
+        store: impl Fn(ImageHandle) -> bool,
+        physical_images: &[(vk::Image, vk::ImageView)],
+        command_buffer: vk::CommandBuffer,
+    ) {
+        let attachment = |(image, load): (ImageHandle, AttachmentLoad), layout| {
+            let (load_op, clear_value) = match load {
+                AttachmentLoad::Load => (vk::AttachmentLoadOp::LOAD, vk::ClearValue::default()),
+                AttachmentLoad::Clear(value) => (vk::AttachmentLoadOp::CLEAR, value),
+                AttachmentLoad::DontCare => {
+                    (vk::AttachmentLoadOp::DONT_CARE, vk::ClearValue::default())
+                }
+            };
+            vk::RenderingAttachmentInfo::default()
+                .image_view(physical_images[image.0].1)
+                .image_layout(layout)
+                .load_op(load_op)
+                .store_op(if store(image) {
+                    vk::AttachmentStoreOp::STORE
+                } else {
+                    vk::AttachmentStoreOp::DONT_CARE
+                })
+                .clear_value(clear_value)
+        };
+        let color_attachments: Vec<_> = pass
+            .color_attachments
+            .iter()
+            .map(|&color| attachment(color, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL))
+            .collect();
+        let depth_attachment = pass
+            .depth_attachment
+            .map(|depth| attachment(depth, vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL));
+
+        let first = pass
+            .color_attachments
+            .first()
+            .or(pass.depth_attachment.as_ref())
+            .map(|(image, _)| *image)
+            .unwrap();
+        let extent = self.images[first.0].extent;
+        let mut info = vk::RenderingInfo::default()
+            .render_area(vk::Rect2D::default().extent(extent))
+            .layer_count(1)
+            .color_attachments(&color_attachments);
+        if let Some(depth_attachment) = &depth_attachment {
+            info = info.depth_attachment(depth_attachment);
+        }
+        unsafe { device.cmd_begin_rendering(command_buffer, &info) };
+    }
+}
+
+fn full_range(format: vk::Format) -> vk::ImageSubresourceRange {
+    vk::ImageSubresourceRange::default()
+        .aspect_mask(aspect_mask(format))
+        .level_count(vk::REMAINING_MIP_LEVELS)
+        .layer_count(vk::REMAINING_ARRAY_LAYERS)
+}
+
+fn aspect_mask(format: vk::Format) -> vk::ImageAspectFlags {
+    match format {
+        vk::Format::D16_UNORM | vk::Format::D32_SFLOAT | vk::Format::X8_D24_UNORM_PACK32 => {
+            vk::ImageAspectFlags::DEPTH
+        }
+        vk::Format::D16_UNORM_S8_UINT
+        | vk::Format::D24_UNORM_S8_UINT
+        | vk::Format::D32_SFLOAT_S8_UINT => {
+            vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+        }
+        vk::Format::S8_UINT => vk::ImageAspectFlags::STENCIL,
+        _ => vk::ImageAspectFlags::COLOR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXTENT: vk::Extent2D = vk::Extent2D {
+        width: 64,
+        height: 64,
+    };
+
+    fn swapchain_image(graph: &mut RenderGraph) -> ImageHandle {
+        graph.import_image(ImportedImage {
+            image: vk::Image::null(),
+            image_view: vk::ImageView::null(),
+            format: vk::Format::B8G8R8A8_SRGB,
+            extent: EXTENT,
+            initial_state: ResourceState::ACQUIRED,
+            final_layout: Some(vk::ImageLayout::PRESENT_SRC_KHR),
+        })
+    }
+
+    fn buffer(graph: &mut RenderGraph, output: bool) -> BufferHandle {
+        graph.import_buffer(ImportedBuffer {
+            buffer: vk::Buffer::null(),
+            initial_state: ResourceState::UNDEFINED,
+            output,
+        })
+    }
+
+    /// An Resource nothing has touched yet
+    fn unused_state() -> State {
+        State::new(ResourceState {
+            layout: vk::ImageLayout::UNDEFINED,
+            stage: vk::PipelineStageFlags2::NONE,
+            access: vk::AccessFlags2::NONE,
+        })
+    }
+
+    #[test]
+    fn cull_passes_without_readers() {
+        let mut graph = RenderGraph::new();
+        let swapchain = swapchain_image(&mut graph);
+        let shadow = graph.create_image(TransientImageInfo {
+            format: vk::Format::D32_SFLOAT,
+            extent: EXTENT,
+        });
+        let unused = graph.create_image(TransientImageInfo {
+            format: vk::Format::R16G16B16A16_SFLOAT,
+            extent: EXTENT,
+        });
+        let clear = AttachmentLoad::Clear(vk::ClearValue::default());
+
+        // The Main Pass clears the Swapchain Image again
+        graph.add_pass(GraphPass::new("Overwritten").color_attachment(swapchain, clear));
+        graph.add_pass(GraphPass::new("Shadow").depth_attachment(shadow, clear));
+        graph.add_pass(GraphPass::new("Unused").color_attachment(unused, clear));
+        graph.add_pass(
+            GraphPass::new("Main")
+                .color_attachment(swapchain, clear)
+                .image(shadow, ImageUsage::DepthRead),
+        );
+        graph.add_pass(GraphPass::new("Side Effect").side_effect());
+        assert_eq!(graph.cull(), [false, true, false, true, true]);
+    }
+
+    #[test]
+    fn cull_buffers() {
+        let mut graph = RenderGraph::new();
+        let readback = buffer(&mut graph, true);
+        let scratch = buffer(&mut graph, false);
+        let unused = buffer(&mut graph, false);
+
+        graph.add_pass(GraphPass::new("Scratch").buffer(scratch, BufferUsage::TransferDst));
+        graph.add_pass(GraphPass::new("Unused").buffer(unused, BufferUsage::TransferDst));
+        graph.add_pass(
+            GraphPass::new("Readback")
+                .buffer(scratch, BufferUsage::TransferSrc)
+                .buffer(readback, BufferUsage::TransferDst),
+        );
+        assert_eq!(graph.cull(), [true, false, true]);
+    }
+
+    #[test]
+    fn read_after_write() {
+        let mut state = State::new(ResourceState::UNDEFINED);
+        let write = color_attachment_access(AttachmentLoad::DontCare);
+        // Earlier Frames may still use the Memory
+        assert_eq!(
+            state.transition(&write, true),
+            Some((
+                vk::PipelineStageFlags2::ALL_COMMANDS,
+                vk::AccessFlags2::MEMORY_WRITE,
+                vk::ImageLayout::UNDEFINED
+            ))
+        );
+
+        let read = ImageUsage::Sampled.access();
+        assert_eq!(read.layout, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+        assert_eq!(
+            read.stage,
+            vk::PipelineStageFlags2::FRAGMENT_SHADER | vk::PipelineStageFlags2::COMPUTE_SHADER
+        );
+        assert_eq!(read.access, vk::AccessFlags2::SHADER_SAMPLED_READ);
+        assert_eq!(
+            state.transition(&read, true),
+            Some((
+                vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+                vk::AccessFlags2::COLOR_ATTACHMENT_READ | vk::AccessFlags2::COLOR_ATTACHMENT_WRITE,
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
+            ))
+        );
+        // Already in the right Layout and synchronized
+        assert_eq!(state.transition(&read, true), None);
+    }
+
+    #[test]
+    fn buffer_read_after_write() {
+        let mut state = unused_state();
+        // Nothing to wait for
+        assert_eq!(
+            state.transition(&BufferUsage::TransferDst.access(), false),
+            None
+        );
+        let vertex = BufferUsage::Vertex.access();
+        assert_eq!(
+            state.transition(&vertex, false),
+            Some((
+                vk::PipelineStageFlags2::TRANSFER,
+                vk::AccessFlags2::TRANSFER_WRITE,
+                vk::ImageLayout::UNDEFINED
+            ))
+        );
+        assert_eq!(state.transition(&vertex, false), None);
+        // Another Stage has to wait for the Write too
+        assert_eq!(
+            state.transition(&BufferUsage::Index.access(), false),
+            Some((
+                vk::PipelineStageFlags2::TRANSFER,
+                vk::AccessFlags2::TRANSFER_WRITE,
+                vk::ImageLayout::UNDEFINED
+            ))
+        );
+    }
+
+    #[test]
+    fn write_after_read_is_execution_only() {
+        let mut state = unused_state();
+        let write = BufferUsage::TransferDst.access();
+        state.transition(&write, false);
+        state.transition(&BufferUsage::Vertex.access(), false);
+        state.transition(&BufferUsage::Index.access(), false);
+        let (src_stage, src_access, _) = state.transition(&write, false).unwrap();
+        assert!(src_stage.contains(
+            vk::PipelineStageFlags2::VERTEX_ATTRIBUTE_INPUT | vk::PipelineStageFlags2::INDEX_INPUT
+        ));
+        assert_eq!(src_access, vk::AccessFlags2::NONE);
+
+        // Same for Images, Where the Write also transitions the Layout back
+        let mut state = unused_state();
+        state.transition(&color_attachment_access(AttachmentLoad::DontCare), true);
+        state.transition(&ImageUsage::Sampled.access(), true);
+        assert_eq!(
+            state.transition(&color_attachment_access(AttachmentLoad::Load), true),
+            Some((
+                vk::PipelineStageFlags2::FRAGMENT_SHADER | vk::PipelineStageFlags2::COMPUTE_SHADER,
+                vk::AccessFlags2::NONE,
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL
+            ))
+        );
+    }
+
+    #[test]
+    fn write_after_write() {
+        let mut state = unused_state();
+        let write = BufferUsage::TransferDst.access();
+        state.transition(&write, false);
+        // The second Write has to wait for the Memory of the first one
+        assert_eq!(
+            state.transition(&write, false),
+            Some((
+                vk::PipelineStageFlags2::TRANSFER,
+                vk::AccessFlags2::TRANSFER_WRITE,
+                vk::ImageLayout::UNDEFINED
+            ))
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+
+use ash::vk;
+
+use crate::{
+    allocator::{Allocation, ResourceKind},
+    instance::VulkanInstance,
+};
+
+use super::{aspect_mask, TransientImageInfo};
+
+/// An Transient Image with the Passes (Indices into the culled Passes) it is used between
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) struct TransientKey {
+    pub info: TransientImageInfo,
+    pub usage: vk::ImageUsageFlags,
+    pub first: usize,
+    pub last: usize,
+}
+
+/// Transient Images of one Graph Layout, Images whose Lifetimes do not overlap share Memory
+struct TransientSet {
+    images: Vec<(vk::Image, vk::ImageView)>,
+    memory: Vec<Allocation>,
+}
+
+/// Transient Images the Render Graph keeps between Frames. As long as the Graph stays the same every Frame reuses them
+#[derive(Default)]
+pub struct RenderGraphResources {
+    transients: HashMap<Vec<TransientKey>, TransientSet>,
+}
+
+impl RenderGraphResources {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Image and View for every Key, In the same Order
+    pub(super) fn transient_images(
+        &mut self,
+        instance: &VulkanInstance,
+        keys: &[TransientKey],
+    ) -> Vec<(vk::Image, vk::ImageView)> {
+        if keys.is_empty() {
+            return Vec::new();
+        }
+        if let Some(set) = self.transients.get(keys) {
+            return set.images.clone();
+        }
+        let set = Self::create_transients(instance, keys);
+        let images = set.images.clone();
+        self.transients.insert(keys.to_vec(), set);
+        images
+    }
+
+    fn create_transients(instance: &VulkanInstance, keys: &[TransientKey]) -> TransientSet {
+        let device = &instance.device;
+        let images: Vec<vk::Image> = keys
+            .iter()
+            .map(|key| {
+                let create_info = vk::ImageCreateInfo::default()
+                    .image_type(vk::ImageType::TYPE_2D)
+                    .format(key.info.format)
+                    .extent(key.info.extent.into())
+                    .mip_levels(1)
+                    .array_layers(1)
+                    .samples(vk::SampleCountFlags::TYPE_1)
+                    .tiling(vk::ImageTiling::OPTIMAL)
+                    .usage(key.usage)
+                    .sharing_mode(vk::SharingMode::EXCLUSIVE);
+                unsafe { device.create_image(&create_info, None) }.unwrap()
+            })
+            .collect();
+
+        // Greedy Interval Assignment, An Slot is reused once the last Image in it is no longer used
+        let mut order: Vec<usize> = (0..keys.len()).collect();
+        order.sort_by_key(|&index| keys[index].first);
+        let mut slots: Vec<(vk::MemoryRequirements, usize)> = Vec::new();
+        let mut image_slots = vec![0; keys.len()];
+        for index in order {
+            let requirements = unsafe { device.get_image_memory_requirements(images[index]) };
+            let key = &keys[index];
+            let free_slot = slots.iter().position(|(slot, last)| {
+                *last < key.first && slot.memory_type_bits & requirements.memory_type_bits != 0
+            });
+            image_slots[index] = match free_slot {
+                Some(slot_index) => {
+                    let (slot, last) = &mut slots[slot_index];
+                    slot.size = slot.size.max(requirements.size);
+                    slot.alignment = slot.alignment.max(requirements.alignment);
+                    slot.memory_type_bits &= requirements.memory_type_bits;
+                    *last = key.last;
+                    slot_index
+                }
+                None => {
+                    slots.push((requirements, key.last));
+                    slots.len() - 1
+                }
+            };
+        }
+        log::debug!(
+            "Render Graph: {} Transient Images share {} Memory Slots",
+            keys.len(),
+            slots.len()
+        );
+
+        let memory: Vec<Allocation> = slots
+            .iter()
+            .map(|(requirements, _)| {
+                instance
+                    .memory_allocator
+                    .allocate(
+                        device,
+                        *requirements,
+                        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+                        ResourceKind::Optimal,
+                        None,
+                    )
+                    .expect("Failed to allocate Transient Image memory")
+            })
+            .collect();
+
+        let images = images
+            .iter()
+            .zip(keys.iter())
+            .zip(image_slots)
+            .map(|((&image, key), slot)| {
+                let allocation = &memory[slot];
+                unsafe {
+                    device
+                        .bind_image_memory(image, allocation.memory(), allocation.offset())
+                        .expect("Failed to bind Transient Image memory");
+                }
+                let view_info = vk::ImageViewCreateInfo::default()
+                    .image(image)
+                    .view_type(vk::ImageViewType::TYPE_2D)
+                    .format(key.info.format)
+                    .subresource_range(
+                        vk::ImageSubresourceRange::default()
+                            .aspect_mask(aspect_mask(key.info.format))
+                            .level_count(1)
+                            .layer_count(1),
+                    );
+                let view = unsafe { device.create_image_view(&view_info, None) }.unwrap();
+                (image, view)
+            })
+            .collect();
+        TransientSet { images, memory }
+    }
+
+    /// Destroys the Transient Images once the current Frame finished, Call after Resizing so Images with the old Extent do not pile up
+    pub fn destroy_later(&mut self, instance: &VulkanInstance) {
+        let sets: Vec<TransientSet> = self.transients.drain().map(|(_, set)| set).collect();
+        instance.destroy_later(move |device| {
+            sets.into_iter().for_each(|set| set.destroy(device));
+        });
+    }
+
+    /// Destroys the Transient Images now, The GPU must not use them anymore
+    pub fn destroy(&mut self, device: &ash::Device) {
+        self.transients
+            .drain()
+            .for_each(|(_, set)| set.destroy(device));
+    }
+}
+
+impl TransientSet {
+    fn destroy(mut self, device: &ash::Device) {
+        unsafe {
+            for (image, view) in self.images {
+                device.destroy_image_view(view, None);
+                device.destroy_image(image, None);
+            }
+        }
+        self.memory
+            .iter_mut()
+            .for_each(|allocation| allocation.free(device));
+    }
+}
@@ -0,0 +1,417 @@
+//! Uploads Buffers and Images through Staging Buffers without waiting on the CPU. Every Copy is recorded into one Batch
+//! which is submitted together with the next Frame, The Frame then waits on the GPU for the Timeline Semaphore the Batch signals
+
+use std::{os::raw::c_void, sync::Mutex};
+
+use ash::{prelude::VkResult, vk};
+
+use crate::{buffer::VulkanBuffer, image::VulkanImage, instance::VulkanInstance};
+
+struct Batch {
+    /// Records the Copies, On the Transfer Queue when there is one
+    copy: vk::CommandBuffer,
+    /// Acquires the Ownership on the Graphics Queue and records Work the Transfer Queue can not do, e.g. Mipmap Blits
+    graphics: Option<vk::CommandBuffer>,
+    staging: Vec<VulkanBuffer>,
+    /// Timeline Value which is signaled when the Batch finished
+    value: u64,
+}
+
+#[derive(Default)]
+struct UploadState {
+    recording: Option<Batch>,
+    submitted: Vec<Batch>,
+    /// Value of the last submitted Batch, Frames wait for it
+    last_value: u64,
+}
+
+///
+/// Batches Uploads to the GPU. With an dedicated Transfer Queue Family the Copies run there and the Ownership
+/// is handed to the Graphics Queue Family afterwards, Otherwise the Graphics Queue copies.
+///
+/// Staging Buffers are destroyed once the Timeline Semaphore shows their Batch finished, See `collect`
+///
+pub struct UploadManager {
+    timeline: vk::Semaphore,
+    graphics_queue: vk::Queue,
+    graphics_family: u32,
+    graphics_pool: vk::CommandPool,
+    /// Queue, Family and Command Pool of the dedicated Transfer Queue
+    transfer: Option<(vk::Queue, u32, vk::CommandPool)>,
+    state: Mutex<UploadState>,
+}
+
+impl UploadManager {
+    pub(crate) fn new(
+        device: &ash::Device,
+        graphics_queue: vk::Queue,
+        graphics_family: u32,
+        transfer: Option<(vk::Queue, u32)>,
+    ) -> Self {
+        let mut type_info = vk::SemaphoreTypeCreateInfo::default()
+            .semaphore_type(vk::SemaphoreType::TIMELINE)
+            .initial_value(0);
+        let semaphore_info = vk::SemaphoreCreateInfo::default().push_next(&mut type_info);
+        let timeline = unsafe { device.create_semaphore(&semaphore_info, None) }.unwrap();
+
+        let create_pool = |family| {
+            let create_info = vk::CommandPoolCreateInfo::default()
+                .queue_family_index(family)
+                .flags(vk::CommandPoolCreateFlags::TRANSIENT);
+            unsafe { device.create_command_pool(&create_info, None) }.unwrap()
+        };
+        Self {
+            timeline,
+            graphics_queue,
+            graphics_family,
+            graphics_pool: create_pool(graphics_family),
+            transfer: transfer.map(|(queue, family)| (queue, family, create_pool(family))),
+            state: Mutex::default(),
+        }
+    }
+
+    /// Whether Copies run on an dedicated Transfer Queue
+    pub fn has_transfer_queue(&self) -> bool {
+        self.transfer.is_some()
+    }
+
+    /// Uploads `data` to the Start of `dst`, which needs `TRANSFER_DST` Usage
+    pub fn upload_buffer<T: Copy>(
+        &self,
+        instance: &VulkanInstance,
+        dst: vk::Buffer,
+        data: &[T],
+    ) -> VkResult<()> {
+        let size = size_of_val(data) as vk::DeviceSize;
+        self.upload_buffer_with(instance, dst, size, |staging, memory| unsafe {
+            staging.upload_data(memory, data, size)
+        })
+    }
+
+    /// Like `upload_buffer`, But `fill` writes the `size` Bytes into the mapped Staging Buffer itself
+    pub fn upload_buffer_with(
+        &self,
+        instance: &VulkanInstance,
+        dst: vk::Buffer,
+        size: vk::DeviceSize,
+        fill: impl FnOnce(&VulkanBuffer, *mut c_void),
+    ) -> VkResult<()> {
+        if size == 0 {
+            return Ok(());
+        }
+        let staging = Self::create_staging(instance, size)?;
+        let memory = staging.map(&instance.device, size);
+        fill(&staging, memory);
+        staging.unmap(&instance.device);
+
+        let device = &instance.device;
+        let mut state = self.state.lock().unwrap();
+        let batch = self.batch(device, &mut state);
+        let region = vk::BufferCopy::default().size(size);
+        unsafe { device.cmd_copy_buffer(batch.copy, *staging, dst, &[region]) };
+
+        // The Buffer may be used by any Stage
+        let barrier = vk::BufferMemoryBarrier2::default()
+            .buffer(dst)
+            .size(vk::WHOLE_SIZE)
+            .src_stage_mask(vk::PipelineStageFlags2::TRANSFER)
+            .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags2::ALL_COMMANDS)
+            .dst_access_mask(vk::AccessFlags2::MEMORY_READ)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED);
+        match (&self.transfer, batch.graphics) {
+            (Some((_, transfer_family, _)), Some(graphics)) => {
+                let barrier = barrier
+                    .src_queue_family_index(*transfer_family)
+                    .dst_queue_family_index(self.graphics_family);
+                // Release only makes the Writes available, Acquire waits on nothing but the Semaphore
+                let release = barrier
+                    .dst_stage_mask(vk::PipelineStageFlags2::NONE)
+                    .dst_access_mask(vk::AccessFlags2::NONE);
+                let acquire = barrier
+                    .src_stage_mask(vk::PipelineStageFlags2::NONE)
+                    .src_access_mask(vk::AccessFlags2::NONE);
+                Self::buffer_barrier(device, batch.copy, release);
+                Self::buffer_barrier(device, graphics, acquire);
+            }
+            _ => Self::buffer_barrier(device, batch.copy, barrier),
+        }
+        batch.staging.push(staging);
+        Ok(())
+    }
+
+    /// Copies `data` into `image` and leaves every Level of `range` in `SHADER_READ_ONLY_OPTIMAL`.
+    /// With `mipmaps` only Level 0 is copied and the other Levels are blitted from it, `mipmaps` is the Size of Level 0
+    pub fn upload_image(
+        &self,
+        instance: &VulkanInstance,
+        image: vk::Image,
+        data: &[u8],
+        regions: &[vk::BufferImageCopy2],
+        range: vk::ImageSubresourceRange,
+        mipmaps: Option<vk::Extent2D>,
+    ) -> VkResult<()> {
+        let size = data.len() as vk::DeviceSize;
+        let staging = Self::create_staging(instance, size.max(1))?;
+        if size > 0 {
+            let memory = staging.map(&instance.device, size);
+            unsafe { staging.upload_data(memory, data, size) };
+            staging.unmap(&instance.device);
+        }
+
+        let device = &instance.device;
+        let mut state = self.state.lock().unwrap();
+        let batch = self.batch(device, &mut state);
+
+        let to_transfer = vk::ImageMemoryBarrier2::default()
+            .image(image)
+            .subresource_range(range)
+            .old_layout(vk::ImageLayout::UNDEFINED)
+            .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .src_stage_mask(vk::PipelineStageFlags2::NONE)
+            .dst_stage_mask(vk::PipelineStageFlags2::TRANSFER)
+            .dst_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED);
+        Self::image_barrier(device, batch.copy, to_transfer);
+
+        let copy_info = vk::CopyBufferToImageInfo2::default()
+            .src_buffer(*staging)
+            .dst_image(image)
+            .dst_image_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .regions(regions);
+        unsafe { device.cmd_copy_buffer_to_image2(batch.copy, &copy_info) };
+
+        // Blits need the Graphics Queue, So the Image stays in TRANSFER_DST_OPTIMAL until the Mipmaps are generated
+        let (new_layout, dst_stage, dst_access) = match mipmaps {
+            Some(_) => (
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::PipelineStageFlags2::TRANSFER,
+                vk::AccessFlags2::TRANSFER_READ | vk::AccessFlags2::TRANSFER_WRITE,
+            ),
+            None => (
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                vk::PipelineStageFlags2::FRAGMENT_SHADER,
+                vk::AccessFlags2::SHADER_READ,
+            ),
+        };
+        let barrier = vk::ImageMemoryBarrier2::default()
+            .image(image)
+            .subresource_range(range)
+            .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .new_layout(new_layout)
+            .src_stage_mask(vk::PipelineStageFlags2::TRANSFER)
+            .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+            .dst_stage_mask(dst_stage)
+            .dst_access_mask(dst_access)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED);
+        let graphics = match (&self.transfer, batch.graphics) {
+            (Some((_, transfer_family, _)), Some(graphics)) => {
+                // Both Sides of an Ownership Transfer have to do the same Layout Transition
+                let barrier = barrier
+                    .src_queue_family_index(*transfer_family)
+                    .dst_queue_family_index(self.graphics_family);
+                let release = barrier
+                    .dst_stage_mask(vk::PipelineStageFlags2::NONE)
+                    .dst_access_mask(vk::AccessFlags2::NONE);
+                let acquire = barrier
+                    .src_stage_mask(vk::PipelineStageFlags2::NONE)
+                    .src_access_mask(vk::AccessFlags2::NONE);
+                Self::image_barrier(device, batch.copy, release);
+                Self::image_barrier(device, graphics, acquire);
+                graphics
+            }
+            _ => {
+                Self::image_barrier(device, batch.copy, barrier);
+                batch.copy
+            }
+        };
+        if let Some(size) = mipmaps {
+            VulkanImage::record_mipmaps(
+                device,
+                graphics,
+                image,
+                size.width,
+                size.height,
+                range.level_count,
+                range.layer_count,
+            );
+        }
+        batch.staging.push(staging);
+        Ok(())
+    }
+
+    /// Submits the recorded Batch and returns the Timeline Value the GPU has to wait for before using any Upload
+    pub(crate) fn flush(&self, device: &ash::Device) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        let Some(mut batch) = state.recording.take() else {
+            return state.last_value;
+        };
+        let mut value = state.last_value + 1;
+        unsafe { device.end_command_buffer(batch.copy) }.unwrap();
+        let copy_queue = self
+            .transfer
+            .map_or(self.graphics_queue, |(queue, _, _)| queue);
+        self.submit(device, copy_queue, batch.copy, None, value);
+        if let Some(graphics) = batch.graphics {
+            unsafe { device.end_command_buffer(graphics) }.unwrap();
+            self.submit(
+                device,
+                self.graphics_queue,
+                graphics,
+                Some(value),
+                value + 1,
+            );
+            value += 1;
+        }
+        batch.value = value;
+        state.last_value = value;
+        state.submitted.push(batch);
+        value
+    }
+
+    /// Destroys the Staging Buffers and Command Buffers of every finished Batch
+    pub(crate) fn collect(&self, device: &ash::Device) {
+        let finished = unsafe { device.get_semaphore_counter_value(self.timeline) }.unwrap();
+        let mut state = self.state.lock().unwrap();
+        let (done, pending) = std::mem::take(&mut state.submitted)
+            .into_iter()
+            .partition(|batch| batch.value <= finished);
+        state.submitted = pending;
+        for batch in done {
+            self.free_batch(device, batch);
+        }
+    }
+
+    /// The Semaphore the Frame waits on, Signaled with the Value returned by `flush`
+    pub(crate) fn timeline(&self) -> vk::Semaphore {
+        self.timeline
+    }
+
+    fn batch<'a>(&self, device: &ash::Device, state: &'a mut UploadState) -> &'a mut Batch {
+        state.recording.get_or_insert_with(|| {
+            let copy_pool = self
+                .transfer
+                .map_or(self.graphics_pool, |(_, _, pool)| pool);
+            Batch {
+                copy: Self::begin(device, copy_pool),
+                graphics: self
+                    .transfer
+                    .map(|_| Self::begin(device, self.graphics_pool)),
+                staging: Vec::new(),
+                value: 0,
+            }
+        })
+    }
+
+    fn begin(device: &ash::Device, command_pool: vk::CommandPool) -> vk::CommandBuffer {
+        let allocate_info = vk::CommandBufferAllocateInfo::default()
+            .command_pool(command_pool)
+            .level(vk::CommandBufferLevel::PRIMARY)
+            .command_buffer_count(1);
+        let command_buffer = unsafe { device.allocate_command_buffers(&allocate_info) }.unwrap()[0];
+        let begin_info = vk::CommandBufferBeginInfo::default()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+        unsafe { device.begin_command_buffer(command_buffer, &begin_info) }.unwrap();
+        command_buffer
+    }
+
+    fn submit(
+        &self,
+        device: &ash::Device,
+        queue: vk::Queue,
+        command_buffer: vk::CommandBuffer,
+        wait: Option<u64>,
+        signal: u64,
+    ) {
+        let wait_infos: Vec<_> = wait
+            .map(|value| {
+                vk::SemaphoreSubmitInfo::default()
+                    .semaphore(self.timeline)
+                    .value(value)
+                    .stage_mask(vk::PipelineStageFlags2::ALL_COMMANDS)
+            })
+            .into_iter()
+            .collect();
+        let signal_infos = [vk::SemaphoreSubmitInfo::default()
+            .semaphore(self.timeline)
+            .value(signal)
+            .stage_mask(vk::PipelineStageFlags2::ALL_COMMANDS)];
+        let command_infos = [vk::CommandBufferSubmitInfo::default().command_buffer(command_buffer)];
+        let submit_info = vk::SubmitInfo2::default()
+            .wait_semaphore_infos(&wait_infos)
+            .command_buffer_infos(&command_infos)
+            .signal_semaphore_infos(&signal_infos);
+        unsafe { device.queue_submit2(queue, &[submit_info], vk::Fence::null()) }
+            .expect("Failed to submit Uploads");
+    }
+
+    fn free_batch(&self, device: &ash::Device, batch: Batch) {
+        let copy_pool = self
+            .transfer
+            .map_or(self.graphics_pool, |(_, _, pool)| pool);
+        unsafe {
+            device.free_command_buffers(copy_pool, &[batch.copy]);
+            if let Some(graphics) = batch.graphics {
+                device.free_command_buffers(self.graphics_pool, &[graphics]);
+            }
+        }
+        batch
+            .staging
+            .into_iter()
+            .for_each(|mut staging| staging.destroy(device));
+    }
+
+    fn create_staging(instance: &VulkanInstance, size: vk::DeviceSize) -> VkResult<VulkanBuffer> {
+        VulkanBuffer::new(
+            instance,
+            size,
+            vk::BufferUsageFlags::TRANSFER_SRC,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+            Some("Staging"),
+        )
+    }
+
+    fn buffer_barrier(
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        barrier: vk::BufferMemoryBarrier2,
+    ) {
+        let barriers = [barrier];
+        let dependency_info = vk::DependencyInfo::default().buffer_memory_barriers(&barriers);
+        unsafe { device.cmd_pipeline_barrier2(command_buffer, &dependency_info) };
+    }
+
+    fn image_barrier(
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        barrier: vk::ImageMemoryBarrier2,
+    ) {
+        let barriers = [barrier];
+        let dependency_info = vk::DependencyInfo::default().image_memory_barriers(&barriers);
+        unsafe { device.cmd_pipeline_barrier2(command_buffer, &dependency_info) };
+    }
+
+    /// The Device has to be idle, Batches which were never submitted are dropped
+    pub fn destroy(&mut self, device: &ash::Device) {
+        let state = self.state.get_mut().unwrap();
+        let batches: Vec<_> = state
+            .recording
+            .take()
+            .into_iter()
+            .chain(state.submitted.drain(..))
+            .collect();
+        for batch in batches {
+            self.free_batch(device, batch);
+        }
+        unsafe {
+            device.destroy_semaphore(self.timeline, None);
+            device.destroy_command_pool(self.graphics_pool, None);
+            if let Some((_, _, pool)) = self.transfer {
+                device.destroy_command_pool(pool, None);
+            }
+        }
+    }
+}
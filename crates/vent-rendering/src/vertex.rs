@@ -2,19 +2,41 @@ use std::mem::offset_of;
 
 use ash::vk;
 
+/// Checked against shader.vert by `PipelineReflection::check_vertex_input`
 #[derive(Clone, Copy, PartialEq)]
+#[repr(C)]
 pub struct Vertex3D {
     pub position: [f32; 3],
     pub tex_coord: [f32; 2],
     pub normal: [f32; 3],
+    /// xyz is the Tangent, w the Bitangent sign. Never zero, The `ModelLoader` gives Meshes without Normal Map
+    /// an arbitrary Tangent perpendicular to the Normal
+    pub tangent: [f32; 4],
+    /// Second UV Set, Same as `tex_coord` when the Mesh has only one
+    pub tex_coord1: [f32; 2],
+    /// Indices into the Joint Matrices
+    pub joints: [u16; 4],
+    /// All zero when the Vertex is not skinned
+    pub weights: [f32; 4],
+}
+
+/// Difference of an Vertex to one Morph Target, Matches the std430 Layout in shader.vert
+#[derive(Clone, Copy, PartialEq, Default)]
+#[repr(C)]
+pub struct MorphDelta {
+    pub position: [f32; 4],
+    pub normal: [f32; 4],
+    pub tangent: [f32; 4],
 }
 
 #[derive(Clone, Copy, PartialEq)]
+#[repr(C)]
 pub struct VertexPos3D {
     pub position: [f32; 3],
 }
 
 #[derive(Clone, Copy, PartialEq)]
+#[repr(C)]
 pub struct Vertex2D {
     pub position: [f32; 2],
     pub tex_coord: [f32; 2],
@@ -28,13 +50,13 @@ impl Vertex3D {
             .stride(std::mem::size_of::<Self>() as u32)
             .input_rate(vk::VertexInputRate::VERTEX)
     }
-    pub fn input_descriptions() -> [vk::VertexInputAttributeDescription; 3] {
+    pub fn input_descriptions() -> [vk::VertexInputAttributeDescription; 7] {
         [
             // offset_of macro got stabilized in rust 1.77
             vk::VertexInputAttributeDescription::default()
                 .location(0)
                 .binding(0)
-                .format(vk::Format::R32G32B32A32_SFLOAT)
+                .format(vk::Format::R32G32B32_SFLOAT)
                 .offset(offset_of!(Self, position) as u32),
             vk::VertexInputAttributeDescription::default()
                 .location(1)
@@ -44,8 +66,28 @@ impl Vertex3D {
             vk::VertexInputAttributeDescription::default()
                 .location(2)
                 .binding(0)
-                .format(vk::Format::R32G32B32A32_SFLOAT)
+                .format(vk::Format::R32G32B32_SFLOAT)
                 .offset(offset_of!(Self, normal) as u32),
+            vk::VertexInputAttributeDescription::default()
+                .location(3)
+                .binding(0)
+                .format(vk::Format::R32G32B32A32_SFLOAT)
+                .offset(offset_of!(Self, tangent) as u32),
+            vk::VertexInputAttributeDescription::default()
+                .location(4)
+                .binding(0)
+                .format(vk::Format::R32G32_SFLOAT)
+                .offset(offset_of!(Self, tex_coord1) as u32),
+            vk::VertexInputAttributeDescription::default()
+                .location(5)
+                .binding(0)
+                .format(vk::Format::R16G16B16A16_UINT)
+                .offset(offset_of!(Self, joints) as u32),
+            vk::VertexInputAttributeDescription::default()
+                .location(6)
+                .binding(0)
+                .format(vk::Format::R32G32B32A32_SFLOAT)
+                .offset(offset_of!(Self, weights) as u32),
         ]
     }
 }
@@ -63,7 +105,7 @@ impl VertexPos3D {
             vk::VertexInputAttributeDescription::default()
                 .location(0)
                 .binding(0)
-                .format(vk::Format::R32G32B32A32_SFLOAT)
+                .format(vk::Format::R32G32B32_SFLOAT)
                 .offset(offset_of!(Self, position) as u32),
         ]
     }
@@ -1,66 +1,203 @@
 #version 450 core
 
-layout (binding = 0) uniform sampler2D texture_diffuse;
-
-layout (binding = 1) uniform Material {
+// BINDLESS is defined for the Variant used with Descriptor Indexing, See build.rs
+#ifdef BINDLESS
+#extension GL_EXT_nonuniform_qualifier : require
+#endif
+
+struct TextureInfo {
+    vec4 transform; // column major mat2
+    vec2 offset;
+    uint tex_coord;
+    // Index into the Texture Array of the Bindless Variant
+    uint texture_index;
+};
+
+struct MaterialData {
     vec4 base_color;
+    vec3 emissive_factor;
     int alpha_mode;
     float alpha_cutoff;
-} material;
+    float metallic_factor;
+    float roughness_factor;
+    float normal_scale;
+    float occlusion_strength;
+    // 1 when the Material has an Normal Map, Every Vertex has an Tangent but only these are meaningful
+    uint normal_mapped;
+    // Base Color, Metallic Roughness, Normal, Occlusion, Emissive
+    TextureInfo textures[5];
+};
+
+#ifdef BINDLESS
+// Every Material of every Model, The Index is pushed before the Meshes of an Material are drawn
+layout (set = 0, binding = 0) readonly buffer Materials {
+    MaterialData materials[];
+};
+
+layout (push_constant) uniform MaterialConstants {
+    // The Vertex Shader uses everything before
+    layout (offset = 92) uint material_index;
+} material_constants;
+
+layout (set = 3, binding = 0) uniform sampler2D textures[];
+
+#define material materials[material_constants.material_index]
+#define MATERIAL_TEXTURE(i) textures[material.textures[i].texture_index]
+#define texture_base_color MATERIAL_TEXTURE(0)
+#define texture_metallic_roughness MATERIAL_TEXTURE(1)
+#define texture_normal MATERIAL_TEXTURE(2)
+#define texture_occlusion MATERIAL_TEXTURE(3)
+#define texture_emissive MATERIAL_TEXTURE(4)
+#else
+layout (binding = 0) uniform sampler2D texture_base_color;
+
+layout (binding = 1) uniform Material {
+    MaterialData material;
+};
 
+layout (binding = 2) uniform sampler2D texture_metallic_roughness;
+layout (binding = 3) uniform sampler2D texture_normal;
+layout (binding = 4) uniform sampler2D texture_occlusion;
+layout (binding = 5) uniform sampler2D texture_emissive;
+#endif
 
-// layout (binding = 2) uniform Light {
+layout (set = 2, binding = 0) uniform samplerCube environment_map;
+
+layout (set = 2, binding = 1) uniform Environment {
+    // Spherical Harmonics of the Irradiance, Already convolved with the Cosine Lobe
+    vec4 irradiance[9];
+    float max_lod;
+} environment;
+
+// layout (binding = 6) uniform Light {
 //     vec3 position;
 //     vec3 color;
 // } light;
 
 layout (location = 0) in vec2 tex_coord;
-layout (location = 1) in vec2 normal;
+layout (location = 1) in vec3 normal;
 layout (location = 2) in vec3 world_position;
-layout (location = 3) in vec3 position;
+layout (location = 3) in vec4 position;
 layout (location = 4) in vec3 view_position;
+layout (location = 5) in vec4 tangent;
+layout (location = 6) in vec2 tex_coord1;
 
 
 layout (location = 0) out vec4 fragColor;
 
-// We don't need (or want) much ambient light, so 0.1 is fine
-const float ambient_strength = 0.1;
-
-
+const float PI = 3.14159265359;
+
+// TODO: Use real Lights
+const vec3 light_dir = normalize(vec3(0.3, 1.0, 0.4));
+const vec3 light_color = vec3(1.0);
+
+vec2 uv(int texture_index) {
+    TextureInfo info = material.textures[texture_index];
+    vec2 uv = info.tex_coord == 1 ? tex_coord1 : tex_coord;
+    return mat2(info.transform.xy, info.transform.zw) * uv + info.offset;
+}
+
+float distribution_ggx(float n_dot_h, float roughness) {
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float denom = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    return a2 / (PI * denom * denom);
+}
+
+float geometry_smith(float n_dot_v, float n_dot_l, float roughness) {
+    float k = (roughness + 1.0) * (roughness + 1.0) / 8.0;
+    float ggx_v = n_dot_v / (n_dot_v * (1.0 - k) + k);
+    float ggx_l = n_dot_l / (n_dot_l * (1.0 - k) + k);
+    return ggx_v * ggx_l;
+}
+
+vec3 fresnel_schlick(float cos_theta, vec3 f0) {
+    return f0 + (1.0 - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+// The Skybox flips X, So we do the same to look up the Environment
+vec3 environment_direction(vec3 direction) {
+    return vec3(-direction.x, direction.yz);
+}
+
+vec3 irradiance(vec3 n) {
+    n = environment_direction(n);
+    vec4 sh[9] = environment.irradiance;
+    vec3 result = sh[0].rgb * 0.282095
+        + sh[1].rgb * 0.488603 * n.y
+        + sh[2].rgb * 0.488603 * n.z
+        + sh[3].rgb * 0.488603 * n.x
+        + sh[4].rgb * 1.092548 * n.x * n.y
+        + sh[5].rgb * 1.092548 * n.y * n.z
+        + sh[6].rgb * 0.315392 * (3.0 * n.z * n.z - 1.0)
+        + sh[7].rgb * 1.092548 * n.x * n.z
+        + sh[8].rgb * 0.546274 * (n.x * n.x - n.y * n.y);
+    return max(result, vec3(0.0));
+}
+
+// Analytic Fit of the split Sum BRDF, So we need no Lookup Texture (Karis, "Physically Based Shading on Mobile")
+vec2 environment_brdf(float n_dot_v, float roughness) {
+    const vec4 c0 = vec4(-1.0, -0.0275, -0.572, 0.022);
+    const vec4 c1 = vec4(1.0, 0.0425, 1.04, -0.04);
+    vec4 r = roughness * c0 + c1;
+    float a004 = min(r.x * r.x, exp2(-9.28 * n_dot_v)) * r.x + r.y;
+    return vec2(-1.04, 1.04) * a004 + r.zw;
+}
 
 void main() {
-    vec4 texture = texture(texture_diffuse, tex_coord) * material.base_color;
+    vec4 base_color = texture(texture_base_color, uv(0)) * material.base_color;
 
     if (material.alpha_mode == 2) { // ALPHA MASK
-		if (texture.a < material.alpha_cutoff) {
+		if (base_color.a < material.alpha_cutoff) {
 			discard;
 		}
 	} else if (material.alpha_mode == 3) { // BLEND
         // todo
     }
 
-    // Calculate the ambient color
-    // vec3 ambient_color = light.color * ambient_strength;
-
-    // // Calculate the light direction
-    // vec3 light_dir = normalize(light.position - world_position);
-
-    // // Calculate the view direction
-    // vec3 view_dir = normalize(view_position.xyz - world_position);
-
-    // // Calculate the half vector
-    // vec3 half_dir = normalize(view_dir + light_dir);
-
-    // // Calculate the diffuse color
-    // float diffuse_strength = max(dot(world_position, light_dir), 0.0);
-    // vec3 diffuse_color = light.color * diffuse_strength;
-
-    // // Calculate the specular color
-    // float specular_strength = pow(max(dot(world_position, half_dir), 0.0), 32.0);
-    // vec3 specular_color = specular_strength * light.color;
-
-    // // Calculate the final color
-    // vec3 result = (ambient_color + diffuse_color + specular_color) * texture.xyz;
+    // Green Channel is Roughness, Blue Channel is Metalness
+    vec4 metallic_roughness = texture(texture_metallic_roughness, uv(1));
+    float roughness = clamp(metallic_roughness.g * material.roughness_factor, 0.04, 1.0);
+    float metallic = clamp(metallic_roughness.b * material.metallic_factor, 0.0, 1.0);
+
+    vec3 n = normalize(normal);
+    if (material.normal_mapped != 0) {
+        vec3 t = normalize(tangent.xyz);
+        vec3 b = cross(n, t) * tangent.w;
+        vec3 tangent_normal = texture(texture_normal, uv(2)).xyz * 2.0 - 1.0;
+        tangent_normal.xy *= material.normal_scale;
+        n = normalize(mat3(t, b, n) * tangent_normal);
+    }
+    if (!gl_FrontFacing) {
+        n = -n;
+    }
 
-    fragColor = texture; // vec4(result, 1.0);
-}
\ No newline at end of file
+    float occlusion = 1.0 + material.occlusion_strength * (texture(texture_occlusion, uv(3)).r - 1.0);
+    vec3 emissive = texture(texture_emissive, uv(4)).rgb * material.emissive_factor;
+
+    vec3 v = normalize(view_position - world_position);
+    vec3 l = light_dir;
+    vec3 h = normalize(v + l);
+    float n_dot_v = max(dot(n, v), 0.0001);
+    float n_dot_l = max(dot(n, l), 0.0);
+    float n_dot_h = max(dot(n, h), 0.0);
+
+    vec3 f0 = mix(vec3(0.04), base_color.rgb, metallic);
+    vec3 f = fresnel_schlick(max(dot(h, v), 0.0), f0);
+    float d = distribution_ggx(n_dot_h, roughness);
+    float g = geometry_smith(n_dot_v, n_dot_l, roughness);
+
+    vec3 specular = d * g * f / (4.0 * n_dot_v * max(n_dot_l, 0.0001));
+    vec3 diffuse = (1.0 - f) * (1.0 - metallic) * base_color.rgb / PI;
+
+    // Image Based Lighting, Rougher Surfaces reflect blurrier Levels of the Environment
+    vec3 r = environment_direction(reflect(-v, n));
+    vec3 prefiltered = textureLod(environment_map, r, roughness * environment.max_lod).rgb;
+    vec2 brdf = environment_brdf(n_dot_v, roughness);
+    vec3 specular_ibl = prefiltered * (f0 * brdf.x + brdf.y);
+    vec3 diffuse_ibl = irradiance(n) * (1.0 - metallic) * base_color.rgb / PI;
+    vec3 ambient = (diffuse_ibl + specular_ibl) * occlusion;
+    vec3 color = ambient + (diffuse + specular) * light_color * n_dot_l * PI + emissive;
+
+    fragColor = vec4(color, base_color.a);
+}
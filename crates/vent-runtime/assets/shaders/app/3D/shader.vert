@@ -3,24 +3,81 @@
 layout(location = 0) in vec3 in_position;
 layout(location = 1) in vec2 in_tex_coord;
 layout(location = 2) in vec3 in_normal;
+layout(location = 3) in vec4 in_tangent;
+layout(location = 4) in vec2 in_tex_coord1;
+layout(location = 5) in uvec4 in_joints;
+layout(location = 6) in vec4 in_weights;
 
 layout(push_constant) uniform PushConsts {
     vec3 view_position;
     mat4 proj_view_trans;
+    // Per Mesh, morph_target_count is 0 when the Mesh has no Morph Targets
+    uint morph_first_delta;
+    uint morph_target_count;
+    uint morph_first_weight;
 } camera;
 
+// Joint Matrices of every Skin of the Model, Unskinned Models bind a single Identity Matrix
+layout(set = 1, binding = 0) readonly buffer Joints {
+    mat4 joint_matrices[];
+};
+
+struct MorphDelta {
+    vec4 position;
+    vec4 normal;
+    vec4 tangent;
+};
+
+// Current Weights of every Mesh of the Model
+layout(set = 1, binding = 1) readonly buffer MorphWeights {
+    float morph_weights[];
+};
+
+// The Deltas of all Targets of an Vertex are stored after each other
+layout(set = 1, binding = 2) readonly buffer MorphDeltas {
+    MorphDelta morph_deltas[];
+};
+
 layout(location = 0) out vec2 tex_coord;
 layout(location = 1) out vec3 normal;
 layout(location = 2) out vec3 world_position;
 layout(location = 3) out vec4 position;
 layout(location = 4) out vec3 view_position;
+layout(location = 5) out vec4 tangent;
+layout(location = 6) out vec2 tex_coord1;
 
 void main() {
+    vec3 morphed_position = in_position;
+    vec3 morphed_normal = in_normal;
+    vec3 morphed_tangent = in_tangent.xyz;
+    uint first_delta = camera.morph_first_delta + uint(gl_VertexIndex) * camera.morph_target_count;
+    for (uint i = 0; i < camera.morph_target_count; i++) {
+        float weight = morph_weights[camera.morph_first_weight + i];
+        MorphDelta delta = morph_deltas[first_delta + i];
+        morphed_position += weight * delta.position.xyz;
+        morphed_normal += weight * delta.normal.xyz;
+        morphed_tangent += weight * delta.tangent.xyz;
+    }
+
+    mat4 skin = mat4(1.0);
+    // Vertices without Weights are not skinned
+    if (dot(in_weights, vec4(1.0)) > 0.0) {
+        skin = in_weights.x * joint_matrices[in_joints.x]
+            + in_weights.y * joint_matrices[in_joints.y]
+            + in_weights.z * joint_matrices[in_joints.z]
+            + in_weights.w * joint_matrices[in_joints.w];
+    }
+    vec4 skinned_position = skin * vec4(morphed_position, 1.0);
+
     tex_coord = in_tex_coord;
-    normal = in_normal;
-    world_position = in_position;
-    position = camera.proj_view_trans * vec4(in_position, 1.0);
+    normal = mat3(skin) * morphed_normal;
+    tangent = vec4(mat3(skin) * morphed_tangent, in_tangent.w);
+    tex_coord1 = in_tex_coord1;
+    world_position = skinned_position.xyz;
+    position = camera.proj_view_trans * skinned_position;
     view_position = camera.view_position;
 
     gl_Position = position;
+    // Needed when drawing Points
+    gl_PointSize = 1.0;
 }
\ No newline at end of file
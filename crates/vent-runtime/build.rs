@@ -1,23 +1,33 @@
-use std::{env, fs::DirEntry, io::Result};
+use std::{env, io::Result};
 
 use std::{
     env::var,
     fs,
     path::{Path, PathBuf},
-    process::{Command, Output},
 };
 
+use vent_shader::{ShaderCache, ShaderCompiler, ShaderLanguage};
+
+type Defines = &'static [(&'static str, &'static str)];
+
+/// Shaders which are compiled again with Defines, Written to `<name>.<variant>.<stage>.spv`
+const VARIANTS: &[(&str, &str, Defines)] =
+    &[("app/3D/shader.frag", "bindless", &[("BINDLESS", "")])];
+
 fn main() {
     // This tells cargo to rerun this script if something in /assets/ changes.
-    println!("cargo:rerun-if-changed=assets/*");
+    println!("cargo:rerun-if-changed=assets");
+    println!("cargo:rerun-if-env-changed=SKIP_SHADER_COMPILATION");
 
     let out_dir = env::var("OUT_DIR").expect("Var: OUT_DIR Not found!");
-    copy_dir_all("assets/", out_dir).expect("Failed to copy to resource Folder");
 
+    // Before copying, So the Resource Folder gets the new SPIR-V
     if !should_skip_shader_compilation() {
         println!("Compiling shaders");
-        compile_shaders(&get_shader_source_dir_path());
+        compile_shaders(&get_shader_source_dir_path(), Path::new(&out_dir));
     }
+
+    copy_dir_all("assets/", out_dir).expect("Failed to copy to resource Folder");
 }
 
 fn copy_dir_all(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> Result<()> {
@@ -42,44 +52,74 @@ fn should_skip_shader_compilation() -> bool {
         .unwrap_or(false)
 }
 
-fn compile_shaders(dir: &Path) {
-    if dir.is_dir() {
-        for entry in fs::read_dir(dir).unwrap() {
-            let entry = entry.unwrap();
-            let path = entry.path();
-            if path.is_dir() {
-                compile_shaders(&path);
-            } else {
-                compile_shader(&entry);
+/// Compiles every Shader Stage below `dir` next to its Source, Fails the Build when an Shader does not compile
+fn compile_shaders(dir: &Path, out_dir: &Path) {
+    let mut compiler = ShaderCompiler::new()
+        .include_dir(dir)
+        .cache(ShaderCache::new(out_dir.join("shader_cache")));
+
+    let mut shaders = Vec::new();
+    find_shaders(dir, &mut shaders);
+
+    let mut jobs: Vec<(PathBuf, PathBuf, Defines)> = shaders
+        .into_iter()
+        .map(|path| {
+            let mut output = path.clone().into_os_string();
+            output.push(".spv");
+            (path, output.into(), &[] as Defines)
+        })
+        .collect();
+    for (shader, variant, defines) in VARIANTS {
+        let path = dir.join(shader);
+        let name = path.file_name().unwrap().to_string_lossy();
+        let (stem, stage) = name.rsplit_once('.').unwrap();
+        let output = path.with_file_name(format!("{}.{}.{}.spv", stem, variant, stage));
+        jobs.push((path, output, defines));
+    }
+
+    let mut failed = 0;
+    for (path, output, defines) in jobs {
+        println!("Compiling {:?}", output.as_os_str());
+        match compiler.compile(&path, defines) {
+            Ok(shader) => {
+                for warning in shader.warnings {
+                    println!("cargo:warning={}", warning);
+                }
+                write_spirv(&output, &shader.spirv);
+            }
+            Err(err) => {
+                failed += 1;
+                // Cargo only shows the Output of failed Build Scripts, Warnings make the Errors show up in IDEs too
+                for line in err.to_string().lines() {
+                    println!("cargo:warning={}", line);
+                }
+                eprintln!("{}", err);
             }
         }
     }
+    if failed > 0 {
+        panic!("{} Shaders failed to compile", failed);
+    }
 }
 
-fn compile_shader(file: &DirEntry) {
-    if file.file_type().unwrap().is_file() {
-        let path = file.path();
-        if let Some(ext) = path.extension() {
-            if ext == "spv" || ext == "wgsl" {
-                return;
-            }
+fn find_shaders(dir: &Path, shaders: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            find_shaders(&path, shaders);
+        } else if ShaderLanguage::from_path(&path).is_some() {
+            shaders.push(path);
         }
+    }
+}
 
-        let name = path.file_name().unwrap().to_str().unwrap();
-        let output_name = format!("{}.spv", &name);
-        println!("Found file {:?}.\nCompiling...", path.as_os_str());
-
-        let result = Command::new("glslangValidator")
-            .current_dir(path.parent().expect("Shader File has not Parent!"))
-            .env("--target-env", "vulkan1.3")
-            .arg("-V")
-            .arg(&path)
-            .arg("-o")
-            .arg(output_name)
-            .output();
-
-        handle_program_result(result);
+/// Only writes when the SPIR-V changed, So the Assets are not touched on every Build
+fn write_spirv(output: &Path, spirv: &[u32]) {
+    let bytes: Vec<u8> = spirv.iter().flat_map(|word| word.to_le_bytes()).collect();
+    if fs::read(output).is_ok_and(|old| old == bytes) {
+        return;
     }
+    fs::write(output, bytes).expect("Failed to write SPIR-V");
 }
 
 fn get_shader_source_dir_path() -> PathBuf {
@@ -91,34 +131,3 @@ fn get_shader_source_dir_path() -> PathBuf {
 fn get_root_path() -> &'static Path {
     Path::new(env!("CARGO_MANIFEST_DIR"))
 }
-
-fn handle_program_result(result: Result<Output>) {
-    match result {
-        Ok(output) => {
-            if output.status.success() {
-                println!("Shader compilation succedeed.");
-                print!(
-                    "stdout: {}",
-                    String::from_utf8(output.stdout)
-                        .unwrap_or("Failed to print program stdout".to_string())
-                );
-            } else {
-                eprintln!("Shader compilation failed. Status: {}", output.status);
-                eprint!(
-                    "stdout: {}",
-                    String::from_utf8(output.stdout)
-                        .unwrap_or("Failed to print program stdout".to_string())
-                );
-                eprint!(
-                    "stderr: {}",
-                    String::from_utf8(output.stderr)
-                        .unwrap_or("Failed to print program stderr".to_string())
-                );
-                eprintln!("Shader compilation failed. Status: {}", output.status);
-            }
-        }
-        Err(error) => {
-            eprintln!("Failed to compile shader. Cause: {}", error);
-        }
-    }
-}
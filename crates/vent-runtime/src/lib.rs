@@ -27,6 +27,7 @@ impl VentApplication {
             render_settings: RenderSettings {
                 dimension: Dimension::D3,
                 vsync: false,
+                device: None,
             },
         };
         let app = VentApplication::new(project);
@@ -22,6 +22,9 @@ pub struct RenderSettings {
     // Inital vsync setting, can be changed later
     pub dimension: Dimension,
     pub vsync: bool,
+    // Index or part of the Name of the GPU to use, the VENT_DEVICE environment variable overrides it
+    #[serde(default)]
+    pub device: Option<String>,
 }
 
 impl VentApplicationProject {
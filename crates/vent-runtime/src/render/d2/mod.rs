@@ -30,7 +30,6 @@ impl Renderer for Renderer2D {
     fn render(
         &mut self,
         _instance: &vent_rendering::instance::VulkanInstance,
-        _image_index: u32,
         _command_buffer: vk::CommandBuffer,
         _camera: &mut dyn Camera,
     ) {
@@ -0,0 +1,210 @@
+use std::{
+    mem::size_of,
+    sync::{Arc, Mutex},
+};
+
+use ash::vk;
+use vent_assets::Material;
+use vent_rendering::{
+    any_as_u8_slice, buffer::VulkanBuffer, descriptor::BindlessTextures, instance::VulkanInstance,
+    reflect::PipelineReflection,
+};
+
+use super::{Camera3DData, MaterialUBO, MeshConstants};
+
+/// Slots of the Material Buffer
+const MAX_MATERIALS: u32 = 4096;
+/// Base Color, Metallic Roughness, Normal, Occlusion, Emissive
+const MATERIAL_TEXTURES: usize = 5;
+/// The Fragment Shader reads the Material Index right after the Push Constants of the Vertex Shader
+const MATERIAL_INDEX_OFFSET: u32 = (size_of::<Camera3DData>() + size_of::<MeshConstants>()) as u32;
+
+///
+/// Materials for Devices with Descriptor Indexing. Every Material is an Slot in one Storage Buffer (Set 0)
+/// and its Textures are in one big Texture Array (Set 3), Both are bound once per Frame.
+/// Drawing an Material then only pushes its Index instead of binding an Descriptor Set
+///
+pub struct BindlessMaterials {
+    pub textures: BindlessTextures,
+    /// Set 0, Only holds the Material Buffer
+    pub set_layout: vk::DescriptorSetLayout,
+    descriptor_set: vk::DescriptorSet,
+    buffer: VulkanBuffer,
+    /// Texture Indices of every Slot, So they can be removed with the Material
+    slots: Vec<[u32; MATERIAL_TEXTURES]>,
+    /// Shared with the Deletion Queue, Which gives removed Slots back
+    free_slots: Arc<Mutex<Vec<u32>>>,
+}
+
+impl BindlessMaterials {
+    /// `None` when the Device does not support Descriptor Indexing, `reflection` is of the Bindless Model Shaders
+    pub fn new(instance: &VulkanInstance, reflection: &PipelineReflection) -> Option<Self> {
+        let textures = BindlessTextures::new(instance, MAX_MATERIALS * MATERIAL_TEXTURES as u32)?;
+
+        let bindings = reflection.set_layout_bindings(0);
+        let layout_info = vk::DescriptorSetLayoutCreateInfo::default().bindings(&bindings);
+        let set_layout = unsafe {
+            instance
+                .device
+                .create_descriptor_set_layout(&layout_info, None)
+        }
+        .unwrap();
+        let descriptor_set = instance
+            .descriptor_allocator
+            .allocate(&instance.device, set_layout)
+            .expect("Failed to allocate Material Descriptor Set");
+
+        let size = (MAX_MATERIALS as usize * size_of::<MaterialUBO>()) as vk::DeviceSize;
+        let buffer = VulkanBuffer::new(
+            instance,
+            size,
+            vk::BufferUsageFlags::STORAGE_BUFFER,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+            Some("Materials"),
+        )
+        .expect("Failed to create Material Buffer");
+        let buffer_info = vk::DescriptorBufferInfo::default()
+            .buffer(*buffer)
+            .offset(0)
+            .range(vk::WHOLE_SIZE);
+        let write = vk::WriteDescriptorSet {
+            dst_set: descriptor_set,
+            dst_binding: 0,
+            descriptor_count: 1,
+            descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+            p_buffer_info: &buffer_info,
+            ..Default::default()
+        };
+        unsafe { instance.device.update_descriptor_sets(&[write], &[]) };
+
+        Some(Self {
+            textures,
+            set_layout,
+            descriptor_set,
+            buffer,
+            slots: Vec::new(),
+            free_slots: Arc::default(),
+        })
+    }
+
+    /// Writes the Material into an free Slot and sets `Material::bindless_index`, Errors are logged and leave the Index unset
+    pub fn add(&mut self, instance: &VulkanInstance, material: &mut Material) {
+        let free_slot = self.free_slots.lock().unwrap().pop();
+        let slot = match free_slot {
+            Some(slot) => slot,
+            None if (self.slots.len() as u32) < MAX_MATERIALS => {
+                self.slots.push([0; MATERIAL_TEXTURES]);
+                self.slots.len() as u32 - 1
+            }
+            None => {
+                log::error!("Material Buffer is full, {} Materials", MAX_MATERIALS);
+                return;
+            }
+        };
+
+        let mut texture_indices = [0; MATERIAL_TEXTURES];
+        for (i, texture) in material.textures().iter().enumerate() {
+            match self.textures.add(
+                &instance.device,
+                texture.image.image_view,
+                texture.image.sampler,
+            ) {
+                Some(index) => texture_indices[i] = index,
+                None => {
+                    log::error!(
+                        "Texture Array is full, {} Textures",
+                        self.textures.capacity()
+                    );
+                    texture_indices[..i]
+                        .iter()
+                        .for_each(|index| self.textures.remove(instance, *index));
+                    // Nothing was written into the Slot yet
+                    self.free_slots.lock().unwrap().push(slot);
+                    return;
+                }
+            }
+        }
+
+        let mut ubo = MaterialUBO::new(material);
+        for (info, index) in ubo.textures.iter_mut().zip(texture_indices) {
+            info.texture_index = index;
+        }
+        let size = size_of::<MaterialUBO>();
+        let memory = self.buffer.map(&instance.device, size as vk::DeviceSize);
+        // Other Slots may be read by Frames in Flight, This one is not used yet
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                any_as_u8_slice(&ubo).as_ptr(),
+                (memory as *mut u8).add(slot as usize * size),
+                size,
+            )
+        };
+        self.buffer.unmap(&instance.device);
+
+        self.slots[slot as usize] = texture_indices;
+        material.bindless_index = Some(slot);
+    }
+
+    /// Its Slot and Textures are reused once the current Frame finished, So Frames in Flight can still draw the Material
+    pub fn remove(&self, instance: &VulkanInstance, material: &mut Material) {
+        if let Some(slot) = material.bindless_index.take() {
+            self.slots[slot as usize]
+                .iter()
+                .for_each(|index| self.textures.remove(instance, *index));
+            let free_slots = self.free_slots.clone();
+            instance.destroy_later(move |_| free_slots.lock().unwrap().push(slot));
+        }
+    }
+
+    /// Binds the Material Buffer and the Texture Array, `pipeline_layout` has to be created with `set_layout` and `textures.layout`
+    pub fn bind(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        pipeline_layout: vk::PipelineLayout,
+    ) {
+        unsafe {
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline_layout,
+                0,
+                &[self.descriptor_set],
+                &[],
+            )
+        };
+        self.textures
+            .bind(device, command_buffer, pipeline_layout, 3);
+    }
+
+    /// Pushes the Index of the Material, Materials without an Slot fall back to the first one
+    pub fn push_material(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        pipeline_layout: vk::PipelineLayout,
+        material: &Material,
+    ) {
+        let index = material.bindless_index.unwrap_or_default();
+        unsafe {
+            device.cmd_push_constants(
+                command_buffer,
+                pipeline_layout,
+                vk::ShaderStageFlags::FRAGMENT,
+                MATERIAL_INDEX_OFFSET,
+                &index.to_ne_bytes(),
+            )
+        };
+    }
+
+    pub fn destroy(&mut self, instance: &VulkanInstance) {
+        self.buffer.destroy(&instance.device);
+        self.textures.destroy(&instance.device);
+        instance.descriptor_allocator.forget_layout(self.set_layout);
+        unsafe {
+            instance
+                .device
+                .destroy_descriptor_set_layout(self.set_layout, None)
+        };
+    }
+}
@@ -0,0 +1,169 @@
+use std::mem::size_of;
+
+use ash::vk;
+use vent_assets::animation::{MorphWeights, Pose};
+use vent_math::scalar::mat4::Mat4;
+use vent_rendering::{buffer::VulkanBuffer, instance::VulkanInstance, vertex::MorphDelta};
+
+/// Everything the Vertex Shader needs to deform an Model, Bound as Set 1:
+/// Joint Matrices (Binding 0), Morph Target Weights (Binding 1) and Morph Target Deltas (Binding 2).
+/// Joints and Weights change every Frame, So they are pushed into the `UniformRing` and bound with dynamic Offsets
+pub struct Deformation {
+    /// Only set when the Model has no Morph Targets, Otherwise the Model owns them
+    placeholder_deltas: Option<VulkanBuffer>,
+    descriptor_set: vk::DescriptorSet,
+    joint_count: usize,
+    weight_count: usize,
+    /// Dynamic Offsets of the Joints and Weights pushed for the current Frame
+    offsets: [u32; 2],
+
+    joint_matrices: Vec<Mat4>,
+    weights: Vec<f32>,
+}
+
+impl Deformation {
+    /// `descriptor_set_layout` needs dynamic Storage Buffers at Binding 0 and 1.
+    /// Nothing is pushed yet, Call `update` or `reset` every Frame before binding
+    pub fn new(
+        instance: &VulkanInstance,
+        descriptor_set_layout: vk::DescriptorSetLayout,
+        joint_count: usize,
+        weight_count: usize,
+        morph_targets: Option<&VulkanBuffer>,
+    ) -> Self {
+        // Empty Buffers are not allowed
+        let joint_count = joint_count.max(1);
+        let weight_count = weight_count.max(1);
+        let joint_size = (joint_count * size_of::<Mat4>()) as vk::DeviceSize;
+        let weight_size = (weight_count * size_of::<f32>()) as vk::DeviceSize;
+
+        let descriptor_set = instance
+            .descriptor_allocator
+            .allocate(&instance.device, descriptor_set_layout)
+            .expect("Failed to allocate Deformation Descriptor Set");
+
+        let placeholder_deltas = morph_targets.is_none().then(|| {
+            VulkanBuffer::new_init(
+                instance,
+                size_of::<MorphDelta>() as vk::DeviceSize,
+                vk::BufferUsageFlags::STORAGE_BUFFER,
+                &[MorphDelta::default()],
+                vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+                Some("Morph Targets"),
+            )
+            .expect("Failed to create Morph Target Placeholder")
+        });
+        let morph_targets = morph_targets
+            .or(placeholder_deltas.as_ref())
+            .map(|buffer| buffer.buffer)
+            .unwrap();
+
+        let ring = *instance.uniform_ring.buffer;
+        let buffer_infos = [
+            (ring, joint_size, vk::DescriptorType::STORAGE_BUFFER_DYNAMIC),
+            (
+                ring,
+                weight_size,
+                vk::DescriptorType::STORAGE_BUFFER_DYNAMIC,
+            ),
+            (
+                morph_targets,
+                vk::WHOLE_SIZE,
+                vk::DescriptorType::STORAGE_BUFFER,
+            ),
+        ]
+        .map(|(buffer, range, ty)| {
+            (
+                vk::DescriptorBufferInfo::default()
+                    .buffer(buffer)
+                    .offset(0)
+                    .range(range),
+                ty,
+            )
+        });
+        let writes: Vec<vk::WriteDescriptorSet> = buffer_infos
+            .iter()
+            .zip(0..)
+            .map(|((buffer_info, ty), binding)| vk::WriteDescriptorSet {
+                dst_set: descriptor_set,
+                dst_binding: binding,
+                descriptor_count: 1,
+                descriptor_type: *ty,
+                p_buffer_info: buffer_info,
+                ..Default::default()
+            })
+            .collect();
+        unsafe { instance.device.update_descriptor_sets(&writes, &[]) };
+
+        Self {
+            placeholder_deltas,
+            descriptor_set,
+            joint_count,
+            weight_count,
+            offsets: [0; 2],
+            joint_matrices: Vec::with_capacity(joint_count),
+            weights: Vec::with_capacity(weight_count),
+        }
+    }
+
+    /// Pushes the Joint Matrices and Morph Weights of the Pose, `node_transforms` are the Global Transforms of the Pose
+    pub fn update(
+        &mut self,
+        instance: &VulkanInstance,
+        model: &vent_assets::Model3D,
+        pose: &Pose,
+        node_transforms: &[Mat4],
+        morph_weights: &mut MorphWeights,
+    ) {
+        morph_weights.apply_pose(&model.skeleton, pose);
+        model
+            .skeleton
+            .joint_matrices(node_transforms, &mut self.joint_matrices);
+        self.weights.clear();
+        self.weights.extend_from_slice(&morph_weights.weights);
+        self.push(instance);
+    }
+
+    /// Pushes Identity Joints and zero Weights, For Models without Skins or Morph Targets
+    pub fn reset(&mut self, instance: &VulkanInstance) {
+        self.joint_matrices.clear();
+        self.weights.clear();
+        self.push(instance);
+    }
+
+    /// The Shader reads the whole Range of the Descriptor, So missing Joints and Weights are filled up
+    fn push(&mut self, instance: &VulkanInstance) {
+        self.joint_matrices.resize(self.joint_count, Mat4::IDENTITY);
+        self.weights.resize(self.weight_count, 0.0);
+        let ring = &instance.uniform_ring;
+        match (ring.push(&self.joint_matrices), ring.push(&self.weights)) {
+            (Some(joints), Some(weights)) => self.offsets = [joints, weights],
+            _ => log::error!("Uniform Ring is full, Deformation is not updated"),
+        }
+    }
+
+    pub fn bind(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        pipeline_layout: vk::PipelineLayout,
+    ) {
+        unsafe {
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline_layout,
+                1,
+                &[self.descriptor_set],
+                &self.offsets,
+            )
+        }
+    }
+
+    pub fn destroy(&mut self, instance: &VulkanInstance) {
+        if let Some(mut buffer) = self.placeholder_deltas.take() {
+            buffer.destroy(&instance.device);
+        }
+        instance.descriptor_allocator.free(&[self.descriptor_set]);
+    }
+}
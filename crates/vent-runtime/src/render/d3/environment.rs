@@ -0,0 +1,114 @@
+use std::mem::size_of;
+
+use ash::vk;
+use vent_assets::environment::EnvironmentMap;
+use vent_rendering::{
+    any_as_u8_slice, buffer::VulkanBuffer, image::VulkanImage, instance::VulkanInstance,
+};
+
+/// Matches the std140 Layout in shader.frag
+#[repr(C)]
+pub struct EnvironmentUBO {
+    /// Spherical Harmonics of the Irradiance, The 4th Component is unused
+    pub irradiance: [[f32; 4]; 9],
+    /// Mip Level used for fully rough Surfaces
+    pub max_lod: f32,
+    _padding: [f32; 3],
+}
+
+/// The Cubemap of the Sky together with its Irradiance, Used by the Skybox and for Image Based Lighting.
+/// Bound as Set 2: Cubemap (Binding 0) and `EnvironmentUBO` (Binding 1), It never changes so one Descriptor Set is enough
+pub struct Environment {
+    pub cubemap: VulkanImage,
+    ubo: VulkanBuffer,
+    descriptor_set: vk::DescriptorSet,
+}
+
+impl Environment {
+    pub fn new(
+        instance: &VulkanInstance,
+        descriptor_set_layout: vk::DescriptorSetLayout,
+        map: &EnvironmentMap,
+    ) -> Self {
+        let cubemap = map
+            .upload(instance, Some("Environment"))
+            .expect("Failed to upload Environment");
+        let ubo_data = EnvironmentUBO {
+            irradiance: map.irradiance.map(|[r, g, b]| [r, g, b, 0.0]),
+            max_lod: map.max_lod(),
+            _padding: [0.0; 3],
+        };
+        let ubo = VulkanBuffer::new_init(
+            instance,
+            size_of::<EnvironmentUBO>() as vk::DeviceSize,
+            vk::BufferUsageFlags::UNIFORM_BUFFER,
+            any_as_u8_slice(&ubo_data),
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+            Some("Environment"),
+        )
+        .expect("Failed to create Environment Buffer");
+
+        let descriptor_set = instance
+            .descriptor_allocator
+            .allocate(&instance.device, descriptor_set_layout)
+            .expect("Failed to allocate Environment Descriptor Set");
+
+        let image_info = vk::DescriptorImageInfo::default()
+            .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .image_view(cubemap.image_view)
+            .sampler(cubemap.sampler);
+        let buffer_info = vk::DescriptorBufferInfo::default()
+            .buffer(*ubo)
+            .offset(0)
+            .range(size_of::<EnvironmentUBO>() as vk::DeviceSize);
+        let writes = [
+            vk::WriteDescriptorSet {
+                dst_set: descriptor_set,
+                dst_binding: 0,
+                descriptor_count: 1,
+                descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                p_image_info: &image_info,
+                ..Default::default()
+            },
+            vk::WriteDescriptorSet {
+                dst_set: descriptor_set,
+                dst_binding: 1,
+                descriptor_count: 1,
+                descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+                p_buffer_info: &buffer_info,
+                ..Default::default()
+            },
+        ];
+        unsafe { instance.device.update_descriptor_sets(&writes, &[]) };
+
+        Self {
+            cubemap,
+            ubo,
+            descriptor_set,
+        }
+    }
+
+    pub fn bind(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        pipeline_layout: vk::PipelineLayout,
+    ) {
+        unsafe {
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline_layout,
+                2,
+                &[self.descriptor_set],
+                &[],
+            )
+        };
+    }
+
+    pub fn destroy(&mut self, instance: &VulkanInstance) {
+        self.cubemap.destroy(&instance.device);
+        self.ubo.destroy(&instance.device);
+        instance.descriptor_allocator.free(&[self.descriptor_set]);
+    }
+}
@@ -22,32 +22,13 @@ impl LightRenderer {
         let vertex_shader = FileAsset::new("assets/shaders/app/3D/light.vert.spv");
         let fragment_shader = FileAsset::new("assets/shaders/app/3D/light.frag.spv");
 
-        let desc_layout_bindings = [
-            vk::DescriptorSetLayoutBinding {
-                binding: 0,
-                descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
-                descriptor_count: 1,
-                stage_flags: vk::ShaderStageFlags::VERTEX,
-                ..Default::default()
-            },
-            vk::DescriptorSetLayoutBinding {
-                binding: 1,
-                descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
-                descriptor_count: 1,
-                stage_flags: vk::ShaderStageFlags::VERTEX,
-                ..Default::default()
-            },
-        ];
-
         let pipeline = VulkanPipeline::create_simple_pipeline(
             instance,
             vertex_shader.root_path(),
             fragment_shader.root_path(),
             &[Vertex3D::binding_description()],
             &Vertex3D::input_descriptions(),
-            instance.surface_resolution,
-            &[],
-            &desc_layout_bindings,
+            &instance.surface_attachment_formats(),
         );
 
         Self { pipeline }
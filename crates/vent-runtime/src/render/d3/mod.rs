@@ -1,35 +1,100 @@
-use std::mem::size_of;
+use std::{mem::size_of, path::Path, time::Instant};
 
 use ash::vk;
 use pollster::FutureExt;
 
+use bindless::BindlessMaterials;
+use deformation::Deformation;
+use environment::Environment;
 use skybox_renderer::SkyBoxRenderer;
-use vent_assets::io::file::FileAsset;
+use vent_assets::{environment::EnvironmentMap, io::file::FileAsset, Material};
 use vent_ecs::world::World;
 use vent_math::{
     scalar::mat4::Mat4,
     vec::{vec3::Vec3, vec4::Vec4},
 };
 use vent_rendering::{
-    any_as_u8_slice, buffer::VulkanBuffer, image::SkyBoxImages, instance::VulkanInstance,
-    mesh::Mesh3D, vertex::VertexPos3D,
+    any_as_u8_slice,
+    buffer::VulkanBuffer,
+    image::SkyBoxImages,
+    instance::VulkanInstance,
+    mesh::Mesh3D,
+    reflect::PipelineReflection,
+    vertex::{Vertex3D, VertexPos3D},
 };
 
 use super::{
     camera::{Camera, Camera3D},
     model::Entity3D,
     model_renderer::ModelRenderer3D,
+    scene::SceneGraph3D,
     Renderer,
 };
 
+pub mod bindless;
+pub mod deformation;
+pub mod environment;
 pub mod light_renderer;
 pub mod skybox_renderer;
 
+const MODEL_VERTEX_SHADER: &str = "assets/shaders/app/3D/shader.vert.spv";
+const MODEL_FRAGMENT_SHADER: &str = "assets/shaders/app/3D/shader.frag.spv";
+/// shader.frag with `BINDLESS` defined, Used when the Device supports Descriptor Indexing
+const MODEL_BINDLESS_FRAGMENT_SHADER: &str = "assets/shaders/app/3D/shader.bindless.frag.spv";
+
+/// Matches the std140 Layout in shader.frag, The Bindless Variant stores an Array of them with the same (std430) Layout
 #[repr(C)]
 pub struct MaterialUBO {
     pub base_color: Vec4,
+    pub emissive_factor: [f32; 3],
     pub alpha_mode: u32,
     pub alpha_cutoff: f32,
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub normal_scale: f32,
+    pub occlusion_strength: f32,
+    /// 1 when the Material has an Normal Map, The Shader only applies it then
+    pub normal_mapped: u32,
+    _padding: [f32; 2],
+    /// Base Color, Metallic Roughness, Normal, Occlusion, Emissive
+    pub textures: [TextureInfoUBO; 5],
+}
+
+/// UV Set and KHR_texture_transform of an Material Texture
+#[repr(C)]
+pub struct TextureInfoUBO {
+    /// Column major 2x2 Matrix
+    pub transform: [f32; 4],
+    pub offset: [f32; 2],
+    pub tex_coord: u32,
+    /// Index into the Texture Array, Only used by the Bindless Variant
+    pub texture_index: u32,
+}
+
+impl MaterialUBO {
+    pub fn new(material: &Material) -> Self {
+        Self {
+            base_color: Vec4::from_array(material.base_color),
+            emissive_factor: material.emissive_factor,
+            alpha_mode: material.alpha_mode as u32,
+            alpha_cutoff: material.alpha_cut,
+            metallic_factor: material.metallic_factor,
+            roughness_factor: material.roughness_factor,
+            normal_scale: material.normal_scale,
+            occlusion_strength: material.occlusion_strength,
+            normal_mapped: material.has_normal_texture as u32,
+            _padding: [0.0; 2],
+            textures: material.textures().map(|texture| {
+                let (transform, offset) = texture.transform.matrix();
+                TextureInfoUBO {
+                    transform,
+                    offset,
+                    tex_coord: texture.tex_coord,
+                    texture_index: 0,
+                }
+            }),
+        }
+    }
 }
 
 #[repr(C)] // This fixed everthing... #[repr(C)]
@@ -39,6 +104,28 @@ pub struct Camera3DData {
     pub proj_view_trans: Mat4,
 }
 
+/// Pushed right after `Camera3DData` before every Mesh is drawn, Matches the Push Constant Block in shader.vert
+#[repr(C)]
+#[derive(Default)]
+pub struct MeshConstants {
+    pub morph_first_delta: u32,
+    /// 0 when the Mesh has no Morph Targets
+    pub morph_target_count: u32,
+    pub morph_first_weight: u32,
+}
+
+impl MeshConstants {
+    pub fn new(mesh: &Mesh3D) -> Self {
+        mesh.morph_targets
+            .map(|targets| Self {
+                morph_first_delta: targets.first_delta,
+                morph_target_count: targets.target_count,
+                morph_first_weight: targets.first_weight,
+            })
+            .unwrap_or_default()
+    }
+}
+
 impl Default for Camera3DData {
     fn default() -> Self {
         Self {
@@ -50,14 +137,25 @@ impl Default for Camera3DData {
 
 pub struct Renderer3D {
     mesh_renderer: ModelRenderer3D,
+    scene_graph: SceneGraph3D,
     skybox_renderer: SkyBoxRenderer,
+    environment: Environment,
     //light_renderer: LightRenderer,
     tmp_light_mesh: Mesh3D,
     pipeline_layout: vk::PipelineLayout,
     descriptor_set_layout: vk::DescriptorSetLayout,
+    deformation_set_layout: vk::DescriptorSetLayout,
+    environment_set_layout: vk::DescriptorSetLayout,
+    /// Replaces the Material Descriptor Sets when the Device supports Descriptor Indexing, `pipeline_layout` is then created for it
+    bindless: Option<BindlessMaterials>,
+    /// Bound for Models without Skins and Morph Targets
+    default_deformation: Deformation,
+    last_frame: Instant,
 
     material_ubos: Vec<VulkanBuffer>,
     light_ubos: Vec<VulkanBuffer>,
+    /// Owns the Entities of the loaded Models
+    world: World,
 }
 
 impl Renderer for Renderer3D {
@@ -66,197 +164,16 @@ impl Renderer for Renderer3D {
         Self: Sized,
     {
         //  let _camera: &Camera3D = camera.downcast_ref().unwrap();
-
-        let skybox_renderer = SkyBoxRenderer::new(
-            instance,
-            SkyBoxImages {
-                right: FileAsset::new("assets/textures/skybox/right.jpg")
-                    .root_path()
-                    .clone(),
-                left: FileAsset::new("assets/textures/skybox/left.jpg")
-                    .root_path()
-                    .clone(),
-                top: FileAsset::new("assets/textures/skybox/top.jpg")
-                    .root_path()
-                    .clone(),
-                bottom: FileAsset::new("assets/textures/skybox/bottom.jpg")
-                    .root_path()
-                    .clone(),
-                front: FileAsset::new("assets/textures/skybox/front.jpg")
-                    .root_path()
-                    .clone(),
-                back: FileAsset::new("assets/textures/skybox/back.jpg")
-                    .root_path()
-                    .clone(),
-            },
-        );
-
-        let push_constant_range = vk::PushConstantRange::default()
-            .size(size_of::<Camera3DData>() as u32)
-            .stage_flags(vk::ShaderStageFlags::VERTEX);
-
-        let desc_layout_bindings = [
-            // Fragment
-            vk::DescriptorSetLayoutBinding {
-                binding: 0,
-                descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-                descriptor_count: 1,
-                stage_flags: vk::ShaderStageFlags::FRAGMENT,
-                ..Default::default()
-            },
-            vk::DescriptorSetLayoutBinding {
-                binding: 1,
-                descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
-                descriptor_count: 1,
-                stage_flags: vk::ShaderStageFlags::FRAGMENT,
-                ..Default::default()
-            },
-            // vk::DescriptorSetLayoutBinding {
-            //     binding: 2,
-            //     descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
-            //     descriptor_count: 1,
-            //     stage_flags: vk::ShaderStageFlags::FRAGMENT,
-            //     ..Default::default()
-            // },
-        ];
-        let info = vk::DescriptorSetLayoutCreateInfo::default().bindings(&desc_layout_bindings);
-
-        let descriptor_set_layout =
-            unsafe { instance.device.create_descriptor_set_layout(&info, None) }.unwrap();
-
-        let pipeline_layout =
-            instance.create_pipeline_layout(&[push_constant_range], &[descriptor_set_layout]);
-
-        let mut mesh_renderer = ModelRenderer3D::default();
+        let mut renderer = Self::new(instance);
 
         // // -------------- DEMO -------------------
-        let mut world = World::new();
-
         let model = FileAsset::new("assets/models/test/Sponza-GLTF/Sponza.gltf");
 
         // Sponza-GLTF/Sponza.gltf
         // bistro_outside.glb
 
-        let mut material_ubos = vec![];
-        let light_ubos = vec![];
-
-        let vertex_shader = FileAsset::new("assets/shaders/app/3D/shader.vert.spv");
-        let fragment_shader = FileAsset::new("assets/shaders/app/3D/shader.frag.spv");
-
-        let mut mesh = Entity3D::new(
-            vent_assets::Model3D::load(
-                instance,
-                vertex_shader.root_path(),
-                fragment_shader.root_path(),
-                pipeline_layout,
-                model.root_path(),
-            )
-            .block_on(),
-        );
-        for material in mesh.model.materials.iter_mut() {
-            let descriptor_sets = VulkanInstance::allocate_descriptor_sets(
-                &instance.device,
-                mesh.model.descriptor_pool,
-                descriptor_set_layout,
-                instance.swapchain_images.len(),
-            );
-
-            for &descriptor_set in descriptor_sets.iter() {
-                let diffuse_texture = &material.diffuse_texture;
-
-                let matieral_buffer = VulkanBuffer::new_init(
-                    instance,
-                    size_of::<MaterialUBO>() as vk::DeviceSize,
-                    vk::BufferUsageFlags::UNIFORM_BUFFER,
-                    any_as_u8_slice(&MaterialUBO {
-                        base_color: Vec4::from_array(material.base_color),
-                        alpha_mode: material.alpha_mode as u32,
-                        alpha_cutoff: material.alpha_cut,
-                    }),
-                    vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::DEVICE_LOCAL,
-                    None,
-                );
-                // let light_buffer = VulkanBuffer::new_init(
-                //     instance,
-                //     size_of::<LightUBO>() as vk::DeviceSize,
-                //     vk::BufferUsageFlags::UNIFORM_BUFFER,
-                //     any_as_u8_slice(&LightUBO {
-                //         position: Vec3::new(2.0, 100.0, 2.0),
-                //         color: Vec3::new(1.0, 1.0, 1.0),
-                //     }),
-                //     vk::MemoryPropertyFlags::HOST_VISIBLE
-                //         | vk::MemoryPropertyFlags::DEVICE_LOCAL,
-                //     None,
-                // );
-
-                let image_info = vk::DescriptorImageInfo::default()
-                    .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
-                    .image_view(diffuse_texture.image_view)
-                    .sampler(diffuse_texture.sampler);
-
-                let material_buffer_info = vk::DescriptorBufferInfo::default()
-                    .buffer(*matieral_buffer)
-                    .offset(0)
-                    .range(size_of::<MaterialUBO>() as vk::DeviceSize);
-
-                // let light_buffer_info = vk::DescriptorBufferInfo::default()
-                //     .buffer(*light_buffer)
-                //     .offset(0)
-                //     .range(size_of::<LightUBO>() as vk::DeviceSize);
-
-                let desc_sets = [
-                    vk::WriteDescriptorSet {
-                        dst_set: descriptor_set,
-                        dst_binding: 0, // From DescriptorSetLayoutBinding
-                        descriptor_count: 1,
-                        descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-                        p_image_info: &image_info,
-                        ..Default::default()
-                    },
-                    vk::WriteDescriptorSet {
-                        dst_set: descriptor_set,
-                        dst_binding: 1,
-                        descriptor_count: 1,
-                        descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
-                        p_buffer_info: &material_buffer_info,
-                        ..Default::default()
-                    },
-                    // vk::WriteDescriptorSet {
-                    //     dst_set: descriptor_set,
-                    //     dst_binding: 2,
-                    //     descriptor_count: 1,
-                    //     descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
-                    //     p_buffer_info: &light_buffer_info,
-                    //     ..Default::default()
-                    // },
-                ];
-
-                unsafe {
-                    instance.device.update_descriptor_sets(&desc_sets, &[]);
-                }
-
-                material_ubos.push(matieral_buffer);
-                //  light_ubos.push(light_buffer);
-            }
-            material.descriptor_set = Some(descriptor_sets);
-        }
-
-        mesh_renderer.insert(world.create_entity(), mesh);
-
-        let tmp_light_mesh = create_simple_cube(instance);
-        //  let light_renderer = LightRenderer::new(instance);
-
-        Self {
-            mesh_renderer,
-            skybox_renderer,
-            descriptor_set_layout,
-            //   light_renderer,
-            tmp_light_mesh,
-            pipeline_layout,
-            material_ubos,
-            light_ubos,
-            // pipeline_wire,
-        }
+        renderer.load_model(instance, model.root_path());
+        renderer
     }
 
     fn resize(
@@ -270,13 +187,17 @@ impl Renderer for Renderer3D {
     fn render(
         &mut self,
         instance: &VulkanInstance,
-        image_index: u32,
         command_buffer: vk::CommandBuffer,
         camera: &mut dyn Camera,
     ) {
         let camera: &mut Camera3D = camera.downcast_mut().unwrap();
 
-        let image_index = image_index as usize;
+        let now = Instant::now();
+        let delta = now.duration_since(self.last_frame).as_secs_f32();
+        self.last_frame = now;
+        self.mesh_renderer.update_animations(instance, delta);
+        self.default_deformation.reset(instance);
+        self.scene_graph.update(&self.mesh_renderer);
 
         let render_area = vk::Rect2D::default()
             .offset(vk::Offset2D::default())
@@ -296,14 +217,20 @@ impl Renderer for Renderer3D {
                 .cmd_set_viewport(command_buffer, 0, &[viewport]);
 
             self.skybox_renderer
-                .draw(&instance.device, command_buffer, camera, image_index);
+                .draw(&instance.device, command_buffer, camera);
 
+            self.environment
+                .bind(&instance.device, command_buffer, self.pipeline_layout);
+            if let Some(bindless) = &self.bindless {
+                bindless.bind(&instance.device, command_buffer, self.pipeline_layout);
+            }
             self.mesh_renderer.record_buffer(
                 instance,
                 command_buffer,
-                image_index,
                 self.pipeline_layout,
                 camera,
+                &self.default_deformation,
+                self.bindless.as_ref(),
             );
 
             //    self.light_renderer.render(instance, command_buffer, image_index, &self.tmp_light_mesh);
@@ -316,12 +243,31 @@ impl Renderer for Renderer3D {
 
     fn destroy(&mut self, instance: &VulkanInstance) {
         unsafe { instance.device.device_wait_idle().unwrap() };
-        self.mesh_renderer.destroy_all(&instance.device);
-        self.skybox_renderer.destroy(&instance.device);
+        self.mesh_renderer
+            .destroy_all(instance, self.bindless.as_ref());
+        self.skybox_renderer.destroy(instance);
+        self.environment.destroy(instance);
+        self.default_deformation.destroy(instance);
+        if let Some(bindless) = &mut self.bindless {
+            bindless.destroy(instance);
+        }
+        for layout in [
+            self.descriptor_set_layout,
+            self.deformation_set_layout,
+            self.environment_set_layout,
+        ] {
+            instance.descriptor_allocator.forget_layout(layout);
+        }
         unsafe {
             instance
                 .device
-                .destroy_descriptor_set_layout(self.descriptor_set_layout, None)
+                .destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+            instance
+                .device
+                .destroy_descriptor_set_layout(self.deformation_set_layout, None);
+            instance
+                .device
+                .destroy_descriptor_set_layout(self.environment_set_layout, None);
         };
         //self.light_renderer.destroy(&instance.device);
         self.material_ubos
@@ -340,6 +286,306 @@ impl Renderer for Renderer3D {
     }
 }
 
+impl Renderer3D {
+    /// Creates the Renderer with an empty Scene, Only the Skybox is drawn until Models are loaded
+    pub(crate) fn new(instance: &mut VulkanInstance) -> Self {
+        let vertex_shader = FileAsset::new(MODEL_VERTEX_SHADER);
+        let fragment_shader = FileAsset::new(MODEL_FRAGMENT_SHADER);
+        let mut reflection = PipelineReflection::from_files(&[
+            vertex_shader.root_path().as_path(),
+            fragment_shader.root_path().as_path(),
+        ])
+        .expect("Failed to reflect Model Shaders");
+        // Joints and Morph Weights are pushed into the Uniform Ring every Frame
+        reflection.make_dynamic(1, 0);
+        reflection.make_dynamic(1, 1);
+        for mismatch in reflection.check_vertex_input(
+            &[Vertex3D::binding_description()],
+            &Vertex3D::input_descriptions(),
+        ) {
+            log::error!(
+                "Vertex3D does not match {}: {}",
+                MODEL_VERTEX_SHADER,
+                mismatch
+            );
+        }
+
+        // Set 0 is the Material, Set 1 the Joint Matrices and Morph Targets, Set 2 the Environment
+        let set_layouts = reflection.create_set_layouts(&instance.device);
+        let [descriptor_set_layout, deformation_set_layout, environment_set_layout] =
+            set_layouts[..]
+        else {
+            panic!(
+                "Model Shaders use {} Descriptor Sets, Expected 3",
+                set_layouts.len()
+            );
+        };
+        let bindless = Self::create_bindless(instance);
+        let pipeline_layout = match &bindless {
+            // Set 1 and 2 are the same in both Variants
+            Some((bindless, reflection)) => instance.create_pipeline_layout(
+                &reflection.push_constant_ranges(),
+                &[
+                    bindless.set_layout,
+                    deformation_set_layout,
+                    environment_set_layout,
+                    bindless.textures.layout,
+                ],
+            ),
+            None => {
+                instance.create_pipeline_layout(&reflection.push_constant_ranges(), &set_layouts)
+            }
+        };
+        let bindless = bindless.map(|(bindless, _)| bindless);
+
+        let environment = Environment::new(
+            instance,
+            environment_set_layout,
+            &Self::load_environment_map(),
+        );
+        let skybox_renderer = SkyBoxRenderer::new(instance, &environment.cubemap);
+
+        let default_deformation = Deformation::new(instance, deformation_set_layout, 1, 1, None);
+
+        let mesh_renderer = ModelRenderer3D::default();
+        let scene_graph = SceneGraph3D::default();
+
+        let tmp_light_mesh = create_simple_cube(instance);
+        //  let light_renderer = LightRenderer::new(instance);
+
+        Self {
+            mesh_renderer,
+            scene_graph,
+            skybox_renderer,
+            environment,
+            descriptor_set_layout,
+            deformation_set_layout,
+            environment_set_layout,
+            bindless,
+            default_deformation,
+            last_frame: Instant::now(),
+            //   light_renderer,
+            tmp_light_mesh,
+            pipeline_layout,
+            material_ubos: vec![],
+            light_ubos: vec![],
+            world: World::new(),
+            // pipeline_wire,
+        }
+    }
+
+    /// `None` when the Device has no Descriptor Indexing or the Bindless Shader was not compiled
+    fn create_bindless(
+        instance: &VulkanInstance,
+    ) -> Option<(BindlessMaterials, PipelineReflection)> {
+        if !instance.capabilities.descriptor_indexing {
+            log::debug!("Descriptor Indexing is not supported, Using Material Descriptor Sets");
+            return None;
+        }
+        let vertex_shader = FileAsset::new(MODEL_VERTEX_SHADER);
+        let fragment_shader = FileAsset::new(MODEL_BINDLESS_FRAGMENT_SHADER);
+        let reflection = match PipelineReflection::from_files(&[
+            vertex_shader.root_path().as_path(),
+            fragment_shader.root_path().as_path(),
+        ]) {
+            Ok(reflection) => reflection,
+            Err(err) => {
+                log::warn!(
+                    "Failed to reflect {}: {}, Using Material Descriptor Sets",
+                    MODEL_BINDLESS_FRAGMENT_SHADER,
+                    err
+                );
+                return None;
+            }
+        };
+        let bindless = BindlessMaterials::new(instance, &reflection)?;
+        log::debug!(
+            "Using Bindless Materials, {} Textures",
+            bindless.textures.capacity()
+        );
+        Some((bindless, reflection))
+    }
+
+    /// Loads an Model and spawns its Scene, Errors are logged
+    pub(crate) fn load_model(&mut self, instance: &mut VulkanInstance, path: &Path) {
+        let vertex_shader = FileAsset::new(MODEL_VERTEX_SHADER);
+        let fragment_shader = FileAsset::new(match self.bindless {
+            Some(_) => MODEL_BINDLESS_FRAGMENT_SHADER,
+            None => MODEL_FRAGMENT_SHADER,
+        });
+
+        match vent_assets::Model3D::load(
+            instance,
+            vertex_shader.root_path().as_path(),
+            fragment_shader.root_path().as_path(),
+            self.pipeline_layout,
+            path,
+        )
+        .block_on()
+        {
+            Ok(model) => {
+                let mut mesh = Entity3D::new(model);
+                let joint_count = mesh.model.skeleton.joint_count();
+                if joint_count > 0 || mesh.model.morph_targets.is_some() {
+                    mesh.deformation = Some(Deformation::new(
+                        instance,
+                        self.deformation_set_layout,
+                        joint_count,
+                        mesh.morph_weights.weights.len(),
+                        mesh.model.morph_targets.as_ref(),
+                    ));
+                }
+                if !mesh.model.animations.is_empty() {
+                    mesh.animation.play(0, true);
+                }
+                for material in mesh.model.materials.iter_mut() {
+                    if let Some(bindless) = &mut self.bindless {
+                        bindless.add(instance, material);
+                        continue;
+                    }
+                    // Materials do not change after loading, So one Set and Buffer is used by every Frame in Flight
+                    let descriptor_set = match instance
+                        .descriptor_allocator
+                        .allocate(&instance.device, self.descriptor_set_layout)
+                    {
+                        Ok(descriptor_set) => descriptor_set,
+                        Err(err) => {
+                            log::error!("Failed to allocate Material Descriptor Set: {}", err);
+                            continue;
+                        }
+                    };
+
+                    let matieral_buffer = match VulkanBuffer::cpu_to_gpu(
+                        instance,
+                        any_as_u8_slice(&MaterialUBO::new(material)),
+                        size_of::<MaterialUBO>() as vk::DeviceSize,
+                        vk::BufferUsageFlags::UNIFORM_BUFFER,
+                        Some("Material"),
+                    ) {
+                        Ok(buffer) => buffer,
+                        Err(err) => {
+                            log::error!("Failed to create Material Buffer: {}", err);
+                            continue;
+                        }
+                    };
+                    // let light_buffer = VulkanBuffer::new_init(
+                    //     instance,
+                    //     size_of::<LightUBO>() as vk::DeviceSize,
+                    //     vk::BufferUsageFlags::UNIFORM_BUFFER,
+                    //     any_as_u8_slice(&LightUBO {
+                    //         position: Vec3::new(2.0, 100.0, 2.0),
+                    //         color: Vec3::new(1.0, 1.0, 1.0),
+                    //     }),
+                    //     vk::MemoryPropertyFlags::HOST_VISIBLE
+                    //         | vk::MemoryPropertyFlags::DEVICE_LOCAL,
+                    //     None,
+                    // );
+
+                    let image_infos = material.textures().map(|texture| {
+                        vk::DescriptorImageInfo::default()
+                            .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                            .image_view(texture.image.image_view)
+                            .sampler(texture.image.sampler)
+                    });
+
+                    let material_buffer_info = vk::DescriptorBufferInfo::default()
+                        .buffer(*matieral_buffer)
+                        .offset(0)
+                        .range(size_of::<MaterialUBO>() as vk::DeviceSize);
+
+                    // let light_buffer_info = vk::DescriptorBufferInfo::default()
+                    //     .buffer(*light_buffer)
+                    //     .offset(0)
+                    //     .range(size_of::<LightUBO>() as vk::DeviceSize);
+
+                    // Base Color is at 0, The other Textures start at 2
+                    let texture_bindings = [0, 2, 3, 4, 5];
+                    let mut desc_sets: Vec<vk::WriteDescriptorSet> = image_infos
+                        .iter()
+                        .zip(texture_bindings)
+                        .map(|(image_info, binding)| vk::WriteDescriptorSet {
+                            dst_set: descriptor_set,
+                            dst_binding: binding, // From DescriptorSetLayoutBinding
+                            descriptor_count: 1,
+                            descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                            p_image_info: image_info,
+                            ..Default::default()
+                        })
+                        .collect();
+                    desc_sets.push(vk::WriteDescriptorSet {
+                        dst_set: descriptor_set,
+                        dst_binding: 1,
+                        descriptor_count: 1,
+                        descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+                        p_buffer_info: &material_buffer_info,
+                        ..Default::default()
+                    });
+                    // desc_sets.push(vk::WriteDescriptorSet {
+                    //     dst_set: descriptor_set,
+                    //     dst_binding: 6,
+                    //     descriptor_count: 1,
+                    //     descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+                    //     p_buffer_info: &light_buffer_info,
+                    //     ..Default::default()
+                    // });
+
+                    unsafe {
+                        instance.device.update_descriptor_sets(&desc_sets, &[]);
+                    }
+
+                    self.material_ubos.push(matieral_buffer);
+                    //  light_ubos.push(light_buffer);
+                    material.descriptor_set = Some(descriptor_set);
+                }
+
+                let entity = self.world.create_entity();
+                self.scene_graph.spawn(&mut self.world, entity, &mesh);
+                log::debug!(
+                    "Spawned Scene, {} Cameras, {} Lights",
+                    self.scene_graph.cameras().count(),
+                    self.scene_graph.lights().count()
+                );
+                self.mesh_renderer.insert(entity, mesh);
+                log::debug!("GPU Memory: {}", instance.memory_allocator.stats());
+                let (pools, sets) = instance.descriptor_allocator.stats();
+                log::debug!("Descriptor Sets: {} in {} Pools", sets, pools);
+            }
+            Err(err) => log::error!("Failed to Load Model {}: {}", path.display(), err),
+        }
+    }
+
+    /// Prefers an HDR Panorama, Older Projects only ship the 6 Skybox Faces
+    fn load_environment_map() -> EnvironmentMap {
+        let panorama = FileAsset::new("assets/textures/skybox/environment.hdr");
+        match EnvironmentMap::load(panorama.root_path()) {
+            Ok(map) => return map,
+            Err(err) => log::debug!(
+                "No Environment Panorama {}: {}, Using the Skybox Faces",
+                panorama.root_path().display(),
+                err
+            ),
+        }
+        let face = |name: &str| {
+            FileAsset::new(format!("assets/textures/skybox/{}.jpg", name))
+                .root_path()
+                .clone()
+        };
+        EnvironmentMap::load_faces(&SkyBoxImages {
+            right: face("right"),
+            left: face("left"),
+            top: face("top"),
+            bottom: face("bottom"),
+            front: face("front"),
+            back: face("back"),
+        })
+        .unwrap_or_else(|err| {
+            log::error!("Failed to Load Skybox: {}", err);
+            // Plain grey Sky, So the Scene is still lit
+            EnvironmentMap::from_faces(1, std::array::from_fn(|_| vec![[0.5, 0.5, 0.5, 1.0]]))
+        })
+    }
+}
+
 fn create_simple_cube(instance: &VulkanInstance) -> Mesh3D {
     let indices = [
         //Top
@@ -384,4 +630,119 @@ fn create_simple_cube(instance: &VulkanInstance) -> Mesh3D {
         vent_rendering::Indices::U16(indices.to_vec()),
         None,
     )
+    .expect("Failed to create Cube Mesh")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::size_of;
+
+    use ash::vk;
+    use vent_assets::io::file::FileAsset;
+    use vent_rendering::{reflect::PipelineReflection, vertex::Vertex3D};
+
+    use super::{
+        Camera3DData, MaterialUBO, MeshConstants, MODEL_BINDLESS_FRAGMENT_SHADER,
+        MODEL_FRAGMENT_SHADER, MODEL_VERTEX_SHADER,
+    };
+
+    /// The SPIR-V next to the Sources is only up to date when build.rs compiled the Shaders
+    fn shaders_compiled() -> bool {
+        option_env!("SKIP_SHADER_COMPILATION").map_or(true, |skip| skip != "true")
+    }
+
+    fn model_reflection() -> PipelineReflection {
+        let vertex_shader = FileAsset::new(MODEL_VERTEX_SHADER);
+        let fragment_shader = FileAsset::new(MODEL_FRAGMENT_SHADER);
+        PipelineReflection::from_files(&[
+            vertex_shader.root_path().as_path(),
+            fragment_shader.root_path().as_path(),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn vertex3d_matches_model_shader() {
+        if !shaders_compiled() {
+            eprintln!("Shader Compilation was skipped, Skipping Reflection Test");
+            return;
+        }
+        let mismatches = model_reflection().check_vertex_input(
+            &[Vertex3D::binding_description()],
+            &Vertex3D::input_descriptions(),
+        );
+        assert!(mismatches.is_empty(), "{:?}", mismatches);
+    }
+
+    #[test]
+    fn model_shader_layout() {
+        if !shaders_compiled() {
+            eprintln!("Shader Compilation was skipped, Skipping Reflection Test");
+            return;
+        }
+        let reflection = model_reflection();
+        assert_eq!(reflection.set_count(), 3);
+
+        let material = reflection.set_layout_bindings(0);
+        assert_eq!(material.len(), 6);
+        assert_eq!(
+            material[1].descriptor_type,
+            vk::DescriptorType::UNIFORM_BUFFER
+        );
+        assert!(material
+            .iter()
+            .all(|binding| binding.stage_flags == vk::ShaderStageFlags::FRAGMENT));
+
+        let deformation = reflection.set_layout_bindings(1);
+        assert_eq!(deformation.len(), 3);
+        assert!(deformation.iter().all(|binding| binding.descriptor_type
+            == vk::DescriptorType::STORAGE_BUFFER
+            && binding.stage_flags == vk::ShaderStageFlags::VERTEX));
+
+        let ranges = reflection.push_constant_ranges();
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(ranges[0].stage_flags, vk::ShaderStageFlags::VERTEX);
+        // Everything the Renderer pushes has to fit into the Block
+        assert!(ranges[0].size as usize >= size_of::<Camera3DData>() + size_of::<MeshConstants>());
+    }
+
+    #[test]
+    fn bindless_shader_layout() {
+        if !shaders_compiled() {
+            eprintln!("Shader Compilation was skipped, Skipping Reflection Test");
+            return;
+        }
+        let vertex_shader = FileAsset::new(MODEL_VERTEX_SHADER);
+        let fragment_shader = FileAsset::new(MODEL_BINDLESS_FRAGMENT_SHADER);
+        let reflection = PipelineReflection::from_files(&[
+            vertex_shader.root_path().as_path(),
+            fragment_shader.root_path().as_path(),
+        ])
+        .unwrap();
+        assert_eq!(reflection.set_count(), 4);
+
+        let materials = reflection.set_layout_bindings(0);
+        assert_eq!(materials.len(), 1);
+        assert_eq!(
+            materials[0].descriptor_type,
+            vk::DescriptorType::STORAGE_BUFFER
+        );
+        let textures = reflection.set_layout_bindings(3);
+        assert_eq!(
+            textures[0].descriptor_type,
+            vk::DescriptorType::COMBINED_IMAGE_SAMPLER
+        );
+
+        // The Material Index follows the Vertex Push Constants
+        let ranges = reflection.push_constant_ranges();
+        let fragment = ranges
+            .iter()
+            .find(|range| range.stage_flags == vk::ShaderStageFlags::FRAGMENT)
+            .unwrap();
+        assert_eq!(
+            fragment.offset as usize,
+            size_of::<Camera3DData>() + size_of::<MeshConstants>()
+        );
+        assert_eq!(size_of::<MaterialUBO>(), 224);
+    }
 }
@@ -1,16 +1,9 @@
-use std::mem::size_of;
-
 use ash::vk;
-use image::GenericImageView;
 use vent_assets::io::file::FileAsset;
 use vent_math::scalar::mat4::Mat4;
 use vent_rendering::{
-    any_as_u8_slice,
-    image::{SkyBoxImages, VulkanImage},
-    instance::VulkanInstance,
-    mesh::Mesh3D,
-    pipeline::VulkanPipeline,
-    vertex::VertexPos3D,
+    any_as_u8_slice, image::VulkanImage, instance::VulkanInstance, mesh::Mesh3D,
+    pipeline::VulkanPipeline, vertex::VertexPos3D,
 };
 
 use crate::render::{camera::Camera3D, d3::create_simple_cube};
@@ -18,10 +11,9 @@ use crate::render::{camera::Camera3D, d3::create_simple_cube};
 #[allow(dead_code)]
 pub struct SkyBoxRenderer {
     pipeline: VulkanPipeline,
-    image: VulkanImage,
-    descriptor_pool: vk::DescriptorPool,
     push_constants: SkyBoxUBO,
-    descriptor_sets: Vec<vk::DescriptorSet>,
+    /// Only samples the Cubemap, So every Frame in Flight can use the same Set
+    descriptor_set: vk::DescriptorSet,
     cube: Mesh3D,
 }
 
@@ -32,32 +24,19 @@ pub struct SkyBoxUBO {
 }
 
 impl SkyBoxRenderer {
-    pub fn new(instance: &VulkanInstance, images: SkyBoxImages) -> Self {
+    /// The Cubemap is owned by the `Environment`, So the Sky matches the Image Based Lighting
+    pub fn new(instance: &VulkanInstance, cubemap: &VulkanImage) -> Self {
         log::debug!("Creating skybox");
         let vertex_shader = FileAsset::new("assets/shaders/app/3D/skybox.vert.spv");
         let fragment_shader = FileAsset::new("assets/shaders/app/3D/skybox.frag.spv");
 
-        let desc_layout_bindings = [vk::DescriptorSetLayoutBinding {
-            binding: 0,
-            descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-            descriptor_count: 1,
-            stage_flags: vk::ShaderStageFlags::FRAGMENT,
-            ..Default::default()
-        }];
-
-        let push_constant_range = vk::PushConstantRange::default()
-            .size(size_of::<SkyBoxUBO>() as u32)
-            .stage_flags(vk::ShaderStageFlags::VERTEX);
-
         let pipeline = VulkanPipeline::create_simple_pipeline(
             instance,
             vertex_shader.root_path(),
             fragment_shader.root_path(),
             &[VertexPos3D::binding_description()],
             &VertexPos3D::input_descriptions(),
-            instance.surface_resolution,
-            &[push_constant_range],
-            &desc_layout_bindings,
+            &instance.surface_attachment_formats(),
         );
         let cube = create_simple_cube(instance);
         let push_constants = SkyBoxUBO {
@@ -65,89 +44,43 @@ impl SkyBoxRenderer {
             model: Mat4::IDENTITY,
         };
 
-        let descriptor_pool =
-            Self::create_descriptor_pool(instance.swapchain_images.len() as u32, &instance.device);
-
-        let descriptor_sets = VulkanInstance::allocate_descriptor_sets(
-            &instance.device,
-            descriptor_pool,
-            pipeline.descriptor_set_layout,
-            instance.swapchain_images.len(),
-        );
-
-        let images = [
-            image::open(images.right).unwrap(),
-            image::open(images.left).unwrap(),
-            image::open(images.top).unwrap(),
-            image::open(images.bottom).unwrap(),
-            image::open(images.front).unwrap(),
-            image::open(images.back).unwrap(),
-        ];
-        let image_size = images[0].dimensions();
-
-        let image = VulkanImage::load_cubemap(
-            instance,
-            images,
-            vk::Extent2D {
-                width: image_size.0,
-                height: image_size.1,
-            },
-        );
-
-        for &descriptor_set in descriptor_sets.iter() {
-            let diffuse_texture = &image;
+        let descriptor_set = instance
+            .descriptor_allocator
+            .allocate(&instance.device, pipeline.descriptor_set_layout)
+            .expect("Failed to allocate Skybox Descriptor Set");
 
-            let image_info = vk::DescriptorImageInfo::default()
-                .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
-                .image_view(diffuse_texture.image_view)
-                .sampler(diffuse_texture.sampler);
+        let image_info = vk::DescriptorImageInfo::default()
+            .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .image_view(cubemap.image_view)
+            .sampler(cubemap.sampler);
 
-            let desc_sets = [vk::WriteDescriptorSet {
-                dst_set: descriptor_set,
-                dst_binding: 0, // From DescriptorSetLayoutBinding
-                descriptor_count: 1,
-                descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-                p_image_info: &image_info,
-                ..Default::default()
-            }];
+        let desc_sets = [vk::WriteDescriptorSet {
+            dst_set: descriptor_set,
+            dst_binding: 0, // From DescriptorSetLayoutBinding
+            descriptor_count: 1,
+            descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            p_image_info: &image_info,
+            ..Default::default()
+        }];
 
-            unsafe {
-                instance.device.update_descriptor_sets(&desc_sets, &[]);
-            }
+        unsafe {
+            instance.device.update_descriptor_sets(&desc_sets, &[]);
         }
 
         Self {
             pipeline,
             cube,
-            image,
             push_constants,
-            descriptor_pool,
-            descriptor_sets,
+            descriptor_set,
         }
     }
 
-    pub fn create_descriptor_pool(
-        swapchain_count: u32,
-        device: &ash::Device,
-    ) -> vk::DescriptorPool {
-        let pool_sizes = [vk::DescriptorPoolSize {
-            ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-            descriptor_count: 2 * swapchain_count,
-        }];
-
-        let create_info = vk::DescriptorPoolCreateInfo::default()
-            .pool_sizes(&pool_sizes)
-            .max_sets(2 * swapchain_count);
-
-        unsafe { device.create_descriptor_pool(&create_info, None) }.unwrap()
-    }
     #[allow(dead_code)]
     pub fn draw(
         &mut self,
         device: &ash::Device,
         command_buffer: vk::CommandBuffer,
         camera: &Camera3D,
-        buffer_index: usize,
     ) {
         self.push_constants = SkyBoxUBO {
             projection: camera.projection,
@@ -171,7 +104,7 @@ impl SkyBoxRenderer {
                 vk::PipelineBindPoint::GRAPHICS,
                 self.pipeline.pipeline_layout,
                 0,
-                &self.descriptor_sets[buffer_index..=buffer_index],
+                &[self.descriptor_set],
                 &[],
             )
         };
@@ -179,12 +112,12 @@ impl SkyBoxRenderer {
         self.cube.draw(device, command_buffer);
     }
 
-    pub fn destroy(&mut self, device: &ash::Device) {
-        self.pipeline.destroy(device);
-        self.cube.destroy(device);
-        self.image.destroy(device);
-        unsafe {
-            device.destroy_descriptor_pool(self.descriptor_pool, None);
-        }
+    pub fn destroy(&mut self, instance: &VulkanInstance) {
+        // The Layout is destroyed with the Pipeline, So the Sets can not be reused
+        instance
+            .descriptor_allocator
+            .forget_layout(self.pipeline.descriptor_set_layout);
+        self.pipeline.destroy(&instance.device);
+        self.cube.destroy(&instance.device);
     }
 }
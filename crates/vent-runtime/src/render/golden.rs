@@ -0,0 +1,403 @@
+//! Golden Image Tests, Predefined Scenes are rendered on an headless Instance and compared with the Reference Images in `tests/golden`.
+//!
+//! The References are recorded on the lavapipe Software Driver so they do not depend on the GPU, e.g.
+//! `VK_ICD_FILENAMES=/usr/share/vulkan/icd.d/lvp_icd.x86_64.json cargo test -p vent-runtime golden`.
+//! Set `VENT_UPDATE_GOLDEN=1` to record them (again after an intended Change) and commit them.
+//! The `Record Golden References` CI Workflow does this on lavapipe and uploads them as an Artifact.
+//! Scenes without an committed Reference are still rendered but not compared, The Test only warns about them
+//! When an Test fails the Rendering and an Diff Image are written to `tests/golden/diff`
+
+use std::path::{Path, PathBuf};
+
+use ash::vk;
+use image::{Rgba, RgbaImage};
+use vent_assets::{
+    io::file::FileAsset,
+    model::{
+        data::{
+            MaterialData, MeshData, ModelData, PrimitiveData, PrimitiveMode, TextureData,
+            TextureImage, TextureTransform,
+        },
+        gltf_exporter::GltfExporter,
+    },
+};
+use vent_math::vec::vec3::Vec3;
+use vent_rendering::{
+    instance::VulkanInstance,
+    render_graph::{AttachmentLoad, GraphPass, RenderGraph, RenderGraphResources},
+};
+use vent_ui::renderer::GuiRenderer;
+
+use super::{
+    camera::{Camera, Camera3D},
+    d3::Renderer3D,
+    RawRuntimeRenderer, Renderer, CLEAR_COLOR, CLEAR_DEPTH,
+};
+
+const WIDTH: u32 = 256;
+const HEIGHT: u32 = 256;
+
+/// How far an Rendering may be off before the Test fails, Software Drivers differ slightly between Versions
+struct Tolerance {
+    /// Largest Difference of an Channel (0-255) for which an Pixel still counts as equal
+    channel: u8,
+    /// Fraction of the Pixels which may differ
+    max_differing: f32,
+}
+
+const DEFAULT_TOLERANCE: Tolerance = Tolerance {
+    channel: 8,
+    max_differing: 0.005,
+};
+
+struct Comparison {
+    differing: usize,
+    max_difference: u8,
+    /// Differing Pixels are red, The others show the dimmed Reference
+    diff: RgbaImage,
+}
+
+fn compare(reference: &RgbaImage, actual: &RgbaImage, tolerance: &Tolerance) -> Comparison {
+    let mut differing = 0;
+    let mut max_difference = 0;
+    let diff = RgbaImage::from_fn(reference.width(), reference.height(), |x, y| {
+        let expected = reference.get_pixel(x, y);
+        let difference = expected
+            .0
+            .iter()
+            .zip(actual.get_pixel(x, y).0)
+            .map(|(&expected, actual)| expected.abs_diff(actual))
+            .max()
+            .unwrap_or(0);
+        max_difference = max_difference.max(difference);
+        if difference > tolerance.channel {
+            differing += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let luma = (expected[0] as u32 + expected[1] as u32 + expected[2] as u32) / 9;
+            Rgba([luma as u8, luma as u8, luma as u8, 255])
+        }
+    });
+    Comparison {
+        differing,
+        max_difference,
+        diff,
+    }
+}
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn assert_golden(name: &str, actual: &RgbaImage, tolerance: &Tolerance) {
+    let reference_path = golden_dir().join(format!("{}.png", name));
+    let update = std::env::var("VENT_UPDATE_GOLDEN").is_ok_and(|update| update == "1");
+    if update {
+        std::fs::create_dir_all(golden_dir()).unwrap();
+        actual
+            .save(&reference_path)
+            .expect("Failed to write Reference Image");
+        eprintln!("Recorded Reference {}", reference_path.display());
+        return;
+    }
+    if !reference_path.exists() {
+        eprintln!(
+            "{}: Reference {} is missing, Skipping the Comparison. Run with VENT_UPDATE_GOLDEN=1 on lavapipe to record it",
+            name,
+            reference_path.display()
+        );
+        return;
+    }
+
+    let reference = image::open(&reference_path)
+        .expect("Failed to read Reference Image")
+        .into_rgba8();
+    let diff_dir = golden_dir().join("diff");
+    std::fs::create_dir_all(&diff_dir).unwrap();
+    let actual_path = diff_dir.join(format!("{}.actual.png", name));
+
+    if reference.dimensions() != actual.dimensions() {
+        actual.save(&actual_path).unwrap();
+        panic!(
+            "{}: Rendered {:?} but the Reference is {:?}",
+            name,
+            actual.dimensions(),
+            reference.dimensions()
+        );
+    }
+
+    let comparison = compare(&reference, actual, tolerance);
+    let pixels = (reference.width() * reference.height()) as usize;
+    if comparison.differing as f32 > tolerance.max_differing * pixels as f32 {
+        let diff_path = diff_dir.join(format!("{}.diff.png", name));
+        actual.save(&actual_path).unwrap();
+        comparison.diff.save(&diff_path).unwrap();
+        panic!(
+            "{}: {} of {} Pixels differ by up to {}, See {}",
+            name,
+            comparison.differing,
+            pixels,
+            comparison.max_difference,
+            diff_path.display()
+        );
+    }
+}
+
+/// Checks for an Device before creating the Instance, So Machines without Vulkan Driver skip the Tests instead of failing
+fn has_vulkan_device() -> bool {
+    let Ok(entry) = (unsafe { ash::Entry::load() }) else {
+        return false;
+    };
+    let app_info = vk::ApplicationInfo::default().api_version(vk::API_VERSION_1_3);
+    let create_info = vk::InstanceCreateInfo::default().application_info(&app_info);
+    let Ok(instance) = (unsafe { entry.create_instance(&create_info, None) }) else {
+        return false;
+    };
+    let devices = unsafe { instance.enumerate_physical_devices() }.unwrap_or_default();
+    unsafe { instance.destroy_instance(None) };
+    !devices.is_empty()
+}
+
+/// An headless Instance and an 3D Camera
+struct Harness {
+    instance: VulkanInstance,
+    graph_resources: RenderGraphResources,
+    camera: Camera3D,
+}
+
+impl Harness {
+    fn new() -> Option<Self> {
+        if !has_vulkan_device() {
+            eprintln!("No Vulkan Device found, Skipping Golden Image Test");
+            return None;
+        }
+        let instance = VulkanInstance::new_headless(
+            &"Vent-Engine Golden Tests\0".to_owned(),
+            1,
+            (WIDTH, HEIGHT),
+        );
+        let camera = Camera3D::new(WIDTH as f32 / HEIGHT as f32);
+        Some(Self {
+            instance,
+            graph_resources: RenderGraphResources::new(),
+            camera,
+        })
+    }
+
+    fn look_at(&mut self, position: Vec3, target: Vec3) {
+        self.camera.position = position;
+        self.camera.direction = (target - position).normalize();
+        self.camera.recreate_view();
+        self.camera.calc_matrix();
+    }
+
+    /// Records one Frame as an single Graph Pass into the Swapchain Image and reads it back, `depth` also attaches the Depth Image
+    fn render(
+        &mut self,
+        depth: bool,
+        mut record: impl FnMut(&VulkanInstance, vk::CommandBuffer, &mut Camera3D),
+    ) -> RgbaImage {
+        let instance = &mut self.instance;
+        let (image_index, _) = instance.next_image().unwrap();
+        let frame = instance.current_frame();
+        let command_buffer = frame.command_buffer;
+        unsafe {
+            instance
+                .device
+                .reset_command_pool(frame.command_pool, vk::CommandPoolResetFlags::empty())
+                .unwrap();
+            let info = vk::CommandBufferBeginInfo::default()
+                .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+            instance
+                .device
+                .begin_command_buffer(command_buffer, &info)
+                .unwrap();
+        }
+        let frame_instance: &VulkanInstance = instance;
+        let camera = &mut self.camera;
+        let mut graph = RenderGraph::new();
+        let (color, depth_image) =
+            RawRuntimeRenderer::import_frame_images(frame_instance, &mut graph, image_index);
+        let mut pass =
+            GraphPass::new("Golden").color_attachment(color, AttachmentLoad::Clear(CLEAR_COLOR));
+        if depth {
+            pass = pass.depth_attachment(depth_image, AttachmentLoad::Clear(CLEAR_DEPTH));
+        }
+        graph.add_pass(pass.execute(|_, command_buffer| {
+            let render_area = vk::Rect2D::default().extent(frame_instance.surface_resolution);
+            let viewport = vk::Viewport::default()
+                .width(WIDTH as f32)
+                .height(HEIGHT as f32)
+                .max_depth(1.0);
+            unsafe {
+                frame_instance
+                    .device
+                    .cmd_set_scissor(command_buffer, 0, &[render_area]);
+                frame_instance
+                    .device
+                    .cmd_set_viewport(command_buffer, 0, &[viewport]);
+            }
+            record(frame_instance, command_buffer, camera);
+        }));
+        graph.execute(frame_instance, &mut self.graph_resources, command_buffer);
+        unsafe { instance.device.end_command_buffer(command_buffer).unwrap() };
+        instance.submit(image_index).unwrap();
+        instance
+            .read_back()
+            .expect("Headless Instance has no submitted Frame")
+    }
+
+    /// Renders the Skybox and the Models at `model_paths`
+    fn render_3d(&mut self, model_paths: &[&Path]) -> RgbaImage {
+        let mut renderer = Renderer3D::new(&mut self.instance);
+        for path in model_paths {
+            renderer.load_model(&mut self.instance, path);
+        }
+        let image = self.render(true, |instance, command_buffer, camera| {
+            renderer.render(instance, command_buffer, camera)
+        });
+        renderer.destroy(&self.instance);
+        image
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        unsafe { self.instance.device.device_wait_idle().unwrap() };
+        self.graph_resources.destroy(&self.instance.device);
+    }
+}
+
+/// An 2x2 Quad facing +Z with an Checkerboard Texture
+fn textured_quad() -> ModelData {
+    let checker = image::DynamicImage::ImageRgba8(RgbaImage::from_fn(8, 8, |x, y| {
+        if (x + y) % 2 == 0 {
+            Rgba([230, 40, 40, 255])
+        } else {
+            Rgba([240, 240, 240, 255])
+        }
+    }));
+    let primitive = PrimitiveData {
+        positions: vec![
+            [-1.0, -1.0, 0.0],
+            [1.0, -1.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [-1.0, 1.0, 0.0],
+        ],
+        normals: Some(vec![[0.0, 0.0, 1.0]; 4]),
+        tex_coords: Some(vec![[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]]),
+        tex_coords1: None,
+        tangents: None,
+        joints: None,
+        weights: None,
+        morph_targets: Vec::new(),
+        indices: vec![0, 1, 2, 0, 2, 3],
+        mode: PrimitiveMode::Triangles,
+        material_index: Some(0),
+        flat_normals: false,
+    };
+    let material = MaterialData {
+        name: Some("Checker".to_owned()),
+        base_color_texture: Some(TextureData {
+            image: TextureImage::Decoded(checker),
+            // Nearest keeps the Edges of the Squares sharp, So they do not depend on the Filtering of the Driver
+            sampler: vk::SamplerCreateInfo {
+                mag_filter: vk::Filter::NEAREST,
+                min_filter: vk::Filter::NEAREST,
+                ..Default::default()
+            },
+            tex_coord: 0,
+            transform: TextureTransform::default(),
+        }),
+        ..Default::default()
+    };
+    ModelData {
+        meshes: vec![MeshData {
+            name: Some("Quad".to_owned()),
+            primitives: vec![primitive],
+            weights: Vec::new(),
+        }],
+        materials: vec![material],
+        nodes: Vec::new(),
+        skins: Vec::new(),
+        animations: Vec::new(),
+        cameras: Vec::new(),
+        lights: Vec::new(),
+        scene_roots: Vec::new(),
+    }
+}
+
+#[test]
+fn golden_cube() {
+    let Some(mut harness) = Harness::new() else {
+        return;
+    };
+    harness.look_at(Vec3::new(3.0, 2.5, 4.0), Vec3::ZERO);
+    let cube = FileAsset::new("assets/models/basic/cube.glb");
+    let image = harness.render_3d(&[cube.root_path().as_path()]);
+    assert_golden("cube", &image, &DEFAULT_TOLERANCE);
+}
+
+#[test]
+fn golden_textured_quad() {
+    let Some(mut harness) = Harness::new() else {
+        return;
+    };
+    let path = std::env::temp_dir().join("vent_golden_textured_quad.glb");
+    GltfExporter::export(&textured_quad(), &path).expect("Failed to export the Quad");
+
+    harness.look_at(Vec3::new(0.0, 0.0, 3.0), Vec3::ZERO);
+    let image = harness.render_3d(&[path.as_path()]);
+    let _ = std::fs::remove_file(&path);
+    assert_golden("textured_quad", &image, &DEFAULT_TOLERANCE);
+}
+
+#[test]
+fn golden_skybox() {
+    let Some(mut harness) = Harness::new() else {
+        return;
+    };
+    harness.look_at(Vec3::ZERO, Vec3::new(1.0, 0.3, 0.5));
+    let image = harness.render_3d(&[]);
+    assert_golden("skybox", &image, &DEFAULT_TOLERANCE);
+}
+
+#[test]
+fn golden_text() {
+    let Some(mut harness) = Harness::new() else {
+        return;
+    };
+    let mut gui_renderer = GuiRenderer::new(&mut harness.instance);
+    let image = harness.render(false, |instance, command_buffer, _camera| {
+        gui_renderer.render_text(
+            instance,
+            command_buffer,
+            "Vent-Engine".to_string(),
+            10.0,
+            10.0,
+            1.0,
+            255255255,
+        )
+    });
+    gui_renderer.destroy(&harness.instance);
+    // Glyph Edges are anti aliased, Their Coverage differs more between Drivers
+    let tolerance = Tolerance {
+        channel: 24,
+        ..DEFAULT_TOLERANCE
+    };
+    assert_golden("text", &image, &tolerance);
+}
+
+#[test]
+fn compare_counts_pixels_outside_the_tolerance() {
+    let reference = RgbaImage::from_pixel(4, 4, Rgba([100, 100, 100, 255]));
+    let mut actual = reference.clone();
+    actual.put_pixel(0, 0, Rgba([104, 100, 100, 255]));
+    actual.put_pixel(1, 0, Rgba([100, 150, 100, 255]));
+
+    let comparison = compare(&reference, &actual, &DEFAULT_TOLERANCE);
+    assert_eq!(comparison.differing, 1);
+    assert_eq!(comparison.max_difference, 50);
+    assert_eq!(*comparison.diff.get_pixel(1, 0), Rgba([255, 0, 0, 255]));
+    assert_ne!(*comparison.diff.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+}
@@ -2,7 +2,13 @@ use std::time::{Duration, Instant};
 
 use ash::vk::{self};
 use serde::{Deserialize, Serialize};
-use vent_rendering::instance::VulkanInstance;
+use vent_rendering::{
+    instance::VulkanInstance,
+    render_graph::{
+        AttachmentLoad, GraphPass, ImageHandle, ImportedImage, RenderGraph, RenderGraphResources,
+        ResourceState,
+    },
+};
 use vent_ui::renderer::GuiRenderer;
 
 use crate::project::VentApplicationProject;
@@ -15,9 +21,13 @@ use self::gui::debug_gui::RenderData;
 pub mod camera;
 pub mod gui;
 pub mod model;
+pub mod scene;
 
 mod model_renderer;
 
+#[cfg(test)]
+mod golden;
+
 mod d2;
 mod d3;
 
@@ -34,6 +44,7 @@ impl DefaultRuntimeRenderer {
             settings.version.parse(),
             settings.render_settings.vsync,
             window,
+            settings.render_settings.device.as_deref(),
         );
         let dimension = &settings.render_settings.dimension;
         let window_size = window.size();
@@ -75,6 +86,19 @@ impl Drop for DefaultRuntimeRenderer {
     }
 }
 
+const CLEAR_COLOR: vk::ClearValue = vk::ClearValue {
+    color: vk::ClearColorValue {
+        float32: [0.2, 0.9, 1.0, 1.0],
+    },
+};
+
+const CLEAR_DEPTH: vk::ClearValue = vk::ClearValue {
+    depth_stencil: vk::ClearDepthStencilValue {
+        depth: 1.0,
+        stencil: 0,
+    },
+};
+
 #[derive(Serialize, Deserialize)]
 pub enum Dimension {
     D2,
@@ -93,10 +117,10 @@ pub trait Renderer {
         camera: &mut dyn Camera,
     );
 
+    /// Records into the current Frame in Flight, See `VulkanInstance::current_frame`
     fn render(
         &mut self,
         instance: &VulkanInstance,
-        image_index: u32,
         command_buffer: vk::CommandBuffer,
         camera: &mut dyn Camera,
     );
@@ -109,6 +133,7 @@ pub struct RawRuntimeRenderer {
     //  gui_renderer: GuiRenderer,
     multi_renderer: Box<dyn Renderer>,
     gui_renderer: GuiRenderer,
+    graph_resources: RenderGraphResources,
     current_data: RenderData,
 
     current_frames: u32,
@@ -137,6 +162,7 @@ impl RawRuntimeRenderer {
         Self {
             multi_renderer,
             gui_renderer,
+            graph_resources: RenderGraphResources::new(),
             current_frames: 0,
             current_data: RenderData::default(),
             last_fps: Instant::now(),
@@ -151,14 +177,14 @@ impl RawRuntimeRenderer {
 
         match image {
             Ok((image_index, _)) => {
-                let command_pool = instance.command_pools[image_index as usize];
+                let frame = instance.current_frame();
+                let command_buffer = frame.command_buffer;
                 unsafe {
                     instance
                         .device
-                        .reset_command_pool(command_pool, vk::CommandPoolResetFlags::empty())
+                        .reset_command_pool(frame.command_pool, vk::CommandPoolResetFlags::empty())
                         .unwrap();
                 }
-                let command_buffer = instance.command_buffers[image_index as usize];
                 unsafe {
                     let info = vk::CommandBufferBeginInfo::default()
                         .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
@@ -168,37 +194,49 @@ impl RawRuntimeRenderer {
                         .begin_command_buffer(command_buffer, &info)
                         .unwrap();
                 }
-                self.cmd_renderpass(instance, command_buffer, image_index as usize);
-
-                self.multi_renderer
-                    .render(instance, image_index, command_buffer, camera);
-
-                self.gui_renderer.render_text(
-                    instance,
-                    command_buffer,
-                    image_index as usize,
-                    "Abc".to_string(),
-                    10.0,
-                    10.0,
-                    0.5,
-                    255255255,
+                let frame_instance: &VulkanInstance = instance;
+                let mut graph = RenderGraph::new();
+                let (color, depth) =
+                    Self::import_frame_images(frame_instance, &mut graph, image_index);
+
+                let multi_renderer = &mut self.multi_renderer;
+                graph.add_pass(
+                    GraphPass::new("Scene")
+                        .color_attachment(color, AttachmentLoad::Clear(CLEAR_COLOR))
+                        .depth_attachment(depth, AttachmentLoad::Clear(CLEAR_DEPTH))
+                        .execute(|_, command_buffer| {
+                            multi_renderer.render(frame_instance, command_buffer, camera)
+                        }),
                 );
 
-                let subpass_end_info = vk::SubpassEndInfo::default();
-                unsafe {
-                    instance
-                        .device
-                        .cmd_end_render_pass2(command_buffer, &subpass_end_info)
-                };
+                let gui_renderer = &mut self.gui_renderer;
+                graph.add_pass(
+                    GraphPass::new("UI")
+                        .color_attachment(color, AttachmentLoad::Load)
+                        .execute(|_, command_buffer| {
+                            gui_renderer.render_text(
+                                frame_instance,
+                                command_buffer,
+                                "Abc".to_string(),
+                                10.0,
+                                10.0,
+                                0.5,
+                                255255255,
+                            )
+                        }),
+                );
+
+                graph.execute(frame_instance, &mut self.graph_resources, command_buffer);
+
                 unsafe { instance.device.end_command_buffer(command_buffer).unwrap() };
                 let result = instance.submit(image_index);
                 if let Err(vk::Result::ERROR_OUT_OF_DATE_KHR | vk::Result::SUBOPTIMAL_KHR) = result
                 {
-                    instance.recreate_swap_chain(None);
+                    self.recreate_swap_chain(instance);
                 }
             }
             Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
-                instance.recreate_swap_chain(None);
+                self.recreate_swap_chain(instance);
             }
             Err(_) => {}
         }
@@ -233,42 +271,41 @@ impl RawRuntimeRenderer {
         self.delta_time
     }
 
-    fn cmd_renderpass(
-        &self,
+    /// Imports the current Swapchain Image and the Depth Image into `graph`
+    pub(crate) fn import_frame_images(
         instance: &VulkanInstance,
-        command_buffer: vk::CommandBuffer,
-        image_index: usize,
-    ) {
-        let render_area = vk::Rect2D::default()
-            .offset(vk::Offset2D::default())
-            .extent(instance.surface_resolution);
-        let color_clear_value = vk::ClearValue {
-            color: vk::ClearColorValue {
-                float32: [0.2, 0.9, 1.0, 1.0],
-            },
-        };
-
-        let depth_clear_value = vk::ClearValue {
-            depth_stencil: vk::ClearDepthStencilValue {
-                depth: 1.0,
-                stencil: 0,
-            },
+        graph: &mut RenderGraph,
+        image_index: u32,
+    ) -> (ImageHandle, ImageHandle) {
+        let final_layout = if instance.headless {
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL
+        } else {
+            vk::ImageLayout::PRESENT_SRC_KHR
         };
+        let color = graph.import_image(ImportedImage {
+            image: instance.swapchain_images[image_index as usize],
+            image_view: instance.swapchain_image_views[image_index as usize],
+            format: instance.surface_format.format,
+            extent: instance.surface_resolution,
+            initial_state: ResourceState::ACQUIRED,
+            final_layout: Some(final_layout),
+        });
+        // The Depth Image is shared by all Frames in Flight, So earlier Frames may still use it
+        let depth = graph.import_image(ImportedImage {
+            image: instance.depth_image.image,
+            image_view: instance.depth_image.image_view,
+            format: instance.depth_format,
+            extent: instance.surface_resolution,
+            initial_state: ResourceState::UNDEFINED,
+            final_layout: None,
+        });
+        (color, depth)
+    }
 
-        let clear_values = &[color_clear_value, depth_clear_value];
-
-        let info = vk::RenderPassBeginInfo::default()
-            .render_pass(instance.render_pass)
-            .framebuffer(instance.frame_buffers[image_index])
-            .render_area(render_area)
-            .clear_values(clear_values);
-        let subpass_info = vk::SubpassBeginInfo::default().contents(vk::SubpassContents::INLINE);
-
-        unsafe {
-            instance
-                .device
-                .cmd_begin_render_pass2(command_buffer, &info, &subpass_info)
-        };
+    /// Transient Images of the Render Graph may have the old Extent
+    fn recreate_swap_chain(&mut self, instance: &mut VulkanInstance) {
+        instance.recreate_swap_chain(None);
+        self.graph_resources.destroy_later(instance);
     }
 
     fn calc_render_data(&mut self, frame_start: Instant) -> RenderData {
@@ -301,12 +338,14 @@ impl RawRuntimeRenderer {
     ) {
         // Uses the NEW Resized config
         instance.recreate_swap_chain(Some(new_size));
+        self.graph_resources.destroy_later(instance);
         self.multi_renderer.resize(instance, new_size, camera)
     }
 
     pub fn destroy(&mut self, instance: &VulkanInstance) {
         self.multi_renderer.destroy(instance);
-        self.gui_renderer.destroy(&instance.device);
+        self.gui_renderer.destroy(instance);
+        self.graph_resources.destroy(&instance.device);
         // TODO Egui destroy
     }
 }
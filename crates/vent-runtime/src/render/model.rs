@@ -1,15 +1,37 @@
+use vent_assets::animation::{AnimationPlayer, MorphWeights, Pose};
 use vent_math::scalar::{mat4::Mat4, quat::Quat};
 
+use super::d3::deformation::Deformation;
+
 pub struct Entity3D {
     pub model: vent_assets::Model3D,
     pub transformation_matrix: Mat4,
+    pub animation: AnimationPlayer,
+    /// Local Transform of every Node, Overwritten while an Animation is playing
+    pub pose: Pose,
+    /// Transform of every Node relative to the Model, Calculated from `pose`
+    pub node_transforms: Vec<Mat4>,
+    /// Starts with the Default Weights of the Model, Animations overwrite them
+    pub morph_weights: MorphWeights,
+    /// Only set when the Model has Skins or Morph Targets
+    pub deformation: Option<Deformation>,
 }
 
 impl Entity3D {
     pub fn new(model: vent_assets::Model3D) -> Self {
+        let pose = model.skeleton.rest_pose();
+        let mut node_transforms = Vec::new();
+        model
+            .skeleton
+            .global_transforms(&pose, &mut node_transforms);
         Self {
             transformation_matrix: Self::calc_trans_matrix(&model),
+            morph_weights: model.morph_weights.clone(),
+            pose,
+            node_transforms,
             model,
+            animation: AnimationPlayer::default(),
+            deformation: None,
         }
     }
 
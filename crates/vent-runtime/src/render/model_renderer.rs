@@ -1,9 +1,18 @@
 use ash::vk::{self};
-use std::collections::HashMap;
+use std::{collections::HashMap, mem::size_of};
+use vent_assets::model::lod;
 use vent_ecs::entity::Entity;
-use vent_rendering::instance::VulkanInstance;
+use vent_math::{
+    scalar::mat4::Mat4,
+    vec::{vec3::Vec3, vec4::Vec4},
+};
+use vent_rendering::{any_as_u8_slice, instance::VulkanInstance, mesh::Mesh3D};
 
-use super::{camera::Camera3D, model::Entity3D};
+use super::{
+    camera::Camera3D,
+    d3::{bindless::BindlessMaterials, deformation::Deformation, Camera3DData, MeshConstants},
+    model::Entity3D,
+};
 
 #[derive(Default)]
 pub struct ModelRenderer3D {
@@ -46,32 +55,139 @@ impl ModelRenderer3D {
         self.map.iter_mut()
     }
 
+    /// Advances the Animations by `delta` Seconds, Updates the Node Transforms and pushes the Joint Matrices and Morph Weights of deformed Models
+    pub fn update_animations(&mut self, instance: &VulkanInstance, delta: f32) {
+        for entity in self.map.values_mut() {
+            let model = &entity.model;
+            if entity.animation.is_playing() {
+                entity.animation.update(delta, &model.animations);
+                entity
+                    .animation
+                    .sample(&model.animations, &model.skeleton, &mut entity.pose);
+            }
+            model
+                .skeleton
+                .global_transforms(&entity.pose, &mut entity.node_transforms);
+            if let Some(deformation) = &mut entity.deformation {
+                deformation.update(
+                    instance,
+                    model,
+                    &entity.pose,
+                    &entity.node_transforms,
+                    &mut entity.morph_weights,
+                );
+            }
+        }
+    }
+
+    /// Models without Skins and Morph Targets bind `default_deformation`, Since the Shader always reads Set 1.
+    /// With `bindless` the Material Index is pushed instead of binding the Material Descriptor Set
     pub fn record_buffer(
         &self,
         instance: &VulkanInstance,
         command_buffer: vk::CommandBuffer,
-        buffer_index: usize,
         pipeline_layout: vk::PipelineLayout,
         camera: &mut Camera3D,
+        default_deformation: &Deformation,
+        bindless: Option<&BindlessMaterials>,
     ) {
         for model in self.map.values() {
-            camera.transformation = Entity3D::calc_trans_matrix(&model.model);
-            camera.calc_matrix();
-            camera.write(instance, pipeline_layout, command_buffer);
-
+            model
+                .deformation
+                .as_ref()
+                .unwrap_or(default_deformation)
+                .bind(&instance.device, command_buffer, pipeline_layout);
+            let model_transformation = Entity3D::calc_trans_matrix(&model.model);
             model.model.draw(
                 &instance.device,
-                pipeline_layout,
                 command_buffer,
-                buffer_index,
-                true,
+                |material| match bindless {
+                    Some(bindless) => bindless.push_material(
+                        &instance.device,
+                        command_buffer,
+                        pipeline_layout,
+                        material,
+                    ),
+                    None => {
+                        if let Some(descriptor_set) = material.descriptor_set {
+                            unsafe {
+                                instance.device.cmd_bind_descriptor_sets(
+                                    command_buffer,
+                                    vk::PipelineBindPoint::GRAPHICS,
+                                    pipeline_layout,
+                                    0,
+                                    &[descriptor_set],
+                                    &[],
+                                )
+                            }
+                        }
+                    }
+                },
+                |mesh, node| {
+                    // Joint Matrices already move skinned Vertices into Model Space
+                    let node_transformation = node
+                        .filter(|&node| !model.model.scene.is_skinned(node))
+                        .and_then(|node| model.node_transforms.get(node));
+                    let transformation = match node_transformation {
+                        Some(node_transformation) => model_transformation * *node_transformation,
+                        None => model_transformation,
+                    };
+                    camera.transformation = transformation;
+                    camera.calc_matrix();
+                    camera.write(instance, pipeline_layout, command_buffer);
+                    unsafe {
+                        instance.device.cmd_push_constants(
+                            command_buffer,
+                            pipeline_layout,
+                            vk::ShaderStageFlags::VERTEX,
+                            size_of::<Camera3DData>() as u32,
+                            any_as_u8_slice(&MeshConstants::new(mesh)),
+                        )
+                    }
+                    Self::select_lod(mesh, &transformation, camera)
+                },
             );
         }
     }
 
-    pub fn destroy_all(&mut self, device: &ash::Device) {
+    /// Selects the LOD of an Mesh based on the projected size of its Bounding Sphere
+    fn select_lod(mesh: &Mesh3D, transformation: &Mat4, camera: &Camera3D) -> usize {
+        let Some(sphere) = mesh.bounding_sphere else {
+            return 0;
+        };
+        let center = *transformation * Vec4::from((Vec3::from(sphere.center), 1.0));
+        let center = Vec3::new(center.x, center.y, center.z);
+
+        let axis_length = |axis: &Vec4| Vec3::new(axis.x, axis.y, axis.z).length();
+        let max_scale = axis_length(&transformation.x_axis)
+            .max(axis_length(&transformation.y_axis))
+            .max(axis_length(&transformation.z_axis));
+        let radius = sphere.radius * max_scale;
+
+        let distance = (center - camera.position).length();
+        if distance <= radius {
+            // Camera is inside the Mesh
+            return 0;
+        }
+        let screen_fraction = radius * camera.projection.y_axis.y.abs() / distance;
+        lod::select_lod(screen_fraction, mesh.lod_count())
+    }
+
+    /// Gives the Material Descriptor Sets or Bindless Slots back before destroying the Models
+    pub fn destroy_all(&mut self, instance: &VulkanInstance, bindless: Option<&BindlessMaterials>) {
         for model in self.map.values_mut() {
-            model.model.destroy(device)
+            for material in &mut model.model.materials {
+                if let Some(descriptor_set) = material.descriptor_set.take() {
+                    instance.descriptor_allocator.free(&[descriptor_set]);
+                }
+                if let Some(bindless) = bindless {
+                    bindless.remove(instance, material);
+                }
+            }
+            model.model.destroy(&instance.device);
+            if let Some(deformation) = &mut model.deformation {
+                deformation.destroy(instance);
+            }
         }
     }
 }
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use vent_assets::{
+    animation::Transform,
+    model::data::{CameraData, LightData},
+};
+use vent_ecs::{entity::Entity, world::World};
+use vent_math::scalar::mat4::Mat4;
+
+use super::{model::Entity3D, model_renderer::ModelRenderer3D};
+
+/// An Node of an Model Scene which was spawned as its own Entity
+pub struct SceneNode3D {
+    pub name: Option<String>,
+    /// Entity of the Model, The Meshes of the Node are drawn by it
+    pub model: Entity,
+    /// Index into the Nodes of the Model
+    pub node: usize,
+    pub parent: Option<Entity>,
+    pub children: Vec<Entity>,
+    /// Relative to the Parent
+    pub transform: Transform,
+    /// Relative to the World
+    pub global_transform: Mat4,
+    pub camera: Option<CameraData>,
+    pub light: Option<LightData>,
+}
+
+/// Every spawned Scene Node, The Nodes follow the Transform and Animations of their Model
+#[derive(Default)]
+pub struct SceneGraph3D {
+    map: HashMap<Entity, SceneNode3D>,
+}
+
+#[allow(dead_code)]
+impl SceneGraph3D {
+    /// Creates an Entity for every Node of the shown Scene of an Model and returns the Root Entities.
+    /// Parents and Children are kept, So every Node keeps its Transform relative to its Parent
+    pub fn spawn(
+        &mut self,
+        world: &mut World,
+        model_entity: Entity,
+        model: &Entity3D,
+    ) -> Vec<Entity> {
+        let scene = &model.model.scene;
+        let skeleton = &model.model.skeleton;
+        let model_transformation = Entity3D::calc_trans_matrix(&model.model);
+
+        let mut entities: Vec<Option<Entity>> = vec![None; scene.nodes.len()];
+        let mut roots = Vec::with_capacity(scene.roots.len());
+        // Parent Entity and Node
+        let mut stack: Vec<(Option<Entity>, usize)> =
+            scene.roots.iter().map(|&root| (None, root)).collect();
+        while let Some((parent, node)) = stack.pop() {
+            // Nodes can only have one Parent, But broken Files could still reference them twice
+            if entities[node].is_some() {
+                continue;
+            }
+            let entity = world.create_entity();
+            entities[node] = Some(entity);
+            match parent.and_then(|parent| self.map.get_mut(&parent)) {
+                Some(parent) => parent.children.push(entity),
+                None => roots.push(entity),
+            }
+
+            let scene_node = &scene.nodes[node];
+            self.map.insert(
+                entity,
+                SceneNode3D {
+                    name: skeleton.nodes[node].name.clone(),
+                    model: model_entity,
+                    node,
+                    parent,
+                    children: Vec::with_capacity(scene_node.children.len()),
+                    transform: model
+                        .pose
+                        .transforms
+                        .get(node)
+                        .copied()
+                        .unwrap_or(skeleton.nodes[node].rest),
+                    global_transform: model_transformation * model.node_transforms[node],
+                    camera: scene_node
+                        .camera
+                        .map(|camera| scene.cameras[camera].clone()),
+                    light: scene_node.light.map(|light| scene.lights[light].clone()),
+                },
+            );
+            stack.extend(
+                scene_node
+                    .children
+                    .iter()
+                    .rev()
+                    .map(|&child| (Some(entity), child)),
+            );
+        }
+        roots
+    }
+
+    /// Copies the Transforms from the Models, So the Nodes follow Animations and moved Models
+    pub fn update(&mut self, models: &ModelRenderer3D) {
+        for scene_node in self.map.values_mut() {
+            let Some(model) = models.get(scene_node.model) else {
+                continue;
+            };
+            if let Some(transform) = model.pose.transforms.get(scene_node.node) {
+                scene_node.transform = *transform;
+            }
+            if let Some(node_transformation) = model.node_transforms.get(scene_node.node) {
+                scene_node.global_transform =
+                    Entity3D::calc_trans_matrix(&model.model) * *node_transformation;
+            }
+        }
+    }
+
+    /// Removes every Node which was spawned for `model`
+    pub fn despawn(&mut self, world: &mut World, model: Entity) {
+        self.map.retain(|&entity, scene_node| {
+            if scene_node.model != model {
+                return true;
+            }
+            let _ = world.delete_entity(entity);
+            false
+        });
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn get(&self, entity: Entity) -> Option<&SceneNode3D> {
+        self.map.get(&entity)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn iter(&self) -> std::collections::hash_map::Iter<'_, Entity, SceneNode3D> {
+        self.map.iter()
+    }
+
+    /// Every spawned Node with an Camera
+    pub fn cameras(&self) -> impl Iterator<Item = (&Entity, &SceneNode3D)> {
+        self.map.iter().filter(|(_, node)| node.camera.is_some())
+    }
+
+    /// Every spawned Node with an Light
+    pub fn lights(&self) -> impl Iterator<Item = (&Entity, &SceneNode3D)> {
+        self.map.iter().filter(|(_, node)| node.light.is_some())
+    }
+}
@@ -0,0 +1,97 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+/// SPIR-V Magic Number, Used to reject broken Cache Files
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+///
+/// Compiled SPIR-V by the Hash of the preprocessed Source, Kept in Memory and in an Directory.
+///
+/// The Key only covers the Source, So the Directory should be cleared after updating shaderc or naga
+///
+pub struct ShaderCache {
+    dir: PathBuf,
+    memory: HashMap<u64, Vec<u32>>,
+}
+
+impl ShaderCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            memory: HashMap::new(),
+        }
+    }
+
+    fn file(&self, key: u64) -> PathBuf {
+        self.dir.join(format!("{:016x}.spv", key))
+    }
+
+    pub fn get(&mut self, key: u64) -> Option<Vec<u32>> {
+        if let Some(spirv) = self.memory.get(&key) {
+            return Some(spirv.clone());
+        }
+        let bytes = fs::read(self.file(key)).ok()?;
+        if bytes.len() % 4 != 0 {
+            return None;
+        }
+        let spirv: Vec<u32> = bytes
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+            .collect();
+        if spirv.first() != Some(&SPIRV_MAGIC) {
+            return None;
+        }
+        self.memory.insert(key, spirv.clone());
+        Some(spirv)
+    }
+
+    /// Failing to write the File is not an Error, The Shader is just compiled again next Time
+    pub fn insert(&mut self, key: u64, spirv: &[u32]) {
+        let bytes: Vec<u8> = spirv.iter().flat_map(|word| word.to_le_bytes()).collect();
+        if let Err(err) =
+            fs::create_dir_all(&self.dir).and_then(|_| fs::write(self.file(key), bytes))
+        {
+            log::warn!(
+                "Failed to write Shader Cache {}: {}",
+                self.dir.display(),
+                err
+            );
+        }
+        self.memory.insert(key, spirv.to_vec());
+    }
+}
+
+/// FNV-1a, Unlike `DefaultHasher` it is stable between Rust Versions which matters for the Files on Disk
+pub(crate) fn hash(parts: &[&[u8]]) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    parts.iter().fold(OFFSET, |hash, part| {
+        // Separate the Parts, So ("ab", "c") and ("a", "bc") differ
+        let hash = part
+            .iter()
+            .fold(hash, |hash, byte| (hash ^ *byte as u64).wrapping_mul(PRIME));
+        (hash ^ 0xff).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_round_trip() {
+        let dir = std::env::temp_dir().join(format!("vent-shader-cache-{}", std::process::id()));
+        let spirv = vec![SPIRV_MAGIC, 0x0001_0000, 0, 1, 0];
+        let key = hash(&[b"vert", b"void main() {}"]);
+        assert_ne!(key, hash(&[b"frag", b"void main() {}"]));
+
+        ShaderCache::new(&dir).insert(key, &spirv);
+        // An new Cache only has the File
+        let mut cache = ShaderCache::new(&dir);
+        assert_eq!(cache.get(key), Some(spirv));
+        assert_eq!(cache.get(key + 1), None);
+
+        fs::write(cache.file(key + 1), [0u8; 8]).unwrap();
+        assert_eq!(cache.get(key + 1), None);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
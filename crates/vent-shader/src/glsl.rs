@@ -0,0 +1,152 @@
+use std::{
+    cell::RefCell,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use shaderc::{CompileOptions, IncludeType, ResolvedInclude, ShaderKind};
+
+use crate::{include, Diagnostic, ShaderError};
+
+/// Options with the Defines and an Include Callback which records every included File into `files`
+fn options<'a>(
+    defines: &[(&str, &str)],
+    include_dirs: &'a [PathBuf],
+    files: &'a RefCell<Vec<PathBuf>>,
+) -> CompileOptions<'a> {
+    let mut options = CompileOptions::new().expect("Failed to create shaderc Options");
+    for (name, value) in defines {
+        options.add_macro_definition(name, (!value.is_empty()).then_some(*value));
+    }
+    options.set_include_callback(move |name, include_type, requesting, _depth| {
+        let relative = include_type == IncludeType::Relative;
+        let path = include::resolve(name, Path::new(requesting), relative, include_dirs)
+            .ok_or_else(|| format!("Include {} not found", name))?;
+        let content = fs::read_to_string(&path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        let resolved_name = path.display().to_string();
+        let mut files = files.borrow_mut();
+        if !files.contains(&path) {
+            files.push(path);
+        }
+        Ok(ResolvedInclude {
+            resolved_name,
+            content,
+        })
+    });
+    options
+}
+
+/// Resolves Includes and Defines, The Result is what the Cache Key is built from. Also returns the Shader and every included File
+pub(crate) fn preprocess(
+    compiler: &mut shaderc::Compiler,
+    path: &Path,
+    source: &str,
+    defines: &[(&str, &str)],
+    include_dirs: &[PathBuf],
+) -> Result<(String, Vec<PathBuf>), ShaderError> {
+    let files = RefCell::new(vec![path.to_path_buf()]);
+    let preprocessed = {
+        let options = options(defines, include_dirs, &files);
+        compiler
+            .preprocess(source, &path.display().to_string(), "main", Some(&options))
+            .map_err(|err| to_shader_error(err, path))?
+            .as_text()
+    };
+    Ok((preprocessed, files.into_inner()))
+}
+
+/// Returns the SPIR-V and the Warnings
+pub(crate) fn compile(
+    compiler: &mut shaderc::Compiler,
+    path: &Path,
+    source: &str,
+    kind: ShaderKind,
+    defines: &[(&str, &str)],
+    include_dirs: &[PathBuf],
+) -> Result<(Vec<u32>, Vec<Diagnostic>), ShaderError> {
+    let files = RefCell::new(Vec::new());
+    let options = options(defines, include_dirs, &files);
+    let artifact = compiler
+        .compile_into_spirv(
+            source,
+            kind,
+            &path.display().to_string(),
+            "main",
+            Some(&options),
+        )
+        .map_err(|err| to_shader_error(err, path))?;
+    let warnings = if artifact.get_num_warnings() > 0 {
+        parse_diagnostics(&artifact.get_warning_messages(), path)
+    } else {
+        Vec::new()
+    };
+    Ok((artifact.as_binary().to_vec(), warnings))
+}
+
+fn to_shader_error(err: shaderc::Error, path: &Path) -> ShaderError {
+    let log = match err {
+        shaderc::Error::CompilationError(_, log) => log,
+        err => err.to_string(),
+    };
+    ShaderError::Compile(parse_diagnostics(&log, path))
+}
+
+/// glslang reports `file:line: error: message`, Lines without an Location are reported for the Shader itself
+fn parse_diagnostics(log: &str, path: &Path) -> Vec<Diagnostic> {
+    log.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            parse_diagnostic(line).unwrap_or_else(|| Diagnostic {
+                file: path.to_path_buf(),
+                line: None,
+                message: line.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn parse_diagnostic(line: &str) -> Option<Diagnostic> {
+    // Split at the first ": " and not at ':', Windows Paths contain an ':' after the Drive
+    let (location, message) = line.split_once(": ")?;
+    let (file, line_number) = location.rsplit_once(':')?;
+    Some(Diagnostic {
+        file: PathBuf::from(file),
+        line: Some(line_number.parse().ok()?),
+        message: message.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glslang_diagnostics() {
+        let path = Path::new("C:\\shaders\\shader.frag");
+        let log = "C:\\shaders\\shader.frag:12: error: 'foo' : undeclared identifier\n\
+                   C:\\shaders\\common.glsl:3: warning: unused\n\
+                   1 error generated.\n";
+        assert_eq!(
+            parse_diagnostics(log, path),
+            [
+                Diagnostic {
+                    file: path.to_path_buf(),
+                    line: Some(12),
+                    message: "error: 'foo' : undeclared identifier".to_string(),
+                },
+                Diagnostic {
+                    file: PathBuf::from("C:\\shaders\\common.glsl"),
+                    line: Some(3),
+                    message: "warning: unused".to_string(),
+                },
+                Diagnostic {
+                    file: path.to_path_buf(),
+                    line: None,
+                    message: "1 error generated.".to_string(),
+                },
+            ]
+        );
+    }
+}
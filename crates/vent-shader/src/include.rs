@@ -0,0 +1,196 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{Diagnostic, ShaderError};
+
+/// Finds the File of an `#include`, `relative` Includes (`"file"`) next to the including File, Others (`<file>`) in the Include Directories
+pub(crate) fn resolve(
+    name: &str,
+    requesting: &Path,
+    relative: bool,
+    include_dirs: &[PathBuf],
+) -> Option<PathBuf> {
+    if relative {
+        let path = requesting.parent().unwrap_or(Path::new("")).join(name);
+        return path.is_file().then_some(path);
+    }
+    include_dirs
+        .iter()
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// Parses `#include "file"` and `#include <file>`, Returns the Name and if it is relative
+fn parse_include(line: &str) -> Option<Result<(&str, bool), ()>> {
+    let rest = line.trim_start().strip_prefix("#include")?.trim();
+    let include = if let Some(name) = rest.strip_prefix('"').and_then(|r| r.strip_suffix('"')) {
+        Ok((name, true))
+    } else if let Some(name) = rest.strip_prefix('<').and_then(|r| r.strip_suffix('>')) {
+        Ok((name, false))
+    } else {
+        Err(())
+    };
+    Some(include)
+}
+
+/// An Source with all Includes pasted in
+pub(crate) struct Expanded {
+    pub source: String,
+    /// Index into `files` and Line for every Line of `source`, `None` for generated Lines
+    pub lines: Vec<Option<(usize, u32)>>,
+    /// The Root File first
+    pub files: Vec<PathBuf>,
+}
+
+impl Expanded {
+    /// Maps an Line of `source` back to the File it came from
+    pub fn diagnostic(&self, line: Option<u32>, message: String) -> Diagnostic {
+        match line.and_then(|line| {
+            self.lines
+                .get((line as usize).checked_sub(1)?)
+                .copied()
+                .flatten()
+        }) {
+            Some((file, line)) => Diagnostic {
+                file: self.files[file].clone(),
+                line: Some(line),
+                message,
+            },
+            None => Diagnostic {
+                file: self.files[0].clone(),
+                line: None,
+                message,
+            },
+        }
+    }
+}
+
+///
+/// Pastes Includes into the Source for Languages without an Preprocessor (WGSL).
+/// Every File is only included once, Like `#pragma once` in GLSL, So two Files can include the same Helpers.
+/// Defines are put in front as `const` Declarations
+///
+pub(crate) fn expand(
+    path: &Path,
+    source: &str,
+    defines: &[(&str, &str)],
+    include_dirs: &[PathBuf],
+) -> Result<Expanded, ShaderError> {
+    let mut expanded = Expanded {
+        source: String::new(),
+        lines: Vec::new(),
+        files: vec![path.to_path_buf()],
+    };
+    for (name, value) in defines {
+        let value = if value.is_empty() { "true" } else { value };
+        expanded
+            .source
+            .push_str(&format!("const {} = {};\n", name, value));
+        expanded.lines.push(None);
+    }
+    let mut errors = Vec::new();
+    expand_file(&mut expanded, 0, source, include_dirs, &mut errors);
+    if errors.is_empty() {
+        Ok(expanded)
+    } else {
+        Err(ShaderError::Compile(errors))
+    }
+}
+
+fn expand_file(
+    expanded: &mut Expanded,
+    file: usize,
+    source: &str,
+    include_dirs: &[PathBuf],
+    errors: &mut Vec<Diagnostic>,
+) {
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i as u32 + 1;
+        let Some(include) = parse_include(line) else {
+            expanded.source.push_str(line);
+            expanded.source.push('\n');
+            expanded.lines.push(Some((file, line_number)));
+            continue;
+        };
+        let diagnostic = |message: String| Diagnostic {
+            file: expanded.files[file].clone(),
+            line: Some(line_number),
+            message,
+        };
+        let Ok((name, relative)) = include else {
+            errors.push(diagnostic(format!(
+                "Expected #include \"file\" or #include <file>, found {}",
+                line.trim()
+            )));
+            continue;
+        };
+        let Some(include_path) = resolve(name, &expanded.files[file], relative, include_dirs)
+        else {
+            errors.push(diagnostic(format!("Include {} not found", name)));
+            continue;
+        };
+        if expanded.files.contains(&include_path) {
+            continue;
+        }
+        match fs::read_to_string(&include_path) {
+            Ok(include_source) => {
+                expanded.files.push(include_path);
+                let include_file = expanded.files.len() - 1;
+                expand_file(
+                    expanded,
+                    include_file,
+                    &include_source,
+                    include_dirs,
+                    errors,
+                );
+            }
+            Err(err) => errors.push(diagnostic(format!(
+                "Failed to read {}: {}",
+                include_path.display(),
+                err
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_includes() {
+        let dir = std::env::temp_dir().join(format!("vent-shader-include-{}", std::process::id()));
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        fs::write(dir.join("common.wgsl"), "fn a() {}\n#include <b.wgsl>\n").unwrap();
+        fs::write(dir.join("lib/b.wgsl"), "fn b() {}\n").unwrap();
+        let root = dir.join("main.wgsl");
+        let source = "#include \"common.wgsl\"\n#include <b.wgsl>\nfn main() {}\n";
+
+        let expanded = expand(&root, source, &[("COUNT", "4")], &[dir.join("lib")]).unwrap();
+        assert_eq!(
+            expanded.source,
+            "const COUNT = 4;\nfn a() {}\nfn b() {}\nfn main() {}\n"
+        );
+        assert_eq!(
+            expanded.files,
+            [
+                root.clone(),
+                dir.join("common.wgsl"),
+                dir.join("lib/b.wgsl")
+            ]
+        );
+        let error = expanded.diagnostic(Some(3), String::new());
+        assert_eq!((error.file, error.line), (dir.join("lib/b.wgsl"), Some(1)));
+        assert_eq!(expanded.diagnostic(Some(4), String::new()).line, Some(3));
+
+        let Err(ShaderError::Compile(errors)) =
+            expand(&root, "\n#include \"missing.wgsl\"", &[], &[])
+        else {
+            panic!("Missing Include was not reported");
+        };
+        assert_eq!((errors[0].file.clone(), errors[0].line), (root, Some(2)));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,248 @@
+//! Compiles GLSL and WGSL Shaders into SPIR-V in Process.
+//! Used by the Build Script of the Runtime and the Foundation for reloading Shaders while running
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+mod cache;
+mod glsl;
+mod include;
+mod wgsl;
+
+pub use cache::ShaderCache;
+
+/// Language of an Shader Source, Taken from the File Extension
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ShaderLanguage {
+    Glsl(shaderc::ShaderKind),
+    /// An WGSL Module can contain multiple Stages, All Entry Points are compiled
+    Wgsl,
+}
+
+impl ShaderLanguage {
+    /// `None` for Files which are no Shader Stage, e.g. `.glsl` Files which are only included
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let kind = match path.extension()?.to_str()? {
+            "vert" => shaderc::ShaderKind::Vertex,
+            "frag" => shaderc::ShaderKind::Fragment,
+            "comp" => shaderc::ShaderKind::Compute,
+            "geom" => shaderc::ShaderKind::Geometry,
+            "tesc" => shaderc::ShaderKind::TessControl,
+            "tese" => shaderc::ShaderKind::TessEvaluation,
+            "wgsl" => return Some(Self::Wgsl),
+            _ => return None,
+        };
+        Some(Self::Glsl(kind))
+    }
+}
+
+/// An Error or Warning at an Location in an Shader Source
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Diagnostic {
+    /// The File the Error is in, Can also be an included File
+    pub file: PathBuf,
+    /// Starts at 1, `None` when the Compiler reported no Line
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file.display(), line, self.message),
+            None => write!(f, "{}: {}", self.file.display(), self.message),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ShaderError {
+    /// The Shader could not be read
+    Io(PathBuf, io::Error),
+    /// The File Extension names no Shader Stage or Language
+    Unsupported(PathBuf),
+    /// The Source is invalid, Contains every Error the Compiler found
+    Compile(Vec<Diagnostic>),
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "Failed to read {}: {}", path.display(), err),
+            Self::Unsupported(path) => write!(f, "Unsupported Shader {}", path.display()),
+            Self::Compile(diagnostics) => {
+                for (i, diagnostic) in diagnostics.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{}", diagnostic)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_, err) => Some(err),
+            Self::Unsupported(_) | Self::Compile(_) => None,
+        }
+    }
+}
+
+pub struct CompiledShader {
+    pub spirv: Vec<u32>,
+    /// The Shader and every File it includes, Watch these to know when the Shader has to be compiled again
+    pub files: Vec<PathBuf>,
+    /// Always empty when the SPIR-V came from the Cache
+    pub warnings: Vec<Diagnostic>,
+}
+
+///
+/// Compiles Shader Files with `#include` Support, GLSL is compiled with shaderc and WGSL with naga.
+///
+/// `#include "file"` is looked up next to the including File, `#include <file>` in the Include Directories.
+/// Defines are passed to the GLSL Preprocessor, For WGSL which has no Preprocessor they become `const` Declarations,
+/// So `("SKINNED", "")` is `const SKINNED = true;` and `("LIGHTS", "4")` is `const LIGHTS = 4;`
+///
+pub struct ShaderCompiler {
+    compiler: shaderc::Compiler,
+    include_dirs: Vec<PathBuf>,
+    cache: Option<ShaderCache>,
+}
+
+impl Default for ShaderCompiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShaderCompiler {
+    pub fn new() -> Self {
+        Self {
+            compiler: shaderc::Compiler::new().expect("Failed to create shaderc Compiler"),
+            include_dirs: Vec::new(),
+            cache: None,
+        }
+    }
+
+    /// Searched for `#include <file>`
+    pub fn include_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.include_dirs.push(dir.into());
+        self
+    }
+
+    /// Skips compiling Shaders whose preprocessed Source was already compiled
+    pub fn cache(mut self, cache: ShaderCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Compiles the Shader at `path` with the given Defines, Every Permutation of an Shader is cached on its own
+    pub fn compile(
+        &mut self,
+        path: &Path,
+        defines: &[(&str, &str)],
+    ) -> Result<CompiledShader, ShaderError> {
+        let language =
+            ShaderLanguage::from_path(path).ok_or_else(|| ShaderError::Unsupported(path.into()))?;
+        let source =
+            fs::read_to_string(path).map_err(|err| ShaderError::Io(path.to_path_buf(), err))?;
+
+        match language {
+            ShaderLanguage::Glsl(kind) => {
+                let (preprocessed, files) = glsl::preprocess(
+                    &mut self.compiler,
+                    path,
+                    &source,
+                    defines,
+                    &self.include_dirs,
+                )?;
+                let key = cache::hash(&[format!("{:?}", kind).as_bytes(), preprocessed.as_bytes()]);
+                if let Some(spirv) = self.cache.as_mut().and_then(|cache| cache.get(key)) {
+                    return Ok(CompiledShader {
+                        spirv,
+                        files,
+                        warnings: Vec::new(),
+                    });
+                }
+                let (spirv, warnings) = glsl::compile(
+                    &mut self.compiler,
+                    path,
+                    &source,
+                    kind,
+                    defines,
+                    &self.include_dirs,
+                )?;
+                if let Some(cache) = &mut self.cache {
+                    cache.insert(key, &spirv);
+                }
+                Ok(CompiledShader {
+                    spirv,
+                    files,
+                    warnings,
+                })
+            }
+            ShaderLanguage::Wgsl => {
+                let expanded = include::expand(path, &source, defines, &self.include_dirs)?;
+                let key = cache::hash(&[b"wgsl", expanded.source.as_bytes()]);
+                let files = expanded.files.clone();
+                if let Some(spirv) = self.cache.as_mut().and_then(|cache| cache.get(key)) {
+                    return Ok(CompiledShader {
+                        spirv,
+                        files,
+                        warnings: Vec::new(),
+                    });
+                }
+                let spirv = wgsl::compile(&expanded)?;
+                if let Some(cache) = &mut self.cache {
+                    cache.insert(key, &spirv);
+                }
+                Ok(CompiledShader {
+                    spirv,
+                    files,
+                    warnings: Vec::new(),
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compile_wgsl() {
+        let dir = std::env::temp_dir().join(format!("vent-shader-wgsl-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("color.wgsl"),
+            "fn color() -> vec4<f32> {\n    return vec4<f32>(BRIGHTNESS);\n}\n",
+        )
+        .unwrap();
+        let shader = dir.join("shader.wgsl");
+        fs::write(
+            &shader,
+            "#include \"color.wgsl\"\n\n@fragment\nfn main() -> @location(0) vec4<f32> {\n    return color();\n}\n",
+        )
+        .unwrap();
+        let mut compiler = ShaderCompiler::new().cache(ShaderCache::new(dir.join("cache")));
+
+        let compiled = compiler.compile(&shader, &[("BRIGHTNESS", "0.5")]).unwrap();
+        assert_eq!(compiled.spirv[0], 0x0723_0203);
+        assert_eq!(compiled.files, [shader.clone(), dir.join("color.wgsl")]);
+
+        // BRIGHTNESS is used in the Include but not defined
+        let Err(ShaderError::Compile(errors)) = compiler.compile(&shader, &[]) else {
+            panic!("Undefined Constant was not reported");
+        };
+        assert_eq!(errors[0].file, dir.join("color.wgsl"));
+        assert_eq!(errors[0].line, Some(2));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
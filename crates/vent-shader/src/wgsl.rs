@@ -0,0 +1,33 @@
+use naga::{
+    back::spv,
+    valid::{Capabilities, ValidationFlags, Validator},
+};
+
+use crate::{include::Expanded, Diagnostic, ShaderError};
+
+pub(crate) fn compile(expanded: &Expanded) -> Result<Vec<u32>, ShaderError> {
+    let source = &expanded.source;
+    let module = naga::front::wgsl::parse_str(source).map_err(|err| {
+        let line = err.location(source).map(|location| location.line_number);
+        ShaderError::Compile(vec![expanded.diagnostic(line, err.message().to_string())])
+    })?;
+    let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+        .validate(&module)
+        .map_err(|err| {
+            let line = err.location(source).map(|location| location.line_number);
+            ShaderError::Compile(vec![expanded.diagnostic(line, err.as_inner().to_string())])
+        })?;
+
+    let mut options = spv::Options::default();
+    // Our Projection Matrices already target Vulkan's Clip Space, Just like the GLSL Shaders
+    options
+        .flags
+        .remove(spv::WriterFlags::ADJUST_COORDINATE_SPACE);
+    spv::write_vec(&module, &info, &options, None).map_err(|err| {
+        ShaderError::Compile(vec![Diagnostic {
+            file: expanded.files[0].clone(),
+            line: None,
+            message: err.to_string(),
+        }])
+    })
+}
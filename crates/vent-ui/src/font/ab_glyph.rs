@@ -17,7 +17,6 @@ impl AbGlyphLoader {
     pub fn load<P>(
         path: P,
         descriptor_set_layout: vk::DescriptorSetLayout,
-        descriptor_pool: vk::DescriptorPool,
         instance: &mut VulkanInstance,
     ) -> super::Font
     where
@@ -127,38 +126,41 @@ impl AbGlyphLoader {
         // };
 
         let dimensions = image.dimensions();
-        let mut texture =
-            VulkanImage::from_image(instance, DynamicImage::ImageRgba8(image), false, None, None);
+        let texture = VulkanImage::from_image(
+            instance,
+            DynamicImage::ImageRgba8(image),
+            vk::Format::R8G8B8A8_UNORM,
+            false,
+            None,
+            None,
+        )
+        .expect("Failed to upload Font Texture");
 
         // TODO: store everything in an Texture Atlas
-        let descriptor_sets = VulkanInstance::allocate_descriptor_sets(
-            &instance.device,
-            descriptor_pool,
-            descriptor_set_layout,
-            instance.swapchain_images.len(),
-        );
-
-        for &descriptor_set in descriptor_sets.iter() {
-            let image_info = vk::DescriptorImageInfo::default()
-                .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
-                .image_view(texture.image_view)
-                .sampler(texture.sampler);
-
-            let desc_sets = [vk::WriteDescriptorSet {
-                dst_set: descriptor_set,
-                dst_binding: 0, // From DescriptorSetLayoutBinding
-                descriptor_count: 1,
-                descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-                p_image_info: &image_info,
-                ..Default::default()
-            }];
-            unsafe {
-                instance.device.update_descriptor_sets(&desc_sets, &[]);
-            }
+        let descriptor_set = instance
+            .descriptor_allocator
+            .allocate(&instance.device, descriptor_set_layout)
+            .expect("Failed to allocate Font Descriptor Set");
+
+        let image_info = vk::DescriptorImageInfo::default()
+            .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .image_view(texture.image_view)
+            .sampler(texture.sampler);
+
+        let desc_sets = [vk::WriteDescriptorSet {
+            dst_set: descriptor_set,
+            dst_binding: 0, // From DescriptorSetLayoutBinding
+            descriptor_count: 1,
+            descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            p_image_info: &image_info,
+            ..Default::default()
+        }];
+        unsafe {
+            instance.device.update_descriptor_sets(&desc_sets, &[]);
         }
-        texture.destroy(&instance.device);
         super::Font {
-            font_atlas: descriptor_sets,
+            font_atlas: descriptor_set,
+            font_texture: texture,
             characters,
             buffer_cache: HashMap::new(),
             atlas_width: dimensions.0,
@@ -128,7 +128,7 @@ impl AbGlyphLoader {
         let sampler = vk::SamplerCreateInfo::default()
             .max_lod(1.0);
         let texture =
-            VulkanImage::from_image(instance, DynamicImage::ImageRgba8(image), false, Some(sampler), Some("Font Atlas"));
+            VulkanImage::from_image(instance, DynamicImage::ImageRgba8(image), vk::Format::R8G8B8A8_UNORM, false, Some(sampler), Some("Font Atlas"));
 
         // TODO: store everything in an Texture Atlas
         let descriptor_sets = VulkanInstance::allocate_descriptor_sets(
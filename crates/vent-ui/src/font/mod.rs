@@ -2,7 +2,9 @@ use std::collections::HashMap;
 
 use ash::vk;
 use vent_math::vec::vec2::Vec2;
-use vent_rendering::{buffer::VulkanBuffer, instance::VulkanInstance, vertex::Vertex2D};
+use vent_rendering::{
+    buffer::VulkanBuffer, image::VulkanImage, instance::VulkanInstance, vertex::Vertex2D,
+};
 
 pub mod ab_glyph;
 
@@ -15,7 +17,10 @@ pub struct Character {
 #[allow(dead_code)]
 pub struct Font {
     buffer_cache: HashMap<String, (u32, VulkanBuffer)>,
-    font_atlas: Vec<vk::DescriptorSet>,
+    /// The Atlas never changes, So every Frame in Flight uses the same Set
+    font_atlas: vk::DescriptorSet,
+    /// Sampled through `font_atlas`, Lives as long as the Font
+    font_texture: VulkanImage,
     atlas_width: u32,
     atlas_height: u32,
     characters: Vec<Character>,
@@ -29,7 +34,6 @@ impl Font {
         instance: &VulkanInstance,
         command_buffer: vk::CommandBuffer,
         pipeline_layout: vk::PipelineLayout,
-        buffer_index: usize,
         text: String,
         x: f32,
         y: f32,
@@ -42,7 +46,7 @@ impl Font {
                 vk::PipelineBindPoint::GRAPHICS,
                 pipeline_layout,
                 0,
-                &self.font_atlas[buffer_index..=buffer_index],
+                &[self.font_atlas],
                 &[],
             )
         };
@@ -153,13 +157,16 @@ impl Font {
             vertex_size,
             vk::BufferUsageFlags::VERTEX_BUFFER,
             Some("Font Buffer"),
-        );
+        )
+        .expect("Failed to upload Font Buffer");
         buffer_cache.insert(text, (vertices.len() as u32, vulkan_buffer));
     }
 
-    pub fn destroy(&mut self, device: &ash::Device) {
+    pub fn destroy(&mut self, instance: &VulkanInstance) {
         for mut buffer in self.buffer_cache.drain() {
-            buffer.1 .1.destroy(device);
+            buffer.1 .1.destroy(&instance.device);
         }
+        instance.descriptor_allocator.free(&[self.font_atlas]);
+        self.font_texture.destroy(&instance.device);
     }
 }
@@ -1,10 +1,13 @@
-use std::{mem::size_of, path::PathBuf};
+use std::path::PathBuf;
 
 use ash::vk::{self};
 use vent_assets::io::file::FileAsset;
 use vent_math::vec::vec2::Vec2;
 use vent_rendering::{
-    any_as_u8_slice, instance::VulkanInstance, pipeline::VulkanPipeline, vertex::Vertex2D,
+    any_as_u8_slice,
+    instance::VulkanInstance,
+    pipeline::{AttachmentFormats, VulkanPipeline},
+    vertex::Vertex2D,
 };
 
 use crate::font::{ab_glyph::AbGlyphLoader, Font};
@@ -13,7 +16,6 @@ use super::GUI;
 
 #[allow(dead_code)]
 pub struct GuiRenderer {
-    descriptor_pool: vk::DescriptorPool,
     pipeline: VulkanPipeline,
 
     // Font
@@ -42,14 +44,7 @@ impl GuiRenderer {
             translate: Vec2::ZERO,
         };
 
-        let descriptor_pool = Self::create_descriptor_pool(
-            1, // 1 Font
-            instance.swapchain_images.len() as u32,
-            &instance.device,
-        );
-
         let mut renderer = Self {
-            descriptor_pool,
             pipeline,
             push_constant,
             font: None,
@@ -65,56 +60,25 @@ impl GuiRenderer {
         let vertex_shader = FileAsset::new("assets/shaders/app/2D/gui.vert.spv");
         let fragment_shader = FileAsset::new("assets/shaders/app/2D/gui.frag.spv");
 
-        let desc_layout_bindings = [
-            // Fragment
-            vk::DescriptorSetLayoutBinding {
-                binding: 0,
-                descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-                descriptor_count: 1,
-                stage_flags: vk::ShaderStageFlags::FRAGMENT,
-                ..Default::default()
-            },
-        ];
-
-        let push_constant_range = vk::PushConstantRange::default()
-            .size(size_of::<PushConstant>() as u32)
-            .stage_flags(vk::ShaderStageFlags::VERTEX);
-
         VulkanPipeline::create_simple_pipeline(
             instance,
             vertex_shader.root_path(),
             fragment_shader.root_path(),
             &[Vertex2D::binding_description()],
             &Vertex2D::input_descriptions(),
-            instance.surface_resolution,
-            &[push_constant_range],
-            &desc_layout_bindings,
+            // Text is drawn over the Scene, So the UI needs no Depth
+            &AttachmentFormats {
+                color: vec![instance.surface_format.format],
+                depth: None,
+            },
         )
     }
 
-    pub fn create_descriptor_pool(
-        material_count: u32,
-        swapchain_count: u32,
-        device: &ash::Device,
-    ) -> vk::DescriptorPool {
-        let pool_sizes = [vk::DescriptorPoolSize {
-            ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-            descriptor_count: material_count * swapchain_count,
-        }];
-
-        let create_info = vk::DescriptorPoolCreateInfo::default()
-            .pool_sizes(&pool_sizes)
-            .max_sets(material_count * swapchain_count);
-
-        unsafe { device.create_descriptor_pool(&create_info, None) }.unwrap()
-    }
-
     #[allow(clippy::too_many_arguments)]
     pub fn render_text(
         &mut self,
         instance: &VulkanInstance,
         command_buffer: vk::CommandBuffer,
-        buffer_index: usize,
         text: String,
         x: f32,
         y: f32,
@@ -159,7 +123,6 @@ impl GuiRenderer {
                 instance,
                 command_buffer,
                 self.pipeline.pipeline_layout,
-                buffer_index,
                 text,
                 x,
                 y,
@@ -173,7 +136,6 @@ impl GuiRenderer {
         self.font = Some(AbGlyphLoader::load(
             path,
             self.pipeline.descriptor_set_layout,
-            self.descriptor_pool,
             instance,
         ));
     }
@@ -193,14 +155,14 @@ impl GuiRenderer {
     #[allow(dead_code)]
     pub fn register_texture(&mut self) {}
 
-    pub fn destroy(&mut self, device: &ash::Device) {
-        unsafe {
-            if let Some(font) = &mut self.font {
-                font.destroy(device);
-                self.font = None
-            }
-            self.pipeline.destroy(device);
-            device.destroy_descriptor_pool(self.descriptor_pool, None);
+    pub fn destroy(&mut self, instance: &VulkanInstance) {
+        if let Some(font) = &mut self.font {
+            font.destroy(instance);
+            self.font = None
         }
+        instance
+            .descriptor_allocator
+            .forget_layout(self.pipeline.descriptor_set_layout);
+        self.pipeline.destroy(&instance.device);
     }
 }